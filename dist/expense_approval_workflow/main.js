@@ -0,0 +1,878 @@
+// Auto-generated by sculpt (cli)
+
+const TARGET = {"type":"cli-ir","version":1,"state":{"amount":890.0,"approvalLimit":1000.0,"approvedCount":0.0,"infoCount":0.0,"queueSize":12.0,"rejectedCount":0.0,"riskScore":18.0,"selectedRequest":"EX-2048"},"views":{"Inbox":[{"kind":"text","text":"Expense Queue","color":"yellow","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"1 = Open request, Esc = Exit","color":"blue","x":null,"y":null,"action":null,"style":null,"css":null}],"Rejected":[{"kind":"text","text":"Rejected with reason code.","color":"red","x":null,"y":null,"action":null,"style":null,"css":null}],"Review":[{"kind":"text","text":"Request #EX-2048","color":"yellow","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"Amount: 890 EUR","color":"white","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"Category: Travel","color":"white","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"A = Approve, R = Reject, N = Need Info","color":"blue","x":null,"y":null,"action":null,"style":null,"css":null}],"Approved":[{"kind":"text","text":"Approved and forwarded to payout batch.","color":"green","x":null,"y":null,"action":null,"style":null,"css":null}],"NeedInfo":[{"kind":"text","text":"Sent request for additional receipt details.","color":"magenta","x":null,"y":null,"action":null,"style":null,"css":null}]},"flow":{"start":"Inbox","transitions":{"Rejected":{"key(enter)":"Inbox"},"Inbox":{"key(esc)":"Exit","key()":"Review"},"Approved":{"key(enter)":"Inbox"},"Exit":{},"NeedInfo":{"key(enter)":"Inbox"},"Review":{"key(esc)":"Inbox","key(r)":"Rejected","escalated":"NeedInfo","key(a)":"Approved","key(n)":"NeedInfo"}}},"window":null,"layout":null,"extensions":{"runtimeRules":[{"assign":[{"op":"add","target":"approvedCount","value":1.0}],"emit":[],"name":"__on_1_Review","on":"key(a)","scopeFlow":"App","scopeState":"Review","when":null},{"assign":[{"op":"add","target":"rejectedCount","value":1.0}],"emit":[],"name":"__on_2_Review","on":"key(r)","scopeFlow":"App","scopeState":"Review","when":null},{"assign":[{"op":"add","target":"infoCount","value":1.0}],"emit":[],"name":"__on_3_Review","on":"key(n)","scopeFlow":"App","scopeState":"Review","when":null},{"assign":[],"emit":["escalated"],"name":"autoEscalate","on":null,"scopeFlow":"App","scopeState":"Review","when":{"kind":"logic","left":{"kind":"logic","left":{"kind":"cmp","left":"riskScore","op":"gte","right":70.0},"op":"and","right":{"kind":"cmp","left":"queueSize","op":"gt","right":10.0}},"op":"or","right":{"kind":"cmp","left":"amount","op":"eq","right":5000.0}}},{"assign":[],"emit":["done"],"name":"policyCheck","on":null,"scopeFlow":null,"scopeState":null,"when":{"kind":"cmp","left":"riskScore","op":"gte","right":80.0}}]}};
+
+const VIEWS = TARGET.views || {};
+const FLOW = TARGET.flow || { start: '', transitions: {} };
+const FLOW_PARENTS = FLOW.parents || {};
+const FLOW_INITIAL = FLOW.initial || {};
+const FLOW_REGIONS = FLOW.regions || {};
+const FLOW_TIMERS = FLOW.timers || {};
+const timerHandles = {};
+let exitedStates = [];
+
+function flowAncestors(s) {
+  const out = [];
+  for (let p = FLOW_PARENTS[s]; p && !out.includes(p); p = FLOW_PARENTS[p]) out.push(p);
+  return out;
+}
+
+function enterState(s) {
+  if (FLOW_REGIONS[s]) return FLOW_REGIONS[s].flatMap(enterState);
+  if (FLOW_INITIAL[s]) return enterState(FLOW_INITIAL[s]);
+  return [s];
+}
+
+function enterTarget(dst, top) {
+  let leaves = enterState(dst);
+  for (let c = dst, p = FLOW_PARENTS[dst]; c !== top && p; c = p, p = FLOW_PARENTS[p]) {
+    const regions = FLOW_REGIONS[p] || [];
+    const at = regions.indexOf(c);
+    if (at < 0) continue;
+    leaves = regions.slice(0, at).flatMap(enterState).concat(leaves, regions.slice(at + 1).flatMap(enterState));
+  }
+  return leaves;
+}
+
+function topBelow(s, ancestor) {
+  let top = s;
+  while (FLOW_PARENTS[top] && FLOW_PARENTS[top] !== ancestor) top = FLOW_PARENTS[top];
+  return top;
+}
+
+function transitionLeaves(active, src, dst) {
+  const dstAncestors = flowAncestors(dst);
+  const common = flowAncestors(src).find((a) => dstAncestors.includes(a)) || null;
+  const exited = topBelow(src, common);
+  exitedStates.push(exited);
+  const inside = (leaf) => leaf === exited || flowAncestors(leaf).includes(exited);
+  const at = active.findIndex(inside);
+  const kept = active.filter((leaf) => !inside(leaf));
+  kept.splice(at < 0 ? kept.length : at, 0, ...enterTarget(dst, topBelow(dst, common)));
+  return kept;
+}
+
+function nextActive(active, event, targetOf) {
+  let out = active;
+  const fired = new Set();
+  for (const leaf of active) {
+    if (!out.includes(leaf)) continue;
+    for (const s of [leaf].concat(flowAncestors(leaf))) {
+      if (fired.has(s)) break;
+      const dst = targetOf(s, event);
+      if (!dst) continue;
+      fired.add(s);
+      out = transitionLeaves(out, s, dst);
+      break;
+    }
+  }
+  return out;
+}
+
+function timerTarget(s, event) {
+  const m = /^timer\((.*)#(\d+)\)$/.exec(event);
+  if (!m || m[1] !== s) return null;
+  const timer = (FLOW_TIMERS[s] || [])[Number(m[2])];
+  return timer ? timer.target : null;
+}
+
+function syncTimers(states, fire) {
+  const left = (s) => !states.includes(s) || exitedStates.some((e) => e === s || flowAncestors(s).includes(e));
+  for (const s of Object.keys(timerHandles)) {
+    if (!left(s)) continue;
+    for (const h of timerHandles[s]) clearTimeout(h);
+    delete timerHandles[s];
+  }
+  exitedStates = [];
+  for (const s of states) {
+    if (timerHandles[s] || !FLOW_TIMERS[s]) continue;
+    timerHandles[s] = FLOW_TIMERS[s].map((t, i) => setTimeout(() => {
+      for (const h of timerHandles[s] || []) clearTimeout(h);
+      delete timerHandles[s];
+      fire(`timer(${s}#${i})`);
+    }, Number(t.afterMs) || 0));
+  }
+}
+
+function activeStates(active) {
+  const out = [];
+  for (const leaf of active) {
+    for (const s of flowAncestors(leaf).reverse().concat([leaf])) {
+      if (!out.includes(s)) out.push(s);
+    }
+  }
+  return out;
+}
+
+let active = enterTarget(FLOW.start || '', null);
+let state = active[0] || '';
+
+const fs = require('fs');
+const path = require('path');
+
+const EXT = TARGET.extensions || {};
+const RUNTIME_RULES = Array.isArray(EXT.runtimeRules) ? EXT.runtimeRules : [];
+const RUNTIME_STATE = (TARGET.state && typeof TARGET.state === 'object') ? JSON.parse(JSON.stringify(TARGET.state)) : {};
+const COLORS = {
+  black: '\x1b[30m', red: '\x1b[31m', green: '\x1b[32m', yellow: '\x1b[33m',
+  blue: '\x1b[34m', magenta: '\x1b[35m', cyan: '\x1b[36m', white: '\x1b[37m'
+};
+const RESET = '\x1b[0m';
+
+function inferBuiltinGame() {
+  if (EXT && EXT.game && EXT.game.kind) return EXT.game;
+  const texts = [];
+  for (const items of Object.values(VIEWS)) {
+    for (const item of items || []) {
+      if (item && item.kind === 'text' && typeof item.text === 'string') texts.push(item.text.toUpperCase());
+    }
+  }
+  const hasPlayState = !!(FLOW.transitions && FLOW.transitions.Play);
+  const isBreakout = texts.some((t) => t.includes('BREAKOUT')) && hasPlayState;
+  if (isBreakout) {
+    return {
+      kind: 'breakout',
+      width: Number(RUNTIME_STATE.width || 42),
+      height: Number(RUNTIME_STATE.height || 22),
+      paddleWidth: Number(RUNTIME_STATE.paddleWidth || 7),
+      tickMs: Number(RUNTIME_STATE.speedMs || 60),
+      lives: Number(RUNTIME_STATE.lives || 3),
+      brickRows: 5,
+      brickCols: 8,
+      brickWidth: 4,
+      gap: 1
+    };
+  }
+  const isSnake = texts.some((t) => t.includes('SNAKE')) && hasPlayState;
+  if (isSnake) {
+    return {
+      kind: 'snake',
+      width: Number(RUNTIME_STATE.width || RUNTIME_STATE.boardWidth || 28),
+      height: Number(RUNTIME_STATE.height || RUNTIME_STATE.boardHeight || 16),
+      tickMs: Number(RUNTIME_STATE.speedMs || 120)
+    };
+  }
+  return null;
+}
+
+const GAME_SPEC = inferBuiltinGame();
+let game = null;
+let tickHandle = null;
+
+function createBreakout(spec) {
+  const width = Math.max(30, Number(spec.width || 42));
+  const height = Math.max(16, Number(spec.height || 22));
+  const paddleWidth = Math.max(3, Number(spec.paddleWidth || 7));
+  const lives = Math.max(1, Number(spec.lives || 3));
+  const brickRows = Math.max(1, Number(spec.brickRows || 5));
+  const brickCols = Math.max(2, Number(spec.brickCols || 8));
+  const brickWidth = Math.max(2, Number(spec.brickWidth || 4));
+  const gap = Math.max(0, Number(spec.gap || 1));
+  const innerWidth = width - 2;
+  const totalBricksWidth = brickCols * brickWidth + (brickCols - 1) * gap;
+  const leftPad = Math.max(1, Math.floor((innerWidth - totalBricksWidth) / 2));
+  const bricks = [];
+  for (let r = 0; r < brickRows; r += 1) {
+    for (let c = 0; c < brickCols; c += 1) {
+      bricks.push({ x: 1 + leftPad + c * (brickWidth + gap), y: 2 + r, w: brickWidth, alive: true });
+    }
+  }
+  const paddleY = height - 2;
+  const paddleX = Math.floor((width - paddleWidth) / 2);
+  return {
+    width,
+    height,
+    paddleWidth,
+    paddleY,
+    paddleX,
+    ballX: paddleX + Math.floor(paddleWidth / 2),
+    ballY: paddleY - 1,
+    vx: 1,
+    vy: -1,
+    launched: false,
+    score: 0,
+    lives,
+    won: false,
+    bricks,
+    tickMs: Math.max(20, Number(spec.tickMs || 60))
+  };
+}
+
+function createSnake(spec) {
+  const width = Math.max(10, Number(spec.width || 16));
+  const height = Math.max(8, Number(spec.height || 12));
+  const cx = Math.floor(width / 2);
+  const cy = Math.floor(height / 2);
+  const snake = [
+    { x: cx, y: cy },
+    { x: cx - 1, y: cy },
+    { x: cx - 2, y: cy }
+  ];
+  const s = {
+    kind: 'snake',
+    width,
+    height,
+    tickMs: Math.max(40, Number(spec.tickMs || 120)),
+    snake,
+    direction: 'right',
+    pendingDirection: 'right',
+    score: 0,
+    alive: true,
+    food: null
+  };
+  s.food = placeFood(s);
+  return s;
+}
+
+function placeFood(s) {
+  const occ = new Set(s.snake.map((p) => `${p.x},${p.y}`));
+  const free = [];
+  for (let y = 1; y < s.height - 1; y += 1) {
+    for (let x = 1; x < s.width - 1; x += 1) {
+      const key = `${x},${y}`;
+      if (!occ.has(key)) free.push({ x, y });
+    }
+  }
+  if (free.length === 0) return null;
+  return free[Math.floor(Math.random() * free.length)];
+}
+
+function enterPlayState() {
+  if (!GAME_SPEC) return;
+  if (GAME_SPEC.kind === 'breakout') {
+    game = createBreakout(GAME_SPEC);
+  } else if (GAME_SPEC.kind === 'snake') {
+    game = createSnake(GAME_SPEC);
+  }
+}
+
+function ensureGameInit() {
+  if (!GAME_SPEC || game) return;
+  enterPlayState();
+}
+
+function resetBall() {
+  game.ballX = game.paddleX + Math.floor(game.paddleWidth / 2);
+  game.ballY = game.paddleY - 1;
+  game.vx = game.vx >= 0 ? 1 : -1;
+  game.vy = -1;
+  game.launched = false;
+}
+
+function stepBreakout() {
+  if (!game || game.kind !== 'breakout' || state !== 'Play') return;
+  if (!game.launched) {
+    game.ballX = game.paddleX + Math.floor(game.paddleWidth / 2);
+    game.ballY = game.paddleY - 1;
+    return;
+  }
+  let nx = game.ballX + game.vx;
+  let ny = game.ballY + game.vy;
+  if (nx <= 1 || nx >= game.width - 2) {
+    game.vx *= -1;
+    nx = game.ballX + game.vx;
+  }
+  if (ny <= 1) {
+    game.vy *= -1;
+    ny = game.ballY + game.vy;
+  }
+  if (ny >= game.height - 1) {
+    game.lives -= 1;
+    if (game.lives <= 0) {
+      dispatch('done');
+      return;
+    }
+    resetBall();
+    return;
+  }
+  if (game.vy > 0 && ny === game.paddleY - 1 && nx >= game.paddleX && nx < game.paddleX + game.paddleWidth) {
+    const center = game.paddleX + (game.paddleWidth - 1) / 2;
+    const offset = nx - center;
+    game.vx = offset < 0 ? -1 : 1;
+    if (offset === 0) game.vx = Math.random() < 0.5 ? -1 : 1;
+    game.vy = -1;
+    ny = game.ballY + game.vy;
+  }
+  let hitBrick = false;
+  for (const b of game.bricks) {
+    if (!b.alive) continue;
+    if (ny === b.y && nx >= b.x && nx < b.x + b.w) {
+      b.alive = false;
+      game.score += 10;
+      game.vy *= -1;
+      hitBrick = true;
+      ny = game.ballY + game.vy;
+      break;
+    }
+  }
+  if (!hitBrick && game.bricks.every((b) => !b.alive)) {
+    game.won = true;
+    dispatch('done');
+    return;
+  }
+  game.ballX = nx;
+  game.ballY = ny;
+}
+
+function handleGameInput(event) {
+  if (!game || state !== 'Play') return;
+  if (game.kind === 'breakout' && (event === 'key(a)' || event === 'key(left)')) {
+    game.paddleX = Math.max(1, game.paddleX - 2);
+  } else if (game.kind === 'breakout' && (event === 'key(d)' || event === 'key(right)')) {
+    game.paddleX = Math.min(game.width - 1 - game.paddleWidth, game.paddleX + 2);
+  } else if (game.kind === 'breakout' && event === 'key(space)') {
+    game.launched = true;
+  } else if (game.kind === 'snake') {
+    if ((event === 'key(w)' || event === 'key(up)') && game.direction !== 'down') game.pendingDirection = 'up';
+    if ((event === 'key(s)' || event === 'key(down)') && game.direction !== 'up') game.pendingDirection = 'down';
+    if ((event === 'key(a)' || event === 'key(left)') && game.direction !== 'right') game.pendingDirection = 'left';
+    if ((event === 'key(d)' || event === 'key(right)') && game.direction !== 'left') game.pendingDirection = 'right';
+  }
+}
+
+function renderBreakout() {
+  const grid = Array.from({ length: game.height }, () => Array.from({ length: game.width }, () => ' '));
+  for (let x = 0; x < game.width; x += 1) { grid[0][x] = '#'; grid[game.height - 1][x] = '#'; }
+  for (let y = 0; y < game.height; y += 1) { grid[y][0] = '#'; grid[y][game.width - 1] = '#'; }
+  for (const b of game.bricks) {
+    if (!b.alive) continue;
+    for (let x = b.x; x < b.x + b.w; x += 1) grid[b.y][x] = '=';
+  }
+  for (let x = game.paddleX; x < game.paddleX + game.paddleWidth; x += 1) grid[game.paddleY][x] = '_';
+  if (game.ballY > 0 && game.ballY < game.height - 1 && game.ballX > 0 && game.ballX < game.width - 1) {
+    grid[game.ballY][game.ballX] = 'o';
+  }
+  console.log(COLORS.cyan + `Score: ${game.score}   Lives: ${game.lives}` + RESET);
+  for (const row of grid) console.log(row.join(''));
+}
+
+function stepSnake() {
+  if (!game || game.kind !== 'snake' || state !== 'Play' || !game.alive) return;
+  game.direction = game.pendingDirection || game.direction;
+  const head = game.snake[0];
+  let nx = head.x;
+  let ny = head.y;
+  if (game.direction === 'up') ny -= 1;
+  if (game.direction === 'down') ny += 1;
+  if (game.direction === 'left') nx -= 1;
+  if (game.direction === 'right') nx += 1;
+  if (nx <= 0 || nx >= game.width - 1 || ny <= 0 || ny >= game.height - 1) {
+    game.alive = false;
+    dispatch('done');
+    return;
+  }
+  for (const p of game.snake) {
+    if (p.x === nx && p.y === ny) {
+      game.alive = false;
+      dispatch('done');
+      return;
+    }
+  }
+  game.snake.unshift({ x: nx, y: ny });
+  const ate = game.food && game.food.x === nx && game.food.y === ny;
+  if (ate) {
+    game.score += 1;
+    game.food = placeFood(game);
+  } else {
+    game.snake.pop();
+  }
+  if (!game.food) {
+    dispatch('done');
+  }
+}
+
+function renderSnake() {
+  const wall = '██';
+  const empty = '  ';
+  const body = COLORS.green + '▓▓' + RESET;
+  const head = COLORS.yellow + '██' + RESET;
+  const food = COLORS.magenta + '◉ ' + RESET;
+  const border = COLORS.cyan + wall + RESET;
+  const grid = Array.from({ length: game.height }, () => Array.from({ length: game.width }, () => empty));
+  for (let x = 0; x < game.width; x += 1) { grid[0][x] = border; grid[game.height - 1][x] = border; }
+  for (let y = 0; y < game.height; y += 1) { grid[y][0] = border; grid[y][game.width - 1] = border; }
+  if (game.food) grid[game.food.y][game.food.x] = food;
+  for (let i = 0; i < game.snake.length; i += 1) {
+    const p = game.snake[i];
+    grid[p.y][p.x] = i === 0 ? head : body;
+  }
+  const hud = COLORS.cyan + `Score: ${game.score}   Length: ${game.snake.length}   Size: ${game.width - 2}x${game.height - 2}` + RESET;
+  console.log(hud);
+  for (const row of grid) console.log(row.join(''));
+}
+
+function startTicker() {
+  if (tickHandle) return;
+  const ms = game ? game.tickMs : Math.max(20, Number(((EXT.runtime || {}).tickMs) || 80));
+  tickHandle = setInterval(() => {
+    dispatch('input.tick');
+    if (game && game.kind === 'breakout') stepBreakout();
+    if (game && game.kind === 'snake') stepSnake();
+    if (game) render();
+  }, ms);
+}
+
+function stopTicker() {
+  if (tickHandle) {
+    clearInterval(tickHandle);
+    tickHandle = null;
+  }
+}
+
+const ruleIntervals = {};
+
+function syncRuleTimers() {
+  const states = activeStates(active);
+  for (const rule of RUNTIME_RULES) {
+    if (!rule || !rule.everyMs) continue;
+    const live = !rule.scopeState || states.includes(rule.scopeState);
+    if (live && !ruleIntervals[rule.name]) {
+      ruleIntervals[rule.name] = setInterval(() => dispatch(rule.on), Math.max(1, Number(rule.everyMs)));
+    } else if (!live && ruleIntervals[rule.name]) {
+      clearInterval(ruleIntervals[rule.name]);
+      delete ruleIntervals[rule.name];
+    }
+  }
+}
+
+function stateNeedsTick(s) {
+  const map = Object.assign({}, (FLOW.guards && FLOW.guards[s]) || {}, (FLOW.transitions && FLOW.transitions[s]) || {});
+  if (Object.prototype.hasOwnProperty.call(map, 'tick')) return true;
+  if (Object.prototype.hasOwnProperty.call(map, 'input.tick')) return true;
+  for (const rule of RUNTIME_RULES) {
+    if (!rule || (rule.on !== 'tick' && rule.on !== 'input.tick')) continue;
+    if (rule.scopeState && rule.scopeState !== s) continue;
+    return true;
+  }
+  return false;
+}
+
+function parseCsv(text) {
+  const lines = String(text || '').split(/\r?\n/).filter((l) => l.length > 0);
+  if (lines.length === 0) return [];
+  const headers = lines[0].split(',').map((h) => h.trim());
+  const rows = [];
+  for (let i = 1; i < lines.length; i += 1) {
+    const cols = lines[i].split(',');
+    const row = {};
+    for (let j = 0; j < headers.length; j += 1) row[headers[j]] = String(cols[j] || '').trim();
+    rows.push(row);
+  }
+  return rows;
+}
+
+function daysDiff(a, b) {
+  const da = new Date(String(a || '') + 'T00:00:00Z');
+  const db = new Date(String(b || '') + 'T00:00:00Z');
+  return Math.round((da.getTime() - db.getTime()) / 86400000);
+}
+
+function approxEq(a, b, tol) {
+  return Math.abs(Number(a || 0) - Number(b || 0)) <= Number(tol || 0);
+}
+
+function nameList(value) {
+  const items = Array.isArray(value) ? value : String(value || '').split(',');
+  return items.map((s) => String(s).trim()).filter(Boolean);
+}
+
+function runtimeOp(name, args) {
+  const op = String(name || '').split('.').pop();
+  if (op === 'csvRead') {
+    const file = String(args[0] || '');
+    const full = path.isAbsolute(file) ? file : path.resolve(process.cwd(), file);
+    try { return parseCsv(fs.readFileSync(full, 'utf8')); } catch { return []; }
+  }
+  if (op === 'rowCount') return Array.isArray(args[0]) ? args[0].length : 0;
+  if (op === 'csvHasColumns') {
+    const rows = Array.isArray(args[0]) ? args[0] : [];
+    const required = nameList(args[1]);
+    if (rows.length === 0) return required.length === 0 ? 1 : 0;
+    const head = rows[0] || {};
+    return required.every((k) => Object.prototype.hasOwnProperty.call(head, k)) ? 1 : 0;
+  }
+  if (op === 'csvMissingColumns') {
+    const rows = Array.isArray(args[0]) ? args[0] : [];
+    const required = nameList(args[1]);
+    if (required.length === 0) return [];
+    if (rows.length === 0) return required;
+    const head = rows[0] || {};
+    return required.filter((k) => !Object.prototype.hasOwnProperty.call(head, k));
+  }
+  if (op === 'schemaErrorMessage') {
+    const invMissing = Array.isArray(args[0]) ? args[0].map((x) => String(x)).filter(Boolean) : [];
+    const payMissing = Array.isArray(args[1]) ? args[1].map((x) => String(x)).filter(Boolean) : [];
+    const parts = [];
+    if (invMissing.length > 0) parts.push(`invoices.csv missing: ${invMissing.join(', ')}`);
+    if (payMissing.length > 0) parts.push(`payments.csv missing: ${payMissing.join(', ')}`);
+    if (parts.length === 0) return 'Input schema invalid';
+    return `Input schema invalid: ${parts.join(' | ')}`;
+  }
+  if (op === 'reconcileInvoices') {
+    const invoices = Array.isArray(args[0]) ? args[0] : [];
+    const payments = Array.isArray(args[1]) ? args[1] : [];
+    const toleranceDays = Number(args[2] || 30);
+    const amountTolerance = Number(args[3] || 0);
+    const startedAt = Date.now();
+    const byInv = new Map();
+    const byCust = new Map();
+    for (const p of payments) {
+      const inv = String(p.invoice_id || '');
+      const cust = String(p.customer_id || '');
+      if (!byInv.has(inv)) byInv.set(inv, []);
+      byInv.get(inv).push(p);
+      if (!byCust.has(cust)) byCust.set(cust, []);
+      byCust.get(cust).push(p);
+    }
+    const usedPayment = new Set();
+    const exceptions = [];
+    const counts = { matched_full: 0, matched_partial: 0, overpaid: 0, missing_payment: 0, duplicate_payment: 0, ambiguous: 0, suspicious: 0 };
+    for (const inv of invoices) {
+      const amountDue = Number(inv.amount_due || 0);
+      const dueDate = String(inv.due_date || '');
+      const invId = String(inv.invoice_id || '');
+      const custId = String(inv.customer_id || '');
+      const direct = (byInv.get(invId) || []).filter((p) => String(p.customer_id || '') === custId);
+      const inWindow = direct.filter((p) => Math.abs(daysDiff(p.payment_date, dueDate)) <= toleranceDays);
+      let candidates = inWindow;
+      if (candidates.length === 0) {
+        const custPool = byCust.get(custId) || [];
+        const fallback = custPool.filter((p) => {
+          const amount = Number(p.amount_paid || 0);
+          return (!p.invoice_id || String(p.invoice_id).length === 0) && approxEq(amount, amountDue, amountTolerance) && Math.abs(daysDiff(p.payment_date, dueDate)) <= toleranceDays;
+        });
+        if (fallback.length === 1) candidates = fallback;
+        if (fallback.length > 1) {
+          counts.ambiguous += 1;
+          exceptions.push({ invoice_id: invId, payment_id: '', classification: 'ambiguous', reason: 'multiple fallback candidates' });
+          continue;
+        }
+      }
+      if (candidates.length === 0) {
+        counts.missing_payment += 1;
+        exceptions.push({ invoice_id: invId, payment_id: '', classification: 'missing_payment', reason: 'no payment found' });
+        continue;
+      }
+      if (candidates.length > 1) {
+        counts.duplicate_payment += 1;
+        exceptions.push({ invoice_id: invId, payment_id: candidates.map((p) => String(p.payment_id || '')).join('|'), classification: 'duplicate_payment', reason: 'multiple payments for one invoice' });
+        for (const p of candidates) usedPayment.add(String(p.payment_id || ''));
+        continue;
+      }
+      const p = candidates[0];
+      usedPayment.add(String(p.payment_id || ''));
+      const amount = Number(p.amount_paid || 0);
+      if (String(p.customer_id || '') !== custId) {
+        counts.suspicious += 1;
+        exceptions.push({ invoice_id: invId, payment_id: String(p.payment_id || ''), classification: 'suspicious', reason: 'customer mismatch' });
+        continue;
+      }
+      if (approxEq(amount, amountDue, amountTolerance)) {
+        counts.matched_full += 1;
+      } else if (amount < amountDue) {
+        counts.matched_partial += 1;
+        exceptions.push({ invoice_id: invId, payment_id: String(p.payment_id || ''), classification: 'matched_partial', reason: `paid ${amount.toFixed(2)} / due ${amountDue.toFixed(2)}` });
+      } else {
+        counts.overpaid += 1;
+        exceptions.push({ invoice_id: invId, payment_id: String(p.payment_id || ''), classification: 'overpaid', reason: `paid ${amount.toFixed(2)} / due ${amountDue.toFixed(2)}` });
+      }
+    }
+    for (const p of payments) {
+      const pid = String(p.payment_id || '');
+      if (usedPayment.has(pid)) continue;
+      if (!p.invoice_id) {
+        counts.suspicious += 1;
+        exceptions.push({ invoice_id: '', payment_id: pid, classification: 'suspicious', reason: 'unmatched payment without invoice_id' });
+        continue;
+      }
+      const exists = invoices.some((i) => String(i.invoice_id || '') === String(p.invoice_id || ''));
+      if (!exists) {
+        counts.suspicious += 1;
+        exceptions.push({ invoice_id: String(p.invoice_id || ''), payment_id: pid, classification: 'suspicious', reason: 'references unknown invoice' });
+      }
+    }
+    return { counts, exceptions, processing_ms: Date.now() - startedAt };
+  }
+  if (op === 'metric') {
+    const rec = args[0] && typeof args[0] === 'object' ? args[0] : {};
+    const key = String(args[1] || '');
+    return Number((((rec.counts || {})[key]) || 0));
+  }
+  if (op === 'buildExceptions') {
+    const rec = args[0] && typeof args[0] === 'object' ? args[0] : {};
+    return Array.isArray(rec.exceptions) ? rec.exceptions.slice() : [];
+  }
+  if (op === 'buildReportJson') {
+    return {
+      input_stats: { invoices: Number(args[0] || 0), payments: Number(args[1] || 0) },
+      classification_counts: {
+        matched_full: Number(args[2] || 0), matched_partial: Number(args[3] || 0), overpaid: Number(args[4] || 0), missing_payment: Number(args[5] || 0), duplicate_payment: Number(args[6] || 0), ambiguous: Number(args[7] || 0), suspicious: Number(args[8] || 0)
+      },
+      rules_version: String(args[9] || '1.0'),
+      processing_ms: Number(args[10] || 0),
+      generated_at: new Date().toISOString()
+    };
+  }
+  if (op === 'processingMs') {
+    const rec = args[0] && typeof args[0] === 'object' ? args[0] : {};
+    return Number(rec.processing_ms || 0);
+  }
+  if (op === 'writeJson') {
+    try {
+      const file = String(args[0] || '');
+      const full = path.isAbsolute(file) ? file : path.resolve(process.cwd(), file);
+      fs.mkdirSync(path.dirname(full), { recursive: true });
+      fs.writeFileSync(full, JSON.stringify(args[1] || {}, null, 2) + '\n', 'utf8');
+      return 1;
+    } catch { return 0; }
+  }
+  if (op === 'sortBy') {
+    const rows = Array.isArray(args[0]) ? args[0].slice() : [];
+    const keys = nameList(args[1]);
+    rows.sort((a, b) => {
+      for (const k of keys) {
+        const av = String((a && a[k]) || '');
+        const bv = String((b && b[k]) || '');
+        if (av < bv) return -1;
+        if (av > bv) return 1;
+      }
+      return 0;
+    });
+    return rows;
+  }
+  if (op === 'writeCsv') {
+    try {
+      const file = String(args[0] || '');
+      const full = path.isAbsolute(file) ? file : path.resolve(process.cwd(), file);
+      fs.mkdirSync(path.dirname(full), { recursive: true });
+      const rows = Array.isArray(args[1]) ? args[1] : [];
+      const header = ['invoice_id','payment_id','classification','reason'];
+      const lines = [header.join(',')];
+      for (const row of rows) {
+        const vals = header.map((k) => String((row && row[k]) || '').replace(/,/g, ';'));
+        lines.push(vals.join(','));
+      }
+      fs.writeFileSync(full, lines.join('\n') + '\n', 'utf8');
+      return 1;
+    } catch { return 0; }
+  }
+  if (op === 'summaryLine') return `${String(args[0] || '')}: ${Number(args[1] || 0)}`;
+  return null;
+}
+
+function exprNumber(v) {
+  return Number(v || 0);
+}
+
+function valuesEqual(l, r) {
+  if (typeof l === 'number' || typeof r === 'number') return exprNumber(l) === exprNumber(r);
+  if (l === null || l === undefined || r === null || r === undefined) {
+    return (l === null || l === undefined) && (r === null || r === undefined);
+  }
+  return String(l) === String(r);
+}
+
+function addValues(l, r) {
+  if (typeof l === 'string' || typeof r === 'string') return String(l ?? '') + String(r ?? '');
+  return exprNumber(l) + exprNumber(r);
+}
+
+function binaryOp(op, l, r) {
+  if (op === 'Add') return addValues(l, r);
+  if (op === 'Sub') return exprNumber(l) - exprNumber(r);
+  if (op === 'Mul') return exprNumber(l) * exprNumber(r);
+  if (op === 'Div') return exprNumber(r) === 0 ? 0 : exprNumber(l) / exprNumber(r);
+  if (op === 'Mod') return exprNumber(r) === 0 ? 0 : exprNumber(l) % exprNumber(r);
+  if (op === 'And') return l && r ? 1 : 0;
+  if (op === 'Or') return l || r ? 1 : 0;
+  if (op === 'Eq') return valuesEqual(l, r) ? 1 : 0;
+  if (op === 'Neq') return valuesEqual(l, r) ? 0 : 1;
+  if (op === 'Gt') return exprNumber(l) > exprNumber(r) ? 1 : 0;
+  if (op === 'Gte') return exprNumber(l) >= exprNumber(r) ? 1 : 0;
+  if (op === 'Lt') return exprNumber(l) < exprNumber(r) ? 1 : 0;
+  if (op === 'Lte') return exprNumber(l) <= exprNumber(r) ? 1 : 0;
+  return null;
+}
+
+function unaryOp(op, v) {
+  if (op === 'Neg') return -exprNumber(v);
+  if (op === 'Not') return v ? 0 : 1;
+  return null;
+}
+
+function compareWhen(op, l, r) {
+  if (op === 'gte') return exprNumber(l) >= exprNumber(r);
+  if (op === 'gt') return exprNumber(l) > exprNumber(r);
+  if (op === 'lt') return exprNumber(l) < exprNumber(r);
+  if (op === 'eq') return valuesEqual(l, r);
+  if (op === 'neq') return !valuesEqual(l, r);
+  return false;
+}
+
+function evalBinaryExpr(node) {
+  if (!node || typeof node !== 'object') return null;
+  return binaryOp(String(node.op || ''), resolveRuntimeValue(node.left), resolveRuntimeValue(node.right));
+}
+
+function evalUnaryExpr(node) {
+  if (!node || typeof node !== 'object') return null;
+  return unaryOp(node.op, resolveRuntimeValue(node.operand));
+}
+
+function resolveRuntimeValue(v) {
+  if (v && typeof v === 'object' && !Array.isArray(v)) {
+    if (typeof v.ident === 'string') return RUNTIME_STATE[v.ident];
+    if (Array.isArray(v.list)) return v.list.map(resolveRuntimeValue);
+    if (Array.isArray(v.template)) return renderTemplate(v.template);
+    if (v.record && typeof v.record === 'object') {
+      const out = {};
+      for (const [k, item] of Object.entries(v.record)) out[k] = resolveRuntimeValue(item);
+      return out;
+    }
+    if (v.binary && typeof v.binary === 'object') return evalBinaryExpr(v.binary);
+    if (v.unary && typeof v.unary === 'object') return evalUnaryExpr(v.unary);
+    if (v.call && typeof v.call === 'object') {
+      const callName = String(v.call.name || '');
+      const rawArgs = Array.isArray(v.call.args) ? v.call.args : [];
+      const args = rawArgs.map((a) => resolveRuntimeValue(a && typeof a === 'object' ? a.value : null));
+      return runtimeOp(callName, args);
+    }
+  }
+  return v;
+}
+
+function renderTemplate(parts) {
+  return parts
+    .map((part) => {
+      if (part && typeof part.text === 'string') return part.text;
+      const value = resolveRuntimeValue(part ? part.expr : null);
+      return value === null || value === undefined ? '' : String(value);
+    })
+    .join('');
+}
+
+function evalWhen(when) {
+  if (!when || typeof when !== 'object') return false;
+  if (when.kind === 'logic') {
+    if (when.op === 'and') return evalWhen(when.left) && evalWhen(when.right);
+    if (when.op === 'or') return evalWhen(when.left) || evalWhen(when.right);
+    return false;
+  }
+  if (when.kind === 'not') return !evalWhen(when.operand);
+  if (when.kind === 'expr') return !!resolveRuntimeValue(when.expr);
+  return compareWhen(when.op, RUNTIME_STATE[String(when.left)], resolveRuntimeValue(when.right));
+}
+
+function applyRuntimeRules(event, firedWhen) {
+  const emitted = [];
+  for (const rule of RUNTIME_RULES) {
+    if (!rule) continue;
+    if (rule.scopeState && !activeStates(active).includes(rule.scopeState)) continue;
+    const isOnRule = typeof rule.on === 'string' && rule.on.length > 0;
+    const isWhenRule = !!rule.when;
+    if (isOnRule && rule.on !== event) continue;
+    if (!isOnRule && isWhenRule && event !== 'tick' && event !== 'input.tick') continue;
+    if (isWhenRule) {
+      if (firedWhen && firedWhen.has(rule.name)) continue;
+      if (!evalWhen(rule.when)) continue;
+      if (firedWhen) firedWhen.add(rule.name);
+    }
+    if (!isOnRule && !isWhenRule) continue;
+    const assigns = Array.isArray(rule.assign) ? rule.assign : [];
+    for (const a of assigns) {
+      if (!a || typeof a.target !== 'string') continue;
+      const value = resolveRuntimeValue(a.value);
+      if (a.op === 'add') {
+        RUNTIME_STATE[a.target] = addValues(RUNTIME_STATE[a.target], value);
+      } else {
+        RUNTIME_STATE[a.target] = value;
+      }
+    }
+    const emits = Array.isArray(rule.emit) ? rule.emit : [];
+    for (const ev of emits) {
+      if (typeof ev === 'string' && ev.length > 0) emitted.push(ev);
+    }
+  }
+  return emitted;
+}
+
+function render() {
+  console.clear();
+  if (game && state === 'Play') {
+    if (game.kind === 'breakout') renderBreakout();
+    if (game.kind === 'snake') renderSnake();
+    return;
+  }
+  const items = activeStates(active).flatMap((s) => VIEWS[s] || []);
+  for (const item of items) {
+    if (item.kind === 'text') {
+      const color = item.color ? (COLORS[item.color] || '') : '';
+      const raw = item.text || '';
+      const txt = Array.isArray(item.textTemplate) ? renderTemplate(item.textTemplate) : (typeof raw === 'string' && Object.prototype.hasOwnProperty.call(RUNTIME_STATE, raw)) ? String(RUNTIME_STATE[raw]) : String(raw);
+      console.log(color + txt + RESET);
+    }
+  }
+}
+
+function transitionTarget(s, event) {
+  if (event.startsWith('timer(')) return timerTarget(s, event);
+  const guarded = (FLOW.guards && FLOW.guards[s] && FLOW.guards[s][event]) || [];
+  for (const g of guarded) {
+    if (g && evalWhen(g.when)) return g.target;
+  }
+  const map = (FLOW.transitions && FLOW.transitions[s]) || {};
+  return map[event] || null;
+}
+
+function processEvent(event, depth, firedWhen) {
+  if (depth > 24) return;
+  if (!firedWhen) firedWhen = new Set();
+  const before = state;
+  const beforeActive = active.join('\n');
+  if (game) handleGameInput(event);
+  active = nextActive(active, event, transitionTarget);
+  state = active[0] || '';
+  const emitted = applyRuntimeRules(event, firedWhen);
+  if (state !== before && state === 'Play') {
+    enterPlayState();
+  }
+  if (activeStates(active).some(stateNeedsTick)) {
+    startTicker();
+  } else {
+    stopTicker();
+  }
+  syncTimers(activeStates(active), dispatch);
+  syncRuleTimers();
+  if (state !== before && String(state).toLowerCase() === 'exit') process.exit(0);
+  if (active.join('\n') !== beforeActive) render();
+  for (const ev of emitted) processEvent(ev, depth + 1, firedWhen);
+}
+
+function dispatch(event) {
+  processEvent(event, 0, new Set());
+}
+
+function normalizeKey(key) {
+  if (key === '\r' || key === '\n' || key.includes('\r') || key.includes('\n')) return 'enter';
+  if (key === '\u001b') return 'esc';
+  if (key === '\u001b[A') return 'up';
+  if (key === '\u001b[B') return 'down';
+  if (key === '\u001b[D') return 'left';
+  if (key === '\u001b[C') return 'right';
+  if (key === ' ') return 'space';
+  return key.trim().toLowerCase();
+}
+
+ensureGameInit();
+if (activeStates(active).some(stateNeedsTick)) startTicker();
+syncTimers(activeStates(active), dispatch);
+syncRuleTimers();
+render();
+if (process.stdin.isTTY) process.stdin.setRawMode(true);
+process.stdin.resume();
+process.stdin.on('data', (buf) => {
+  const key = buf.toString('utf8');
+  if (key === '\u0003') process.exit(0);
+  const k = normalizeKey(key);
+  dispatch(`key(${k})`);
+});
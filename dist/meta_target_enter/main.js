@@ -0,0 +1 @@
+console.log('ok')
\ No newline at end of file
@@ -0,0 +1,135 @@
+import json
+import tkinter as tk
+from tkinter import messagebox
+
+TARGET = json.loads("{\"type\":\"gui-ir\",\"version\":1,\"state\":{\"notes\":\"Investigating customer-facing outage indicators\",\"owner\":\"Mia\",\"ticketState\":\"open\"},\"views\":{\"Ticket\":[{\"kind\":\"heading\",\"text\":\"Incident\",\"color\":null,\"x\":null,\"y\":null,\"action\":null,\"style\":null,\"css\":null},{\"kind\":\"input\",\"text\":\"Owner\",\"color\":null,\"x\":null,\"y\":null,\"action\":null,\"style\":null,\"css\":null},{\"kind\":\"select\",\"text\":\"State\",\"color\":null,\"x\":null,\"y\":null,\"action\":null,\"style\":null,\"css\":null},{\"kind\":\"textarea\",\"text\":\"Notes\",\"color\":null,\"x\":null,\"y\":null,\"action\":null,\"style\":null,\"css\":null},{\"kind\":\"button\",\"text\":\"Save\",\"color\":null,\"x\":null,\"y\":null,\"action\":\"save\",\"style\":null,\"css\":null},{\"kind\":\"button\",\"text\":\"Resolve\",\"color\":null,\"x\":null,\"y\":null,\"action\":\"resolve\",\"style\":null,\"css\":null},{\"kind\":\"button\",\"text\":\"Back\",\"color\":null,\"x\":null,\"y\":null,\"action\":\"back\",\"style\":null,\"css\":null}],\"ConfirmResolve\":[{\"kind\":\"button\",\"text\":\"Cancel\",\"color\":null,\"x\":null,\"y\":null,\"action\":\"cancel\",\"style\":null,\"css\":null}],\"Inbox\":[{\"kind\":\"heading\",\"text\":\"Service Desk\",\"color\":null,\"x\":null,\"y\":null,\"action\":null,\"style\":null,\"css\":null},{\"kind\":\"table\",\"text\":\"ID | Priority | Assignee | State\",\"color\":null,\"x\":null,\"y\":null,\"action\":null,\"style\":null,\"css\":null},{\"kind\":\"text\",\"text\":\"INC-4821 | P1 | Mia | open\",\"color\":null,\"x\":null,\"y\":null,\"action\":null,\"style\":null,\"css\":null},{\"kind\":\"text\",\"text\":\"INC-4822 | P2 | Leo | in_progress\",\"color\":null,\"x\":null,\"y\":null,\"action\":null,\"style\":null,\"css\":null},{\"kind\":\"button\",\"text\":\"Open INC-4821\",\"color\":null,\"x\":null,\"y\":null,\"action\":\"open4821\",\"style\":null,\"css\":null},{\"kind\":\"button\",\"text\":\"Open INC-4822\",\"color\":null,\"x\":null,\"y\":null,\"action\":\"open4822\",\"style\":null,\"css\":null},{\"kind\":\"button\",\"text\":\"Close\",\"color\":null,\"x\":null,\"y\":null,\"action\":\"close\",\"style\":null,\"css\":null}]},\"flow\":{\"start\":\"Inbox\",\"transitions\":{\"Exit\":{},\"Inbox\":{\"input.closeWindow\":\"Exit\",\"input.click(open4821)\":\"Ticket\",\"input.click(open4822)\":\"Ticket\",\"input.click(close)\":\"Exit\"},\"ConfirmResolve\":{\"input.click(cancel)\":\"Ticket\",\"input.click(ok)\":\"Resolved\"},\"Resolved\":{\"input.tick\":\"Inbox\"},\"Saved\":{\"input.tick\":\"Inbox\"},\"Ticket\":{\"input.click(save)\":\"Saved\",\"input.click(back)\":\"Inbox\",\"input.click(resolve)\":\"ConfirmResolve\"}}},\"window\":null,\"layout\":null,\"extensions\":{}}")
+VIEWS = TARGET.get('views', {})
+FLOW = TARGET.get('flow', {})
+TRANSITIONS = FLOW.get('transitions', {})
+TIMERS = FLOW.get('timers', {})
+state = FLOW.get('start', '')
+timer_jobs = []
+
+root = tk.Tk()
+window = TARGET.get('window') or {}
+root.title(window.get('title') or 'SCULPT')
+width = int(window.get('width') or 420)
+height = int(window.get('height') or 260)
+root.geometry(f'{width}x{height}')
+root.resizable(False, False)
+root.configure(bg='#0b111a')
+
+frame = tk.Frame(root, padx=24, pady=24, bg='#142131')
+frame.pack(fill='both', expand=True)
+
+def map_color(name):
+    c = str(name or '').lower()
+    return {
+        'yellow': '#ffd60a',
+        'blue': '#0a84ff',
+        'green': '#30d158',
+        'red': '#ff453a',
+        'magenta': '#ea5172',
+        'secondary': '#9aa0aa',
+        'white': '#eaf5ff',
+        'black': '#0b111a',
+    }.get(c, '#eaf5ff')
+
+def map_font(style):
+    s = str(style or '')
+    if s == 'title': return ('Menlo', 20, 'bold')
+    if s == 'subtitle': return ('Menlo', 13, 'bold')
+    if s == 'caption': return ('Menlo', 11)
+    return ('Menlo', 13)
+
+def arm_timers():
+    for job in timer_jobs:
+        root.after_cancel(job)
+    timer_jobs.clear()
+    for timer in TIMERS.get(state, []):
+        timer_jobs.append(root.after(int(timer.get('afterMs') or 0), lambda target=timer.get('target'): go(target)))
+
+def go(next_state):
+    global state
+    state = next_state
+    if str(state).lower() == 'exit':
+        root.destroy()
+        return
+    arm_timers()
+    render()
+
+def dispatch(event):
+    next_state = (TRANSITIONS.get(state) or {}).get(event)
+    if next_state:
+        go(next_state)
+    elif event == 'key(esc)':
+        root.destroy()
+
+def current_button_action():
+    for item in VIEWS.get(state, []):
+        if item.get('kind') == 'button':
+            return item.get('action')
+    return None
+
+def on_primary(_event=None):
+    action = current_button_action()
+    if action == 'modal.ok':
+        messagebox.showinfo('OK', 'OK')
+    if action:
+        dispatch(f"input.click({action})")
+    else:
+        dispatch('input.click')
+
+def normalize_key(evt):
+    k = str(evt.keysym or '').lower()
+    if k in ('return', 'kp_enter'): return 'enter'
+    if k == 'escape': return 'esc'
+    if k == 'space': return 'space'
+    if len(k) == 1: return k
+    return k
+
+def on_key(evt):
+    dispatch(f"key({normalize_key(evt)})")
+
+def render():
+    for child in frame.winfo_children():
+        child.destroy()
+    for idx, item in enumerate(VIEWS.get(state, [])):
+        kind = item.get('kind')
+        text = item.get('text') or ''
+        color = map_color(item.get('color'))
+        if kind == 'button':
+            tk.Button(frame, text=text or 'OK', command=on_primary, bg='#004b73', fg='#eaf5ff', activebackground='#006896', activeforeground='#eaf5ff', relief='flat', padx=10, pady=6).pack(anchor='w', pady=(8, 0))
+        elif kind == 'heading':
+            tk.Label(frame, text=text, fg=color, bg='#142131', font=('Menlo', 16, 'bold')).pack(anchor='w', pady=(0, 8))
+        elif kind == 'input':
+            entry = tk.Entry(frame, width=36, bg='#0b111a', fg='#eaf5ff', disabledforeground='#9fb6c8', insertbackground='#00ffff', relief='flat')
+            entry.insert(0, text)
+            entry.configure(state='readonly')
+            entry.pack(anchor='w', pady=(0, 6))
+        elif kind == 'checkbox':
+            var = tk.BooleanVar(value=False)
+            cb = tk.Checkbutton(frame, text=text or 'Option', variable=var, bg='#142131', fg=color, selectcolor='#0b111a', activebackground='#142131', activeforeground=color)
+            cb.configure(state='disabled')
+            cb.pack(anchor='w', pady=(0, 6))
+        elif kind == 'table':
+            box = tk.Text(frame, width=44, height=6, bg='#0b111a', fg='#eaf5ff', relief='flat')
+            box.insert('1.0', text)
+            box.configure(state='disabled')
+            box.pack(anchor='w', pady=(0, 6))
+        elif kind in ('panel', 'card'):
+            panel = tk.LabelFrame(frame, text='', bg='#101a29', fg='#9fb6c8', bd=1, relief='solid')
+            panel.pack(anchor='w', fill='x', pady=(0, 6))
+            tk.Label(panel, text=text, fg=color, bg='#101a29').pack(anchor='w', padx=8, pady=6)
+        elif kind == 'modal':
+            tk.Button(frame, text=text or 'Open', command=lambda: messagebox.showinfo('Info', text or 'OK'), bg='#004b73', fg='#eaf5ff', activebackground='#006896', activeforeground='#eaf5ff', relief='flat', padx=10, pady=6).pack(anchor='w', pady=(0, 6))
+        else:
+            tk.Label(frame, text=text, fg=color, bg='#142131', font=map_font(item.get('style'))).pack(anchor='w', pady=(0, 8 if idx == 0 else 4))
+
+root.bind('<KeyPress>', on_key)
+root.bind('<Escape>', lambda _e: dispatch('key(esc)'))
+root.bind('<Return>', on_primary)
+root.bind('<KP_Enter>', on_primary)
+arm_timers()
+render()
+root.mainloop()
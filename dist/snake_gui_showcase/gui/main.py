@@ -0,0 +1,140 @@
+import tkinter as tk
+import random
+
+TITLE = "SCULPT"
+CELL = 20
+BOARD_W = 32
+BOARD_H = 20
+TICK_MS = 100
+TARGET_SCORE = 12
+LIVES_START = 3
+
+root = tk.Tk()
+root.title(TITLE)
+root.configure(bg='#111111')
+root.resizable(False, False)
+
+hud = tk.Label(root, text='', fg='#00ffff', bg='#111111', font=('Menlo', 12, 'bold'))
+hud.pack(pady=(8, 4))
+canvas = tk.Canvas(root, width=BOARD_W*CELL, height=BOARD_H*CELL, bg='#0b0c10', highlightthickness=1, highlightbackground='#00ffff')
+canvas.pack(padx=12, pady=8)
+hint = tk.Label(root, text='Enter: start/restart  P: pause  Esc: quit  WASD/Arrows: move', fg='#00a8ff', bg='#111111', font=('Menlo', 10))
+hint.pack(pady=(4, 10))
+
+state = 'title'
+direction = 'right'
+pending = 'right'
+snake = []
+food = None
+score = 0
+lives = LIVES_START
+
+def center_spawn():
+    cx = BOARD_W // 2
+    cy = BOARD_H // 2
+    return [(cx, cy), (cx-1, cy), (cx-2, cy)]
+
+def place_food():
+    occupied = set(snake)
+    free = [(x, y) for y in range(1, BOARD_H-1) for x in range(1, BOARD_W-1) if (x, y) not in occupied]
+    return random.choice(free) if free else None
+
+def reset_round():
+    global snake, food, direction, pending
+    snake = center_spawn()
+    food = place_food()
+    direction = 'right'
+    pending = 'right'
+
+def draw_cell(x, y, color):
+    x0 = x * CELL
+    y0 = y * CELL
+    canvas.create_rectangle(x0, y0, x0 + CELL, y0 + CELL, fill=color, width=0)
+
+def render():
+    canvas.delete('all')
+    for x in range(BOARD_W):
+        draw_cell(x, 0, '#00ffff')
+        draw_cell(x, BOARD_H-1, '#00ffff')
+    for y in range(BOARD_H):
+        draw_cell(0, y, '#00ffff')
+        draw_cell(BOARD_W-1, y, '#00ffff')
+    if food:
+        draw_cell(food[0], food[1], '#ea5172')
+    for i, p in enumerate(snake):
+        draw_cell(p[0], p[1], '#ffd60a' if i == 0 else '#30d158')
+    if state == 'title':
+        hud.config(text='SNAKE GUI // Enter to Start', fg='#00ffff')
+    elif state == 'pause':
+        hud.config(text=f'PAUSED  Score: {score}  Lives: {lives}', fg='#ffd60a')
+    elif state == 'gameover':
+        hud.config(text=f'GAME OVER  Score: {score}  Enter to Retry', fg='#ff453a')
+    elif state == 'victory':
+        hud.config(text=f'YOU WIN  Score: {score}  Enter to Retry', fg='#30d158')
+    else:
+        hud.config(text=f'Score: {score}  Lives: {lives}  Length: {len(snake)}', fg='#00ffff')
+
+def step():
+    global state, direction, pending, snake, food, score, lives
+    if state == 'play':
+        direction = pending
+        hx, hy = snake[0]
+        nx, ny = hx, hy
+        if direction == 'up': ny -= 1
+        elif direction == 'down': ny += 1
+        elif direction == 'left': nx -= 1
+        elif direction == 'right': nx += 1
+        if nx <= 0 or nx >= BOARD_W-1 or ny <= 0 or ny >= BOARD_H-1 or (nx, ny) in snake:
+            lives -= 1
+            if lives <= 0:
+                state = 'gameover'
+            else:
+                reset_round()
+        else:
+            snake.insert(0, (nx, ny))
+            if food and (nx, ny) == food:
+                score += 1
+                food = place_food()
+                if score >= TARGET_SCORE:
+                    state = 'victory'
+            else:
+                snake.pop()
+    render()
+    root.after(TICK_MS, step)
+
+def start_game():
+    global state, score, lives
+    score = 0
+    lives = LIVES_START
+    reset_round()
+    state = 'play'
+    render()
+
+def on_key(evt):
+    global pending, state
+    k = (evt.keysym or '').lower()
+    if k == 'escape':
+        root.destroy()
+        return
+    if k in ('return', 'kp_enter'):
+        if state in ('title', 'gameover', 'victory'):
+            start_game()
+        elif state == 'pause':
+            state = 'play'
+        return
+    if k == 'p':
+        if state == 'play': state = 'pause'
+        elif state == 'pause': state = 'play'
+        return
+    if state != 'play':
+        return
+    if k in ('w', 'up') and direction != 'down': pending = 'up'
+    elif k in ('s', 'down') and direction != 'up': pending = 'down'
+    elif k in ('a', 'left') and direction != 'right': pending = 'left'
+    elif k in ('d', 'right') and direction != 'left': pending = 'right'
+
+root.bind('<KeyPress>', on_key)
+reset_round()
+render()
+root.after(TICK_MS, step)
+root.mainloop()
@@ -0,0 +1,490 @@
+// Auto-generated by sculpt (web)
+
+const TARGET = {"type":"web-ir","version":1,"state":{},"views":{"InProgress":[{"kind":"text","text":"Ticket moved to In Progress.","color":"green","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"Enter = Back to board","color":"blue","x":null,"y":null,"action":null,"style":null,"css":null}],"Board":[{"kind":"text","text":"Support Ticket Board","color":"yellow","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"1 = Open ticket #4821","color":"white","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"2 = Open ticket #4822","color":"white","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"S = SLA overview","color":"blue","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"Esc = Exit","color":"blue","x":null,"y":null,"action":null,"style":null,"css":null}],"Ticket4821":[{"kind":"text","text":"Ticket #4821","color":"yellow","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"Customer: ACME Retail","color":"white","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"Issue: Checkout timeout","color":"white","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"Priority: High","color":"red","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"A = Mark in progress, R = Return","color":"blue","x":null,"y":null,"action":null,"style":null,"css":null}],"Ticket4822":[{"kind":"text","text":"Ticket #4822","color":"yellow","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"Customer: Nova Health","color":"white","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"Issue: CSV export mismatch","color":"white","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"Priority: Medium","color":"magenta","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"A = Mark in progress, R = Return","color":"blue","x":null,"y":null,"action":null,"style":null,"css":null}],"SLA":[{"kind":"text","text":"SLA Overview","color":"yellow","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"High: 1 overdue","color":"red","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"Medium: 2 due in < 4h","color":"magenta","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"Low: 6 on track","color":"green","x":null,"y":null,"action":null,"style":null,"css":null},{"kind":"text","text":"Enter = Back to board","color":"blue","x":null,"y":null,"action":null,"style":null,"css":null}]},"flow":{"start":"Board","transitions":{"Ticket4822":{"key(esc)":"Exit","key(a)":"InProgress","key(r)":"Board"},"Ticket4821":{"key(esc)":"Exit","key(r)":"Board","key(a)":"InProgress"},"SLA":{"key(enter)":"Board","key(esc)":"Exit"},"Board":{"key()":"Ticket4822","key(esc)":"Exit","key(s)":"SLA"},"InProgress":{"key(esc)":"Exit","key(enter)":"Board"},"Exit":{}}},"window":null,"layout":null,"extensions":{}};
+
+const VIEWS = TARGET.views || {};
+const FLOW = TARGET.flow || { start: '', transitions: {} };
+const STATE = (TARGET.state && typeof TARGET.state === 'object') ? TARGET.state : {};
+const FLOW_PARENTS = FLOW.parents || {};
+const FLOW_INITIAL = FLOW.initial || {};
+const FLOW_REGIONS = FLOW.regions || {};
+const FLOW_TIMERS = FLOW.timers || {};
+const timerHandles = {};
+let exitedStates = [];
+
+function flowAncestors(s) {
+  const out = [];
+  for (let p = FLOW_PARENTS[s]; p && !out.includes(p); p = FLOW_PARENTS[p]) out.push(p);
+  return out;
+}
+
+function enterState(s) {
+  if (FLOW_REGIONS[s]) return FLOW_REGIONS[s].flatMap(enterState);
+  if (FLOW_INITIAL[s]) return enterState(FLOW_INITIAL[s]);
+  return [s];
+}
+
+function enterTarget(dst, top) {
+  let leaves = enterState(dst);
+  for (let c = dst, p = FLOW_PARENTS[dst]; c !== top && p; c = p, p = FLOW_PARENTS[p]) {
+    const regions = FLOW_REGIONS[p] || [];
+    const at = regions.indexOf(c);
+    if (at < 0) continue;
+    leaves = regions.slice(0, at).flatMap(enterState).concat(leaves, regions.slice(at + 1).flatMap(enterState));
+  }
+  return leaves;
+}
+
+function topBelow(s, ancestor) {
+  let top = s;
+  while (FLOW_PARENTS[top] && FLOW_PARENTS[top] !== ancestor) top = FLOW_PARENTS[top];
+  return top;
+}
+
+function transitionLeaves(active, src, dst) {
+  const dstAncestors = flowAncestors(dst);
+  const common = flowAncestors(src).find((a) => dstAncestors.includes(a)) || null;
+  const exited = topBelow(src, common);
+  exitedStates.push(exited);
+  const inside = (leaf) => leaf === exited || flowAncestors(leaf).includes(exited);
+  const at = active.findIndex(inside);
+  const kept = active.filter((leaf) => !inside(leaf));
+  kept.splice(at < 0 ? kept.length : at, 0, ...enterTarget(dst, topBelow(dst, common)));
+  return kept;
+}
+
+function nextActive(active, event, targetOf) {
+  let out = active;
+  const fired = new Set();
+  for (const leaf of active) {
+    if (!out.includes(leaf)) continue;
+    for (const s of [leaf].concat(flowAncestors(leaf))) {
+      if (fired.has(s)) break;
+      const dst = targetOf(s, event);
+      if (!dst) continue;
+      fired.add(s);
+      out = transitionLeaves(out, s, dst);
+      break;
+    }
+  }
+  return out;
+}
+
+function timerTarget(s, event) {
+  const m = /^timer\((.*)#(\d+)\)$/.exec(event);
+  if (!m || m[1] !== s) return null;
+  const timer = (FLOW_TIMERS[s] || [])[Number(m[2])];
+  return timer ? timer.target : null;
+}
+
+function syncTimers(states, fire) {
+  const left = (s) => !states.includes(s) || exitedStates.some((e) => e === s || flowAncestors(s).includes(e));
+  for (const s of Object.keys(timerHandles)) {
+    if (!left(s)) continue;
+    for (const h of timerHandles[s]) clearTimeout(h);
+    delete timerHandles[s];
+  }
+  exitedStates = [];
+  for (const s of states) {
+    if (timerHandles[s] || !FLOW_TIMERS[s]) continue;
+    timerHandles[s] = FLOW_TIMERS[s].map((t, i) => setTimeout(() => {
+      for (const h of timerHandles[s] || []) clearTimeout(h);
+      delete timerHandles[s];
+      fire(`timer(${s}#${i})`);
+    }, Number(t.afterMs) || 0));
+  }
+}
+
+function activeStates(active) {
+  const out = [];
+  for (const leaf of active) {
+    for (const s of flowAncestors(leaf).reverse().concat([leaf])) {
+      if (!out.includes(s)) out.push(s);
+    }
+  }
+  return out;
+}
+
+let active = enterTarget(FLOW.start || '', null);
+let state = active[0] || '';
+let snake = null;
+let tickHandle = null;
+
+const IS_SNAKE = Object.values(VIEWS).some((items) => (items || []).some((it) => it && it.kind === 'text' && String(it.text || '').toUpperCase().includes('SNAKE')));
+
+function placeFood() {
+  const occupied = new Set(snake.body.map((p) => `${p.x},${p.y}`));
+  const free = [];
+  for (let y = 1; y < snake.height - 1; y += 1) {
+    for (let x = 1; x < snake.width - 1; x += 1) {
+      const key = `${x},${y}`;
+      if (!occupied.has(key)) free.push({ x, y });
+    }
+  }
+  if (free.length === 0) return null;
+  return free[Math.floor(Math.random() * free.length)];
+}
+
+function initSnake() {
+  const width = Math.max(18, Number(STATE.boardWidth || 32));
+  const height = Math.max(12, Number(STATE.boardHeight || 20));
+  const cx = Math.floor(width / 2);
+  const cy = Math.floor(height / 2);
+  snake = {
+    width,
+    height,
+    tickMs: Math.max(45, Number(STATE.speedMs || 100)),
+    targetScore: Math.max(6, Number(STATE.targetScore || 12)),
+    lives: Math.max(1, Number(STATE.lives || 3)),
+    score: 0,
+    direction: 'right',
+    pending: 'right',
+    body: [
+      { x: cx, y: cy },
+      { x: cx - 1, y: cy },
+      { x: cx - 2, y: cy }
+    ],
+    food: null
+  };
+  snake.food = placeFood();
+}
+
+function stepSnake() {
+  if (!snake || state !== 'Play') return;
+  snake.direction = snake.pending;
+  const head = snake.body[0];
+  let nx = head.x;
+  let ny = head.y;
+  if (snake.direction === 'up') ny -= 1;
+  if (snake.direction === 'down') ny += 1;
+  if (snake.direction === 'left') nx -= 1;
+  if (snake.direction === 'right') nx += 1;
+  if (nx <= 0 || nx >= snake.width - 1 || ny <= 0 || ny >= snake.height - 1) {
+    snake.lives -= 1;
+    if (snake.lives <= 0) {
+      dispatch('done');
+      return;
+    }
+    initSnake();
+    return;
+  }
+  for (const p of snake.body) {
+    if (p.x === nx && p.y === ny) {
+      snake.lives -= 1;
+      if (snake.lives <= 0) {
+        dispatch('done');
+        return;
+      }
+      initSnake();
+      return;
+    }
+  }
+  snake.body.unshift({ x: nx, y: ny });
+  const ate = snake.food && snake.food.x === nx && snake.food.y === ny;
+  if (ate) {
+    snake.score += 1;
+    snake.food = placeFood();
+  } else {
+    snake.body.pop();
+  }
+  if (snake.score >= snake.targetScore) dispatch('win');
+}
+
+function handleSnakeInput(event) {
+  if (!snake) return;
+  const d = snake.direction;
+  if ((event === 'key(w)' || event === 'key(up)') && d !== 'down') snake.pending = 'up';
+  if ((event === 'key(s)' || event === 'key(down)') && d !== 'up') snake.pending = 'down';
+  if ((event === 'key(a)' || event === 'key(left)') && d !== 'right') snake.pending = 'left';
+  if ((event === 'key(d)' || event === 'key(right)') && d !== 'left') snake.pending = 'right';
+  if (event === 'key(space)') snake.score += 1;
+  if (event === 'key(backspace)') { snake.lives = 0; dispatch('done'); }
+}
+
+function renderSnake(root) {
+  if (!snake) initSnake();
+  root.innerHTML = '';
+  root.className = 'sculpt-app sculpt-snake';
+  const hud = document.createElement('div');
+  hud.style.color = '#00ffff';
+  hud.style.fontFamily = 'monospace';
+  hud.style.marginBottom = '8px';
+  hud.textContent = `Score: ${snake.score}  Lives: ${snake.lives}  Length: ${snake.body.length}`;
+  root.appendChild(hud);
+  const pre = document.createElement('pre');
+  pre.style.fontFamily = 'Menlo,Consolas,monospace';
+  pre.style.fontSize = '14px';
+  pre.style.lineHeight = '1.1';
+  pre.style.color = '#ffffff';
+  pre.style.background = '#0f1115';
+  pre.style.padding = '12px';
+  pre.style.border = '1px solid #00ffff';
+  pre.style.display = 'inline-block';
+  const occ = new Map();
+  snake.body.forEach((p, i) => occ.set(`${p.x},${p.y}`, i === 0 ? 'head' : 'body'));
+  let txt = '';
+  for (let y = 0; y < snake.height; y += 1) {
+    for (let x = 0; x < snake.width; x += 1) {
+      if (x === 0 || x === snake.width - 1 || y === 0 || y === snake.height - 1) {
+        txt += '██';
+      } else if (snake.food && snake.food.x === x && snake.food.y === y) {
+        txt += '◉ ';
+      } else {
+        const v = occ.get(`${x},${y}`);
+        if (v === 'head') txt += '██';
+        else if (v === 'body') txt += '▓▓';
+        else txt += '  ';
+      }
+    }
+    txt += '\n';
+  }
+  pre.textContent = txt;
+  root.appendChild(pre);
+}
+
+function uiColor(name) {
+  const key = String(name || '').toLowerCase();
+  if (!key) return '';
+  const map = {
+    cyan: '#00ffff', blue: '#58b6ff', yellow: '#ffd166', red: '#ea5172',
+    green: '#66f7a8', white: '#eaf5ff', heading: '#00ffff',
+    success: '#66f7a8', warning: '#ffd166', danger: '#ea5172', info: '#58b6ff'
+  };
+  return map[key] || key;
+}
+
+function applyItemStyle(el, item) {
+  if (item.color) {
+    const c = uiColor(item.color);
+    if (c) el.style.color = c;
+  }
+  if (item.css && typeof item.css === 'object') Object.assign(el.style, item.css);
+}
+
+function templateText(parts) {
+  return parts.map((part) => {
+    if (part && typeof part.text === 'string') return part.text;
+    const value = guardValue(part ? part.expr : null);
+    return value === null || value === undefined ? '' : String(value);
+  }).join('');
+}
+
+function itemText(item, fallback) {
+  if (!Array.isArray(item.textTemplate)) return item.text || fallback;
+  return templateText(item.textTemplate);
+}
+
+function buildWidget(item) {
+  const kind = String(item.kind || 'text');
+  if (kind === 'button') {
+    const btn = document.createElement('button');
+    btn.className = 'sculpt-btn';
+    btn.textContent = itemText(item, 'Action');
+    btn.addEventListener('click', () => {
+      const action = item.action ? String(item.action) : '';
+      if (action) dispatch(`input.click(${action})`);
+      else dispatch('input.click');
+    });
+    applyItemStyle(btn, item);
+    return btn;
+  }
+  if (kind === 'input') {
+    const field = document.createElement('input');
+    field.className = 'sculpt-input';
+    field.placeholder = itemText(item, 'Input');
+    field.addEventListener('keydown', (e) => {
+      if (e.key === 'Enter') {
+        const action = item.action ? String(item.action) : '';
+        if (action) dispatch(`input.submit(${action})`);
+        else dispatch('input.submit');
+      }
+    });
+    return field;
+  }
+  if (kind === 'textarea') {
+    const area = document.createElement('textarea');
+    area.className = 'sculpt-input';
+    area.rows = 4;
+    area.placeholder = itemText(item, 'Notes');
+    return area;
+  }
+  const tag = kind === 'heading' ? 'h2' : (kind === 'table' ? 'pre' : 'div');
+  const el = document.createElement(tag);
+  if (kind === 'heading') el.className = 'sculpt-heading';
+  else if (kind === 'metric') el.className = 'sculpt-metric';
+  else if (kind === 'badge' || kind === 'toast' || kind === 'banner') el.className = 'sculpt-badge';
+  else if (kind === 'panel' || kind === 'card' || kind === 'modal') el.className = 'sculpt-card';
+  else if (kind === 'table') el.className = 'sculpt-table';
+  else if (kind === 'tabs') el.className = 'sculpt-tabs';
+  else if (kind === 'list') el.className = 'sculpt-list';
+  else el.className = 'sculpt-text';
+  el.textContent = kind === 'list' ? `• ${itemText(item, '')}` : itemText(item, '');
+  applyItemStyle(el, item);
+  return el;
+}
+
+function applyRender() {
+  const root = document.getElementById('app');
+  if (!root) return;
+  if (IS_SNAKE && state === 'Play') {
+    renderSnake(root);
+    return;
+  }
+  root.className = 'sculpt-app';
+  root.innerHTML = '';
+  const shell = document.createElement('div');
+  shell.className = 'sculpt-shell';
+  const title = document.createElement('div');
+  title.className = 'sculpt-titlebar';
+  title.textContent = `SCULPT WEB • ${state}`;
+  shell.appendChild(title);
+  const body = document.createElement('div');
+  body.className = 'sculpt-body';
+  const items = activeStates(active).flatMap((s) => VIEWS[s] || []);
+  for (const item of items) body.appendChild(buildWidget(item));
+  shell.appendChild(body);
+  root.appendChild(shell);
+}
+
+function startTicker() {
+  if (!IS_SNAKE || tickHandle) return;
+  const ms = snake ? snake.tickMs : Math.max(50, Number(STATE.speedMs || 100));
+  tickHandle = setInterval(() => {
+    if (state !== 'Play') return;
+    dispatch('input.tick');
+    stepSnake();
+    applyRender();
+  }, ms);
+}
+
+function stopTicker() {
+  if (!tickHandle) return;
+  clearInterval(tickHandle);
+  tickHandle = null;
+}
+
+function exprNumber(v) {
+  return Number(v || 0);
+}
+
+function valuesEqual(l, r) {
+  if (typeof l === 'number' || typeof r === 'number') return exprNumber(l) === exprNumber(r);
+  if (l === null || l === undefined || r === null || r === undefined) {
+    return (l === null || l === undefined) && (r === null || r === undefined);
+  }
+  return String(l) === String(r);
+}
+
+function addValues(l, r) {
+  if (typeof l === 'string' || typeof r === 'string') return String(l ?? '') + String(r ?? '');
+  return exprNumber(l) + exprNumber(r);
+}
+
+function binaryOp(op, l, r) {
+  if (op === 'Add') return addValues(l, r);
+  if (op === 'Sub') return exprNumber(l) - exprNumber(r);
+  if (op === 'Mul') return exprNumber(l) * exprNumber(r);
+  if (op === 'Div') return exprNumber(r) === 0 ? 0 : exprNumber(l) / exprNumber(r);
+  if (op === 'Mod') return exprNumber(r) === 0 ? 0 : exprNumber(l) % exprNumber(r);
+  if (op === 'And') return l && r ? 1 : 0;
+  if (op === 'Or') return l || r ? 1 : 0;
+  if (op === 'Eq') return valuesEqual(l, r) ? 1 : 0;
+  if (op === 'Neq') return valuesEqual(l, r) ? 0 : 1;
+  if (op === 'Gt') return exprNumber(l) > exprNumber(r) ? 1 : 0;
+  if (op === 'Gte') return exprNumber(l) >= exprNumber(r) ? 1 : 0;
+  if (op === 'Lt') return exprNumber(l) < exprNumber(r) ? 1 : 0;
+  if (op === 'Lte') return exprNumber(l) <= exprNumber(r) ? 1 : 0;
+  return null;
+}
+
+function unaryOp(op, v) {
+  if (op === 'Neg') return -exprNumber(v);
+  if (op === 'Not') return v ? 0 : 1;
+  return null;
+}
+
+function compareWhen(op, l, r) {
+  if (op === 'gte') return exprNumber(l) >= exprNumber(r);
+  if (op === 'gt') return exprNumber(l) > exprNumber(r);
+  if (op === 'lt') return exprNumber(l) < exprNumber(r);
+  if (op === 'eq') return valuesEqual(l, r);
+  if (op === 'neq') return !valuesEqual(l, r);
+  return false;
+}
+
+function guardValue(v) {
+  if (v && typeof v === 'object' && !Array.isArray(v)) {
+    if (typeof v.ident === 'string') return STATE[v.ident];
+    if (Array.isArray(v.template)) return templateText(v.template);
+    if (Array.isArray(v.list)) return v.list.map(guardValue);
+    if (v.record && typeof v.record === 'object') {
+      const out = {};
+      for (const [k, item] of Object.entries(v.record)) out[k] = guardValue(item);
+      return out;
+    }
+    if (v.unary) return unaryOp(v.unary.op, guardValue(v.unary.operand));
+    if (v.binary) return binaryOp(v.binary.op, guardValue(v.binary.left), guardValue(v.binary.right));
+    return null;
+  }
+  return v;
+}
+
+function evalGuard(when) {
+  if (!when || typeof when !== 'object') return false;
+  if (when.kind === 'logic') return when.op === 'and' ? evalGuard(when.left) && evalGuard(when.right) : evalGuard(when.left) || evalGuard(when.right);
+  if (when.kind === 'not') return !evalGuard(when.operand);
+  if (when.kind === 'expr') return !!guardValue(when.expr);
+  return compareWhen(when.op, STATE[String(when.left)], guardValue(when.right));
+}
+
+function transitionTarget(s, event) {
+  if (event.startsWith('timer(')) return timerTarget(s, event);
+  const guarded = (FLOW.guards && FLOW.guards[s] && FLOW.guards[s][event]) || [];
+  const hit = guarded.find((g) => g && evalGuard(g.when));
+  if (hit) return hit.target;
+  const map = (FLOW.transitions && FLOW.transitions[s]) || {};
+  return map[event] || null;
+}
+
+function dispatch(event) {
+  const before = state;
+  const beforeActive = active.join('\n');
+  if (IS_SNAKE && event.startsWith('key(')) handleSnakeInput(event);
+  active = nextActive(active, event, transitionTarget);
+  state = active[0] || '';
+  syncTimers(activeStates(active), dispatch);
+  if (active.join('\n') !== beforeActive) {
+    if (IS_SNAKE) {
+      if (state === 'Play') {
+        if (!snake || before !== 'Play') initSnake();
+        startTicker();
+      } else {
+        stopTicker();
+      }
+    }
+    applyRender();
+  }
+}
+
+function normalizeKey(key) {
+  const k = String(key || '');
+  if (k === 'Enter') return 'enter';
+  if (k === 'Escape') return 'esc';
+  if (k === 'ArrowUp') return 'up';
+  if (k === 'ArrowDown') return 'down';
+  if (k === 'ArrowLeft') return 'left';
+  if (k === 'ArrowRight') return 'right';
+  if (k === ' ') return 'space';
+  if (k === 'Backspace') return 'backspace';
+  return k.trim().toLowerCase();
+}
+
+window.addEventListener('DOMContentLoaded', () => {
+  applyRender();
+  if (IS_SNAKE && state === 'Play') startTicker();
+  syncTimers(activeStates(active), dispatch);
+  window.addEventListener('keydown', (e) => {
+    const key = normalizeKey(e.key);
+    dispatch(`key(${key})`);
+  });
+});
@@ -53,8 +53,8 @@ if ! git diff --name-only "${BASE_REF}".."${HEAD_REF}" | grep -q '^Cargo.toml$';
   exit 1
 fi
 
-BASE_VERSION="$(git show "${BASE_REF}:Cargo.toml" 2>/dev/null | sed -n 's/^version = \"\\([^\"]*\\)\"/\\1/p' | head -n1)"
-HEAD_VERSION="$(sed -n 's/^version = \"\\([^\"]*\\)\"/\\1/p' Cargo.toml | head -n1)"
+BASE_VERSION="$(git show "${BASE_REF}:Cargo.toml" 2>/dev/null | sed -n 's/^version = "\([^"]*\)"/\1/p' | head -n1)"
+HEAD_VERSION="$(sed -n 's/^version = "\([^"]*\)"/\1/p' Cargo.toml | head -n1)"
 
 if [[ -z "${BASE_VERSION}" || -z "${HEAD_VERSION}" ]]; then
   echo "ERROR: could not parse version from Cargo.toml." >&2
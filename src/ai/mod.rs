@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::time::{Duration, Instant};
 
 use anyhow::{bail, Result};
@@ -82,7 +83,7 @@ pub fn generate_target_ir(
                 &mut value,
                 &target_spec.standard_ir,
                 sculpt_ir,
-            );
+            )?;
             Ok((value, None))
         }
     }
@@ -161,7 +162,7 @@ fn openai_generate(
 
     let parsed = parse_json_response(&text)?;
     let mut normalized = normalize_llm_ir(&target_spec.standard_ir, &parsed);
-    patch_target_ir_with_deterministic_parts(&mut normalized, &target_spec.standard_ir, sculpt_ir);
+    patch_target_ir_with_deterministic_parts(&mut normalized, &target_spec.standard_ir, sculpt_ir)?;
     Ok((
         normalized,
         DebugCapture {
@@ -247,7 +248,7 @@ fn anthropic_generate(
     }
     let parsed = parse_json_response(&text)?;
     let mut normalized = normalize_llm_ir(&target_spec.standard_ir, &parsed);
-    patch_target_ir_with_deterministic_parts(&mut normalized, &target_spec.standard_ir, sculpt_ir);
+    patch_target_ir_with_deterministic_parts(&mut normalized, &target_spec.standard_ir, sculpt_ir)?;
     Ok((
         normalized,
         DebugCapture {
@@ -315,7 +316,7 @@ fn gemini_generate(
     }
     let parsed = parse_json_response(&text)?;
     let mut normalized = normalize_llm_ir(&target_spec.standard_ir, &parsed);
-    patch_target_ir_with_deterministic_parts(&mut normalized, &target_spec.standard_ir, sculpt_ir);
+    patch_target_ir_with_deterministic_parts(&mut normalized, &target_spec.standard_ir, sculpt_ir)?;
     Ok((
         normalized,
         DebugCapture {
@@ -503,41 +504,115 @@ fn patch_target_ir_with_deterministic_parts(
     target: &mut Value,
     standard_ir: &str,
     sculpt_ir: &Value,
-) {
+) -> Result<()> {
     if standard_ir != "cli-ir" && standard_ir != "gui-ir" && standard_ir != "web-ir" {
-        return;
+        return Ok(());
     }
     let Some(root) = target.as_object_mut() else {
-        return;
+        return Ok(());
     };
     let Some(flows) = sculpt_ir.get("flows").and_then(Value::as_array) else {
-        return;
+        return Ok(());
     };
     let Some(flow) = flows.first() else {
-        return;
+        return Ok(());
     };
     let start = flow
         .get("start")
         .and_then(Value::as_str)
         .unwrap_or("Title")
         .to_string();
-    let Some(states) = flow.get("states").and_then(Value::as_array) else {
-        return;
-    };
+    if flow.get("states").and_then(Value::as_array).is_none() {
+        return Ok(());
+    }
+    let mut lowered = LoweredFlows::default();
+    lower_flow(flow, &ModuleScope::entry(), &mut lowered)?;
+    // Flows of linked modules join the state map under their full names
+    // (`Billing.Rules.Main.Idle`), so the entry's states keep theirs.
+    for module in linked_modules(sculpt_ir) {
+        let scope = ModuleScope::linked(module);
+        for flow in module
+            .get("flows")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            lower_flow(flow, &scope, &mut lowered)?;
+        }
+    }
+    let LoweredFlows {
+        transitions,
+        guards,
+        timers,
+        views,
+        hierarchy,
+    } = lowered;
+
+    let mut flow_json = json!({
+      "start": start,
+      "transitions": transitions
+    });
+    if !guards.is_empty() {
+        flow_json["guards"] = Value::Object(guards);
+    }
+    if !timers.is_empty() {
+        flow_json["timers"] = Value::Object(timers);
+    }
+    for (key, map) in [
+        ("parents", hierarchy.parents),
+        ("initial", hierarchy.initial),
+        ("regions", hierarchy.regions),
+    ] {
+        if !map.is_empty() {
+            flow_json[key] = Value::Object(map);
+        }
+    }
+    root.insert("flow".to_string(), flow_json);
+    root.insert("views".to_string(), Value::Object(views));
+
+    let state = build_runtime_state(sculpt_ir);
+    if !state.values.is_empty() {
+        root.insert("state".to_string(), Value::Object(state.values));
+    }
+    if !state.computed.is_empty() {
+        insert_extension(root, "stateInit", Value::Array(state.computed));
+    }
+    inject_runtime_rules(root, sculpt_ir);
+    Ok(())
+}
+
+/// Target IR flow parts, keyed by state node name.
+#[derive(Default)]
+struct LoweredFlows {
+    transitions: serde_json::Map<String, Value>,
+    guards: serde_json::Map<String, Value>,
+    timers: serde_json::Map<String, Value>,
+    views: serde_json::Map<String, Value>,
+    hierarchy: FlowHierarchy,
+}
 
-    let mut transitions = serde_json::Map::new();
-    let mut views = serde_json::Map::new();
+/// Lowers the states of `flow` into `out`, naming them and their variables as `scope` says.
+fn lower_flow(flow: &Value, scope: &ModuleScope, out: &mut LoweredFlows) -> Result<()> {
+    let Some(top_states) = flow.get("states").and_then(Value::as_array) else {
+        return Ok(());
+    };
+    let prefix = scope.state_prefix(flow.get("name").and_then(Value::as_str).unwrap_or_default());
+    let mut states = Vec::new();
+    flatten_states(top_states, None, &prefix, &mut states, &mut out.hierarchy);
 
     for state in states {
         let Some(name) = state.get("name").and_then(Value::as_str) else {
             continue;
         };
+        let name = format!("{prefix}{name}");
         let statements = state
             .get("statements")
             .and_then(Value::as_array)
             .cloned()
             .unwrap_or_default();
         let mut event_map = serde_json::Map::new();
+        let mut guard_map = serde_json::Map::new();
+        let mut timer_list = Vec::new();
         let mut render_items = Vec::new();
 
         for stmt in statements {
@@ -554,10 +629,40 @@ fn patch_target_ir_with_deterministic_parts(
                         event_obj.get("args").and_then(Value::as_array),
                     );
                     if !ev.is_empty() {
-                        event_map.insert(ev, Value::String(dst.to_string()));
+                        match on.get("guard") {
+                            Some(guard) => {
+                                // Dropping the guard would make the transition unconditional.
+                                let Some(when) = extract_when_condition(guard) else {
+                                    bail!(
+                                        "Guard on '{}' in state '{}' cannot be lowered for the target runtime",
+                                        ev,
+                                        name
+                                    );
+                                };
+                                let mut when = when;
+                                scope.qualify(&mut when);
+                                let entry = guard_map.entry(ev).or_insert_with(|| json!([]));
+                                if let Some(list) = entry.as_array_mut() {
+                                    list.push(
+                                        json!({ "when": when, "target": format!("{prefix}{dst}") }),
+                                    );
+                                }
+                            }
+                            None => {
+                                event_map.insert(ev, Value::String(format!("{prefix}{dst}")));
+                            }
+                        }
                     }
                 }
             }
+            if let Some(after) = stmt.get("After").and_then(Value::as_object) {
+                let delay = after.get("delay_ms").and_then(Value::as_u64);
+                let dst = after.get("target").and_then(Value::as_str);
+                if let (Some(delay), Some(dst)) = (delay, dst) {
+                    timer_list
+                        .push(json!({ "afterMs": delay, "target": format!("{prefix}{dst}") }));
+                }
+            }
             if let Some(expr) = stmt.get("Expr").and_then(Value::as_object) {
                 let expr_name = expr.get("name").and_then(Value::as_str).unwrap_or_default();
                 if expr_name == "render" {
@@ -583,30 +688,205 @@ fn patch_target_ir_with_deterministic_parts(
             }
         }
 
-        transitions.insert(name.to_string(), Value::Object(event_map));
+        if !guard_map.is_empty() {
+            out.guards.insert(name.clone(), Value::Object(guard_map));
+        }
+        if !timer_list.is_empty() {
+            out.timers.insert(name.clone(), Value::Array(timer_list));
+        }
         if !render_items.is_empty() {
-            views.insert(name.to_string(), Value::Array(render_items));
+            let mut items = Value::Array(render_items);
+            scope.qualify(&mut items);
+            out.views.insert(name.clone(), items);
         }
+        out.transitions.insert(name, Value::Object(event_map));
     }
 
-    root.insert(
-        "flow".to_string(),
-        json!({
-          "start": start,
-          "transitions": transitions
-        }),
-    );
-    root.insert("views".to_string(), Value::Object(views));
+    Ok(())
+}
 
-    if let Some(state_obj) = build_runtime_state(sculpt_ir) {
-        root.insert("state".to_string(), Value::Object(state_obj));
+/// How the names of one module appear in the target IR. The entry module keeps its own;
+/// a linked module's variables, rules and states carry its full name, as references from
+/// other modules do.
+struct ModuleScope {
+    prefix: String,
+    /// Variables the module declares or assigns.
+    locals: HashSet<String>,
+}
+
+impl ModuleScope {
+    fn entry() -> Self {
+        Self {
+            prefix: String::new(),
+            locals: HashSet::new(),
+        }
     }
-    inject_runtime_rules(root, sculpt_ir);
+
+    fn linked(module: &Value) -> Self {
+        let name = module
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let global = module.get("global_state").and_then(Value::as_array);
+        let rule_body = module
+            .get("rules")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|rule| rule.get("body").and_then(Value::as_array))
+            .flatten();
+        let locals = global
+            .into_iter()
+            .flatten()
+            .chain(rule_body)
+            .filter_map(|stmt| stmt.pointer("/Assign/target").and_then(Value::as_str))
+            .map(str::to_string)
+            .collect();
+        Self {
+            prefix: format!("{}.", name),
+            locals,
+        }
+    }
+
+    fn name(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    /// Prefix of the state nodes of `flow`: none for the entry, `Module.Flow.` otherwise.
+    fn state_prefix(&self, flow: &str) -> String {
+        if self.prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{}{}.", self.prefix, flow)
+        }
+    }
+
+    fn variable(&self, name: &str) -> String {
+        if self.locals.contains(name) {
+            self.name(name)
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// Qualifies the module's variables in a lowered expression, `when` condition or view.
+    fn qualify(&self, value: &mut Value) {
+        match value {
+            Value::Object(obj) => {
+                let is_cmp = obj.get("kind").and_then(Value::as_str) == Some("cmp");
+                for (key, field) in obj.iter_mut() {
+                    match field {
+                        Value::String(name) if key == "ident" || (is_cmp && key == "left") => {
+                            *name = self.variable(name);
+                        }
+                        _ => self.qualify(field),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.qualify(item)),
+            _ => {}
+        }
+    }
+}
+
+/// The modules linked into a project program, dependencies first.
+fn linked_modules(sculpt_ir: &Value) -> impl Iterator<Item = &Value> {
+    sculpt_ir
+        .get("linked")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+/// Statechart structure of a flow, in target IR form.
+#[derive(Default)]
+struct FlowHierarchy {
+    parents: serde_json::Map<String, Value>,
+    initial: serde_json::Map<String, Value>,
+    regions: serde_json::Map<String, Value>,
 }
 
-fn build_runtime_state(sculpt_ir: &Value) -> Option<serde_json::Map<String, Value>> {
-    let mut state_obj = serde_json::Map::new();
-    let global = sculpt_ir.get("global_state").and_then(Value::as_array)?;
+/// Flattens nested and region states into `out`, parents first. Each region becomes a node
+/// named `State/Region` between the parallel state and the region's states. Node names in
+/// `hierarchy` start with `prefix`.
+fn flatten_states<'a>(
+    states: &'a [Value],
+    parent: Option<&str>,
+    prefix: &str,
+    out: &mut Vec<&'a Value>,
+    hierarchy: &mut FlowHierarchy,
+) {
+    for state in states {
+        out.push(state);
+        let Some(name) = state.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        let name = format!("{prefix}{name}");
+        if let Some(parent) = parent {
+            hierarchy
+                .parents
+                .insert(name.clone(), Value::String(parent.to_string()));
+        }
+        if let Some(start) = state.get("start").and_then(Value::as_str) {
+            hierarchy
+                .initial
+                .insert(name.clone(), Value::String(format!("{prefix}{start}")));
+        }
+        if let Some(children) = state.get("states").and_then(Value::as_array) {
+            flatten_states(children, Some(&name), prefix, out, hierarchy);
+        }
+        let mut nodes = Vec::new();
+        let regions = state.get("regions").and_then(Value::as_array);
+        for region in regions.into_iter().flatten() {
+            let Some(region_name) = region.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            let node = format!("{}/{}", name, region_name);
+            hierarchy
+                .parents
+                .insert(node.clone(), Value::String(name.clone()));
+            if let Some(start) = region.get("start").and_then(Value::as_str) {
+                hierarchy
+                    .initial
+                    .insert(node.clone(), Value::String(format!("{prefix}{start}")));
+            }
+            if let Some(children) = region.get("states").and_then(Value::as_array) {
+                flatten_states(children, Some(&node), prefix, out, hierarchy);
+            }
+            nodes.push(Value::String(node));
+        }
+        if !nodes.is_empty() {
+            hierarchy.regions.insert(name, Value::Array(nodes));
+        }
+    }
+}
+
+/// Initial runtime state. Literal initializers are stored as plain JSON; computed ones
+/// (`x = -5`, `s = "Hi ${name}"`) start as `null` and are listed in `computed`, which the
+/// runtimes evaluate in declaration order at startup.
+#[derive(Default)]
+struct RuntimeState {
+    values: serde_json::Map<String, Value>,
+    computed: Vec<Value>,
+}
+
+fn build_runtime_state(sculpt_ir: &Value) -> RuntimeState {
+    let mut state = RuntimeState::default();
+    let Some(global) = sculpt_ir.get("global_state").and_then(Value::as_array) else {
+        return state;
+    };
+    insert_initial_state(&mut state, global, &ModuleScope::entry());
+    // Linked project modules are referenced by their full name (`Billing.Rules.limit`).
+    for module in linked_modules(sculpt_ir) {
+        let Some(global) = module.get("global_state").and_then(Value::as_array) else {
+            continue;
+        };
+        insert_initial_state(&mut state, global, &ModuleScope::linked(module));
+    }
+    state
+}
+
+fn insert_initial_state(state: &mut RuntimeState, global: &[Value], scope: &ModuleScope) {
     for stmt in global {
         let Some(assign) = stmt.get("Assign").and_then(Value::as_object) else {
             continue;
@@ -616,24 +896,99 @@ fn build_runtime_state(sculpt_ir: &Value) -> Option<serde_json::Map<String, Valu
         };
         let op = assign.get("op").and_then(Value::as_str).unwrap_or("Set");
         let value = assign.get("value");
-        if op == "Set" {
-            if let Some(v) = extract_runtime_expr(value) {
-                state_obj.insert(target.to_string(), v);
-            }
+        if op != "Set" {
+            continue;
+        }
+        let Some(mut v) = extract_runtime_expr(value) else {
+            continue;
+        };
+        let target = scope.name(target);
+        if is_constant_expr(&v) {
+            state.values.insert(target, plain_literal(v));
+        } else {
+            scope.qualify(&mut v);
+            state.values.insert(target.clone(), Value::Null);
+            state.computed.push(json!({ "target": target, "value": v }));
         }
     }
-    if state_obj.is_empty() {
-        None
-    } else {
-        Some(state_obj)
+}
+
+/// Whether a lowered expression is a literal, or a list or record of literals.
+fn is_constant_expr(value: &Value) -> bool {
+    match value {
+        Value::Object(obj) if obj.len() == 1 => match obj.iter().next() {
+            Some((key, Value::Array(items))) if key == "list" => items.iter().all(is_constant_expr),
+            Some((key, Value::Object(fields))) if key == "record" => {
+                fields.values().all(is_constant_expr)
+            }
+            _ => false,
+        },
+        Value::Object(_) | Value::Array(_) => false,
+        _ => true,
     }
 }
 
+/// Initial state is read as plain JSON, so list and record literals are unwrapped there.
+fn plain_literal(value: Value) -> Value {
+    let Value::Object(mut obj) = value else {
+        return value;
+    };
+    if obj.len() == 1 {
+        match obj.remove("list") {
+            Some(Value::Array(items)) => {
+                return Value::Array(items.into_iter().map(plain_literal).collect())
+            }
+            Some(other) => {
+                obj.insert("list".to_string(), other);
+            }
+            None => {}
+        }
+        match obj.remove("record") {
+            Some(Value::Object(fields)) => {
+                return Value::Object(
+                    fields
+                        .into_iter()
+                        .map(|(key, value)| (key, plain_literal(value)))
+                        .collect(),
+                )
+            }
+            Some(other) => {
+                obj.insert("record".to_string(), other);
+            }
+            None => {}
+        }
+    }
+    Value::Object(obj)
+}
+
 fn inject_runtime_rules(root: &mut serde_json::Map<String, Value>, sculpt_ir: &Value) {
-    let Some(rules) = sculpt_ir.get("rules").and_then(Value::as_array) else {
+    let mut runtime_rules = Vec::new();
+    lower_runtime_rules(sculpt_ir, &ModuleScope::entry(), &mut runtime_rules);
+    for module in linked_modules(sculpt_ir) {
+        lower_runtime_rules(module, &ModuleScope::linked(module), &mut runtime_rules);
+    }
+
+    if runtime_rules.is_empty() {
+        return;
+    }
+
+    insert_extension(root, "runtimeRules", Value::Array(runtime_rules));
+}
+
+fn insert_extension(root: &mut serde_json::Map<String, Value>, key: &str, value: Value) {
+    let extensions = root
+        .entry("extensions".to_string())
+        .or_insert_with(|| Value::Object(serde_json::Map::new()));
+    if let Some(ext_obj) = extensions.as_object_mut() {
+        ext_obj.insert(key.to_string(), value);
+    }
+}
+
+/// Lowers the rules of `module` (the entry or a linked module) into `out`.
+fn lower_runtime_rules(module: &Value, scope: &ModuleScope, out: &mut Vec<Value>) {
+    let Some(rules) = module.get("rules").and_then(Value::as_array) else {
         return;
     };
-    let mut runtime_rules = Vec::new();
     for rule in rules {
         let Some(rule_obj) = rule.as_object() else {
             continue;
@@ -646,18 +1001,35 @@ fn inject_runtime_rules(root: &mut serde_json::Map<String, Value>, sculpt_ir: &V
             .and_then(Value::as_object)
             .map(normalize_event_name_from_call)
             .unwrap_or_default();
-        let when = trigger.get("When").and_then(extract_when_condition);
+        let mut when = trigger.get("When").and_then(extract_when_condition);
+        if let Some(when) = &mut when {
+            scope.qualify(when);
+        }
+        let every_ms = trigger.get("Every").and_then(Value::as_u64);
+        let name = scope.name(
+            rule_obj
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or("rule"),
+        );
+        // A periodic rule reacts to its own timer event, which the runtime dispatches
+        // every `everyMs` milliseconds.
+        let event = match every_ms {
+            Some(_) => format!("every({})", name),
+            None => event,
+        };
         if event.is_empty() && when.is_none() {
             continue;
         }
-        let scope_flow = rule_obj
-            .get("scope_flow")
-            .and_then(Value::as_str)
-            .map(|s| s.to_string());
+        let scope_flow = rule_obj.get("scope_flow").and_then(Value::as_str);
         let scope_state = rule_obj
             .get("scope_state")
             .and_then(Value::as_str)
-            .map(|s| s.to_string());
+            .map(|state| match scope_flow {
+                Some(flow) => format!("{}{}", scope.state_prefix(flow), state),
+                None => state.to_string(),
+            });
+        let scope_flow = scope_flow.map(|flow| scope.name(flow));
         let mut emits = Vec::<Value>::new();
         let mut assigns = Vec::<Value>::new();
         if let Some(body) = rule_obj.get("body").and_then(Value::as_array) {
@@ -669,9 +1041,10 @@ fn inject_runtime_rules(root: &mut serde_json::Map<String, Value>, sculpt_ir: &V
                 } else if let Some(assign) = stmt.get("Assign").and_then(Value::as_object) {
                     if let Some(target) = assign.get("target").and_then(Value::as_str) {
                         let op = assign.get("op").and_then(Value::as_str).unwrap_or("Set");
-                        if let Some(value) = extract_runtime_expr(assign.get("value")) {
+                        if let Some(mut value) = extract_runtime_expr(assign.get("value")) {
+                            scope.qualify(&mut value);
                             assigns.push(json!({
-                              "target": target,
+                              "target": scope.variable(target),
                               "op": if op == "Add" { "add" } else { "set" },
                               "value": value
                             }));
@@ -680,28 +1053,20 @@ fn inject_runtime_rules(root: &mut serde_json::Map<String, Value>, sculpt_ir: &V
                 }
             }
         }
-        runtime_rules.push(json!({
-          "name": rule_obj.get("name").and_then(Value::as_str).unwrap_or("rule"),
+        let mut runtime_rule = json!({
+          "name": name,
           "scopeFlow": scope_flow,
           "scopeState": scope_state,
           "on": if event.is_empty() { Value::Null } else { Value::String(event) },
           "when": when,
           "emit": emits,
           "assign": assigns
-        }));
-    }
-
-    if runtime_rules.is_empty() {
-        return;
+        });
+        if let Some(every_ms) = every_ms {
+            runtime_rule["everyMs"] = json!(every_ms);
+        }
+        out.push(runtime_rule);
     }
-
-    let extensions = root
-        .entry("extensions".to_string())
-        .or_insert_with(|| Value::Object(serde_json::Map::new()));
-    let Some(ext_obj) = extensions.as_object_mut() else {
-        return;
-    };
-    ext_obj.insert("runtimeRules".to_string(), Value::Array(runtime_rules));
 }
 
 fn normalize_event_name_from_call(call: &serde_json::Map<String, Value>) -> String {
@@ -738,6 +1103,16 @@ fn normalize_event_name_from_call(call: &serde_json::Map<String, Value>) -> Stri
 
 fn extract_when_condition(value: &Value) -> Option<Value> {
     let obj = value.as_object()?;
+    if let Some(unary) = obj.get("Unary").and_then(Value::as_object) {
+        if unary.get("op").and_then(Value::as_str) != Some("Not") {
+            return None;
+        }
+        let operand = extract_when_condition(unary.get("operand")?)?;
+        return Some(json!({
+          "kind": "not",
+          "operand": operand
+        }));
+    }
     let binary = obj.get("Binary")?.as_object()?;
     let op = binary.get("op").and_then(Value::as_str)?;
     match op {
@@ -752,11 +1127,18 @@ fn extract_when_condition(value: &Value) -> Option<Value> {
             }))
         }
         "Gte" | "Gt" | "Lt" | "Eq" | "Neq" => {
-            let left_ident = binary
+            let Some(left_ident) = binary
                 .get("left")
                 .and_then(Value::as_object)
                 .and_then(|v| v.get("Ident"))
-                .and_then(Value::as_str)?;
+                .and_then(Value::as_str)
+            else {
+                // Computed left operand: the runtime evaluates the whole comparison.
+                return Some(json!({
+                  "kind": "expr",
+                  "expr": extract_runtime_expr(Some(value))?
+                }));
+            };
             let right = extract_runtime_expr(binary.get("right"))?;
             let cmp = match op {
                 "Gte" => "gte",
@@ -782,10 +1164,34 @@ fn extract_runtime_expr(value: Option<&Value>) -> Option<Value> {
     if let Some(n) = v.get("Number").and_then(Value::as_f64) {
         return Some(json!(n));
     }
+    if let Some(ms) = v.get("Duration").and_then(Value::as_u64) {
+        return Some(json!(ms));
+    }
     if let Some(s) = v.get("String").and_then(Value::as_str) {
         return Some(Value::String(s.to_string()));
     }
-    if v.get("Null").is_some() {
+    if let Some(b) = v.get("Bool").and_then(Value::as_bool) {
+        return Some(Value::Bool(b));
+    }
+    if let Some(parts) = v.get("Interpolated").and_then(Value::as_array) {
+        return Some(json!({ "template": runtime_template_parts(parts)? }));
+    }
+    if let Some(items) = v.get("List").and_then(Value::as_array) {
+        let items = items
+            .iter()
+            .map(|item| extract_runtime_expr(Some(item)))
+            .collect::<Option<Vec<_>>>()?;
+        return Some(json!({ "list": items }));
+    }
+    if let Some(fields) = v.get("Record").and_then(Value::as_array) {
+        let mut record = serde_json::Map::new();
+        for field in fields {
+            let key = field.get("key").and_then(Value::as_str)?;
+            record.insert(key.to_string(), extract_runtime_expr(field.get("value"))?);
+        }
+        return Some(json!({ "record": record }));
+    }
+    if v.get("Null").is_some() || v.as_str() == Some("Null") {
         return Some(Value::Null);
     }
     if let Some(id) = v.get("Ident").and_then(Value::as_str) {
@@ -840,6 +1246,20 @@ fn extract_runtime_expr(value: Option<&Value>) -> Option<Value> {
           }
         }));
     }
+    if let Some(unary) = v.get("Unary").and_then(Value::as_object) {
+        let op = unary
+            .get("op")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let operand = extract_runtime_expr(unary.get("operand"))?;
+        return Some(json!({
+          "unary": {
+            "op": op,
+            "operand": operand
+          }
+        }));
+    }
     None
 }
 
@@ -869,6 +1289,21 @@ fn normalize_event_name(name: &str, args: Option<&Vec<Value>>) -> String {
     name.to_string()
 }
 
+/// Lowers `Interpolated` string parts to `{text}` / `{expr}` entries the runtimes join at
+/// render time.
+fn runtime_template_parts(parts: &[Value]) -> Option<Vec<Value>> {
+    parts
+        .iter()
+        .map(|part| {
+            if let Some(text) = part.get("Text").and_then(Value::as_str) {
+                Some(json!({ "text": text }))
+            } else {
+                Some(json!({ "expr": extract_runtime_expr(part.get("Expr"))? }))
+            }
+        })
+        .collect()
+}
+
 fn render_item_from_call(
     call: &serde_json::Map<String, Value>,
 ) -> Option<serde_json::Map<String, Value>> {
@@ -918,6 +1353,17 @@ fn render_item_from_call(
             if idx == 0 {
                 if let Some(s) = extract_scalar_string(val) {
                     item.insert("text".to_string(), Value::String(s));
+                } else if let Some(parts) = val
+                    .and_then(|v| v.get("Interpolated"))
+                    .and_then(Value::as_array)
+                    .and_then(|parts| runtime_template_parts(parts))
+                {
+                    let text: String = parts
+                        .iter()
+                        .filter_map(|part| part.get("text").and_then(Value::as_str))
+                        .collect();
+                    item.insert("text".to_string(), Value::String(text));
+                    item.insert("textTemplate".to_string(), Value::Array(parts));
                 }
             }
             if name == Some("color") {
@@ -1012,4 +1458,301 @@ mod tests {
         assert_eq!(usage.output_tokens, Some(6));
         assert_eq!(usage.total_tokens, Some(11));
     }
+
+    #[test]
+    fn runtime_state_carries_bool_list_and_record_literals() {
+        let src = r#"module(Billing):
+  state():
+    valid = true
+    columns = ["invoice_id", "customer_id"]
+    limits = { max: 3, strict: false }
+  end
+end
+"#;
+        let module = crate::parser::parse_source(src).expect("parse");
+        let ir = serde_json::to_value(crate::ir::from_ast(module)).expect("ir");
+        let state = build_runtime_state(&ir).values;
+        assert_eq!(state["valid"], json!(true));
+        assert_eq!(state["columns"], json!(["invoice_id", "customer_id"]));
+        assert_eq!(state["limits"], json!({ "max": 3.0, "strict": false }));
+    }
+
+    #[test]
+    fn runtime_state_defers_computed_initializers_to_the_runtime() {
+        let src = r#"module(Billing):
+  state():
+    limit = -5
+    greeting = "Hi ${limit}"
+    tags = ["a", limit]
+  end
+end
+"#;
+        let module = crate::parser::parse_source(src).expect("parse");
+        let ir = serde_json::to_value(crate::ir::from_ast(module)).expect("ir");
+        let state = build_runtime_state(&ir);
+        assert_eq!(state.values["limit"], Value::Null);
+        let targets: Vec<&str> = state
+            .computed
+            .iter()
+            .filter_map(|init| init["target"].as_str())
+            .collect();
+        assert_eq!(targets, ["limit", "greeting", "tags"]);
+        assert!(state.computed[0]["value"]["unary"].is_object());
+    }
+
+    #[test]
+    fn views_carry_interpolated_text_templates() {
+        let src = r#"module(Billing):
+  flow(Main):
+    start > Show
+    state(Show):
+      ui.text("Invoice ${selectedInvoice} (${count + 1})", color: "yellow")
+      terminate
+    end
+  end
+end
+"#;
+        let module = crate::parser::parse_source(src).expect("parse");
+        let ir = serde_json::to_value(crate::ir::from_ast(module)).expect("ir");
+        let mut target = json!({});
+        patch_target_ir_with_deterministic_parts(&mut target, "cli-ir", &ir).expect("lower");
+        let item = &target["views"]["Show"][0];
+        assert_eq!(item["text"], json!("Invoice  ()"));
+        assert_eq!(
+            item["textTemplate"],
+            json!([
+              { "text": "Invoice " },
+              { "expr": { "ident": "selectedInvoice" } },
+              { "text": " (" },
+              { "expr": { "binary": { "op": "Add", "left": { "ident": "count" }, "right": 1.0 } } },
+              { "text": ")" }
+            ])
+        );
+        let typed: crate::target_ir::RenderItem =
+            serde_json::from_value(item.clone()).expect("render item");
+        let back = serde_json::to_value(&typed).expect("json");
+        assert_eq!(back["textTemplate"], item["textTemplate"]);
+    }
+
+    #[test]
+    fn guarded_transitions_lower_into_flow_guards() {
+        let src = r#"module(Billing):
+  flow(Main):
+    start > List
+    state(List):
+      on input.key(Enter) when selectedInvoice != null > Detail
+      on input.key(Esc) > List
+    end
+    state(Detail):
+      terminate
+    end
+  end
+end
+"#;
+        let module = crate::parser::parse_source(src).expect("parse");
+        let ir = serde_json::to_value(crate::ir::from_ast(module)).expect("ir");
+        let mut target = json!({});
+        patch_target_ir_with_deterministic_parts(&mut target, "cli-ir", &ir).expect("lower");
+        assert_eq!(
+            target["flow"]["transitions"]["List"],
+            json!({ "key(esc)": "List" })
+        );
+        assert_eq!(
+            target["flow"]["guards"]["List"]["key(enter)"],
+            json!([{
+              "when": { "kind": "cmp", "op": "neq", "left": "selectedInvoice", "right": null },
+              "target": "Detail"
+            }])
+        );
+        assert!(target["flow"]["guards"].get("Detail").is_none());
+    }
+
+    #[test]
+    fn guards_that_cannot_be_lowered_fail_the_build() {
+        let src = r#"module(Billing):
+  flow(Main):
+    start > List
+    state(List):
+      on input.key(Enter) when count + 1 > Detail
+    end
+    state(Detail):
+      terminate
+    end
+  end
+end
+"#;
+        let module = crate::parser::parse_source(src).expect("parse");
+        let ir = serde_json::to_value(crate::ir::from_ast(module)).expect("ir");
+        let mut target = json!({});
+        let err = patch_target_ir_with_deterministic_parts(&mut target, "cli-ir", &ir)
+            .expect_err("guard must not be dropped");
+        assert!(err.to_string().contains("'key(enter)'"), "{err}");
+    }
+
+    #[test]
+    fn nested_and_parallel_states_lower_into_flow_hierarchy() {
+        let src = r#"module(Arcade):
+  flow(Game):
+    start > Play
+    state(Play):
+      on input.key(Esc) > Exit
+      region(Main):
+        start > Running
+        state(Running):
+          on input.key(P) > Paused
+        end
+        state(Paused):
+          on input.key(P) > Running
+        end
+      end
+      region(Hud):
+        start > Clock
+        state(Clock):
+          on input.tick > Clock
+        end
+      end
+    end
+    state(Exit):
+      terminate
+    end
+  end
+end
+"#;
+        let module = crate::parser::parse_source(src).expect("parse");
+        let ir = serde_json::to_value(crate::ir::from_ast(module)).expect("ir");
+        let mut target = json!({});
+        patch_target_ir_with_deterministic_parts(&mut target, "cli-ir", &ir).expect("lower");
+        let flow = &target["flow"];
+        assert_eq!(flow["transitions"]["Play"], json!({ "key(esc)": "Exit" }));
+        assert_eq!(
+            flow["transitions"]["Paused"],
+            json!({ "key(p)": "Running" })
+        );
+        assert_eq!(flow["parents"]["Running"], json!("Play/Main"));
+        assert_eq!(flow["parents"]["Clock"], json!("Play/Hud"));
+        assert_eq!(flow["parents"]["Play/Hud"], json!("Play"));
+        assert!(flow["parents"].get("Play").is_none());
+        assert_eq!(flow["initial"]["Play/Main"], json!("Running"));
+        assert_eq!(flow["regions"]["Play"], json!(["Play/Main", "Play/Hud"]));
+
+        let parsed = crate::target_ir::from_json_value(json!({
+          "type": "cli-ir",
+          "version": 1,
+          "views": {},
+          "flow": flow
+        }))
+        .expect("target ir");
+        assert_eq!(parsed.flow.regions["Play"], vec!["Play/Main", "Play/Hud"]);
+        assert_eq!(parsed.flow.initial["Play/Hud"], "Clock");
+    }
+
+    #[test]
+    fn timers_lower_into_flow_timers_and_periodic_rules() {
+        let src = r#"module(Kiosk):
+  flow(Main):
+    start > Splash
+    state(Splash):
+      after(1500ms) > Menu
+      on input.key(Enter) > Menu
+    end
+    state(Menu):
+      after(2m) > Splash
+      rule(blink):
+        every(500ms):
+          visible = 1
+        end
+      end
+    end
+  end
+end
+"#;
+        let module = crate::parser::parse_source(src).expect("parse");
+        let ir = serde_json::to_value(crate::ir::from_ast(module)).expect("ir");
+        let mut target = json!({});
+        patch_target_ir_with_deterministic_parts(&mut target, "cli-ir", &ir).expect("lower");
+        let flow = &target["flow"];
+        assert_eq!(
+            flow["timers"]["Splash"],
+            json!([{ "afterMs": 1500, "target": "Menu" }])
+        );
+        assert_eq!(
+            flow["timers"]["Menu"],
+            json!([{ "afterMs": 120000, "target": "Splash" }])
+        );
+        assert_eq!(
+            flow["transitions"]["Splash"],
+            json!({ "key(enter)": "Menu" })
+        );
+        let rules = target["extensions"]["runtimeRules"]
+            .as_array()
+            .expect("runtime rules");
+        let blink = rules
+            .iter()
+            .find(|rule| rule["name"] == "blink")
+            .expect("blink rule");
+        assert_eq!(blink["on"], json!("every(blink)"));
+        assert_eq!(blink["everyMs"], json!(500));
+        assert_eq!(blink["scopeState"], json!("Menu"));
+
+        let parsed = crate::target_ir::from_json_value(json!({
+          "type": "cli-ir",
+          "version": 1,
+          "views": {},
+          "flow": flow
+        }))
+        .expect("target ir");
+        assert_eq!(parsed.flow.timers["Splash"][0].after_ms, 1500);
+    }
+
+    #[test]
+    fn runtime_rules_carry_arithmetic_and_not() {
+        let src = r#"module(Billing):
+  state():
+    amount = 10
+    paid = 4
+  end
+  rule(settle):
+    when not paid >= amount and (amount - paid) * 2 > 5:
+      amount = -amount % 3
+    end
+  end
+end
+"#;
+        let module = crate::parser::parse_source(src).expect("parse");
+        let ir = serde_json::to_value(crate::ir::from_ast(module)).expect("ir");
+        let mut root = serde_json::Map::new();
+        inject_runtime_rules(&mut root, &ir);
+        let rule = &root["extensions"]["runtimeRules"][0];
+        assert_eq!(
+            rule["when"],
+            json!({
+              "kind": "logic",
+              "op": "and",
+              "left": {
+                "kind": "not",
+                "operand": { "kind": "cmp", "op": "gte", "left": "paid", "right": { "ident": "amount" } }
+              },
+              "right": {
+                "kind": "expr",
+                "expr": { "binary": {
+                  "op": "Gt",
+                  "left": { "binary": {
+                    "op": "Mul",
+                    "left": { "binary": { "op": "Sub", "left": { "ident": "amount" }, "right": { "ident": "paid" } } },
+                    "right": 2.0
+                  } },
+                  "right": 5.0
+                } }
+              }
+            })
+        );
+        assert_eq!(
+            rule["assign"][0]["value"],
+            json!({ "binary": {
+              "op": "Mod",
+              "left": { "unary": { "op": "Neg", "operand": { "ident": "amount" } } },
+              "right": 3.0
+            } })
+        );
+    }
 }
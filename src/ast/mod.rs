@@ -1,5 +1,161 @@
+use std::fmt;
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 
+/// A parsed source text. Every span produced by one parse shares the same `Arc`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SourceFile {
+    pub path: String,
+    pub text: String,
+}
+
+impl SourceFile {
+    pub fn new(path: impl Into<String>, text: impl Into<String>) -> Arc<Self> {
+        Arc::new(Self {
+            path: path.into(),
+            text: text.into(),
+        })
+    }
+
+    /// Returns the full text of the 1-based `line`, without its line terminator.
+    pub fn line_text(&self, line: usize) -> Option<&str> {
+        self.text
+            .split('\n')
+            .nth(line.checked_sub(1)?)
+            .map(|l| l.strip_suffix('\r').unwrap_or(l))
+    }
+}
+
+/// Byte range of a node in its source file, plus the 1-based line/column of its first byte.
+///
+/// Spans are positional metadata only: they are skipped during serialization so that
+/// `ir.json` and the IR hash do not change when code merely moves around.
+#[derive(Clone, Default)]
+pub struct Span {
+    pub source: Option<Arc<SourceFile>>,
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Span {
+    pub fn new(source: Arc<SourceFile>, start: usize, end: usize, line: usize, col: usize) -> Self {
+        Self {
+            source: Some(source),
+            start,
+            end,
+            line,
+            col,
+        }
+    }
+
+    /// True for spans of nodes that were not produced by the parser.
+    pub fn is_dummy(&self) -> bool {
+        self.source.is_none()
+    }
+
+    pub fn path(&self) -> &str {
+        self.source.as_ref().map(|s| s.path.as_str()).unwrap_or("")
+    }
+
+    /// The source text covered by this span.
+    pub fn text(&self) -> &str {
+        self.source
+            .as_ref()
+            .and_then(|s| s.text.get(self.start..self.end))
+            .unwrap_or("")
+    }
+
+    /// Smallest span covering both `self` and `other` (which must share a source).
+    pub fn to(&self, other: &Span) -> Span {
+        if self.is_dummy() {
+            return other.clone();
+        }
+        if other.is_dummy() {
+            return self.clone();
+        }
+        let first = if self.start <= other.start {
+            self
+        } else {
+            other
+        };
+        Span {
+            source: self.source.clone(),
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+            line: first.line,
+            col: first.col,
+        }
+    }
+
+    /// The first occurrence of `word` in this span as a whole name (a dotted name counts as
+    /// one word), e.g. the target inside the span of a transition.
+    pub fn find_word(&self, word: &str) -> Option<Span> {
+        self.word_spans(word).next()
+    }
+
+    /// Like `find_word`, for the last occurrence.
+    pub fn rfind_word(&self, word: &str) -> Option<Span> {
+        self.word_spans(word).last()
+    }
+
+    fn word_spans<'a>(&'a self, word: &'a str) -> impl Iterator<Item = Span> + 'a {
+        let text = self.text();
+        let is_name = |c: char| c.is_alphanumeric() || c == '_' || c == '.';
+        text.match_indices(word)
+            .filter(move |(at, _)| {
+                !word.is_empty()
+                    && !text[..*at].ends_with(is_name)
+                    && !text[at + word.len()..].starts_with(is_name)
+            })
+            .map(move |(at, _)| self.sub_span(at, at + word.len()))
+    }
+
+    /// The part of this span between the byte offsets `from` and `to` of its text.
+    fn sub_span(&self, from: usize, to: usize) -> Span {
+        let before = &self.text()[..from];
+        let (line, col) = match before.rsplit_once('\n') {
+            Some((head, last)) => (
+                self.line + head.matches('\n').count() + 1,
+                last.chars().count() + 1,
+            ),
+            None => (self.line, self.col + before.chars().count()),
+        };
+        Span {
+            source: self.source.clone(),
+            start: self.start + from,
+            end: self.start + to,
+            line,
+            col,
+        }
+    }
+}
+
+impl fmt::Debug for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_dummy() {
+            return write!(f, "Span(<none>)");
+        }
+        write!(
+            f,
+            "Span({}:{}:{} @{}..{})",
+            self.path(),
+            self.line,
+            self.col,
+            self.start,
+            self.end
+        )
+    }
+}
+
+impl PartialEq for Span {
+    fn eq(&self, other: &Self) -> bool {
+        self.path() == other.path() && self.start == other.start && self.end == other.end
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Module {
     pub name: String,
@@ -8,19 +164,40 @@ pub struct Module {
     pub uses: Vec<UseDecl>,
     #[serde(default)]
     pub imports: Vec<ImportDecl>,
+    /// `exports(...)` lists; symbols not listed are private to the module.
+    #[serde(default)]
+    pub exports: Vec<ExportDecl>,
     pub items: Vec<Item>,
+    #[serde(skip)]
+    pub span: Span,
+    /// Span of each `key=value` pair in the `@meta` headers, keyed like `meta`.
+    #[serde(skip)]
+    pub meta_spans: std::collections::HashMap<String, Span>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UseDecl {
     pub path: String,
     pub alias: Option<String>,
+    #[serde(skip)]
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportDecl {
     pub path: String,
     pub alias: Option<String>,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+/// `exports(total, Main, approvalLimit)`: the flows, rules, state variables, defines and nd
+/// blocks that importing modules may reference.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportDecl {
+    pub names: Vec<String>,
+    #[serde(skip)]
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,35 +212,279 @@ pub enum Item {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Flow {
     pub name: String,
+    /// Typed parameters: `flow(Confirm, message: string)`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<FlowParam>,
+    /// Result type: `flow(Confirm) -> bool`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub returns: Option<TypeName>,
     pub start: Option<String>,
     pub states: Vec<StateBlock>,
+    #[serde(default, skip_serializing_if = "Annotations::is_empty")]
+    pub annotations: Annotations,
+    #[serde(skip)]
+    pub span: Span,
+    /// Span of the `start > State` statement, if present.
+    #[serde(skip)]
+    pub start_span: Option<Span>,
+}
+
+/// `##` doc comments and `@name(...)` attributes written above a flow, state, rule or nd
+/// block.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Annotations {
+    /// The doc comment lines without their `##` marker, joined with newlines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attrs: Vec<Attribute>,
+}
+
+impl Annotations {
+    pub fn is_empty(&self) -> bool {
+        self.doc.is_none() && self.attrs.is_empty()
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&Attribute> {
+        self.attrs.iter().find(|attr| attr.name == name)
+    }
+
+    /// `Some(message)` for `@deprecated("message")`, `Some("")` for a bare `@deprecated`.
+    pub fn deprecated(&self) -> Option<String> {
+        let attr = self.attr("deprecated")?;
+        Some(attr.text_arg().unwrap_or_default())
+    }
+
+    /// The rule priority from `@priority(n)`, `0` when absent or invalid.
+    pub fn priority(&self) -> i64 {
+        self.attr("priority")
+            .and_then(Attribute::int_arg)
+            .unwrap_or(0)
+    }
 }
 
+/// An item attribute: `@critical`, `@owner(billing)`, `@deprecated("use Checkout")`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attribute {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<CallArg>,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+impl Attribute {
+    /// The first argument as text, for names and strings alike.
+    pub fn text_arg(&self) -> Option<String> {
+        match &self.args.first()?.value {
+            Expr::String(text) | Expr::Ident(text) => Some(text.clone()),
+            _ => None,
+        }
+    }
+
+    /// The only argument as a whole number, negative numbers included.
+    pub fn int_arg(&self) -> Option<i64> {
+        let [arg] = self.args.as_slice() else {
+            return None;
+        };
+        let value = match (&arg.name, &arg.value) {
+            (None, Expr::Number(n)) => *n,
+            (
+                None,
+                Expr::Unary {
+                    op: UnaryOp::Neg,
+                    operand,
+                },
+            ) => match operand.as_ref() {
+                Expr::Number(n) => -n,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        (value.fract() == 0.0 && value.abs() <= i64::MAX as f64).then_some(value as i64)
+    }
+}
+
+/// A flow parameter. Parameters are state variables that `run` sets before the flow starts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowParam {
+    pub name: String,
+    pub ty: TypeName,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+impl Flow {
+    /// Every state of the flow, nested and region states included, in source order.
+    pub fn all_states(&self) -> Vec<&StateBlock> {
+        walk_states(&self.states)
+    }
+}
+
+/// A state block. Inside flows a state may nest child states (entered through its own
+/// `start > Child`) or split into orthogonal `region`s that are active at the same time.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StateBlock {
     pub name: Option<String>,
     pub statements: Vec<StateStmt>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub states: Vec<StateBlock>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub regions: Vec<Region>,
+    #[serde(default, skip_serializing_if = "Annotations::is_empty")]
+    pub annotations: Annotations,
+    #[serde(skip)]
+    pub span: Span,
+    /// Span of the nested `start > State` statement, if present.
+    #[serde(skip)]
+    pub start_span: Option<Span>,
+}
+
+impl StateBlock {
+    /// Nested states and the states of all regions, one level deep.
+    pub fn children(&self) -> impl Iterator<Item = &StateBlock> {
+        self.states
+            .iter()
+            .chain(self.regions.iter().flat_map(|region| region.states.iter()))
+    }
+}
+
+/// `region(Name):` inside a parallel state: an independent sub-flow with its own start.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Region {
+    pub name: String,
+    pub start: Option<String>,
+    pub states: Vec<StateBlock>,
+    #[serde(skip)]
+    pub span: Span,
+    #[serde(skip)]
+    pub start_span: Option<Span>,
+}
+
+/// The canonical spelling of a duration: the largest unit that divides it evenly.
+pub fn duration_text(ms: u64) -> String {
+    match ms {
+        0 => "0ms".to_string(),
+        ms if ms % 3_600_000 == 0 => format!("{}h", ms / 3_600_000),
+        ms if ms % 60_000 == 0 => format!("{}m", ms / 60_000),
+        ms if ms % 1_000 == 0 => format!("{}s", ms / 1_000),
+        ms => format!("{}ms", ms),
+    }
+}
+
+/// Flattens a state tree depth-first, parents before their children.
+pub fn walk_states(states: &[StateBlock]) -> Vec<&StateBlock> {
+    fn visit<'a>(state: &'a StateBlock, out: &mut Vec<&'a StateBlock>) {
+        out.push(state);
+        for child in state.children() {
+            visit(child, out);
+        }
+    }
+    let mut out = Vec::new();
+    for state in states {
+        visit(state, &mut out);
+    }
+    out
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum StateStmt {
+    /// `on event > Target`, optionally guarded: `on event when cond > Target`.
     On {
         event: Call,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        guard: Option<Expr>,
         target: String,
+        #[serde(skip)]
+        span: Span,
     },
     Rule(Rule),
+    /// `after(2s) > Target`: leaves the state once it has been active for `delay_ms`.
+    After {
+        delay_ms: u64,
+        target: String,
+        #[serde(skip)]
+        span: Span,
+    },
+    /// `run Flow`, `run Flow(args)` or `run Flow(args) -> result`.
     Run {
         flow: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        args: Vec<CallArg>,
+        /// State variable that receives the sub-flow's `return` value.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        result: Option<String>,
+        #[serde(skip)]
+        span: Span,
     },
     Terminate,
+    /// `return` or `return value`: finishes a flow started by `run`.
+    Return {
+        value: Option<Expr>,
+        #[serde(skip)]
+        span: Span,
+    },
+    /// `target = value`, `target += value`, or a typed declaration `target: ty = value`.
+    /// A declaration without a value (`target: ty`) carries `Expr::Null`.
     Assign {
         target: String,
         op: AssignOp,
         value: Expr,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ty: Option<TypeName>,
+        #[serde(skip)]
+        span: Span,
     },
     Expr(Call),
 }
 
+/// A type annotation on a state variable.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TypeName {
+    Int,
+    Float,
+    String,
+    Bool,
+    Record,
+    /// `list<T>`; a bare `list` is `list<any>`.
+    List(Box<TypeName>),
+    Any,
+}
+
+impl TypeName {
+    /// Parses the scalar spelling of a type (`int`, `string`, ...); `list<T>` is assembled by
+    /// the parser.
+    pub fn from_keyword(name: &str) -> Option<Self> {
+        match name {
+            "int" => Some(Self::Int),
+            "float" => Some(Self::Float),
+            "string" => Some(Self::String),
+            "bool" => Some(Self::Bool),
+            "record" => Some(Self::Record),
+            "list" => Some(Self::List(Box::new(Self::Any))),
+            "any" => Some(Self::Any),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TypeName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int => f.write_str("int"),
+            Self::Float => f.write_str("float"),
+            Self::String => f.write_str("string"),
+            Self::Bool => f.write_str("bool"),
+            Self::Record => f.write_str("record"),
+            Self::List(item) if **item == Self::Any => f.write_str("list"),
+            Self::List(item) => write!(f, "list<{item}>"),
+            Self::Any => f.write_str("any"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AssignOp {
     Set,
@@ -80,12 +501,18 @@ pub struct Rule {
     pub scope_state: Option<String>,
     pub trigger: RuleTrigger,
     pub body: Vec<RuleStmt>,
+    #[serde(default, skip_serializing_if = "Annotations::is_empty")]
+    pub annotations: Annotations,
+    #[serde(skip)]
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RuleTrigger {
     On(Call),
     When(Expr),
+    /// `every(100ms)`: fires periodically, in milliseconds.
+    Every(u64),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -94,9 +521,13 @@ pub enum RuleStmt {
         target: String,
         op: AssignOp,
         value: Expr,
+        #[serde(skip)]
+        span: Span,
     },
     Emit {
         event: String,
+        #[serde(skip)]
+        span: Span,
     },
 }
 
@@ -108,6 +539,10 @@ pub struct NdBlock {
     pub defines: Vec<SoftDefine>,
     pub propose: Call,
     pub constraints: Vec<Call>,
+    #[serde(default, skip_serializing_if = "Annotations::is_empty")]
+    pub annotations: Annotations,
+    #[serde(skip)]
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,20 +551,46 @@ pub struct SoftDefine {
     #[serde(default)]
     pub params: Vec<String>,
     pub template: String,
+    #[serde(skip)]
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Expr {
     Number(f64),
+    /// A duration literal (`400ms`, `2s`), in milliseconds.
+    Duration(u64),
     String(String),
+    /// A string literal with `${...}` parts.
+    Interpolated(Vec<StringPart>),
+    Bool(bool),
     Null,
     Ident(String),
     Call(Call),
+    List(Vec<Expr>),
+    Record(Vec<RecordField>),
     Binary {
         left: Box<Expr>,
         op: BinaryOp,
         right: Box<Expr>,
     },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum StringPart {
+    Text(String),
+    Expr(Expr),
+}
+
+/// One `key: value` entry of a `{ ... }` record literal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordField {
+    pub key: String,
+    pub value: Expr,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -141,16 +602,56 @@ pub enum BinaryOp {
     Neq,
     And,
     Or,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum UnaryOp {
+    Neg,
+    Not,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Call {
     pub name: String,
     pub args: Vec<CallArg>,
+    #[serde(skip)]
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CallArg {
     pub name: Option<String>,
     pub value: Expr,
+    #[serde(skip)]
+    pub span: Span,
+}
+
+impl StateStmt {
+    /// Source span of the statement (the whole rule for inline rules, the call for
+    /// command statements). `terminate` carries no span of its own.
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            StateStmt::On { span, .. }
+            | StateStmt::After { span, .. }
+            | StateStmt::Run { span, .. }
+            | StateStmt::Return { span, .. }
+            | StateStmt::Assign { span, .. } => Some(span),
+            StateStmt::Rule(rule) => Some(&rule.span),
+            StateStmt::Expr(call) => Some(&call.span),
+            StateStmt::Terminate => None,
+        }
+    }
+}
+
+impl RuleStmt {
+    pub fn span(&self) -> &Span {
+        match self {
+            RuleStmt::Assign { span, .. } | RuleStmt::Emit { span, .. } => span,
+        }
+    }
 }
@@ -19,20 +19,29 @@ use sha2::{Digest, Sha256};
 use crate::ai::{generate_target_ir, AiProvider, DebugCapture, TargetSpec};
 use crate::build_meta::{dist_dir_for_input, now_unix_ms, write_build_meta, BuildMeta, TokenUsage};
 use crate::contracts::{
-    contract_signature_for_symbol, parse_target_contract, validate_module_against_contract,
+    check_module_against_contract, contract_signature_for_symbol, parse_target_contract,
+    validate_module_against_contract,
 };
 use crate::convergence::{ConvergenceControls, FallbackMode};
+use crate::diagnostics::{
+    apply_safe_suggestions, diagnostics_to_json, diagnostics_to_sarif, format_diagnostics,
+    has_errors, Diagnostic, DiagnosticCode, DiagnosticLevel, DiagnosticsError,
+};
 use crate::freeze::{create_lock, read_lock, verify_lock, write_lock};
+use crate::graph::build_graph;
+use crate::ir::ids::{assign_ids, read_id_map, sidecar_path, write_id_map, IdMap};
 use crate::ir::{from_ast, to_pretty_json, IrModule};
-use crate::parser::parse_source;
+use crate::linker::link_program;
+use crate::parser::{parse_source_recovering_with_path, parse_source_with_path, SyntaxErrors};
+use crate::refactor::{rename_symbol, SymbolKind};
 use crate::report::generate_report;
-use crate::semantics::{format_diagnostics, has_errors, validate_module_with_imports};
+use crate::semantics::validate_module_with_imports;
 use crate::target_ir::{from_json_value, TargetIr};
 use crate::targets::{
     describe_target, emit_cli, emit_gui, emit_web, list_targets, resolve_target, run_cli,
     run_external_target, run_gui, run_web, TargetKind,
 };
-use crate::versioning::LANGUAGE_DEFAULT;
+use crate::versioning::{LANGUAGE_DEFAULT, LANGUAGE_SUPPORT_RANGE};
 use serde_json::Value;
 
 #[derive(Parser)]
@@ -84,6 +93,8 @@ pub enum Command {
         strict_provider: bool,
         #[arg(long, value_name = "level", num_args = 0..=1, default_missing_value = "compact", value_parser = ["compact", "raw", "all", "json"])]
         debug: Option<String>,
+        #[arg(long = "diagnostics-format", value_parser = ["text", "json", "sarif"], default_value = "text", help = "Also write diagnostics to dist/<script>/diagnostics.json or .sarif")]
+        diagnostics_format: String,
     },
     Freeze {
         input: PathBuf,
@@ -99,17 +110,68 @@ pub enum Command {
         target: Option<String>,
         #[arg(long, value_name = "level", num_args = 0..=1, default_missing_value = "compact", value_parser = ["compact", "raw", "all", "json"])]
         debug: Option<String>,
+        #[arg(long = "diagnostics-format", value_parser = ["text", "json", "sarif"], default_value = "text", help = "Also write diagnostics to dist/<script>/diagnostics.json or .sarif")]
+        diagnostics_format: String,
     },
     Replay {
         input: PathBuf,
         #[arg(long)]
         target: Option<String>,
+        #[arg(long = "diagnostics-format", value_parser = ["text", "json", "sarif"], default_value = "text", help = "Also write diagnostics to dist/<script>/diagnostics.json or .sarif")]
+        diagnostics_format: String,
     },
     Run {
         input: PathBuf,
         #[arg(long)]
         target: Option<String>,
     },
+    Explain {
+        #[arg(help = "Diagnostic code, e.g. F105")]
+        code: Option<String>,
+    },
+    Fmt {
+        #[arg(required = true, help = "Files or directories (searched for *.sculpt)")]
+        inputs: Vec<PathBuf>,
+        #[arg(long, help = "List unformatted files and exit non-zero; write nothing")]
+        check: bool,
+    },
+    Graph {
+        input: PathBuf,
+        #[arg(long, value_parser = ["json", "dot", "mermaid"], default_value = "json")]
+        format: String,
+        #[arg(
+            long,
+            help = "Only this flow, state or rule (e.g. Main.Idle) and its neighbours"
+        )]
+        focus: Option<String>,
+        #[arg(
+            long,
+            help = "Target whose contract names packages and capabilities (default: @meta target)"
+        )]
+        target: Option<String>,
+    },
+    Rename {
+        input: PathBuf,
+        #[arg(help = "Flow, Flow.State, rule, Flow.State.rule, variable, nd or define name")]
+        from: String,
+        to: String,
+        #[arg(
+            long,
+            help = "Symbol kind if the name is ambiguous: flow|state|rule|global|nd|define"
+        )]
+        kind: Option<String>,
+    },
+    Fix {
+        #[arg(required = true, help = "Files or directories (searched for *.sculpt)")]
+        inputs: Vec<PathBuf>,
+        #[arg(
+            long,
+            help = "Target whose contract is checked (default: @meta target)"
+        )]
+        target: Option<String>,
+        #[arg(long, help = "List safe fixes and exit non-zero; write nothing")]
+        check: bool,
+    },
     Clean {
         input: Option<PathBuf>,
         #[arg(long)]
@@ -272,7 +334,9 @@ struct SculptProjectFile {
 #[derive(Debug, Clone)]
 struct ProjectContext {
     entry_module: String,
-    modules: HashMap<String, (PathBuf, crate::ast::Module)>,
+    modules: HashMap<String, crate::ast::Module>,
+    /// Syntax warnings of the module sources.
+    warnings: Vec<Diagnostic>,
 }
 
 pub fn run() -> Result<()> {
@@ -343,15 +407,18 @@ pub fn run() -> Result<()> {
             model,
             strict_provider,
             debug,
-        } => build(
-            &input,
-            target.as_deref(),
-            nd_policy,
-            provider,
-            model,
-            strict_provider,
-            debug,
-        ),
+            diagnostics_format,
+        } => with_diagnostics_format(&input, nd_policy.as_deref(), &diagnostics_format, || {
+            build(
+                &input,
+                target.as_deref(),
+                nd_policy.clone(),
+                provider,
+                model,
+                strict_provider,
+                debug,
+            )
+        }),
         Command::Freeze {
             input,
             nd_policy,
@@ -360,17 +427,45 @@ pub fn run() -> Result<()> {
             strict_provider,
             target,
             debug,
-        } => freeze(
-            &input,
-            nd_policy,
-            provider,
-            model,
-            strict_provider,
-            target.as_deref(),
-            debug,
-        ),
-        Command::Replay { input, target } => replay(&input, target.as_deref()),
+            diagnostics_format,
+        } => with_diagnostics_format(&input, nd_policy.as_deref(), &diagnostics_format, || {
+            freeze(
+                &input,
+                nd_policy.clone(),
+                provider,
+                model,
+                strict_provider,
+                target.as_deref(),
+                debug,
+            )
+        }),
+        Command::Replay {
+            input,
+            target,
+            diagnostics_format,
+        } => with_diagnostics_format(&input, None, &diagnostics_format, || {
+            replay(&input, target.as_deref())
+        }),
         Command::Run { input, target } => run_cmd(&input, target.as_deref()),
+        Command::Explain { code } => explain_cmd(code.as_deref()),
+        Command::Fmt { inputs, check } => fmt_cmd(&inputs, check),
+        Command::Graph {
+            input,
+            format,
+            focus,
+            target,
+        } => graph_cmd(&input, &format, focus.as_deref(), target.as_deref()),
+        Command::Rename {
+            input,
+            from,
+            to,
+            kind,
+        } => rename_cmd(&input, &from, &to, kind.as_deref()),
+        Command::Fix {
+            inputs,
+            target,
+            check,
+        } => fix_cmd(&inputs, target.as_deref(), check),
         Command::Clean {
             input,
             all,
@@ -1258,8 +1353,8 @@ fn project_create(name: &str, path: Option<&Path>, files: Option<&[String]>) ->
     for file in &module_files {
         let source = fs::read_to_string(file)
             .with_context(|| format!("Failed to read {}", file.display()))?;
-        let module =
-            parse_source(&source).with_context(|| format!("Failed to parse {}", file.display()))?;
+        let module = parse_source_with_path(&source, file)
+            .with_context(|| format!("Failed to parse {}", file.display()))?;
         if entry_module.is_none() {
             entry_module = Some(module.name.clone());
         }
@@ -1613,6 +1708,7 @@ fn freeze(
         &provider_info.model,
     )?;
     write_lock(Path::new("sculpt.lock"), &lock)?;
+    write_id_sidecars(&ir)?;
 
     let dist_dir = dist_dir(input);
     fs::create_dir_all(&dist_dir)?;
@@ -1771,7 +1867,14 @@ fn emit_debug(
 ) {
     let llm_ms = capture.map(|c| c.llm_ms).unwrap_or(0);
     let view_count = target_ir.views.len();
-    let transition_count: usize = target_ir.flow.transitions.values().map(|m| m.len()).sum();
+    let transition_count: usize = target_ir.flow.transitions.values().map(|m| m.len()).sum::<usize>()
+        + target_ir
+            .flow
+            .guards
+            .values()
+            .flat_map(|m| m.values())
+            .map(Vec::len)
+            .sum::<usize>();
 
     let mut out = serde_json::json!({
       "provider": provider.name,
@@ -1954,9 +2057,10 @@ fn validate_required_output_contract(
         .get("runtimeRules")
         .and_then(|v| v.as_array())
     else {
-        bail!(
-            "C910: required_outputs configured but target IR has no extensions.runtimeRules for writer validation"
-        );
+        bail!(DiagnosticsError::new(vec![Diagnostic::new(
+            "C910",
+            "required_outputs configured but target IR has no extensions.runtimeRules for writer validation"
+        )]));
     };
 
     let state_strings = target_ir
@@ -2000,21 +2104,26 @@ fn validate_required_output_contract(
         } else if req_norm.ends_with(".csv") {
             "writeCsv"
         } else {
-            bail!(
-                "C910: required_outputs entry '{}' has unsupported extension (expected .json or .csv)",
-                required
-            );
+            bail!(DiagnosticsError::new(vec![Diagnostic::new(
+                "C910",
+                format!(
+                    "required_outputs entry '{}' has unsupported extension (expected .json or .csv)",
+                    required
+                )
+            )]));
         };
 
         let matched = writer_paths
             .iter()
             .any(|(writer, p)| writer == needed_writer && path_like_match(p, &req_norm));
         if !matched {
-            bail!(
-                "C911: required output '{}' is not backed by deterministic '{}' call in runtime rules",
-                required,
-                needed_writer
-            );
+            bail!(DiagnosticsError::new(vec![Diagnostic::new(
+                "C911",
+                format!(
+                    "required output '{}' is not backed by deterministic '{}' call in runtime rules",
+                    required, needed_writer
+                )
+            )]));
         }
     }
 
@@ -2202,6 +2311,230 @@ struct CleanRetention {
     max_size_mb: Option<u64>,
 }
 
+fn explain_cmd(code: Option<&str>) -> Result<()> {
+    let Some(code) = code else {
+        for entry in DiagnosticCode::all() {
+            println!(
+                "{:<6} {:<8} {}",
+                entry.code,
+                entry.level_name(),
+                entry.summary
+            );
+        }
+        return Ok(());
+    };
+    let Some(entry) = DiagnosticCode::lookup(code) else {
+        bail!(
+            "Unknown diagnostic code '{}' (run `sculpt explain` to list all codes)",
+            code
+        );
+    };
+    print!("{}", entry.explain());
+    Ok(())
+}
+
+fn fmt_cmd(inputs: &[PathBuf], check: bool) -> Result<()> {
+    let mut files = Vec::new();
+    for input in inputs {
+        collect_sculpt_files(input, &mut files)?;
+    }
+    let mut unformatted = Vec::new();
+    let mut failed = 0;
+    for file in &files {
+        let src = fs::read_to_string(file).with_context(|| format!("Failed to read {:?}", file))?;
+        let formatted = match crate::formatter::format_source(&src, file) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                eprintln!("{}", err);
+                failed += 1;
+                continue;
+            }
+        };
+        if formatted == src {
+            continue;
+        }
+        if check {
+            println!("Would reformat {}", file.display());
+        } else {
+            fs::write(file, &formatted).with_context(|| format!("Failed to write {:?}", file))?;
+            println!("Formatted {}", file.display());
+        }
+        unformatted.push(file);
+    }
+    if failed > 0 {
+        bail!("{} file(s) could not be parsed", failed);
+    }
+    if check && !unformatted.is_empty() {
+        bail!("{} file(s) are not formatted", unformatted.len());
+    }
+    Ok(())
+}
+
+fn graph_cmd(input: &Path, format: &str, focus: Option<&str>, target: Option<&str>) -> Result<()> {
+    let (ir, _) = load_ir_with_warnings(input, None)?;
+    let target = target
+        .map(str::to_string)
+        .or_else(|| ir.meta.get("target").cloned());
+    let contract = match target {
+        Some(target) => Some(parse_target_contract(&describe_target(&target)?)?),
+        None => None,
+    };
+    let mut graph = build_graph(&ir, contract.as_ref());
+    if let Some(focus) = focus {
+        let Some(node) = graph.resolve(focus, &ir.name) else {
+            bail!("No flow, state, rule or nd block named '{}'", focus);
+        };
+        graph = graph.focus(&node.id.clone());
+    }
+    match format {
+        "dot" => print!("{}", graph.to_dot()),
+        "mermaid" => print!("{}", graph.to_mermaid()),
+        _ => println!("{}", serde_json::to_string_pretty(&graph)?),
+    }
+    Ok(())
+}
+
+fn rename_cmd(input: &Path, from: &str, to: &str, kind: Option<&str>) -> Result<()> {
+    let kind = match kind {
+        Some(name) => Some(SymbolKind::parse(name).with_context(|| {
+            format!(
+                "Unknown symbol kind '{}' (expected flow, state, rule, global, nd or define)",
+                name
+            )
+        })?),
+        None => None,
+    };
+    let src = fs::read_to_string(input).with_context(|| format!("Failed to read {:?}", input))?;
+    let module = parse_source_with_path(&src, input)?;
+    let renamed = rename_symbol(&module, &src, from, to, kind)?;
+    let updated = parse_source_with_path(&renamed.text, input)
+        .context("Rename produced unparsable source; nothing was written")?;
+    let error_count = |module: &crate::ast::Module| {
+        validate_module_with_imports(module, &HashSet::new())
+            .iter()
+            .filter(|d| d.level == DiagnosticLevel::Error)
+            .count()
+    };
+    if error_count(&updated) > error_count(&module) {
+        bail!(
+            "Renaming '{}' to '{}' introduces semantic errors; nothing was written",
+            from,
+            to
+        );
+    }
+
+    // Pin the IDs under the old keys first, so the renamed node keeps its ID.
+    let sidecar = sidecar_path(input);
+    let mut map = read_id_map(&sidecar)?.unwrap_or_default();
+    let mut ir = from_ast(module);
+    assign_ids(&mut ir, &map.ids);
+    map.module = ir.name;
+    map.ids = ir.ids;
+    map.rename(&renamed.old_key, &renamed.new_key);
+
+    fs::write(input, &renamed.text).with_context(|| format!("Failed to write {:?}", input))?;
+    write_id_map(&sidecar, &map)?;
+    println!(
+        "Renamed {} '{}' to '{}' in {} ({} edit(s))",
+        renamed.kind.as_str(),
+        from,
+        to,
+        input.display(),
+        renamed.edits
+    );
+    Ok(())
+}
+
+fn fix_cmd(inputs: &[PathBuf], target: Option<&str>, check: bool) -> Result<()> {
+    let mut files = Vec::new();
+    for input in inputs {
+        collect_sculpt_files(input, &mut files)?;
+    }
+    let mut fixable = 0;
+    let mut failed = 0;
+    for file in &files {
+        let src = fs::read_to_string(file).with_context(|| format!("Failed to read {:?}", file))?;
+        let diagnostics = match fix_diagnostics(&src, file, target) {
+            Ok(diagnostics) => diagnostics,
+            Err(err) => {
+                eprintln!("{}", err);
+                failed += 1;
+                continue;
+            }
+        };
+        let path = file.display().to_string();
+        let (fixed, applied) = apply_safe_suggestions(&src, &path, &diagnostics);
+        if applied == 0 {
+            continue;
+        }
+        fixable += 1;
+        let mut safe: Vec<_> = diagnostics
+            .iter()
+            .flat_map(|d| d.suggestions.iter().map(move |s| (d.code, s)))
+            .filter(|(_, s)| s.safe)
+            .collect();
+        safe.sort_by_key(|(_, s)| s.span.start);
+        for (code, suggestion) in safe {
+            println!(
+                "{}:{}:{}: {} {}",
+                path, suggestion.span.line, suggestion.span.col, code, suggestion.message
+            );
+        }
+        if check {
+            println!("Would fix {} issue(s) in {}", applied, file.display());
+        } else {
+            fs::write(file, &fixed).with_context(|| format!("Failed to write {:?}", file))?;
+            println!("Fixed {} issue(s) in {}", applied, file.display());
+        }
+    }
+    if failed > 0 {
+        bail!("{} file(s) could not be parsed", failed);
+    }
+    if check && fixable > 0 {
+        bail!("{} file(s) have fixable issues", fixable);
+    }
+    Ok(())
+}
+
+/// Semantic diagnostics of one file plus, when the target is known, its contract
+/// diagnostics; the source of the suggestions `sculpt fix` applies.
+fn fix_diagnostics(src: &str, file: &Path, target: Option<&str>) -> Result<Vec<Diagnostic>> {
+    let module = parse_source_with_path(src, file)?;
+    let mut diagnostics = validate_module_with_imports(&module, &HashSet::new());
+    let target = target
+        .map(str::to_string)
+        .or_else(|| module.meta.get("target").cloned());
+    if let Some(target) = target {
+        let contract = parse_target_contract(&describe_target(&target)?)?;
+        diagnostics.extend(check_module_against_contract(
+            &from_ast(module),
+            &target,
+            &contract,
+        ));
+    }
+    Ok(diagnostics)
+}
+
+fn collect_sculpt_files(path: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    if !path.is_dir() {
+        out.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries = fs::read_dir(path)
+        .with_context(|| format!("Failed to read directory {:?}", path))?
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|e| e.path());
+    for entry in entries {
+        let child = entry.path();
+        if child.is_dir() {
+            collect_sculpt_files(&child, out)?;
+        } else if child.extension().and_then(|e| e.to_str()) == Some("sculpt") {
+            out.push(child);
+        }
+    }
+    Ok(())
+}
+
 fn clean_cmd(input: Option<&Path>, all: bool, retention: CleanRetention) -> Result<()> {
     let root = std::env::current_dir()?;
     validate_retention(retention)?;
@@ -2710,7 +3043,10 @@ fn color_24(s: &str, r: u8, g: u8, b: u8, bold: bool) -> String {
 
 fn target_list() -> Result<()> {
     let targets = list_targets()?;
-    println!("Language: default {}", LANGUAGE_DEFAULT);
+    println!(
+        "Language: default {} (supported {})",
+        LANGUAGE_DEFAULT, LANGUAGE_SUPPORT_RANGE
+    );
     println!("Available targets:");
     for t in targets {
         println!("  {}", t);
@@ -4246,44 +4582,158 @@ fn auth_check(provider: &str, verify: bool) -> Result<()> {
 }
 
 fn load_ir(input: &Path, nd_policy_override: Option<&str>) -> Result<crate::ir::IrModule> {
-    let (mut module, imported_roots) = if is_project_file(input) {
-        let project = load_project_context(input)?;
-        let (_, entry_module) = project
-            .modules
-            .get(&project.entry_module)
-            .cloned()
-            .ok_or_else(|| {
-                anyhow::anyhow!("Project entry module '{}' not found", project.entry_module)
-            })?;
-        let roots = resolve_imported_module_roots_project(&entry_module, &project.modules)?;
-        (entry_module, roots)
-    } else {
-        let src =
-            fs::read_to_string(input).with_context(|| format!("Failed to read {:?}", input))?;
-        let module = parse_source(&src)?;
-        if !module.imports.is_empty() {
-            bail!(
-                "Imports require a project file (*.sculpt.json). Stand-alone scripts cannot import modules."
-            );
+    let (ir, warnings) = load_ir_with_warnings(input, nd_policy_override)?;
+    if !warnings.is_empty() {
+        eprintln!(
+            "Semantic validation warnings:\n{}",
+            format_diagnostics(&warnings)
+        );
+    }
+    Ok(ir)
+}
+
+/// Parses and validates the input; semantic warnings are returned instead of printed.
+fn load_ir_with_warnings(
+    input: &Path,
+    nd_policy_override: Option<&str>,
+) -> Result<(crate::ir::IrModule, Vec<Diagnostic>)> {
+    if is_project_file(input) {
+        let mut project = load_project_context(input)?;
+        if let Some(value) = nd_policy_override {
+            if let Some(entry) = project.modules.get_mut(&project.entry_module) {
+                entry
+                    .meta
+                    .insert("nd_policy".to_string(), value.to_string());
+            }
         }
-        (module, HashSet::new())
-    };
+        let (mut program, warnings) = link_program(&project.entry_module, &project.modules)?;
+        apply_id_sidecars(&mut program)?;
+        project.warnings.extend(warnings);
+        return Ok((program, project.warnings));
+    }
 
+    let src = fs::read_to_string(input).with_context(|| format!("Failed to read {:?}", input))?;
+    let (mut module, parse_warnings) = parse_script(&src, input)?;
+    if !module.imports.is_empty() {
+        bail!(
+            "Imports require a project file (*.sculpt.json). Stand-alone scripts cannot import modules."
+        );
+    }
     if let Some(value) = nd_policy_override {
         module
             .meta
             .insert("nd_policy".to_string(), value.to_string());
     }
-    let diagnostics = validate_module_with_imports(&module, &imported_roots);
-    if !diagnostics.is_empty() {
-        let rendered = format_diagnostics(&diagnostics);
-        if !has_errors(&diagnostics) {
-            eprintln!("Semantic validation warnings:\n{}", rendered);
-        } else {
-            bail!("Semantic validation failed:\n{}", rendered);
-        }
+    let mut diagnostics = parse_warnings;
+    diagnostics.extend(validate_module_with_imports(&module, &HashSet::new()));
+    if has_errors(&diagnostics) {
+        bail!(DiagnosticsError::with_heading(
+            "Semantic validation failed",
+            diagnostics
+        ));
     }
-    Ok(from_ast(module))
+    let mut ir = from_ast(module);
+    apply_id_sidecars(&mut ir)?;
+    Ok((ir, diagnostics))
+}
+
+/// Pins the IDs of `ir` and its linked modules to their `<script>.ids.json` sidecars.
+fn apply_id_sidecars(ir: &mut IrModule) -> Result<()> {
+    for linked in &mut ir.linked {
+        apply_id_sidecars(linked)?;
+    }
+    if ir.span.is_dummy() {
+        return Ok(());
+    }
+    if let Some(map) = read_id_map(&sidecar_path(Path::new(ir.span.path())))? {
+        assign_ids(ir, &map.ids);
+    }
+    Ok(())
+}
+
+/// Records the current IDs of `ir` and its linked modules in their sidecars.
+fn write_id_sidecars(ir: &IrModule) -> Result<()> {
+    for linked in &ir.linked {
+        write_id_sidecars(linked)?;
+    }
+    if ir.span.is_dummy() {
+        return Ok(());
+    }
+    let path = sidecar_path(Path::new(ir.span.path()));
+    let current = read_id_map(&path)?;
+    if current.is_some_and(|map| map.module == ir.name && map.ids == ir.ids) {
+        return Ok(());
+    }
+    write_id_map(
+        &path,
+        &IdMap {
+            module: ir.name.clone(),
+            ids: ir.ids.clone(),
+        },
+    )
+}
+
+/// Parses a stand-alone script and returns it with its syntax warnings. On syntax errors the
+/// well-formed parts are still validated, so one run reports both kinds of problems.
+fn parse_script(src: &str, input: &Path) -> Result<(crate::ast::Module, Vec<Diagnostic>)> {
+    let outcome = parse_source_recovering_with_path(src, input);
+    if !has_errors(&outcome.diagnostics) {
+        return Ok((outcome.module, outcome.diagnostics));
+    }
+    let mut report = format!(
+        "Parsing failed:\n{}",
+        format_diagnostics(&outcome.diagnostics)
+    );
+    let semantic = validate_module_with_imports(&outcome.module, &HashSet::new());
+    if !semantic.is_empty() {
+        report.push_str(&format!(
+            "\nSemantic validation of the well-formed parts:\n{}",
+            format_diagnostics(&semantic)
+        ));
+    }
+    let mut diagnostics = outcome.diagnostics;
+    diagnostics.extend(semantic);
+    bail!(DiagnosticsError {
+        message: report,
+        diagnostics,
+    })
+}
+
+/// Runs build, freeze or replay and, for `--diagnostics-format=json|sarif`, writes the
+/// semantic warnings and the diagnostics of a failed check to `dist/<script>/diagnostics.*`
+/// for CI and code-scanning tools. Text output on the terminal is unchanged.
+fn with_diagnostics_format(
+    input: &Path,
+    nd_policy_override: Option<&str>,
+    format: &str,
+    run: impl FnOnce() -> Result<()>,
+) -> Result<()> {
+    if format == "text" {
+        return run();
+    }
+    // The command prints warnings but does not return them; validating again is cheap.
+    let mut reported = load_ir_with_warnings(input, nd_policy_override)
+        .map(|(_, warnings)| warnings)
+        .unwrap_or_default();
+    let result = run();
+    if let Some(failed) = result
+        .as_ref()
+        .err()
+        .and_then(|e| e.downcast_ref::<DiagnosticsError>())
+    {
+        reported.extend(failed.diagnostics.iter().cloned());
+    }
+    let default_path = input.display().to_string();
+    let document = match format {
+        "sarif" => diagnostics_to_sarif(&reported, &default_path),
+        _ => diagnostics_to_json(&reported, &default_path),
+    };
+    let dist_dir = dist_dir(input);
+    fs::create_dir_all(&dist_dir)?;
+    let path = dist_dir.join(format!("diagnostics.{}", format));
+    fs::write(&path, serde_json::to_string_pretty(&document)?)?;
+    eprintln!("Diagnostics written to {}", path.display());
+    result
 }
 
 fn is_project_file(path: &Path) -> bool {
@@ -4307,16 +4757,19 @@ fn load_project_context(project_file: &Path) -> Result<ProjectContext> {
         .map(Path::to_path_buf)
         .unwrap_or_else(|| PathBuf::from("."));
     let mut modules = HashMap::new();
+    let mut warnings = Vec::new();
     for rel in &spec.modules {
         let path = base_dir.join(rel);
         let src = fs::read_to_string(&path)
             .with_context(|| format!("Failed to read module source {}", path.display()))?;
-        let module = parse_source(&src)
-            .with_context(|| format!("Failed to parse module source {}", path.display()))?;
-        if modules
-            .insert(module.name.clone(), (path.clone(), module))
-            .is_some()
-        {
+        let outcome = parse_source_recovering_with_path(&src, &path);
+        if has_errors(&outcome.diagnostics) {
+            return Err(anyhow::Error::from(SyntaxErrors(outcome.diagnostics)))
+                .with_context(|| format!("Failed to parse module source {}", path.display()));
+        }
+        let module = outcome.module;
+        warnings.extend(outcome.diagnostics);
+        if modules.insert(module.name.clone(), module).is_some() {
             bail!("Duplicate module namespace in project: {}", rel);
         }
     }
@@ -4351,57 +4804,10 @@ fn load_project_context(project_file: &Path) -> Result<ProjectContext> {
     Ok(ProjectContext {
         entry_module,
         modules,
+        warnings,
     })
 }
 
-fn resolve_imported_module_roots_project(
-    module: &crate::ast::Module,
-    modules: &HashMap<String, (PathBuf, crate::ast::Module)>,
-) -> Result<HashSet<String>> {
-    let mut roots = HashSet::new();
-    let mut visited = HashSet::new();
-    resolve_imported_module_roots_project_inner(module, modules, &mut roots, &mut visited)?;
-    Ok(roots)
-}
-
-fn resolve_imported_module_roots_project_inner(
-    module: &crate::ast::Module,
-    modules: &HashMap<String, (PathBuf, crate::ast::Module)>,
-    roots: &mut HashSet<String>,
-    visited: &mut HashSet<String>,
-) -> Result<()> {
-    for import in &module.imports {
-        let imported = modules
-            .get(&import.path)
-            .map(|(_, m)| m)
-            .ok_or_else(|| anyhow::anyhow!("Unknown imported module '{}'", import.path))?;
-        if !visited.insert(import.path.clone()) {
-            continue;
-        }
-
-        if let Some(alias) = &import.alias {
-            roots.insert(alias.clone());
-        } else {
-            let module_root = import
-                .path
-                .split('.')
-                .next()
-                .unwrap_or("")
-                .trim()
-                .to_string();
-            if module_root.is_empty() {
-                bail!(
-                    "Imported module '{}' has invalid namespace root",
-                    import.path
-                );
-            }
-            roots.insert(module_root);
-        }
-        resolve_imported_module_roots_project_inner(imported, modules, roots, visited)?;
-    }
-    Ok(())
-}
-
 fn select_ai_provider(
     provider_override: Option<String>,
     model_override: Option<String>,
@@ -1,3 +1,4 @@
+use super::{push_expr_ops_js, push_statechart_js};
 use crate::target_ir::TargetIr;
 
 pub fn generate_cli_js(target: &TargetIr) -> String {
@@ -8,7 +9,9 @@ pub fn generate_cli_js(target: &TargetIr) -> String {
     out.push_str(&format!("const TARGET = {};\n\n", target_json));
     out.push_str("const VIEWS = TARGET.views || {};\n");
     out.push_str("const FLOW = TARGET.flow || { start: '', transitions: {} };\n");
-    out.push_str("let state = FLOW.start || '';\n\n");
+    push_statechart_js(&mut out);
+    out.push_str("let active = enterTarget(FLOW.start || '', null);\n");
+    out.push_str("let state = active[0] || '';\n\n");
     out.push_str("const fs = require('fs');\n");
     out.push_str("const path = require('path');\n\n");
     out.push_str("const EXT = TARGET.extensions || {};\n");
@@ -341,8 +344,23 @@ pub fn generate_cli_js(target: &TargetIr) -> String {
     out.push_str("  }\n");
     out.push_str("}\n\n");
 
+    out.push_str("const ruleIntervals = {};\n\n");
+    out.push_str("function syncRuleTimers() {\n");
+    out.push_str("  const states = activeStates(active);\n");
+    out.push_str("  for (const rule of RUNTIME_RULES) {\n");
+    out.push_str("    if (!rule || !rule.everyMs) continue;\n");
+    out.push_str("    const live = !rule.scopeState || states.includes(rule.scopeState);\n");
+    out.push_str("    if (live && !ruleIntervals[rule.name]) {\n");
+    out.push_str("      ruleIntervals[rule.name] = setInterval(() => dispatch(rule.on), Math.max(1, Number(rule.everyMs)));\n");
+    out.push_str("    } else if (!live && ruleIntervals[rule.name]) {\n");
+    out.push_str("      clearInterval(ruleIntervals[rule.name]);\n");
+    out.push_str("      delete ruleIntervals[rule.name];\n");
+    out.push_str("    }\n");
+    out.push_str("  }\n");
+    out.push_str("}\n\n");
+
     out.push_str("function stateNeedsTick(s) {\n");
-    out.push_str("  const map = (FLOW.transitions && FLOW.transitions[s]) || {};\n");
+    out.push_str("  const map = Object.assign({}, (FLOW.guards && FLOW.guards[s]) || {}, (FLOW.transitions && FLOW.transitions[s]) || {});\n");
     out.push_str("  if (Object.prototype.hasOwnProperty.call(map, 'tick')) return true;\n");
     out.push_str("  if (Object.prototype.hasOwnProperty.call(map, 'input.tick')) return true;\n");
     out.push_str("  for (const rule of RUNTIME_RULES) {\n");
@@ -379,6 +397,13 @@ pub fn generate_cli_js(target: &TargetIr) -> String {
     out.push_str("  return Math.abs(Number(a || 0) - Number(b || 0)) <= Number(tol || 0);\n");
     out.push_str("}\n\n");
 
+    out.push_str("function nameList(value) {\n");
+    out.push_str(
+        "  const items = Array.isArray(value) ? value : String(value || '').split(',');\n",
+    );
+    out.push_str("  return items.map((s) => String(s).trim()).filter(Boolean);\n");
+    out.push_str("}\n\n");
+
     out.push_str("function runtimeOp(name, args) {\n");
     out.push_str("  const op = String(name || '').split('.').pop();\n");
     out.push_str("  if (op === 'csvRead') {\n");
@@ -393,14 +418,14 @@ pub fn generate_cli_js(target: &TargetIr) -> String {
     out.push_str("  if (op === 'rowCount') return Array.isArray(args[0]) ? args[0].length : 0;\n");
     out.push_str("  if (op === 'csvHasColumns') {\n");
     out.push_str("    const rows = Array.isArray(args[0]) ? args[0] : [];\n");
-    out.push_str("    const required = String(args[1] || '').split(',').map((s) => s.trim()).filter(Boolean);\n");
+    out.push_str("    const required = nameList(args[1]);\n");
     out.push_str("    if (rows.length === 0) return required.length === 0 ? 1 : 0;\n");
     out.push_str("    const head = rows[0] || {};\n");
     out.push_str("    return required.every((k) => Object.prototype.hasOwnProperty.call(head, k)) ? 1 : 0;\n");
     out.push_str("  }\n");
     out.push_str("  if (op === 'csvMissingColumns') {\n");
     out.push_str("    const rows = Array.isArray(args[0]) ? args[0] : [];\n");
-    out.push_str("    const required = String(args[1] || '').split(',').map((s) => s.trim()).filter(Boolean);\n");
+    out.push_str("    const required = nameList(args[1]);\n");
     out.push_str("    if (required.length === 0) return [];\n");
     out.push_str("    if (rows.length === 0) return required;\n");
     out.push_str("    const head = rows[0] || {};\n");
@@ -545,9 +570,7 @@ pub fn generate_cli_js(target: &TargetIr) -> String {
     out.push_str("  }\n");
     out.push_str("  if (op === 'sortBy') {\n");
     out.push_str("    const rows = Array.isArray(args[0]) ? args[0].slice() : [];\n");
-    out.push_str(
-        "    const keys = String(args[1] || '').split(',').map((k) => k.trim()).filter(Boolean);\n",
-    );
+    out.push_str("    const keys = nameList(args[1]);\n");
     out.push_str("    rows.sort((a, b) => {\n");
     out.push_str("      for (const k of keys) {\n");
     out.push_str("        const av = String((a && a[k]) || '');\n");
@@ -583,32 +606,34 @@ pub fn generate_cli_js(target: &TargetIr) -> String {
     out.push_str("  return null;\n");
     out.push_str("}\n\n");
 
+    push_expr_ops_js(&mut out);
+
     out.push_str("function evalBinaryExpr(node) {\n");
     out.push_str("  if (!node || typeof node !== 'object') return null;\n");
-    out.push_str("  const op = String(node.op || '');\n");
-    out.push_str("  const left = resolveRuntimeValue(node.left);\n");
-    out.push_str("  const right = resolveRuntimeValue(node.right);\n");
-    out.push_str("  if (op === 'Add') return Number(left || 0) + Number(right || 0);\n");
-    out.push_str("  if (op === 'Sub') return Number(left || 0) - Number(right || 0);\n");
-    out.push_str("  if (op === 'Mul') return Number(left || 0) * Number(right || 0);\n");
-    out.push_str("  if (op === 'Div') return Number(right || 0) === 0 ? 0 : Number(left || 0) / Number(right || 1);\n");
-    out.push_str("  if (op === 'And') return (left ? 1 : 0) && (right ? 1 : 0) ? 1 : 0;\n");
-    out.push_str("  if (op === 'Or') return (left ? 1 : 0) || (right ? 1 : 0) ? 1 : 0;\n");
-    out.push_str("  if (op === 'Eq') return left === right ? 1 : 0;\n");
-    out.push_str("  if (op === 'Neq') return left !== right ? 1 : 0;\n");
-    out.push_str("  if (op === 'Gt') return Number(left || 0) > Number(right || 0) ? 1 : 0;\n");
-    out.push_str("  if (op === 'Gte') return Number(left || 0) >= Number(right || 0) ? 1 : 0;\n");
-    out.push_str("  if (op === 'Lt') return Number(left || 0) < Number(right || 0) ? 1 : 0;\n");
-    out.push_str("  if (op === 'Lte') return Number(left || 0) <= Number(right || 0) ? 1 : 0;\n");
-    out.push_str("  return null;\n");
+    out.push_str("  return binaryOp(String(node.op || ''), resolveRuntimeValue(node.left), resolveRuntimeValue(node.right));\n");
+    out.push_str("}\n\n");
+
+    out.push_str("function evalUnaryExpr(node) {\n");
+    out.push_str("  if (!node || typeof node !== 'object') return null;\n");
+    out.push_str("  return unaryOp(node.op, resolveRuntimeValue(node.operand));\n");
     out.push_str("}\n\n");
 
     out.push_str("function resolveRuntimeValue(v) {\n");
     out.push_str("  if (v && typeof v === 'object' && !Array.isArray(v)) {\n");
     out.push_str("    if (typeof v.ident === 'string') return RUNTIME_STATE[v.ident];\n");
+    out.push_str("    if (Array.isArray(v.list)) return v.list.map(resolveRuntimeValue);\n");
+    out.push_str("    if (Array.isArray(v.template)) return renderTemplate(v.template);\n");
+    out.push_str("    if (v.record && typeof v.record === 'object') {\n");
+    out.push_str("      const out = {};\n");
+    out.push_str("      for (const [k, item] of Object.entries(v.record)) out[k] = resolveRuntimeValue(item);\n");
+    out.push_str("      return out;\n");
+    out.push_str("    }\n");
     out.push_str(
         "    if (v.binary && typeof v.binary === 'object') return evalBinaryExpr(v.binary);\n",
     );
+    out.push_str(
+        "    if (v.unary && typeof v.unary === 'object') return evalUnaryExpr(v.unary);\n",
+    );
     out.push_str("    if (v.call && typeof v.call === 'object') {\n");
     out.push_str("      const callName = String(v.call.name || '');\n");
     out.push_str("      const rawArgs = Array.isArray(v.call.args) ? v.call.args : [];\n");
@@ -619,6 +644,16 @@ pub fn generate_cli_js(target: &TargetIr) -> String {
     out.push_str("  return v;\n");
     out.push_str("}\n\n");
 
+    out.push_str("function renderTemplate(parts) {\n");
+    out.push_str("  return parts\n");
+    out.push_str("    .map((part) => {\n");
+    out.push_str("      if (part && typeof part.text === 'string') return part.text;\n");
+    out.push_str("      const value = resolveRuntimeValue(part ? part.expr : null);\n");
+    out.push_str("      return value === null || value === undefined ? '' : String(value);\n");
+    out.push_str("    })\n");
+    out.push_str("    .join('');\n");
+    out.push_str("}\n\n");
+
     out.push_str("function evalWhen(when) {\n");
     out.push_str("  if (!when || typeof when !== 'object') return false;\n");
     out.push_str("  if (when.kind === 'logic') {\n");
@@ -628,46 +663,18 @@ pub fn generate_cli_js(target: &TargetIr) -> String {
     out.push_str("    if (when.op === 'or') return evalWhen(when.left) || evalWhen(when.right);\n");
     out.push_str("    return false;\n");
     out.push_str("  }\n");
-    out.push_str("  const mode = when.kind === 'cmp' ? when.op : when.op;\n");
-    out.push_str("  if (mode === 'gte') {\n");
-    out.push_str("    const left = Number(RUNTIME_STATE[String(when.left)] || 0);\n");
-    out.push_str("    const right = Number(resolveRuntimeValue(when.right) || 0);\n");
-    out.push_str("    return left >= right;\n");
-    out.push_str("  }\n");
-    out.push_str("  if (mode === 'gt') {\n");
-    out.push_str("    const left = Number(RUNTIME_STATE[String(when.left)] || 0);\n");
-    out.push_str("    const right = Number(resolveRuntimeValue(when.right) || 0);\n");
-    out.push_str("    return left > right;\n");
-    out.push_str("  }\n");
-    out.push_str("  if (mode === 'lt') {\n");
-    out.push_str("    const left = Number(RUNTIME_STATE[String(when.left)] || 0);\n");
-    out.push_str("    const right = Number(resolveRuntimeValue(when.right) || 0);\n");
-    out.push_str("    return left < right;\n");
-    out.push_str("  }\n");
-    out.push_str("  if (mode === 'eq') {\n");
-    out.push_str("    const left = RUNTIME_STATE[String(when.left)];\n");
-    out.push_str("    const right = resolveRuntimeValue(when.right);\n");
-    out.push_str("    if (typeof left === 'number' || typeof right === 'number') {\n");
-    out.push_str("      return Number(left || 0) === Number(right || 0);\n");
-    out.push_str("    }\n");
-    out.push_str("    return String(left) === String(right);\n");
-    out.push_str("  }\n");
-    out.push_str("  if (mode === 'neq') {\n");
-    out.push_str("    const left = RUNTIME_STATE[String(when.left)];\n");
-    out.push_str("    const right = resolveRuntimeValue(when.right);\n");
-    out.push_str("    if (typeof left === 'number' || typeof right === 'number') {\n");
-    out.push_str("      return Number(left || 0) !== Number(right || 0);\n");
-    out.push_str("    }\n");
-    out.push_str("    return String(left) !== String(right);\n");
-    out.push_str("  }\n");
-    out.push_str("  return false;\n");
+    out.push_str("  if (when.kind === 'not') return !evalWhen(when.operand);\n");
+    out.push_str("  if (when.kind === 'expr') return !!resolveRuntimeValue(when.expr);\n");
+    out.push_str("  return compareWhen(when.op, RUNTIME_STATE[String(when.left)], resolveRuntimeValue(when.right));\n");
     out.push_str("}\n\n");
 
     out.push_str("function applyRuntimeRules(event, firedWhen) {\n");
     out.push_str("  const emitted = [];\n");
     out.push_str("  for (const rule of RUNTIME_RULES) {\n");
     out.push_str("    if (!rule) continue;\n");
-    out.push_str("    if (rule.scopeState && rule.scopeState !== state) continue;\n");
+    out.push_str(
+        "    if (rule.scopeState && !activeStates(active).includes(rule.scopeState)) continue;\n",
+    );
     out.push_str("    const isOnRule = typeof rule.on === 'string' && rule.on.length > 0;\n");
     out.push_str("    const isWhenRule = !!rule.when;\n");
     out.push_str("    if (isOnRule && rule.on !== event) continue;\n");
@@ -683,9 +690,7 @@ pub fn generate_cli_js(target: &TargetIr) -> String {
     out.push_str("      if (!a || typeof a.target !== 'string') continue;\n");
     out.push_str("      const value = resolveRuntimeValue(a.value);\n");
     out.push_str("      if (a.op === 'add') {\n");
-    out.push_str("        const cur = Number(RUNTIME_STATE[a.target] || 0);\n");
-    out.push_str("        const add = Number(value || 0);\n");
-    out.push_str("        RUNTIME_STATE[a.target] = cur + add;\n");
+    out.push_str("        RUNTIME_STATE[a.target] = addValues(RUNTIME_STATE[a.target], value);\n");
     out.push_str("      } else {\n");
     out.push_str("        RUNTIME_STATE[a.target] = value;\n");
     out.push_str("      }\n");
@@ -705,37 +710,52 @@ pub fn generate_cli_js(target: &TargetIr) -> String {
     out.push_str("    if (game.kind === 'snake') renderSnake();\n");
     out.push_str("    return;\n");
     out.push_str("  }\n");
-    out.push_str("  const items = VIEWS[state] || [];\n");
+    out.push_str("  const items = activeStates(active).flatMap((s) => VIEWS[s] || []);\n");
     out.push_str("  for (const item of items) {\n");
     out.push_str("    if (item.kind === 'text') {\n");
     out.push_str("      const color = item.color ? (COLORS[item.color] || '') : '';\n");
     out.push_str("      const raw = item.text || '';\n");
-    out.push_str("      const txt = (typeof raw === 'string' && Object.prototype.hasOwnProperty.call(RUNTIME_STATE, raw)) ? String(RUNTIME_STATE[raw]) : String(raw);\n");
+    out.push_str("      const txt = Array.isArray(item.textTemplate) ? renderTemplate(item.textTemplate) : (typeof raw === 'string' && Object.prototype.hasOwnProperty.call(RUNTIME_STATE, raw)) ? String(RUNTIME_STATE[raw]) : String(raw);\n");
     out.push_str("      console.log(color + txt + RESET);\n");
     out.push_str("    }\n");
     out.push_str("  }\n");
     out.push_str("}\n\n");
 
+    out.push_str("function transitionTarget(s, event) {\n");
+    out.push_str("  if (event.startsWith('timer(')) return timerTarget(s, event);\n");
+    out.push_str(
+        "  const guarded = (FLOW.guards && FLOW.guards[s] && FLOW.guards[s][event]) || [];\n",
+    );
+    out.push_str("  for (const g of guarded) {\n");
+    out.push_str("    if (g && evalWhen(g.when)) return g.target;\n");
+    out.push_str("  }\n");
+    out.push_str("  const map = (FLOW.transitions && FLOW.transitions[s]) || {};\n");
+    out.push_str("  return map[event] || null;\n");
+    out.push_str("}\n\n");
+
     out.push_str("function processEvent(event, depth, firedWhen) {\n");
     out.push_str("  if (depth > 24) return;\n");
     out.push_str("  if (!firedWhen) firedWhen = new Set();\n");
     out.push_str("  const before = state;\n");
+    out.push_str("  const beforeActive = active.join('\\n');\n");
     out.push_str("  if (game) handleGameInput(event);\n");
-    out.push_str("  const map = (FLOW.transitions && FLOW.transitions[state]) || {};\n");
-    out.push_str("  if (map[event]) state = map[event];\n");
+    out.push_str("  active = nextActive(active, event, transitionTarget);\n");
+    out.push_str("  state = active[0] || '';\n");
     out.push_str("  const emitted = applyRuntimeRules(event, firedWhen);\n");
     out.push_str("  if (state !== before && state === 'Play') {\n");
     out.push_str("    enterPlayState();\n");
     out.push_str("  }\n");
-    out.push_str("  if (stateNeedsTick(state)) {\n");
+    out.push_str("  if (activeStates(active).some(stateNeedsTick)) {\n");
     out.push_str("    startTicker();\n");
     out.push_str("  } else {\n");
     out.push_str("    stopTicker();\n");
     out.push_str("  }\n");
+    out.push_str("  syncTimers(activeStates(active), dispatch);\n");
+    out.push_str("  syncRuleTimers();\n");
     out.push_str(
         "  if (state !== before && String(state).toLowerCase() === 'exit') process.exit(0);\n",
     );
-    out.push_str("  if (state !== before) render();\n");
+    out.push_str("  if (active.join('\\n') !== beforeActive) render();\n");
     out.push_str("  for (const ev of emitted) processEvent(ev, depth + 1, firedWhen);\n");
     out.push_str("}\n\n");
 
@@ -754,8 +774,13 @@ pub fn generate_cli_js(target: &TargetIr) -> String {
     out.push_str("  return key.trim().toLowerCase();\n");
     out.push_str("}\n\n");
 
+    out.push_str("for (const init of Array.isArray(EXT.stateInit) ? EXT.stateInit : []) {\n");
+    out.push_str("  RUNTIME_STATE[init.target] = resolveRuntimeValue(init.value);\n");
+    out.push_str("}\n");
     out.push_str("ensureGameInit();\n");
-    out.push_str("if (stateNeedsTick(state)) startTicker();\n");
+    out.push_str("if (activeStates(active).some(stateNeedsTick)) startTicker();\n");
+    out.push_str("syncTimers(activeStates(active), dispatch);\n");
+    out.push_str("syncRuleTimers();\n");
     out.push_str("render();\n");
     out.push_str("if (process.stdin.isTTY) process.stdin.setRawMode(true);\n");
     out.push_str("process.stdin.resume();\n");
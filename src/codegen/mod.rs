@@ -1,2 +1,180 @@
 pub mod cli;
 pub mod web;
+
+/// Statechart helpers shared by the generated runtimes. The runtime keeps `active`, the
+/// active leaf states (one per orthogonal region); events bubble from each leaf to its
+/// ancestors until a state resolves them through `targetOf(state, event)`.
+///
+/// `after(...)` timers are armed by `syncTimers` for every state that is active after an
+/// event and cancelled once the state is left. An expired timer is dispatched as the event
+/// `timer(State#index)`, which `timerTarget` resolves.
+pub(crate) fn push_statechart_js(out: &mut String) {
+    out.push_str("const FLOW_PARENTS = FLOW.parents || {};\n");
+    out.push_str("const FLOW_INITIAL = FLOW.initial || {};\n");
+    out.push_str("const FLOW_REGIONS = FLOW.regions || {};\n");
+    out.push_str("const FLOW_TIMERS = FLOW.timers || {};\n");
+    out.push_str("const timerHandles = {};\n");
+    out.push_str("let exitedStates = [];\n\n");
+
+    out.push_str("function flowAncestors(s) {\n");
+    out.push_str("  const out = [];\n");
+    out.push_str("  for (let p = FLOW_PARENTS[s]; p && !out.includes(p); p = FLOW_PARENTS[p]) out.push(p);\n");
+    out.push_str("  return out;\n");
+    out.push_str("}\n\n");
+
+    out.push_str("function enterState(s) {\n");
+    out.push_str("  if (FLOW_REGIONS[s]) return FLOW_REGIONS[s].flatMap(enterState);\n");
+    out.push_str("  if (FLOW_INITIAL[s]) return enterState(FLOW_INITIAL[s]);\n");
+    out.push_str("  return [s];\n");
+    out.push_str("}\n\n");
+
+    out.push_str("function enterTarget(dst, top) {\n");
+    out.push_str("  let leaves = enterState(dst);\n");
+    out.push_str("  for (let c = dst, p = FLOW_PARENTS[dst]; c !== top && p; c = p, p = FLOW_PARENTS[p]) {\n");
+    out.push_str("    const regions = FLOW_REGIONS[p] || [];\n");
+    out.push_str("    const at = regions.indexOf(c);\n");
+    out.push_str("    if (at < 0) continue;\n");
+    out.push_str("    leaves = regions.slice(0, at).flatMap(enterState).concat(leaves, regions.slice(at + 1).flatMap(enterState));\n");
+    out.push_str("  }\n");
+    out.push_str("  return leaves;\n");
+    out.push_str("}\n\n");
+
+    out.push_str("function topBelow(s, ancestor) {\n");
+    out.push_str("  let top = s;\n");
+    out.push_str(
+        "  while (FLOW_PARENTS[top] && FLOW_PARENTS[top] !== ancestor) top = FLOW_PARENTS[top];\n",
+    );
+    out.push_str("  return top;\n");
+    out.push_str("}\n\n");
+
+    out.push_str("function transitionLeaves(active, src, dst) {\n");
+    out.push_str("  const dstAncestors = flowAncestors(dst);\n");
+    out.push_str(
+        "  const common = flowAncestors(src).find((a) => dstAncestors.includes(a)) || null;\n",
+    );
+    out.push_str("  const exited = topBelow(src, common);\n");
+    out.push_str("  exitedStates.push(exited);\n");
+    out.push_str(
+        "  const inside = (leaf) => leaf === exited || flowAncestors(leaf).includes(exited);\n",
+    );
+    out.push_str("  const at = active.findIndex(inside);\n");
+    out.push_str("  const kept = active.filter((leaf) => !inside(leaf));\n");
+    out.push_str("  kept.splice(at < 0 ? kept.length : at, 0, ...enterTarget(dst, topBelow(dst, common)));\n");
+    out.push_str("  return kept;\n");
+    out.push_str("}\n\n");
+
+    out.push_str("function nextActive(active, event, targetOf) {\n");
+    out.push_str("  let out = active;\n");
+    out.push_str("  const fired = new Set();\n");
+    out.push_str("  for (const leaf of active) {\n");
+    out.push_str("    if (!out.includes(leaf)) continue;\n");
+    out.push_str("    for (const s of [leaf].concat(flowAncestors(leaf))) {\n");
+    out.push_str("      if (fired.has(s)) break;\n");
+    out.push_str("      const dst = targetOf(s, event);\n");
+    out.push_str("      if (!dst) continue;\n");
+    out.push_str("      fired.add(s);\n");
+    out.push_str("      out = transitionLeaves(out, s, dst);\n");
+    out.push_str("      break;\n");
+    out.push_str("    }\n");
+    out.push_str("  }\n");
+    out.push_str("  return out;\n");
+    out.push_str("}\n\n");
+
+    out.push_str("function timerTarget(s, event) {\n");
+    out.push_str("  const m = /^timer\\((.*)#(\\d+)\\)$/.exec(event);\n");
+    out.push_str("  if (!m || m[1] !== s) return null;\n");
+    out.push_str("  const timer = (FLOW_TIMERS[s] || [])[Number(m[2])];\n");
+    out.push_str("  return timer ? timer.target : null;\n");
+    out.push_str("}\n\n");
+
+    out.push_str("function syncTimers(states, fire) {\n");
+    out.push_str("  const left = (s) => !states.includes(s) || exitedStates.some((e) => e === s || flowAncestors(s).includes(e));\n");
+    out.push_str("  for (const s of Object.keys(timerHandles)) {\n");
+    out.push_str("    if (!left(s)) continue;\n");
+    out.push_str("    for (const h of timerHandles[s]) clearTimeout(h);\n");
+    out.push_str("    delete timerHandles[s];\n");
+    out.push_str("  }\n");
+    out.push_str("  exitedStates = [];\n");
+    out.push_str("  for (const s of states) {\n");
+    out.push_str("    if (timerHandles[s] || !FLOW_TIMERS[s]) continue;\n");
+    out.push_str("    timerHandles[s] = FLOW_TIMERS[s].map((t, i) => setTimeout(() => {\n");
+    out.push_str("      for (const h of timerHandles[s] || []) clearTimeout(h);\n");
+    out.push_str("      delete timerHandles[s];\n");
+    out.push_str("      fire(`timer(${s}#${i})`);\n");
+    out.push_str("    }, Number(t.afterMs) || 0));\n");
+    out.push_str("  }\n");
+    out.push_str("}\n\n");
+
+    out.push_str("function activeStates(active) {\n");
+    out.push_str("  const out = [];\n");
+    out.push_str("  for (const leaf of active) {\n");
+    out.push_str("    for (const s of flowAncestors(leaf).reverse().concat([leaf])) {\n");
+    out.push_str("      if (!out.includes(s)) out.push(s);\n");
+    out.push_str("    }\n");
+    out.push_str("  }\n");
+    out.push_str("  return out;\n");
+    out.push_str("}\n\n");
+}
+
+/// Operator semantics shared by the generated runtimes, so an expression evaluates the same
+/// on every target. Operands are resolved values. `+` concatenates when either side is a
+/// string and adds numbers otherwise; `/` and `%` by zero yield 0. `==`/`!=` compare
+/// numerically when either side is a number (a missing value counts as 0), treat `null` and a
+/// missing value as equal, and compare as strings otherwise. Comparisons, `and`, `or` and
+/// `not` yield 1 or 0.
+pub(crate) fn push_expr_ops_js(out: &mut String) {
+    out.push_str("function exprNumber(v) {\n");
+    out.push_str("  return Number(v || 0);\n");
+    out.push_str("}\n\n");
+
+    out.push_str("function valuesEqual(l, r) {\n");
+    out.push_str("  if (typeof l === 'number' || typeof r === 'number') return exprNumber(l) === exprNumber(r);\n");
+    out.push_str("  if (l === null || l === undefined || r === null || r === undefined) {\n");
+    out.push_str(
+        "    return (l === null || l === undefined) && (r === null || r === undefined);\n",
+    );
+    out.push_str("  }\n");
+    out.push_str("  return String(l) === String(r);\n");
+    out.push_str("}\n\n");
+
+    out.push_str("function addValues(l, r) {\n");
+    out.push_str("  if (typeof l === 'string' || typeof r === 'string') return String(l ?? '') + String(r ?? '');\n");
+    out.push_str("  return exprNumber(l) + exprNumber(r);\n");
+    out.push_str("}\n\n");
+
+    out.push_str("function binaryOp(op, l, r) {\n");
+    out.push_str("  if (op === 'Add') return addValues(l, r);\n");
+    out.push_str("  if (op === 'Sub') return exprNumber(l) - exprNumber(r);\n");
+    out.push_str("  if (op === 'Mul') return exprNumber(l) * exprNumber(r);\n");
+    out.push_str(
+        "  if (op === 'Div') return exprNumber(r) === 0 ? 0 : exprNumber(l) / exprNumber(r);\n",
+    );
+    out.push_str(
+        "  if (op === 'Mod') return exprNumber(r) === 0 ? 0 : exprNumber(l) % exprNumber(r);\n",
+    );
+    out.push_str("  if (op === 'And') return l && r ? 1 : 0;\n");
+    out.push_str("  if (op === 'Or') return l || r ? 1 : 0;\n");
+    out.push_str("  if (op === 'Eq') return valuesEqual(l, r) ? 1 : 0;\n");
+    out.push_str("  if (op === 'Neq') return valuesEqual(l, r) ? 0 : 1;\n");
+    out.push_str("  if (op === 'Gt') return exprNumber(l) > exprNumber(r) ? 1 : 0;\n");
+    out.push_str("  if (op === 'Gte') return exprNumber(l) >= exprNumber(r) ? 1 : 0;\n");
+    out.push_str("  if (op === 'Lt') return exprNumber(l) < exprNumber(r) ? 1 : 0;\n");
+    out.push_str("  if (op === 'Lte') return exprNumber(l) <= exprNumber(r) ? 1 : 0;\n");
+    out.push_str("  return null;\n");
+    out.push_str("}\n\n");
+
+    out.push_str("function unaryOp(op, v) {\n");
+    out.push_str("  if (op === 'Neg') return -exprNumber(v);\n");
+    out.push_str("  if (op === 'Not') return v ? 0 : 1;\n");
+    out.push_str("  return null;\n");
+    out.push_str("}\n\n");
+
+    out.push_str("function compareWhen(op, l, r) {\n");
+    out.push_str("  if (op === 'gte') return exprNumber(l) >= exprNumber(r);\n");
+    out.push_str("  if (op === 'gt') return exprNumber(l) > exprNumber(r);\n");
+    out.push_str("  if (op === 'lt') return exprNumber(l) < exprNumber(r);\n");
+    out.push_str("  if (op === 'eq') return valuesEqual(l, r);\n");
+    out.push_str("  if (op === 'neq') return !valuesEqual(l, r);\n");
+    out.push_str("  return false;\n");
+    out.push_str("}\n\n");
+}
@@ -1,3 +1,4 @@
+use super::{push_expr_ops_js, push_statechart_js};
 use crate::target_ir::TargetIr;
 
 pub fn generate_web_js(target: &TargetIr) -> String {
@@ -11,7 +12,9 @@ pub fn generate_web_js(target: &TargetIr) -> String {
     out.push_str(
         "const STATE = (TARGET.state && typeof TARGET.state === 'object') ? TARGET.state : {};\n",
     );
-    out.push_str("let state = FLOW.start || '';\n");
+    push_statechart_js(&mut out);
+    out.push_str("let active = enterTarget(FLOW.start || '', null);\n");
+    out.push_str("let state = active[0] || '';\n");
     out.push_str("let snake = null;\n");
     out.push_str("let tickHandle = null;\n\n");
 
@@ -159,7 +162,9 @@ pub fn generate_web_js(target: &TargetIr) -> String {
     out.push_str("  const map = {\n");
     out.push_str("    cyan: '#00ffff', blue: '#58b6ff', yellow: '#ffd166', red: '#ea5172',\n");
     out.push_str("    green: '#66f7a8', white: '#eaf5ff', heading: '#00ffff',\n");
-    out.push_str("    success: '#66f7a8', warning: '#ffd166', danger: '#ea5172', info: '#58b6ff'\n");
+    out.push_str(
+        "    success: '#66f7a8', warning: '#ffd166', danger: '#ea5172', info: '#58b6ff'\n",
+    );
     out.push_str("  };\n");
     out.push_str("  return map[key] || key;\n");
     out.push_str("}\n\n");
@@ -169,7 +174,22 @@ pub fn generate_web_js(target: &TargetIr) -> String {
     out.push_str("    const c = uiColor(item.color);\n");
     out.push_str("    if (c) el.style.color = c;\n");
     out.push_str("  }\n");
-    out.push_str("  if (item.css && typeof item.css === 'object') Object.assign(el.style, item.css);\n");
+    out.push_str(
+        "  if (item.css && typeof item.css === 'object') Object.assign(el.style, item.css);\n",
+    );
+    out.push_str("}\n\n");
+
+    out.push_str("function templateText(parts) {\n");
+    out.push_str("  return parts.map((part) => {\n");
+    out.push_str("    if (part && typeof part.text === 'string') return part.text;\n");
+    out.push_str("    const value = guardValue(part ? part.expr : null);\n");
+    out.push_str("    return value === null || value === undefined ? '' : String(value);\n");
+    out.push_str("  }).join('');\n");
+    out.push_str("}\n\n");
+
+    out.push_str("function itemText(item, fallback) {\n");
+    out.push_str("  if (!Array.isArray(item.textTemplate)) return item.text || fallback;\n");
+    out.push_str("  return templateText(item.textTemplate);\n");
     out.push_str("}\n\n");
 
     out.push_str("function buildWidget(item) {\n");
@@ -177,7 +197,7 @@ pub fn generate_web_js(target: &TargetIr) -> String {
     out.push_str("  if (kind === 'button') {\n");
     out.push_str("    const btn = document.createElement('button');\n");
     out.push_str("    btn.className = 'sculpt-btn';\n");
-    out.push_str("    btn.textContent = item.text || 'Action';\n");
+    out.push_str("    btn.textContent = itemText(item, 'Action');\n");
     out.push_str("    btn.addEventListener('click', () => {\n");
     out.push_str("      const action = item.action ? String(item.action) : '';\n");
     out.push_str("      if (action) dispatch(`input.click(${action})`);\n");
@@ -189,7 +209,7 @@ pub fn generate_web_js(target: &TargetIr) -> String {
     out.push_str("  if (kind === 'input') {\n");
     out.push_str("    const field = document.createElement('input');\n");
     out.push_str("    field.className = 'sculpt-input';\n");
-    out.push_str("    field.placeholder = item.text || 'Input';\n");
+    out.push_str("    field.placeholder = itemText(item, 'Input');\n");
     out.push_str("    field.addEventListener('keydown', (e) => {\n");
     out.push_str("      if (e.key === 'Enter') {\n");
     out.push_str("        const action = item.action ? String(item.action) : '';\n");
@@ -203,7 +223,7 @@ pub fn generate_web_js(target: &TargetIr) -> String {
     out.push_str("    const area = document.createElement('textarea');\n");
     out.push_str("    area.className = 'sculpt-input';\n");
     out.push_str("    area.rows = 4;\n");
-    out.push_str("    area.placeholder = item.text || 'Notes';\n");
+    out.push_str("    area.placeholder = itemText(item, 'Notes');\n");
     out.push_str("    return area;\n");
     out.push_str("  }\n");
     out.push_str("  const tag = kind === 'heading' ? 'h2' : (kind === 'table' ? 'pre' : 'div');\n");
@@ -216,7 +236,9 @@ pub fn generate_web_js(target: &TargetIr) -> String {
     out.push_str("  else if (kind === 'tabs') el.className = 'sculpt-tabs';\n");
     out.push_str("  else if (kind === 'list') el.className = 'sculpt-list';\n");
     out.push_str("  else el.className = 'sculpt-text';\n");
-    out.push_str("  el.textContent = kind === 'list' ? `• ${item.text || ''}` : (item.text || '');\n");
+    out.push_str(
+        "  el.textContent = kind === 'list' ? `• ${itemText(item, '')}` : itemText(item, '');\n",
+    );
     out.push_str("  applyItemStyle(el, item);\n");
     out.push_str("  return el;\n");
     out.push_str("}\n\n");
@@ -238,7 +260,7 @@ pub fn generate_web_js(target: &TargetIr) -> String {
     out.push_str("  shell.appendChild(title);\n");
     out.push_str("  const body = document.createElement('div');\n");
     out.push_str("  body.className = 'sculpt-body';\n");
-    out.push_str("  const items = VIEWS[state] || [];\n");
+    out.push_str("  const items = activeStates(active).flatMap((s) => VIEWS[s] || []);\n");
     out.push_str("  for (const item of items) body.appendChild(buildWidget(item));\n");
     out.push_str("  shell.appendChild(body);\n");
     out.push_str("  root.appendChild(shell);\n");
@@ -263,12 +285,59 @@ pub fn generate_web_js(target: &TargetIr) -> String {
     out.push_str("  tickHandle = null;\n");
     out.push_str("}\n\n");
 
+    push_expr_ops_js(&mut out);
+
+    out.push_str("function guardValue(v) {\n");
+    out.push_str("  if (v && typeof v === 'object' && !Array.isArray(v)) {\n");
+    out.push_str("    if (typeof v.ident === 'string') return STATE[v.ident];\n");
+    out.push_str("    if (Array.isArray(v.template)) return templateText(v.template);\n");
+    out.push_str("    if (Array.isArray(v.list)) return v.list.map(guardValue);\n");
+    out.push_str("    if (v.record && typeof v.record === 'object') {\n");
+    out.push_str("      const out = {};\n");
+    out.push_str(
+        "      for (const [k, item] of Object.entries(v.record)) out[k] = guardValue(item);\n",
+    );
+    out.push_str("      return out;\n");
+    out.push_str("    }\n");
+    out.push_str("    if (v.unary) return unaryOp(v.unary.op, guardValue(v.unary.operand));\n");
+    out.push_str("    if (v.binary) return binaryOp(v.binary.op, guardValue(v.binary.left), guardValue(v.binary.right));\n");
+    out.push_str("    return null;\n");
+    out.push_str("  }\n");
+    out.push_str("  return v;\n");
+    out.push_str("}\n\n");
+
+    out.push_str("const STATE_INIT = (TARGET.extensions && TARGET.extensions.stateInit) || [];\n");
+    out.push_str("for (const init of STATE_INIT) STATE[init.target] = guardValue(init.value);\n\n");
+
+    out.push_str("function evalGuard(when) {\n");
+    out.push_str("  if (!when || typeof when !== 'object') return false;\n");
+    out.push_str("  if (when.kind === 'logic') return when.op === 'and' ? evalGuard(when.left) && evalGuard(when.right) : evalGuard(when.left) || evalGuard(when.right);\n");
+    out.push_str("  if (when.kind === 'not') return !evalGuard(when.operand);\n");
+    out.push_str("  if (when.kind === 'expr') return !!guardValue(when.expr);\n");
+    out.push_str(
+        "  return compareWhen(when.op, STATE[String(when.left)], guardValue(when.right));\n",
+    );
+    out.push_str("}\n\n");
+
+    out.push_str("function transitionTarget(s, event) {\n");
+    out.push_str("  if (event.startsWith('timer(')) return timerTarget(s, event);\n");
+    out.push_str(
+        "  const guarded = (FLOW.guards && FLOW.guards[s] && FLOW.guards[s][event]) || [];\n",
+    );
+    out.push_str("  const hit = guarded.find((g) => g && evalGuard(g.when));\n");
+    out.push_str("  if (hit) return hit.target;\n");
+    out.push_str("  const map = (FLOW.transitions && FLOW.transitions[s]) || {};\n");
+    out.push_str("  return map[event] || null;\n");
+    out.push_str("}\n\n");
+
     out.push_str("function dispatch(event) {\n");
     out.push_str("  const before = state;\n");
+    out.push_str("  const beforeActive = active.join('\\n');\n");
     out.push_str("  if (IS_SNAKE && event.startsWith('key(')) handleSnakeInput(event);\n");
-    out.push_str("  const map = (FLOW.transitions && FLOW.transitions[state]) || {};\n");
-    out.push_str("  if (map[event]) state = map[event];\n");
-    out.push_str("  if (state !== before) {\n");
+    out.push_str("  active = nextActive(active, event, transitionTarget);\n");
+    out.push_str("  state = active[0] || '';\n");
+    out.push_str("  syncTimers(activeStates(active), dispatch);\n");
+    out.push_str("  if (active.join('\\n') !== beforeActive) {\n");
     out.push_str("    if (IS_SNAKE) {\n");
     out.push_str("      if (state === 'Play') {\n");
     out.push_str("        if (!snake || before !== 'Play') initSnake();\n");
@@ -297,6 +366,7 @@ pub fn generate_web_js(target: &TargetIr) -> String {
     out.push_str("window.addEventListener('DOMContentLoaded', () => {\n");
     out.push_str("  applyRender();\n");
     out.push_str("  if (IS_SNAKE && state === 'Play') startTicker();\n");
+    out.push_str("  syncTimers(activeStates(active), dispatch);\n");
     out.push_str("  window.addEventListener('keydown', (e) => {\n");
     out.push_str("    const key = normalizeKey(e.key);\n");
     out.push_str("    dispatch(`key(${key})`);\n");
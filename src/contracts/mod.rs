@@ -3,7 +3,8 @@ use std::collections::{BTreeMap, HashSet};
 use anyhow::{bail, Result};
 use serde_json::Value;
 
-use crate::ast::{Call, Expr, Rule, RuleTrigger, StateStmt};
+use crate::ast::{walk_states, Call, Expr, Rule, RuleTrigger, Span, StateStmt, StringPart};
+use crate::diagnostics::{closest_name, Diagnostic, DiagnosticsError};
 use crate::ir::IrModule;
 
 #[derive(Debug, Clone)]
@@ -37,6 +38,13 @@ struct ContractPackage {
     exports: HashSet<String>,
 }
 
+impl TargetContract {
+    /// Id of the package that provides `namespace` (`ui` -> `builtin.cli.ui@1`).
+    pub fn package_id(&self, namespace: &str) -> Option<&str> {
+        self.packages.get(namespace).map(|pkg| pkg.id.as_str())
+    }
+}
+
 pub fn parse_target_contract(spec: &Value) -> Result<TargetContract> {
     let contract = spec
         .get("contract")
@@ -108,28 +116,60 @@ pub fn validate_module_against_contract(
     target: &str,
     contract: &TargetContract,
 ) -> Result<()> {
+    let errors = check_module_against_contract(ir, target, contract);
+    if !errors.is_empty() {
+        bail!(DiagnosticsError::new(errors));
+    }
+    Ok(())
+}
+
+/// Checks the module against the target contract: `@meta` keys and values, required
+/// capabilities, package exports and deterministic call signatures (`C9xx`).
+pub fn check_module_against_contract(
+    ir: &IrModule,
+    target: &str,
+    contract: &TargetContract,
+) -> Vec<Diagnostic> {
     let mut errors = Vec::new();
 
     for (key, value) in &ir.meta {
         let Some(spec) = contract.meta_schema.get(key) else {
             if !key.starts_with("x_") {
-                errors.push(format!(
-          "C903: Unknown @meta key '{}' for target '{}' (declare it in target contract meta schema)",
+                errors.push(
+                    Diagnostic::new(
+                        "C903",
+                        format!(
+          "Unknown @meta key '{}' for target '{}' (declare it in target contract meta schema)",
           key, target
-        ));
+        ),
+                    )
+                    .with_meta_span(&ir.meta_spans, key),
+                );
             }
             continue;
         };
-        validate_meta_value(spec, value, target, &mut errors);
+        let mut value_errors = Vec::new();
+        validate_meta_value(spec, value, target, &mut value_errors);
+        errors.extend(
+            value_errors
+                .into_iter()
+                .map(|error| error.with_meta_span(&ir.meta_spans, key)),
+        );
     }
 
     if let Some(raw) = ir.meta.get("requires") {
         for capability in parse_capability_list(raw) {
             if !contract.capabilities.contains(&capability) {
-                errors.push(format!(
-                    "C902: Required capability '{}' is not provided by target '{}'",
-                    capability, target
-                ));
+                errors.push(
+                    Diagnostic::new(
+                        "C902",
+                        format!(
+                            "Required capability '{}' is not provided by target '{}'",
+                            capability, target
+                        ),
+                    )
+                    .with_meta_span(&ir.meta_spans, "requires"),
+                );
             }
         }
     }
@@ -138,34 +178,37 @@ pub fn validate_module_against_contract(
         if layout.trim().eq_ignore_ascii_case("explicit")
             && !contract.capabilities.contains("layout.explicit")
         {
-            errors.push(format!(
-                "C904: layout=explicit requires capability 'layout.explicit' on target '{}'",
-                target
-            ));
+            errors.push(
+                Diagnostic::new(
+                    "C904",
+                    format!(
+                        "layout=explicit requires capability 'layout.explicit' on target '{}'",
+                        target
+                    ),
+                )
+                .with_meta_span(&ir.meta_spans, "layout"),
+            );
         }
     }
 
     if let Some(raw) = ir.meta.get("contract_version") {
         match raw.trim().parse::<u32>() {
             Ok(v) if v == contract.version => {}
-            Ok(v) => errors.push(format!(
-                "C915: @meta contract_version='{}' mismatches target '{}' contract version '{}' (update script or target contract)",
+            Ok(v) => errors.push(Diagnostic::new("C915", format!(
+                "@meta contract_version='{}' mismatches target '{}' contract version '{}' (update script or target contract)",
                 v, target, contract.version
-            )),
-            Err(_) => errors.push(format!(
-                "C901: @meta contract_version='{}' is invalid for target '{}' (expected integer)",
+            )).with_meta_span(&ir.meta_spans, "contract_version")),
+            Err(_) => errors.push(Diagnostic::new("C901", format!(
+                "@meta contract_version='{}' is invalid for target '{}' (expected integer)",
                 raw, target
-            )),
+            )).with_meta_span(&ir.meta_spans, "contract_version")),
         }
     }
 
     validate_symbols_against_packages(ir, contract, target, &mut errors);
     validate_portable_profile(ir, target, &mut errors);
-
-    if !errors.is_empty() {
-        bail!("{}", errors.join("\n"));
-    }
-    Ok(())
+    validate_runtime_features(ir, target, contract, &mut errors);
+    errors
 }
 
 pub fn contract_signature_for_symbol(namespace: &str, symbol: &str) -> Option<&'static str> {
@@ -180,7 +223,146 @@ pub fn contract_signature_for_symbol(namespace: &str, symbol: &str) -> Option<&'
     }
 }
 
-fn validate_portable_profile(ir: &IrModule, target: &str, errors: &mut Vec<String>) {
+/// Parameter types of contract calls, as `(name, type)` pairs in positional order. Types use
+/// the state annotation spelling; `a|b` accepts either. Used by the semantic type checker.
+pub fn contract_param_types(
+    namespace: &str,
+    symbol: &str,
+) -> Option<&'static [(&'static str, &'static str)]> {
+    match namespace {
+        "ui" => ui_param_types(symbol),
+        "window" => window_param_types(symbol),
+        "data" => data_param_types(symbol),
+        _ => None,
+    }
+}
+
+fn ui_param_types(symbol: &str) -> Option<&'static [(&'static str, &'static str)]> {
+    match symbol {
+        "text" | "line" => Some(&[("content", "any"), ("color", "string")]),
+        "heading" => Some(&[("text", "string"), ("level", "int"), ("color", "string")]),
+        "button" => Some(&[("label", "string"), ("action", "string")]),
+        "panel" | "card" => Some(&[("title", "string"), ("style", "string")]),
+        "badge" | "toast" => Some(&[("text", "string"), ("tone", "string")]),
+        "banner" => Some(&[
+            ("title", "string"),
+            ("subtitle", "string"),
+            ("tone", "string"),
+        ]),
+        "status" => Some(&[("label", "string"), ("value", "any"), ("tone", "string")]),
+        "metric" => Some(&[("label", "string"), ("value", "any"), ("trend", "string")]),
+        "progress" => Some(&[("label", "string"), ("value", "float"), ("max", "float")]),
+        "list" => Some(&[("items", "list|string"), ("style", "string")]),
+        "table" => Some(&[("rows", "list|string"), ("columns", "list|string")]),
+        "tabs" => Some(&[("items", "list|string"), ("active", "any")]),
+        "select" => Some(&[
+            ("name", "string"),
+            ("options", "list|string"),
+            ("value", "any"),
+        ]),
+        "input" | "textarea" => Some(&[
+            ("name", "string"),
+            ("placeholder", "string"),
+            ("value", "any"),
+        ]),
+        "link" => Some(&[("label", "string"), ("href", "string")]),
+        "image" => Some(&[("source", "string"), ("alt", "string")]),
+        "spacer" => Some(&[("size", "float")]),
+        _ => None,
+    }
+}
+
+fn window_param_types(symbol: &str) -> Option<&'static [(&'static str, &'static str)]> {
+    match symbol {
+        "open" | "close" => Some(&[("name", "string")]),
+        "resize" => Some(&[("width", "int"), ("height", "int")]),
+        "modalOk" | "modalConfirm" => Some(&[("title", "string"), ("message", "string")]),
+        "notify" => Some(&[
+            ("message", "string"),
+            ("level", "string"),
+            ("tone", "string"),
+        ]),
+        _ => None,
+    }
+}
+
+fn data_param_types(symbol: &str) -> Option<&'static [(&'static str, &'static str)]> {
+    match symbol {
+        "csvRead" => Some(&[("path", "string")]),
+        "csvHasColumns" | "csvMissingColumns" => {
+            Some(&[("rows", "any"), ("requiredColumns", "list|string")])
+        }
+        "sortBy" => Some(&[("rows", "any"), ("keys", "list|string")]),
+        "metric" => Some(&[("reconciliation", "any"), ("key", "string")]),
+        "reconcileInvoices" => Some(&[
+            ("invoices", "any"),
+            ("payments", "any"),
+            ("dateToleranceDays", "float"),
+            ("amountTolerance", "float"),
+        ]),
+        "writeJson" => Some(&[("path", "string"), ("jsonObject", "any")]),
+        "writeCsv" => Some(&[("path", "string"), ("rows", "any")]),
+        "summaryLine" => Some(&[("label", "string"), ("value", "any")]),
+        "paginate" => Some(&[("rows", "any"), ("page", "int"), ("pageSize", "int")]),
+        _ => None,
+    }
+}
+
+/// Language features the generated runtime has to implement are only accepted when the
+/// target contract declares the matching capability (`C916`).
+fn validate_runtime_features(
+    ir: &IrModule,
+    target: &str,
+    contract: &TargetContract,
+    errors: &mut Vec<Diagnostic>,
+) {
+    let mut require = |capability: &str, feature: &str, span: &Span| {
+        if !contract.capabilities.contains(capability) {
+            errors.push(
+                Diagnostic::new(
+                    "C916",
+                    format!(
+                        "{} is not supported by target '{}' (needs capability '{}')",
+                        feature, target, capability
+                    ),
+                )
+                .with_span(span),
+            );
+        }
+    };
+    for flow in &ir.flows {
+        for state in walk_states(&flow.states) {
+            if !state.states.is_empty() || !state.regions.is_empty() {
+                require("flow.hierarchy", "Nested or parallel states", &state.span);
+            }
+            for stmt in &state.statements {
+                match stmt {
+                    StateStmt::Expr(call)
+                        if call
+                            .args
+                            .iter()
+                            .any(|arg| matches!(arg.value, Expr::Interpolated(_))) =>
+                    {
+                        require("text.interpolation", "String interpolation", &call.span);
+                    }
+                    StateStmt::On {
+                        guard: Some(_),
+                        span,
+                        ..
+                    } => require("flow.guards", "Guarded transition", span),
+                    _ => {}
+                }
+            }
+        }
+    }
+    for rule in &ir.rules {
+        if let RuleTrigger::Every(_) = rule.trigger {
+            require("rules.every", "Periodic rule", &rule.span);
+        }
+    }
+}
+
+fn validate_portable_profile(ir: &IrModule, target: &str, errors: &mut Vec<Diagnostic>) {
     let profile = ir
         .meta
         .get("profile")
@@ -202,10 +384,10 @@ fn validate_portable_profile(ir: &IrModule, target: &str, errors: &mut Vec<Strin
             .to_string();
         let alias = use_decl.alias.clone().unwrap_or_else(|| namespace.clone());
         if !portable_namespace_exports.contains_key(namespace.as_str()) {
-            errors.push(format!(
-                "C913: @meta profile=portable only allows use(ui|input|data). Found use({}) for target '{}'",
+            errors.push(Diagnostic::new("C913", format!(
+                "@meta profile=portable only allows use(ui|input|data). Found use({}) for target '{}'",
                 use_decl.path, target
-            ));
+            )).with_span(&use_decl.span));
             continue;
         }
         alias_to_namespace.insert(alias, namespace);
@@ -224,24 +406,24 @@ fn validate_portable_profile(ir: &IrModule, target: &str, errors: &mut Vec<Strin
         if !exports.contains(symbol) {
             let mut list: Vec<_> = exports.iter().copied().collect();
             list.sort_unstable();
-            errors.push(format!(
-                "C914: '{}.{}' is not portable (target '{}', context: {}). Allowed portable {} exports: {}",
+            errors.push(Diagnostic::new("C914", format!(
+                "'{}.{}' is not portable (target '{}', context: {}). Allowed portable {} exports: {}",
                 root,
                 symbol,
                 target,
                 ctx,
                 namespace,
                 list.join(", ")
-            ));
+            )).with_span(&call.span));
         }
     };
 
     for flow in &ir.flows {
-        for state in &flow.states {
+        for state in walk_states(&flow.states) {
             let state_name = state.name.as_deref().unwrap_or("<unnamed>");
             for stmt in &state.statements {
                 match stmt {
-                    StateStmt::On { .. } => {}
+                    StateStmt::On { .. } | StateStmt::After { .. } => {}
                     StateStmt::Expr(call) => check_call(
                         call,
                         &format!("flow '{}', state '{}', expression", flow.name, state_name),
@@ -257,7 +439,26 @@ fn validate_portable_profile(ir: &IrModule, target: &str, errors: &mut Vec<Strin
                         flow.name.as_str(),
                         state_name,
                     ),
-                    StateStmt::Run { .. } | StateStmt::Terminate => {}
+                    StateStmt::Run { args, .. } => {
+                        for arg in args {
+                            walk_expr_calls(
+                                &arg.value,
+                                &mut check_call,
+                                &format!(
+                                    "flow '{}', state '{}', run argument",
+                                    flow.name, state_name
+                                ),
+                            );
+                        }
+                    }
+                    StateStmt::Return {
+                        value: Some(value), ..
+                    } => walk_expr_calls(
+                        value,
+                        &mut check_call,
+                        &format!("flow '{}', state '{}', return value", flow.name, state_name),
+                    ),
+                    StateStmt::Return { value: None, .. } | StateStmt::Terminate => {}
                 }
             }
         }
@@ -314,7 +515,7 @@ fn validate_symbols_against_packages(
     ir: &IrModule,
     contract: &TargetContract,
     target: &str,
-    errors: &mut Vec<String>,
+    errors: &mut Vec<Diagnostic>,
 ) {
     if contract.packages.is_empty() {
         return;
@@ -330,10 +531,24 @@ fn validate_symbols_against_packages(
             .to_string();
         let alias = use_decl.alias.clone().unwrap_or_else(|| namespace.clone());
         let Some(pkg) = contract.packages.get(&namespace) else {
-            errors.push(format!(
-                "C905: Unknown package namespace '{}' in use({}) for target '{}'",
-                namespace, use_decl.path, target
-            ));
+            let mut diagnostic = Diagnostic::new(
+                "C905",
+                format!(
+                    "Unknown package namespace '{}' in use({}) for target '{}'",
+                    namespace, use_decl.path, target
+                ),
+            )
+            .with_span(&use_decl.span);
+            if let (Some(at), Some((best, unique))) = (
+                use_decl.span.find_word(&use_decl.path),
+                closest_name(&namespace, contract.packages.keys().map(String::as_str)),
+            ) {
+                let prefix = &use_decl.path[..use_decl.path.len() - namespace.len()];
+                let replacement = format!("{}{}", prefix, best);
+                let message = format!("did you mean '{}'?", replacement);
+                diagnostic = diagnostic.with_suggestion(&at, replacement, message, unique);
+            }
+            errors.push(diagnostic);
             continue;
         };
         let _ = &pkg.namespace;
@@ -346,17 +561,17 @@ fn validate_symbols_against_packages(
                 continue;
             }
             let Some((root, symbol)) = split_qualified_call(&c.name) else {
-                errors.push(format!(
-                    "C910: ND constraint '{}' in nd '{}' must be explicit: use '?define(...)', '?\"...\"', or a namespaced contract call (e.g. guide.{})",
+                errors.push(Diagnostic::new("C910", format!(
+                    "ND constraint '{}' in nd '{}' must be explicit: use '?define(...)', '?\"...\"', or a namespaced contract call (e.g. guide.{})",
                     c.name, nd.name, c.name
-                ));
+                )).with_span(&c.span));
                 continue;
             };
             let Some(namespace) = alias_to_namespace.get(root) else {
-                errors.push(format!(
-                    "C911: ND constraint '{}.{}' in nd '{}' uses unknown alias '{}' (import the package with use(...))",
+                errors.push(Diagnostic::new("C911", format!(
+                    "ND constraint '{}.{}' in nd '{}' uses unknown alias '{}' (import the package with use(...))",
                     root, symbol, nd.name, root
-                ));
+                )).with_span(&c.span));
                 continue;
             };
             let Some(pkg) = contract.packages.get(namespace) else {
@@ -365,14 +580,25 @@ fn validate_symbols_against_packages(
             if !pkg.exports.contains(symbol) {
                 let mut exports: Vec<_> = pkg.exports.iter().cloned().collect();
                 exports.sort();
-                errors.push(format!(
-                    "C906: Symbol '{}.{}' not exported by package '{}' (target '{}', context: nd '{}' satisfy, exports: {})",
+                let diagnostic = Diagnostic::new(
+                    "C906",
+                    format!(
+                        "Symbol '{}.{}' not exported by package '{}' (target '{}', context: nd '{}' satisfy, exports: {})",
+                        root,
+                        symbol,
+                        pkg.id,
+                        target,
+                        nd.name,
+                        exports.join(", ")
+                    ),
+                )
+                .with_span(&c.span);
+                errors.push(suggest_export(
+                    diagnostic,
                     root,
                     symbol,
-                    pkg.id,
-                    target,
-                    nd.name,
-                    exports.join(", ")
+                    &pkg.exports,
+                    &c.span,
                 ));
             }
         }
@@ -391,26 +617,37 @@ fn validate_symbols_against_packages(
         if !pkg.exports.contains(symbol) {
             let mut exports: Vec<_> = pkg.exports.iter().cloned().collect();
             exports.sort();
-            errors.push(format!(
-        "C906: Symbol '{}.{}' not exported by package '{}' (target '{}', context: {}, exports: {})",
-        root,
-        symbol,
-        pkg.id,
-        target,
-        ctx,
-        exports.join(", ")
-        ));
+            let diagnostic = Diagnostic::new(
+                "C906",
+                format!(
+                    "Symbol '{}.{}' not exported by package '{}' (target '{}', context: {}, exports: {})",
+                    root,
+                    symbol,
+                    pkg.id,
+                    target,
+                    ctx,
+                    exports.join(", ")
+                ),
+            )
+            .with_span(&call.span);
+            errors.push(suggest_export(
+                diagnostic,
+                root,
+                symbol,
+                &pkg.exports,
+                &call.span,
+            ));
             return;
         }
         validate_namespaced_contract_signature(namespace, symbol, call, target, ctx, errors);
     };
 
     for flow in &ir.flows {
-        for state in &flow.states {
+        for state in walk_states(&flow.states) {
             let state_name = state.name.as_deref().unwrap_or("<unnamed>");
             for stmt in &state.statements {
                 match stmt {
-                    StateStmt::On { .. } => {}
+                    StateStmt::On { .. } | StateStmt::After { .. } => {}
                     StateStmt::Expr(call) => check_call(
                         call,
                         &format!("flow '{}', state '{}', expression", flow.name, state_name),
@@ -423,7 +660,26 @@ fn validate_symbols_against_packages(
                     StateStmt::Rule(rule) => {
                         validate_rule_calls(rule, &mut check_call, flow.name.as_str(), state_name)
                     }
-                    StateStmt::Run { .. } | StateStmt::Terminate => {}
+                    StateStmt::Run { args, .. } => {
+                        for arg in args {
+                            walk_expr_calls(
+                                &arg.value,
+                                &mut check_call,
+                                &format!(
+                                    "flow '{}', state '{}', run argument",
+                                    flow.name, state_name
+                                ),
+                            );
+                        }
+                    }
+                    StateStmt::Return {
+                        value: Some(value), ..
+                    } => walk_expr_calls(
+                        value,
+                        &mut check_call,
+                        &format!("flow '{}', state '{}', return value", flow.name, state_name),
+                    ),
+                    StateStmt::Return { value: None, .. } | StateStmt::Terminate => {}
                 }
             }
         }
@@ -443,7 +699,27 @@ fn validate_symbols_against_packages(
     validate_deterministic_runtime_calls(ir, target, errors);
 }
 
-fn validate_deterministic_runtime_calls(ir: &IrModule, target: &str, errors: &mut Vec<String>) {
+/// Suggests the export closest to `symbol` for the call `root.symbol` inside `span`.
+fn suggest_export(
+    diagnostic: Diagnostic,
+    root: &str,
+    symbol: &str,
+    exports: &HashSet<String>,
+    span: &Span,
+) -> Diagnostic {
+    let name = format!("{}.{}", root, symbol);
+    let (Some(at), Some((best, unique))) = (
+        span.find_word(&name),
+        closest_name(symbol, exports.iter().map(String::as_str)),
+    ) else {
+        return diagnostic;
+    };
+    let replacement = format!("{}.{}", root, best);
+    let message = format!("did you mean '{}'?", replacement);
+    diagnostic.with_suggestion(&at, replacement, message, unique)
+}
+
+fn validate_deterministic_runtime_calls(ir: &IrModule, target: &str, errors: &mut Vec<Diagnostic>) {
     if target != "cli" {
         return;
     }
@@ -454,22 +730,28 @@ fn validate_deterministic_runtime_calls(ir: &IrModule, target: &str, errors: &mu
         }
 
         let Some(expected_arity) = cli_runtime_call_arity(&call.name) else {
-            errors.push(format!(
-                "C907: Unknown unqualified deterministic call '{}' (target '{}', context: {})",
-                call.name, target, ctx
-            ));
+            errors.push(
+                Diagnostic::new(
+                    "C907",
+                    format!(
+                        "Unknown unqualified deterministic call '{}' (target '{}', context: {})",
+                        call.name, target, ctx
+                    ),
+                )
+                .with_span(&call.span),
+            );
             return;
         };
 
         if call.args.len() != expected_arity {
-            errors.push(format!(
-                "C908: Invalid arg count for deterministic call '{}' (expected {}, got {}, target '{}', context: {})",
+            errors.push(Diagnostic::new("C908", format!(
+                "Invalid arg count for deterministic call '{}' (expected {}, got {}, target '{}', context: {})",
                 call.name,
                 expected_arity,
                 call.args.len(),
                 target,
                 ctx
-            ));
+            )).with_span(&call.span));
             return;
         }
 
@@ -477,11 +759,11 @@ fn validate_deterministic_runtime_calls(ir: &IrModule, target: &str, errors: &mu
     };
 
     for flow in &ir.flows {
-        for state in &flow.states {
+        for state in walk_states(&flow.states) {
             let state_name = state.name.as_deref().unwrap_or("<unnamed>");
             for stmt in &state.statements {
                 match stmt {
-                    StateStmt::On { .. } => {}
+                    StateStmt::On { .. } | StateStmt::After { .. } => {}
                     StateStmt::Expr(call) => check_call(
                         call,
                         &format!("flow '{}', state '{}', expression", flow.name, state_name),
@@ -497,7 +779,26 @@ fn validate_deterministic_runtime_calls(ir: &IrModule, target: &str, errors: &mu
                         flow.name.as_str(),
                         state_name,
                     ),
-                    StateStmt::Run { .. } | StateStmt::Terminate => {}
+                    StateStmt::Run { args, .. } => {
+                        for arg in args {
+                            walk_expr_calls(
+                                &arg.value,
+                                &mut check_call,
+                                &format!(
+                                    "flow '{}', state '{}', run argument",
+                                    flow.name, state_name
+                                ),
+                            );
+                        }
+                    }
+                    StateStmt::Return {
+                        value: Some(value), ..
+                    } => walk_expr_calls(
+                        value,
+                        &mut check_call,
+                        &format!("flow '{}', state '{}', return value", flow.name, state_name),
+                    ),
+                    StateStmt::Return { value: None, .. } | StateStmt::Terminate => {}
                 }
             }
         }
@@ -508,7 +809,12 @@ fn validate_deterministic_runtime_calls(ir: &IrModule, target: &str, errors: &mu
     }
 }
 
-fn validate_cli_runtime_signature(call: &Call, target: &str, ctx: &str, errors: &mut Vec<String>) {
+fn validate_cli_runtime_signature(
+    call: &Call,
+    target: &str,
+    ctx: &str,
+    errors: &mut Vec<Diagnostic>,
+) {
     validate_data_signature(call.name.as_str(), call, target, ctx, errors);
 }
 
@@ -518,7 +824,7 @@ fn validate_namespaced_contract_signature(
     call: &Call,
     target: &str,
     ctx: &str,
-    errors: &mut Vec<String>,
+    errors: &mut Vec<Diagnostic>,
 ) {
     if namespace == "data" {
         validate_data_signature(symbol, call, target, ctx, errors);
@@ -530,7 +836,7 @@ fn validate_data_signature(
     call: &Call,
     target: &str,
     ctx: &str,
-    errors: &mut Vec<String>,
+    errors: &mut Vec<Diagnostic>,
 ) {
     let arg_expr = |idx: usize| call.args.get(idx).map(|a| &a.value);
     let literal_string = |idx: usize| match arg_expr(idx) {
@@ -540,14 +846,20 @@ fn validate_data_signature(
 
     if let Some(expected) = cli_runtime_call_arity(symbol) {
         if call.args.len() != expected {
-            errors.push(format!(
-                "C908: Invalid arg count for call '{}' (expected {}, got {}, target '{}', context: {})",
+            errors.push(
+                Diagnostic::new(
+                    "C908",
+                    format!(
+                "Invalid arg count for call '{}' (expected {}, got {}, target '{}', context: {})",
                 call.name,
                 expected,
                 call.args.len(),
                 target,
                 ctx
-            ));
+            ),
+                )
+                .with_span(&call.span),
+            );
             return;
         }
     }
@@ -555,64 +867,27 @@ fn validate_data_signature(
     match symbol {
         "csvRead" => {
             if !is_path_like_expr(arg_expr(0)) {
-                errors.push(format!(
-          "C909: Invalid signature for '{}' (arg1 must be path-like string or identifier, target '{}', context: {})",
+                errors.push(Diagnostic::new("C909", format!(
+          "Invalid signature for '{}' (arg1 must be path-like string or identifier, target '{}', context: {})",
           call.name, target, ctx
-        ));
+        )).with_span(&call.span));
             }
         }
-        "csvHasColumns" => {
-            if let Some(columns) = literal_string(1) {
-                let cols: Vec<_> = columns
-                    .split(',')
-                    .map(|c| c.trim())
-                    .filter(|c| !c.is_empty())
-                    .collect();
-                if cols.is_empty() {
-                    errors.push(format!(
-            "C909: Invalid signature for '{}' (arg2 column list is empty, target '{}', context: {})",
-            call.name, target, ctx
-          ));
-                }
-            } else if !is_identifier_expr(arg_expr(1)) {
-                errors.push(format!(
-          "C909: Invalid signature for '{}' (arg2 must be comma-separated string or identifier, target '{}', context: {})",
-          call.name, target, ctx
-        ));
-            }
-        }
-        "csvMissingColumns" => {
-            if let Some(columns) = literal_string(1) {
-                let cols: Vec<_> = columns
-                    .split(',')
-                    .map(|c| c.trim())
-                    .filter(|c| !c.is_empty())
-                    .collect();
-                if cols.is_empty() {
-                    errors.push(format!(
-            "C909: Invalid signature for '{}' (arg2 column list is empty, target '{}', context: {})",
-            call.name, target, ctx
-          ));
-                }
-            } else if !is_identifier_expr(arg_expr(1)) {
-                errors.push(format!(
-          "C909: Invalid signature for '{}' (arg2 must be comma-separated string or identifier, target '{}', context: {})",
-          call.name, target, ctx
-        ));
-            }
+        "csvHasColumns" | "csvMissingColumns" => {
+            validate_name_list_arg(call, arg_expr(1), "column", target, ctx, errors);
         }
         "schemaErrorMessage" => {
             if !is_identifier_expr(arg_expr(0)) {
-                errors.push(format!(
-          "C909: Invalid signature for '{}' (arg1 must be identifier from csvMissingColumns(...), target '{}', context: {})",
+                errors.push(Diagnostic::new("C909", format!(
+          "Invalid signature for '{}' (arg1 must be identifier from csvMissingColumns(...), target '{}', context: {})",
           call.name, target, ctx
-        ));
+        )).with_span(&call.span));
             }
             if !is_identifier_expr(arg_expr(1)) {
-                errors.push(format!(
-          "C909: Invalid signature for '{}' (arg2 must be identifier from csvMissingColumns(...), target '{}', context: {})",
+                errors.push(Diagnostic::new("C909", format!(
+          "Invalid signature for '{}' (arg2 must be identifier from csvMissingColumns(...), target '{}', context: {})",
           call.name, target, ctx
-        ));
+        )).with_span(&call.span));
             }
         }
         "metric" => {
@@ -627,56 +902,39 @@ fn validate_data_signature(
                     "suspicious",
                 ];
                 if !allowed.contains(&metric_name) {
-                    errors.push(format!(
-            "C909: Invalid signature for '{}' (arg2 metric key '{}' not allowed; allowed: {}, target '{}', context: {})",
+                    errors.push(Diagnostic::new("C909", format!(
+            "Invalid signature for '{}' (arg2 metric key '{}' not allowed; allowed: {}, target '{}', context: {})",
             call.name,
             metric_name,
             allowed.join(", "),
             target,
             ctx
-          ));
+          )).with_span(&call.span));
                 }
             } else if !is_identifier_expr(arg_expr(1)) {
-                errors.push(format!(
-          "C909: Invalid signature for '{}' (arg2 must be known metric key string or identifier, target '{}', context: {})",
+                errors.push(Diagnostic::new("C909", format!(
+          "Invalid signature for '{}' (arg2 must be known metric key string or identifier, target '{}', context: {})",
           call.name, target, ctx
-        ));
+        )).with_span(&call.span));
             }
         }
         "sortBy" => {
-            if let Some(keys) = literal_string(1) {
-                let parsed: Vec<_> = keys
-                    .split(',')
-                    .map(|c| c.trim())
-                    .filter(|c| !c.is_empty())
-                    .collect();
-                if parsed.is_empty() {
-                    errors.push(format!(
-            "C909: Invalid signature for '{}' (arg2 sort key list is empty, target '{}', context: {})",
-            call.name, target, ctx
-          ));
-                }
-            } else if !is_identifier_expr(arg_expr(1)) {
-                errors.push(format!(
-          "C909: Invalid signature for '{}' (arg2 must be comma-separated sort-key string or identifier, target '{}', context: {})",
-          call.name, target, ctx
-        ));
-            }
+            validate_name_list_arg(call, arg_expr(1), "sort key", target, ctx, errors);
         }
         "writeJson" | "writeCsv" => {
             if !is_path_like_expr(arg_expr(0)) {
-                errors.push(format!(
-          "C909: Invalid signature for '{}' (arg1 must be output path string or identifier, target '{}', context: {})",
+                errors.push(Diagnostic::new("C909", format!(
+          "Invalid signature for '{}' (arg1 must be output path string or identifier, target '{}', context: {})",
           call.name, target, ctx
-        ));
+        )).with_span(&call.span));
             }
         }
         "summaryLine" => {
             if literal_string(0).is_none() && !is_identifier_expr(arg_expr(0)) {
-                errors.push(format!(
-          "C909: Invalid signature for '{}' (arg1 must be label string or identifier, target '{}', context: {})",
+                errors.push(Diagnostic::new("C909", format!(
+          "Invalid signature for '{}' (arg1 must be label string or identifier, target '{}', context: {})",
           call.name, target, ctx
-        ));
+        )).with_span(&call.span));
             }
         }
         "buildReportJson" => {
@@ -699,10 +957,10 @@ fn validate_data_signature(
             for (idx, (field_name, expected)) in fields.iter().enumerate() {
                 if let Some(expr) = arg_expr(idx) {
                     match validate_arg_type(expr, *expected) {
-                        Some(msg) => errors.push(format!(
-                            "C912: buildReportJson field '{}' has invalid value ({}, target '{}', context: {})",
+                        Some(msg) => errors.push(Diagnostic::new("C912", format!(
+                            "buildReportJson field '{}' has invalid value ({}, target '{}', context: {})",
                             field_name, msg, target, ctx
-                        )),
+                        )).with_span(&call.span)),
                         None => {}
                     }
                 }
@@ -716,8 +974,8 @@ fn data_signature_doc(symbol: &str) -> Option<&'static str> {
     match symbol {
         "csvRead" => Some("data.csvRead(path)"),
         "rowCount" => Some("data.rowCount(rows)"),
-        "csvHasColumns" => Some("data.csvHasColumns(rows, requiredCsv)"),
-        "csvMissingColumns" => Some("data.csvMissingColumns(rows, requiredCsv)"),
+        "csvHasColumns" => Some("data.csvHasColumns(rows, requiredColumns)"),
+        "csvMissingColumns" => Some("data.csvMissingColumns(rows, requiredColumns)"),
         "schemaErrorMessage" => Some("data.schemaErrorMessage(missingInvoiceCols, missingPaymentCols)"),
         "reconcileInvoices" => {
             Some("data.reconcileInvoices(invoices, payments, dateToleranceDays, amountTolerance)")
@@ -729,7 +987,7 @@ fn data_signature_doc(symbol: &str) -> Option<&'static str> {
         ),
         "processingMs" => Some("data.processingMs(reconciliation)"),
         "writeJson" => Some("data.writeJson(path, jsonObject)"),
-        "sortBy" => Some("data.sortBy(rows, keyCsv)"),
+        "sortBy" => Some("data.sortBy(rows, keys)"),
         "writeCsv" => Some("data.writeCsv(path, rows)"),
         "summaryLine" => Some("data.summaryLine(label, value)"),
         "query" => Some("data.query(source, expression)"),
@@ -893,6 +1151,57 @@ fn guide_signature_doc(symbol: &str) -> Option<&'static str> {
     }
 }
 
+/// Checks arg2 of list-taking calls: a list of strings, a comma-separated string or an
+/// identifier.
+fn validate_name_list_arg(
+    call: &Call,
+    expr: Option<&Expr>,
+    noun: &str,
+    target: &str,
+    ctx: &str,
+    errors: &mut Vec<Diagnostic>,
+) {
+    let names: Vec<&str> = match expr {
+        Some(Expr::String(list)) => list.split(',').map(str::trim).collect(),
+        Some(Expr::List(items)) => {
+            let mut names = Vec::new();
+            for item in items {
+                match item {
+                    Expr::String(name) => names.push(name.trim()),
+                    _ => {
+                        errors.push(Diagnostic::new("C909", format!(
+                            "Invalid signature for '{}' (arg2 list items must be {} name strings, target '{}', context: {})",
+                            call.name, noun, target, ctx
+                        )).with_span(&call.span));
+                        return;
+                    }
+                }
+            }
+            names
+        }
+        Some(Expr::Ident(_)) => return,
+        _ => {
+            errors.push(Diagnostic::new("C909", format!(
+                "Invalid signature for '{}' (arg2 must be {} list, comma-separated string or identifier, target '{}', context: {})",
+                call.name, noun, target, ctx
+            )).with_span(&call.span));
+            return;
+        }
+    };
+    if names.iter().all(|name| name.is_empty()) {
+        errors.push(
+            Diagnostic::new(
+                "C909",
+                format!(
+                    "Invalid signature for '{}' (arg2 {} list is empty, target '{}', context: {})",
+                    call.name, noun, target, ctx
+                ),
+            )
+            .with_span(&call.span),
+        );
+    }
+}
+
 #[derive(Clone, Copy)]
 enum ArgType {
     NumberLike,
@@ -905,14 +1214,28 @@ fn validate_arg_type(expr: &Expr, expected: ArgType) -> Option<&'static str> {
     }
     match expected {
         ArgType::NumberLike => match expr {
-            Expr::Number(_) | Expr::Ident(_) | Expr::Call(_) | Expr::Binary { .. } => None,
-            Expr::String(_) => Some("expected numeric expression, got string"),
+            Expr::Number(_)
+            | Expr::Duration(_)
+            | Expr::Ident(_)
+            | Expr::Call(_)
+            | Expr::Binary { .. }
+            | Expr::Unary { .. } => None,
+            Expr::String(_) | Expr::Interpolated(_) => {
+                Some("expected numeric expression, got string")
+            }
+            Expr::Bool(_) => Some("expected numeric expression, got boolean"),
+            Expr::List(_) => Some("expected numeric expression, got list"),
+            Expr::Record(_) => Some("expected numeric expression, got record"),
             Expr::Null => Some("null is not allowed"),
         },
         ArgType::StringLike => match expr {
-            Expr::String(_) | Expr::Ident(_) => None,
+            Expr::String(_) | Expr::Interpolated(_) | Expr::Ident(_) => None,
             Expr::Number(_) => Some("expected string expression, got number"),
-            Expr::Call(_) | Expr::Binary { .. } => {
+            Expr::Duration(_) => Some("expected string expression, got duration"),
+            Expr::Bool(_) => Some("expected string expression, got boolean"),
+            Expr::List(_) => Some("expected string expression, got list"),
+            Expr::Record(_) => Some("expected string expression, got record"),
+            Expr::Call(_) | Expr::Binary { .. } | Expr::Unary { .. } => {
                 Some("expected string/identifier, got computed expression")
             }
             Expr::Null => Some("null is not allowed"),
@@ -968,6 +1291,7 @@ where
                 flow_name, state_name, rule.name
             ),
         ),
+        RuleTrigger::Every(_) => {}
     }
     for stmt in &rule.body {
         if let crate::ast::RuleStmt::Assign { value, .. } = stmt {
@@ -1021,7 +1345,30 @@ where
             walk_expr_calls(left, check_call, ctx);
             walk_expr_calls(right, check_call, ctx);
         }
-        Expr::Number(_) | Expr::String(_) | Expr::Null | Expr::Ident(_) => {}
+        Expr::Unary { operand, .. } => walk_expr_calls(operand, check_call, ctx),
+        Expr::Interpolated(parts) => {
+            for part in parts {
+                if let StringPart::Expr(expr) = part {
+                    walk_expr_calls(expr, check_call, ctx);
+                }
+            }
+        }
+        Expr::List(items) => {
+            for item in items {
+                walk_expr_calls(item, check_call, ctx);
+            }
+        }
+        Expr::Record(fields) => {
+            for field in fields {
+                walk_expr_calls(&field.value, check_call, ctx);
+            }
+        }
+        Expr::Number(_)
+        | Expr::Duration(_)
+        | Expr::String(_)
+        | Expr::Bool(_)
+        | Expr::Null
+        | Expr::Ident(_) => {}
     }
 }
 
@@ -1044,6 +1391,22 @@ fn default_meta_schema() -> BTreeMap<String, MetaFieldSpec> {
             meta_type: MetaType::String,
         },
     );
+    // Checked by semantics (`M712`).
+    map.insert(
+        "flow_analysis".to_string(),
+        MetaFieldSpec {
+            key: "flow_analysis".to_string(),
+            meta_type: MetaType::String,
+        },
+    );
+    // Checked against the supported range by semantics (`M710`, `M711`).
+    map.insert(
+        "language".to_string(),
+        MetaFieldSpec {
+            key: "language".to_string(),
+            meta_type: MetaType::String,
+        },
+    );
     map.insert(
         "contract_version".to_string(),
         MetaFieldSpec {
@@ -1176,7 +1539,12 @@ fn parse_meta_field(key: &str, value: &Value) -> Result<Option<MetaFieldSpec>> {
     }))
 }
 
-fn validate_meta_value(spec: &MetaFieldSpec, value: &str, target: &str, errors: &mut Vec<String>) {
+fn validate_meta_value(
+    spec: &MetaFieldSpec,
+    value: &str,
+    target: &str,
+    errors: &mut Vec<Diagnostic>,
+) {
     let trimmed = value.trim();
     match &spec.meta_type {
         MetaType::Bool => {
@@ -1186,36 +1554,48 @@ fn validate_meta_value(spec: &MetaFieldSpec, value: &str, target: &str, errors:
                 "1" | "0" | "true" | "false" | "yes" | "no" | "on" | "off"
             );
             if !ok {
-                errors.push(format!(
-                    "C901: @meta {}='{}' is invalid for target '{}' (expected bool)",
-                    spec.key, value, target
+                errors.push(Diagnostic::new(
+                    "C901",
+                    format!(
+                        "@meta {}='{}' is invalid for target '{}' (expected bool)",
+                        spec.key, value, target
+                    ),
                 ));
             }
         }
         MetaType::IntRange { min, max } => match trimmed.parse::<i64>() {
             Ok(v) if v >= *min && v <= *max => {}
-            _ => errors.push(format!(
-                "C901: @meta {}='{}' is invalid for target '{}' (expected int {}..{})",
-                spec.key, value, target, min, max
+            _ => errors.push(Diagnostic::new(
+                "C901",
+                format!(
+                    "@meta {}='{}' is invalid for target '{}' (expected int {}..{})",
+                    spec.key, value, target, min, max
+                ),
             )),
         },
         MetaType::FloatRange { min, max } => match trimmed.parse::<f64>() {
             Ok(v) if v >= *min && v <= *max => {}
-            _ => errors.push(format!(
-                "C901: @meta {}='{}' is invalid for target '{}' (expected float {}..{})",
-                spec.key, value, target, min, max
+            _ => errors.push(Diagnostic::new(
+                "C901",
+                format!(
+                    "@meta {}='{}' is invalid for target '{}' (expected float {}..{})",
+                    spec.key, value, target, min, max
+                ),
             )),
         },
         MetaType::Enum { values } => {
             if !values.contains(trimmed) {
                 let mut list: Vec<_> = values.iter().cloned().collect();
                 list.sort();
-                errors.push(format!(
-                    "C901: @meta {}='{}' is invalid for target '{}' (expected one of: {})",
-                    spec.key,
-                    value,
-                    target,
-                    list.join(", ")
+                errors.push(Diagnostic::new(
+                    "C901",
+                    format!(
+                        "@meta {}='{}' is invalid for target '{}' (expected one of: {})",
+                        spec.key,
+                        value,
+                        target,
+                        list.join(", ")
+                    ),
                 ));
             }
         }
@@ -1224,7 +1604,7 @@ fn validate_meta_value(spec: &MetaFieldSpec, value: &str, target: &str, errors:
     }
 }
 
-fn parse_capability_list(raw: &str) -> Vec<String> {
+pub(crate) fn parse_capability_list(raw: &str) -> Vec<String> {
     raw.split(',')
         .map(|s| s.trim())
         .filter(|s| !s.is_empty())
@@ -0,0 +1,562 @@
+//! Lossless concrete syntax tree.
+//!
+//! Every byte of the source belongs to exactly one token: its own text or the whitespace,
+//! comments and unlexable characters ("trivia") in front of it. Printing the tokens in order
+//! re-emits the file byte for byte. Nodes group token ranges by syntactic construct, and the
+//! [`Module`] AST is parsed from the tree's tokens, so tools can read the AST, edit the tree
+//! and write back without touching anything outside the edit.
+
+use anyhow::Result;
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::ast::*;
+use crate::diagnostics::{has_errors, Diagnostic};
+use crate::lexer::{lex_recovering, LexError, Token, TokenKind};
+use crate::parser::{parse_tokens, SyntaxErrors};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    /// Spaces, tabs and carriage returns. Line breaks are `Newline` tokens.
+    Whitespace,
+    /// A `#` comment, without trailing whitespace or its line break.
+    Comment,
+    /// Characters the lexer rejected (reported as `S007`).
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntaxToken {
+    pub kind: TokenKind,
+    /// Trivia between the previous token and this one.
+    pub leading_trivia: Vec<Trivia>,
+    /// The token's own source text (empty for `Eof`).
+    pub text: String,
+    /// Byte offset of `text` in the file.
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// The whole file, including trivia after the last token.
+    Module,
+    /// One `key=value` pair of an `@meta` line.
+    Meta,
+    Use,
+    Import,
+    Export,
+    Flow,
+    Start,
+    State,
+    Region,
+    On,
+    After,
+    Run,
+    Return,
+    Assign,
+    Emit,
+    Rule,
+    Nd,
+    Define,
+    Call,
+    Arg,
+    /// One `@name(...)` line above a flow, state, rule or nd block.
+    Attribute,
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntaxNode {
+    pub kind: NodeKind,
+    /// Indices into [`SyntaxTree::tokens`].
+    pub tokens: Range<usize>,
+    pub children: Vec<SyntaxNode>,
+}
+
+impl SyntaxNode {
+    /// This node and all nodes below it, in source order.
+    pub fn descendants(&self) -> Vec<&SyntaxNode> {
+        let mut out = vec![self];
+        for child in &self.children {
+            out.extend(child.descendants());
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntaxTree {
+    source: Arc<SourceFile>,
+    tokens: Vec<SyntaxToken>,
+    root: SyntaxNode,
+    module: Module,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl SyntaxTree {
+    pub fn parse(text: &str) -> Self {
+        Self::from_source(SourceFile::new("<input>", text))
+    }
+
+    pub fn parse_with_path(text: &str, path: &Path) -> Self {
+        Self::from_source(SourceFile::new(path.display().to_string(), text))
+    }
+
+    fn from_source(source: Arc<SourceFile>) -> Self {
+        let (lexed, lex_errors) = lex_recovering(&source.text);
+        let tokens = attach_trivia(&source.text, &lexed);
+        let mut tree = Self {
+            root: SyntaxNode {
+                kind: NodeKind::Module,
+                tokens: 0..tokens.len(),
+                children: Vec::new(),
+            },
+            tokens,
+            module: Module {
+                name: String::new(),
+                meta: Default::default(),
+                uses: Vec::new(),
+                imports: Vec::new(),
+                exports: Vec::new(),
+                items: Vec::new(),
+                span: Span::default(),
+                meta_spans: Default::default(),
+            },
+            diagnostics: Vec::new(),
+            source,
+        };
+        tree.derive_ast(lex_errors);
+        tree
+    }
+
+    /// Parses the tree's tokens and nests nodes along the spans of the resulting AST.
+    fn derive_ast(&mut self, lex_errors: Vec<LexError>) {
+        let outcome = parse_tokens(self.lexer_tokens(), lex_errors, self.source.clone());
+        let mut spans = Vec::new();
+        collect_module(&outcome.module, &mut spans);
+        self.root.children = self.nest(spans);
+        self.module = outcome.module;
+        self.diagnostics = outcome.diagnostics;
+    }
+
+    /// The tree's non-trivia tokens in the lexer's representation.
+    fn lexer_tokens(&self) -> Vec<Token> {
+        let mut pos = (1, 1);
+        let mut out = Vec::with_capacity(self.tokens.len());
+        for token in &self.tokens {
+            for trivia in &token.leading_trivia {
+                advance_line_col(&mut pos, &trivia.text);
+            }
+            out.push(Token {
+                kind: token.kind.clone(),
+                line: pos.0,
+                col: pos.1,
+                start: token.offset,
+                end: token.offset + token.text.len(),
+            });
+            advance_line_col(&mut pos, &token.text);
+        }
+        out
+    }
+
+    fn nest(&self, spans: Vec<(NodeKind, Span)>) -> Vec<SyntaxNode> {
+        // `Eof` only ever belongs to the root.
+        let last = self.tokens.len().saturating_sub(1);
+        let mut nodes: Vec<SyntaxNode> = spans
+            .into_iter()
+            .filter(|(_, span)| !span.is_dummy())
+            .filter_map(|(kind, span)| {
+                let first = self.tokens.partition_point(|t| t.offset < span.start);
+                let end =
+                    self.tokens[..last].partition_point(|t| t.offset + t.text.len() <= span.end);
+                (first < end).then(|| SyntaxNode {
+                    kind,
+                    tokens: first..end,
+                    children: Vec::new(),
+                })
+            })
+            .collect();
+        // Parents were collected before their children, so a stable sort keeps that order
+        // for nodes with equal ranges.
+        nodes.sort_by_key(|n| (n.tokens.start, std::cmp::Reverse(n.tokens.end)));
+
+        let mut roots: Vec<SyntaxNode> = Vec::new();
+        let mut stack: Vec<SyntaxNode> = Vec::new();
+        for node in nodes {
+            while let Some(top) = stack.last() {
+                if top.tokens.start <= node.tokens.start && node.tokens.end <= top.tokens.end {
+                    break;
+                }
+                let done = stack.pop().expect("non-empty stack");
+                attach(&mut stack, &mut roots, done);
+            }
+            stack.push(node);
+        }
+        while let Some(done) = stack.pop() {
+            attach(&mut stack, &mut roots, done);
+        }
+        roots
+    }
+
+    pub fn tokens(&self) -> &[SyntaxToken] {
+        &self.tokens
+    }
+
+    pub fn root(&self) -> &SyntaxNode {
+        &self.root
+    }
+
+    /// The module parsed from this tree. With syntax errors it holds the well-formed parts.
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    /// Syntax diagnostics (`S0xx`) of the current text.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn to_ast(&self) -> Result<Module> {
+        if !has_errors(&self.diagnostics) {
+            Ok(self.module.clone())
+        } else {
+            Err(SyntaxErrors(self.diagnostics.clone()).into())
+        }
+    }
+
+    /// Source text of `node`, without the trivia in front of its first token.
+    pub fn node_text(&self, node: &SyntaxNode) -> String {
+        let mut out = String::new();
+        for (idx, token) in self.tokens[node.tokens.clone()].iter().enumerate() {
+            if idx > 0 {
+                push_trivia(&mut out, &token.leading_trivia);
+            }
+            out.push_str(&token.text);
+        }
+        out
+    }
+
+    /// Comments on the lines directly above `node`, up to the first blank or code line.
+    pub fn leading_comments(&self, node: &SyntaxNode) -> Vec<&str> {
+        let mut out = Vec::new();
+        let mut idx = node.tokens.start;
+        // A comment-only line is a `Newline` token with a comment in front, preceded by
+        // another line break (or the start of the file).
+        while idx > 0 && self.tokens[idx - 1].kind == TokenKind::Newline {
+            let starts_line = idx == 1 || self.tokens[idx - 2].kind == TokenKind::Newline;
+            let comment = self.tokens[idx - 1]
+                .leading_trivia
+                .iter()
+                .find(|t| t.kind == TriviaKind::Comment);
+            match comment {
+                Some(comment) if starts_line => out.push(comment.text.as_str()),
+                _ => break,
+            }
+            idx -= 1;
+        }
+        out.reverse();
+        out
+    }
+
+    /// All comments in source order.
+    pub fn comments(&self) -> impl Iterator<Item = &str> {
+        self.tokens
+            .iter()
+            .flat_map(|t| &t.leading_trivia)
+            .filter(|t| t.kind == TriviaKind::Comment)
+            .map(|t| t.text.as_str())
+    }
+
+    /// Replaces the text of tokens `range` (and the trivia between them) with `text`. The
+    /// trivia in front of the first token stays. Tokens, nodes and AST are rebuilt.
+    pub fn replace_tokens(&mut self, range: Range<usize>, text: &str) {
+        let mut out = String::with_capacity(self.source.text.len() + text.len());
+        for (idx, token) in self.tokens.iter().enumerate() {
+            if idx == range.start {
+                push_trivia(&mut out, &token.leading_trivia);
+                out.push_str(text);
+            }
+            if range.contains(&idx) {
+                continue;
+            }
+            push_trivia(&mut out, &token.leading_trivia);
+            out.push_str(&token.text);
+        }
+        self.reparse(out);
+    }
+
+    pub fn replace_node(&mut self, node: &SyntaxNode, text: &str) {
+        self.replace_tokens(node.tokens.clone(), text);
+    }
+
+    /// Inserts `text` right before token `index` (after its leading trivia).
+    pub fn insert_before(&mut self, index: usize, text: &str) {
+        let token = &self.tokens[index];
+        let replacement = format!("{}{}", text, token.text);
+        self.replace_tokens(index..index + 1, &replacement);
+    }
+
+    fn reparse(&mut self, text: String) {
+        *self = Self::from_source(SourceFile::new(self.source.path.clone(), text));
+    }
+}
+
+impl fmt::Display for SyntaxTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in &self.tokens {
+            for trivia in &token.leading_trivia {
+                f.write_str(&trivia.text)?;
+            }
+            f.write_str(&token.text)?;
+        }
+        Ok(())
+    }
+}
+
+fn advance_line_col((line, col): &mut (usize, usize), text: &str) {
+    for c in text.chars() {
+        if c == '\n' {
+            *line += 1;
+            *col = 1;
+        } else {
+            *col += 1;
+        }
+    }
+}
+
+fn push_trivia(out: &mut String, trivia: &[Trivia]) {
+    for t in trivia {
+        out.push_str(&t.text);
+    }
+}
+
+fn attach(stack: &mut [SyntaxNode], roots: &mut Vec<SyntaxNode>, node: SyntaxNode) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => roots.push(node),
+    }
+}
+
+/// Splits the source into tokens, giving each token the text between it and its
+/// predecessor as trivia.
+fn attach_trivia(text: &str, lexed: &[Token]) -> Vec<SyntaxToken> {
+    let mut pos = 0;
+    lexed
+        .iter()
+        .map(|token| {
+            let leading_trivia = split_trivia(&text[pos..token.start]);
+            pos = token.end;
+            SyntaxToken {
+                kind: token.kind.clone(),
+                leading_trivia,
+                text: text[token.start..token.end].to_string(),
+                offset: token.start,
+            }
+        })
+        .collect()
+}
+
+/// Gaps between tokens never contain string literals, so a `#` always starts a comment.
+fn split_trivia(gap: &str) -> Vec<Trivia> {
+    let mut out: Vec<Trivia> = Vec::new();
+    let mut rest = gap;
+    while let Some(c) = rest.chars().next() {
+        let (kind, len) = if c == '#' {
+            let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+            (TriviaKind::Comment, line.trim_end().len())
+        } else if matches!(c, ' ' | '\t' | '\r') {
+            let len = rest
+                .find(|c: char| !matches!(c, ' ' | '\t' | '\r'))
+                .unwrap_or(rest.len());
+            (TriviaKind::Whitespace, len)
+        } else {
+            (TriviaKind::Skipped, c.len_utf8())
+        };
+        match out.last_mut() {
+            Some(last) if last.kind == kind && kind != TriviaKind::Comment => {
+                last.text.push_str(&rest[..len]);
+            }
+            _ => out.push(Trivia {
+                kind,
+                text: rest[..len].to_string(),
+            }),
+        }
+        rest = &rest[len..];
+    }
+    out
+}
+
+fn collect_module(module: &Module, out: &mut Vec<(NodeKind, Span)>) {
+    let mut meta: Vec<_> = module.meta_spans.values().collect();
+    meta.sort_by_key(|span| span.start);
+    out.extend(meta.into_iter().map(|span| (NodeKind::Meta, span.clone())));
+    for decl in &module.uses {
+        out.push((NodeKind::Use, decl.span.clone()));
+    }
+    for decl in &module.imports {
+        out.push((NodeKind::Import, decl.span.clone()));
+    }
+    for decl in &module.exports {
+        out.push((NodeKind::Export, decl.span.clone()));
+    }
+    for item in &module.items {
+        match item {
+            Item::Flow(flow) => {
+                out.push((NodeKind::Flow, flow.span.clone()));
+                collect_annotations(&flow.annotations, out);
+                if let Some(span) = &flow.start_span {
+                    out.push((NodeKind::Start, span.clone()));
+                }
+                for state in &flow.states {
+                    collect_state(state, out);
+                }
+            }
+            Item::GlobalState(state) => collect_state(state, out),
+            Item::Rule(rule) => collect_rule(rule, out),
+            Item::Nd(nd) => {
+                out.push((NodeKind::Nd, nd.span.clone()));
+                collect_annotations(&nd.annotations, out);
+                for define in &nd.defines {
+                    out.push((NodeKind::Define, define.span.clone()));
+                }
+                collect_call(&nd.propose, out);
+                for call in &nd.constraints {
+                    collect_call(call, out);
+                }
+            }
+            Item::Define(define) => out.push((NodeKind::Define, define.span.clone())),
+        }
+    }
+}
+
+fn collect_state(state: &StateBlock, out: &mut Vec<(NodeKind, Span)>) {
+    out.push((NodeKind::State, state.span.clone()));
+    collect_annotations(&state.annotations, out);
+    for stmt in &state.statements {
+        match stmt {
+            StateStmt::On {
+                event, guard, span, ..
+            } => {
+                out.push((NodeKind::On, span.clone()));
+                collect_call(event, out);
+                if let Some(guard) = guard {
+                    collect_expr(guard, out);
+                }
+            }
+            StateStmt::After { span, .. } => out.push((NodeKind::After, span.clone())),
+            StateStmt::Rule(rule) => collect_rule(rule, out),
+            StateStmt::Run { args, span, .. } => {
+                out.push((NodeKind::Run, span.clone()));
+                for arg in args {
+                    out.push((NodeKind::Arg, arg.span.clone()));
+                    collect_expr(&arg.value, out);
+                }
+            }
+            StateStmt::Return { value, span } => {
+                out.push((NodeKind::Return, span.clone()));
+                if let Some(value) = value {
+                    collect_expr(value, out);
+                }
+            }
+            StateStmt::Terminate => {}
+            StateStmt::Assign { value, span, .. } => {
+                out.push((NodeKind::Assign, span.clone()));
+                collect_expr(value, out);
+            }
+            StateStmt::Expr(call) => collect_call(call, out),
+        }
+    }
+    if let Some(span) = &state.start_span {
+        out.push((NodeKind::Start, span.clone()));
+    }
+    for child in &state.states {
+        collect_state(child, out);
+    }
+    for region in &state.regions {
+        out.push((NodeKind::Region, region.span.clone()));
+        if let Some(span) = &region.start_span {
+            out.push((NodeKind::Start, span.clone()));
+        }
+        for child in &region.states {
+            collect_state(child, out);
+        }
+    }
+}
+
+fn collect_rule(rule: &Rule, out: &mut Vec<(NodeKind, Span)>) {
+    out.push((NodeKind::Rule, rule.span.clone()));
+    collect_annotations(&rule.annotations, out);
+    match &rule.trigger {
+        RuleTrigger::On(call) => collect_call(call, out),
+        RuleTrigger::When(expr) => collect_expr(expr, out),
+        RuleTrigger::Every(_) => {}
+    }
+    for stmt in &rule.body {
+        match stmt {
+            RuleStmt::Assign { value, span, .. } => {
+                out.push((NodeKind::Assign, span.clone()));
+                collect_expr(value, out);
+            }
+            RuleStmt::Emit { span, .. } => out.push((NodeKind::Emit, span.clone())),
+        }
+    }
+}
+
+fn collect_annotations(annotations: &Annotations, out: &mut Vec<(NodeKind, Span)>) {
+    for attr in &annotations.attrs {
+        out.push((NodeKind::Attribute, attr.span.clone()));
+        for arg in &attr.args {
+            out.push((NodeKind::Arg, arg.span.clone()));
+            collect_expr(&arg.value, out);
+        }
+    }
+}
+
+fn collect_call(call: &Call, out: &mut Vec<(NodeKind, Span)>) {
+    out.push((NodeKind::Call, call.span.clone()));
+    for arg in &call.args {
+        out.push((NodeKind::Arg, arg.span.clone()));
+        collect_expr(&arg.value, out);
+    }
+}
+
+fn collect_expr(expr: &Expr, out: &mut Vec<(NodeKind, Span)>) {
+    match expr {
+        Expr::Call(call) => collect_call(call, out),
+        Expr::Binary { left, right, .. } => {
+            collect_expr(left, out);
+            collect_expr(right, out);
+        }
+        Expr::Unary { operand, .. } => collect_expr(operand, out),
+        Expr::Interpolated(parts) => {
+            for part in parts {
+                if let StringPart::Expr(expr) = part {
+                    collect_expr(expr, out);
+                }
+            }
+        }
+        Expr::List(items) => {
+            for item in items {
+                collect_expr(item, out);
+            }
+        }
+        Expr::Record(fields) => {
+            for field in fields {
+                collect_expr(&field.value, out);
+            }
+        }
+        Expr::Number(_)
+        | Expr::Duration(_)
+        | Expr::String(_)
+        | Expr::Bool(_)
+        | Expr::Null
+        | Expr::Ident(_) => {}
+    }
+}
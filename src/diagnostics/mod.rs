@@ -0,0 +1,571 @@
+//! Diagnostics reported by the parser, the semantic checks and the target contracts, their
+//! text, JSON and SARIF renderings, and the registry of diagnostic codes: every code the
+//! compiler reports, with its default severity, a one-line summary and the long-form help
+//! printed by `sculpt explain`.
+
+mod registry;
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde_json::{json, Value};
+
+use crate::ast::Span;
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub code: &'static str,
+    pub message: String,
+    pub level: DiagnosticLevel,
+    /// Primary source location of the problem, when the offending node came from the parser.
+    pub span: Option<Span>,
+    /// Secondary locations, e.g. the first definition of a duplicate.
+    pub labels: Vec<Label>,
+    /// Proposed fixes, each a concrete text edit.
+    pub suggestions: Vec<Suggestion>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// A proposed fix: replace the source text under `span` with `replacement`.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub message: String,
+    pub span: Span,
+    pub replacement: String,
+    /// Safe suggestions are applied by `sculpt fix`; the others are only shown.
+    pub safe: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticLevel {
+    Error,
+    Warning,
+}
+
+impl Diagnostic {
+    pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            level: DiagnosticLevel::Error,
+            span: None,
+            labels: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    pub fn warn(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            level: DiagnosticLevel::Warning,
+            span: None,
+            labels: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    /// Attaches the primary span. Spans of synthesized nodes (no source) are ignored.
+    pub fn with_span(mut self, span: &Span) -> Self {
+        if !span.is_dummy() {
+            self.span = Some(span.clone());
+        }
+        self
+    }
+
+    /// Attaches the span of the `@meta key=...` entry, if the module declared one.
+    pub(crate) fn with_meta_span(self, meta_spans: &HashMap<String, Span>, key: &str) -> Self {
+        match meta_spans.get(key) {
+            Some(span) => self.with_span(span),
+            None => self,
+        }
+    }
+
+    /// Adds a secondary labelled span. Spans of synthesized nodes are ignored.
+    pub fn with_label(mut self, span: &Span, message: impl Into<String>) -> Self {
+        if !span.is_dummy() {
+            self.labels.push(Label {
+                span: span.clone(),
+                message: message.into(),
+            });
+        }
+        self
+    }
+
+    /// Adds a suggestion to replace the text under `span`. Spans of synthesized nodes are
+    /// ignored.
+    pub fn with_suggestion(
+        mut self,
+        span: &Span,
+        replacement: impl Into<String>,
+        message: impl Into<String>,
+        safe: bool,
+    ) -> Self {
+        if !span.is_dummy() {
+            self.suggestions.push(Suggestion {
+                message: message.into(),
+                span: span.clone(),
+                replacement: replacement.into(),
+                safe,
+            });
+        }
+        self
+    }
+
+    /// Suggests the candidate closest to the misspelled `name` found under `span`, if any is
+    /// close enough. The suggestion is safe when no other candidate is as close.
+    pub(crate) fn did_you_mean<'a>(
+        self,
+        name: &str,
+        span: Option<Span>,
+        candidates: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        let (Some(span), Some((best, unique))) = (span, closest_name(name, candidates)) else {
+            return self;
+        };
+        self.with_suggestion(&span, best, format!("did you mean '{}'?", best), unique)
+    }
+}
+
+/// The candidate with the smallest edit distance to `name`, and whether it is the only one
+/// at that distance. Candidates further away than a third of the name's length (at least
+/// one edit) do not count.
+pub fn closest_name<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<(&'a str, bool)> {
+    let limit = (name.chars().count() / 3).max(1);
+    let mut best: Option<(&str, usize, bool)> = None;
+    for candidate in candidates {
+        let distance = edit_distance(name, candidate);
+        if distance == 0 || distance > limit {
+            continue;
+        }
+        match &mut best {
+            Some((_, d, _)) if *d < distance => {}
+            Some((current, d, unique)) if *d == distance => {
+                if *current != candidate {
+                    // Ties go to the alphabetically first name, independent of input order.
+                    *current = (*current).min(candidate);
+                    *unique = false;
+                }
+            }
+            _ => best = Some((candidate, distance, true)),
+        }
+    }
+    best.map(|(candidate, _, unique)| (candidate, unique))
+}
+
+/// Edit distance over characters where inserting, deleting or replacing a character and
+/// swapping two adjacent characters each cost one (optimal string alignment).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Applies the safe suggestions whose span lies in `path` to `source`, the text of that
+/// file. Overlapping edits after the first are skipped; returns the new text and the number
+/// of edits applied.
+pub fn apply_safe_suggestions(source: &str, path: &str, diags: &[Diagnostic]) -> (String, usize) {
+    let mut edits: Vec<&Suggestion> = diags
+        .iter()
+        .flat_map(|d| &d.suggestions)
+        .filter(|s| s.safe && s.span.path() == path && s.span.end <= source.len())
+        .collect();
+    edits.sort_by_key(|s| (s.span.start, s.span.end));
+    let mut out = String::with_capacity(source.len());
+    let mut copied = 0;
+    let mut applied = 0;
+    for edit in edits {
+        if edit.span.start < copied {
+            continue;
+        }
+        out.push_str(&source[copied..edit.span.start]);
+        out.push_str(&edit.replacement);
+        copied = edit.span.end;
+        applied += 1;
+    }
+    out.push_str(&source[copied..]);
+    (out, applied)
+}
+
+/// A failed check, carrying its diagnostics so callers can render them as text, JSON or
+/// SARIF. `Display` prints the text rendering.
+#[derive(Debug)]
+pub struct DiagnosticsError {
+    /// The text rendering, including any heading.
+    pub message: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl DiagnosticsError {
+    pub fn new(diagnostics: Vec<Diagnostic>) -> Self {
+        Self {
+            message: format_diagnostics(&diagnostics),
+            diagnostics,
+        }
+    }
+
+    /// Prefixes the text rendering with `heading:` on its own line.
+    pub fn with_heading(heading: &str, diagnostics: Vec<Diagnostic>) -> Self {
+        Self {
+            message: format!("{}:\n{}", heading, format_diagnostics(&diagnostics)),
+            diagnostics,
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for DiagnosticsError {}
+
+/// Renders diagnostics as text. Diagnostics with spans get a source excerpt with carets
+/// under the primary location and dashes under labelled secondary locations.
+pub fn format_diagnostics(diags: &[Diagnostic]) -> String {
+    diags
+        .iter()
+        .map(format_diagnostic)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_diagnostic(d: &Diagnostic) -> String {
+    let mut out = match d.level {
+        DiagnosticLevel::Error => format!("{}: {}", d.code, d.message),
+        DiagnosticLevel::Warning => format!("{} [warn]: {}", d.code, d.message),
+    };
+    let Some(primary) = &d.span else {
+        return out;
+    };
+    let gutter = std::iter::once(primary)
+        .chain(d.labels.iter().map(|l| &l.span))
+        .map(|s| s.line.to_string().len())
+        .max()
+        .unwrap_or(1);
+    out.push_str(&format!(
+        "\n{:gutter$}--> {}:{}:{}",
+        "",
+        primary.path(),
+        primary.line,
+        primary.col
+    ));
+    out.push_str(&render_excerpt(primary, '^', "", gutter));
+    for label in &d.labels {
+        out.push_str(&format!(
+            "\n{:gutter$}::: {}:{}:{}",
+            "",
+            label.span.path(),
+            label.span.line,
+            label.span.col
+        ));
+        out.push_str(&render_excerpt(&label.span, '-', &label.message, gutter));
+    }
+    for suggestion in &d.suggestions {
+        out.push_str(&format!("\n{:gutter$} = help: {}", "", suggestion.message));
+    }
+    out
+}
+
+fn render_excerpt(span: &Span, marker: char, note: &str, gutter: usize) -> String {
+    let Some(line_text) = span
+        .source
+        .as_ref()
+        .and_then(|source| source.line_text(span.line))
+    else {
+        return String::new();
+    };
+    let indent: String = line_text
+        .chars()
+        .take(span.col.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    // Underline up to the end of the first line for spans that cover several lines.
+    let first_line = span.text().split('\n').next().unwrap_or("").trim_end();
+    let width = first_line.chars().count().max(1);
+    let underline = marker.to_string().repeat(width);
+    let mut out = format!("\n{:gutter$} |", "");
+    out.push_str(&format!("\n{:>gutter$} | {}", span.line, line_text));
+    out.push_str(&format!("\n{:gutter$} | {}{}", "", indent, underline));
+    if !note.is_empty() {
+        out.push(' ');
+        out.push_str(note);
+    }
+    out
+}
+
+pub fn has_errors(diags: &[Diagnostic]) -> bool {
+    diags.iter().any(|d| d.level == DiagnosticLevel::Error)
+}
+
+/// Renders diagnostics as a JSON document. Diagnostics without a span are reported against
+/// `default_path` (the compiled input) with no line or column.
+pub fn diagnostics_to_json(diags: &[Diagnostic], default_path: &str) -> Value {
+    let location = |span: &Span| {
+        let (end_line, end_column) = end_position(span);
+        json!({
+            "file": span.path(),
+            "line": span.line,
+            "column": span.col,
+            "end_line": end_line,
+            "end_column": end_column,
+        })
+    };
+    let entries: Vec<Value> = diags
+        .iter()
+        .map(|d| {
+            let mut entry = json!({
+                "code": d.code,
+                "level": level_name(d.level),
+                "message": d.message,
+                "file": default_path,
+                "line": Value::Null,
+                "column": Value::Null,
+            });
+            if let Some(span) = &d.span {
+                merge(&mut entry, location(span));
+            }
+            entry["labels"] = d
+                .labels
+                .iter()
+                .map(|label| {
+                    let mut out = location(&label.span);
+                    out["message"] = json!(label.message);
+                    out
+                })
+                .collect();
+            entry["suggestions"] = d
+                .suggestions
+                .iter()
+                .map(|suggestion| {
+                    let mut out = location(&suggestion.span);
+                    out["message"] = json!(suggestion.message);
+                    out["replacement"] = json!(suggestion.replacement);
+                    out["safe"] = json!(suggestion.safe);
+                    out
+                })
+                .collect();
+            if let Some(code) = DiagnosticCode::lookup(d.code) {
+                entry["summary"] = json!(code.summary);
+            }
+            entry
+        })
+        .collect();
+    json!({
+        "version": 1,
+        "errors": diags.iter().filter(|d| d.level == DiagnosticLevel::Error).count(),
+        "warnings": diags.iter().filter(|d| d.level == DiagnosticLevel::Warning).count(),
+        "diagnostics": entries,
+    })
+}
+
+/// Renders diagnostics as a SARIF 2.1.0 log with one run, for code-scanning tools. Rules
+/// come from the code registry; diagnostics without a span point at line 1 of
+/// `default_path`.
+pub fn diagnostics_to_sarif(diags: &[Diagnostic], default_path: &str) -> Value {
+    let mut rule_ids: Vec<&str> = diags.iter().map(|d| d.code).collect();
+    rule_ids.sort_unstable();
+    rule_ids.dedup();
+    let rules: Vec<Value> = rule_ids
+        .iter()
+        .map(|id| match DiagnosticCode::lookup(id) {
+            Some(code) => json!({
+                "id": code.code,
+                "shortDescription": { "text": code.summary },
+                "fullDescription": { "text": code.explanation.trim() },
+                "help": { "text": code.explain() },
+                "defaultConfiguration": { "level": code.level_name() },
+            }),
+            None => json!({ "id": id }),
+        })
+        .collect();
+
+    let physical = |span: &Span| {
+        let (end_line, end_column) = end_position(span);
+        json!({
+            "artifactLocation": { "uri": artifact_uri(span.path()) },
+            "region": {
+                "startLine": span.line,
+                "startColumn": span.col,
+                "endLine": end_line,
+                "endColumn": end_column,
+            },
+        })
+    };
+    let results: Vec<Value> = diags
+        .iter()
+        .map(|d| {
+            let location = match &d.span {
+                Some(span) => physical(span),
+                None => json!({
+                    "artifactLocation": { "uri": artifact_uri(default_path) },
+                    "region": { "startLine": 1 },
+                }),
+            };
+            let mut result = json!({
+                "ruleId": d.code,
+                "ruleIndex": rule_ids.binary_search(&d.code).unwrap_or_default(),
+                "level": level_name(d.level),
+                "message": { "text": d.message },
+                "locations": [{ "physicalLocation": location }],
+            });
+            if !d.labels.is_empty() {
+                result["relatedLocations"] = d
+                    .labels
+                    .iter()
+                    .enumerate()
+                    .map(|(id, label)| {
+                        json!({
+                            "id": id,
+                            "physicalLocation": physical(&label.span),
+                            "message": { "text": label.message },
+                        })
+                    })
+                    .collect();
+            }
+            if !d.suggestions.is_empty() {
+                result["fixes"] = d
+                    .suggestions
+                    .iter()
+                    .map(|suggestion| {
+                        let deleted = physical(&suggestion.span);
+                        json!({
+                            "description": { "text": suggestion.message },
+                            "artifactChanges": [{
+                                "artifactLocation": deleted["artifactLocation"],
+                                "replacements": [{
+                                    "deletedRegion": deleted["region"],
+                                    "insertedContent": { "text": suggestion.replacement },
+                                }],
+                            }],
+                        })
+                    })
+                    .collect();
+            }
+            result
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "sculpt",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn level_name(level: DiagnosticLevel) -> &'static str {
+    match level {
+        DiagnosticLevel::Error => "error",
+        DiagnosticLevel::Warning => "warning",
+    }
+}
+
+/// Line and column just past the last character of the span, 1-based like the start.
+fn end_position(span: &Span) -> (usize, usize) {
+    let text = span.text();
+    match text.rsplit_once('\n') {
+        Some((before, last)) => (
+            span.line + before.matches('\n').count() + 1,
+            last.chars().count() + 1,
+        ),
+        None => (span.line, span.col + text.chars().count()),
+    }
+}
+
+/// SARIF wants URIs: forward slashes, relative paths kept relative to the working directory.
+fn artifact_uri(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+fn merge(into: &mut Value, from: Value) {
+    if let (Value::Object(into), Value::Object(from)) = (into, from) {
+        into.extend(from);
+    }
+}
+
+/// One registered diagnostic code.
+#[derive(Debug)]
+pub struct DiagnosticCode {
+    pub code: &'static str,
+    /// Severity when no `@meta` setting or language edition changes it.
+    pub level: DiagnosticLevel,
+    pub summary: &'static str,
+    pub explanation: &'static str,
+    /// Source that triggers the diagnostic.
+    pub example: &'static str,
+    /// The same source with the problem fixed.
+    pub fixed: &'static str,
+}
+
+impl DiagnosticCode {
+    /// All registered codes, grouped by family as in the semantics spec.
+    pub fn all() -> &'static [DiagnosticCode] {
+        registry::CODES
+    }
+
+    /// Looks a code up, ignoring case (`f105` finds `F105`).
+    pub fn lookup(code: &str) -> Option<&'static DiagnosticCode> {
+        let code = code.trim();
+        Self::all()
+            .iter()
+            .find(|entry| entry.code.eq_ignore_ascii_case(code))
+    }
+
+    pub fn level_name(&self) -> &'static str {
+        level_name(self.level)
+    }
+
+    /// The long-form help: summary, explanation and the example before and after the fix.
+    pub fn explain(&self) -> String {
+        let indent = |text: &str| {
+            text.trim_matches('\n')
+                .lines()
+                .map(|line| format!("    {}", line).trim_end().to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        format!(
+            "{} ({}): {}\n\n{}\n\nExample:\n\n{}\n\nFixed:\n\n{}\n",
+            self.code,
+            self.level_name(),
+            self.summary,
+            self.explanation.trim(),
+            indent(self.example),
+            indent(self.fixed)
+        )
+    }
+}
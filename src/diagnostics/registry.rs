@@ -0,0 +1,1794 @@
+use super::DiagnosticCode;
+use crate::diagnostics::DiagnosticLevel::{Error, Warning};
+
+pub(super) static CODES: &[DiagnosticCode] = &[
+    // Structural (parser)
+    DiagnosticCode {
+        code: "S001",
+        level: Error,
+        summary: "Missing module root",
+        explanation: "Every file holds exactly one `module(Name):` block, and everything else \
+(flows, rules, state, nd blocks) lives inside it. Only `@meta` lines and comments may come \
+before the module header.",
+        example: r#"
+flow(Main):
+  start > Title
+end
+"#,
+        fixed: r#"
+module(App):
+  flow(Main):
+    start > Title
+  end
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "S002",
+        level: Warning,
+        summary: "Multiple module roots",
+        explanation: "A file holds exactly one module; everything after the first module's \
+closing `end` is ignored. Split further modules into their own files and combine them with a \
+`.sculpt.json` project and `import(...)`.",
+        example: r#"
+module(Billing):
+end
+module(Shipping):
+end
+"#,
+        fixed: r#"
+# billing.sculpt
+module(Billing):
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "S003",
+        level: Error,
+        summary: "Unexpected top-level construct",
+        explanation: "Inside a module only `use`, `import`, `flow`, global `state()`, `rule`, \
+`define` and `nd` may appear.",
+        example: r#"
+module(App):
+  start > Title
+end
+"#,
+        fixed: r#"
+module(App):
+  flow(Main):
+    start > Title
+  end
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "S004",
+        level: Error,
+        summary: "Unbalanced or missing `end`",
+        explanation: "Every block (`module`, `flow`, `state`, `rule`, trigger bodies, `nd`, \
+`define`) is closed by its own `end`. When an `end` is missing the parser closes the block at \
+the next line indented no deeper than its header and reports this code there.",
+        example: r#"
+state(Title):
+  ui.text("Hi")
+state(Next):
+  terminate
+end
+"#,
+        fixed: r#"
+state(Title):
+  ui.text("Hi")
+end
+state(Next):
+  terminate
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "S005",
+        level: Error,
+        summary: "Identifier expected",
+        explanation: "A name is required here, for example the name of a state inside a flow. \
+Only global state blocks are written `state()` without a name.",
+        example: r#"
+flow(Main):
+  start > Title
+  state():
+    terminate
+  end
+end
+"#,
+        fixed: r#"
+flow(Main):
+  start > Title
+  state(Title):
+    terminate
+  end
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "S006",
+        level: Error,
+        summary: "Unexpected token",
+        explanation: "The parser expected another token here. The message names what was \
+expected and what was found, for example a rule body that contains something other than \
+assignments and `emit`, or an unknown type name.",
+        example: r#"
+rule(tick):
+  on input.tick:
+    ui.text("tick")
+  end
+end
+"#,
+        fixed: r#"
+rule(tick):
+  on input.tick:
+    ticks += 1
+  end
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "S007",
+        level: Error,
+        summary: "Lexical error",
+        explanation: "The source contains a character that is not part of the language, an \
+unterminated string, or a malformed number.",
+        example: r#"
+ui.text("Hello)
+"#,
+        fixed: r#"
+ui.text("Hello")
+"#,
+    },
+    DiagnosticCode {
+        code: "S008",
+        level: Error,
+        summary: "Unknown `@` directive or misplaced attribute",
+        explanation: "Lines starting with `@` are either `@meta key=value` headers or item \
+attributes. An attribute stands on its own line directly above a flow, state, rule or nd \
+block.",
+        example: r#"
+@owner(payments) flow(Checkout):
+"#,
+        fixed: r#"
+@owner(payments)
+flow(Checkout):
+"#,
+    },
+    DiagnosticCode {
+        code: "S009",
+        level: Error,
+        summary: "Construct not available in the declared language edition",
+        explanation: "The file pins an older language with `@meta language=...`, and the \
+construct was added in a later edition (guards, nested states, flow signatures, timers and \
+attributes need 1.1). Raise the pin or rewrite the construct.",
+        example: r#"
+@meta language=1.0
+...
+  after 2s > Next
+"#,
+        fixed: r#"
+@meta language=1.1
+...
+  after 2s > Next
+"#,
+    },
+    DiagnosticCode {
+        code: "S010",
+        level: Warning,
+        summary: "Content after the module's closing `end`",
+        explanation: "Only blank lines and comments may follow the module's closing `end`. \
+Anything else is ignored by the compiler; move it into the module or delete it. `sculpt fmt` \
+refuses to format the file until then, since it would drop the text.",
+        example: r#"
+module(App):
+  ...
+end
+flow(Extra):
+end
+"#,
+        fixed: r#"
+module(App):
+  ...
+  flow(Extra):
+  end
+end
+"#,
+    },
+    // Flow/State
+    DiagnosticCode {
+        code: "F101",
+        level: Error,
+        summary: "Duplicate flow name",
+        explanation: "Flow names are unique within a module; `run` and references resolve \
+flows by name.",
+        example: r#"
+flow(Main):
+  ...
+end
+flow(Main):
+  ...
+end
+"#,
+        fixed: r#"
+flow(Main):
+  ...
+end
+flow(Settings):
+  ...
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "F102",
+        level: Error,
+        summary: "Missing `start` in flow",
+        explanation: "A flow declares its initial state with `start > State`.",
+        example: r#"
+flow(Main):
+  state(Title):
+    terminate
+  end
+end
+"#,
+        fixed: r#"
+flow(Main):
+  start > Title
+  state(Title):
+    terminate
+  end
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "F103",
+        level: Error,
+        summary: "Unknown start state",
+        explanation: "`start > State` names a state that the flow does not define.",
+        example: r#"
+flow(Main):
+  start > Intro
+  state(Title):
+    terminate
+  end
+end
+"#,
+        fixed: r#"
+flow(Main):
+  start > Title
+  state(Title):
+    terminate
+  end
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "F104",
+        level: Error,
+        summary: "Duplicate state name in flow",
+        explanation: "State names are unique across a whole flow, nested and region states \
+included, because transitions may target any of them by name.",
+        example: r#"
+state(Menu):
+  ...
+end
+state(Menu):
+  ...
+end
+"#,
+        fixed: r#"
+state(Menu):
+  ...
+end
+state(Options):
+  ...
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "F105",
+        level: Error,
+        summary: "Unknown transition target state",
+        explanation: "`on <event> > Target` must name a state of the same flow.",
+        example: r#"
+state(Title):
+  on input.key(enter) > Gmae
+end
+state(Game):
+  ...
+end
+"#,
+        fixed: r#"
+state(Title):
+  on input.key(enter) > Game
+end
+state(Game):
+  ...
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "F106",
+        level: Error,
+        summary: "Duplicate event handler in one state",
+        explanation: "One state maps each event signature to a single target, unless the \
+handlers carry different guards.",
+        example: r#"
+state(Title):
+  on input.key(enter) > Game
+  on input.key(enter) > Help
+end
+"#,
+        fixed: r#"
+state(Title):
+  on input.key(enter) > Game
+  on input.key(h) > Help
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "F107",
+        level: Error,
+        summary: "Unsupported transition guard",
+        explanation: "Guards use the same forms as rule `when` conditions: comparisons over \
+values and arithmetic, combined with `and`, `or` and `not`.",
+        example: r#"
+on input.key(enter) when ui.ready() > Game
+"#,
+        fixed: r#"
+on input.key(enter) when lives > 0 > Game
+"#,
+    },
+    DiagnosticCode {
+        code: "F108",
+        level: Error,
+        summary: "Nested states or region without `start`",
+        explanation: "A state with nested states, and every region of a parallel state, names \
+the child that is entered first with `start > State`.",
+        example: r#"
+state(Play):
+  state(Running):
+    ...
+  end
+end
+"#,
+        fixed: r#"
+state(Play):
+  start > Running
+  state(Running):
+    ...
+  end
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "F109",
+        level: Error,
+        summary: "Nested `start` is not a direct child",
+        explanation: "The `start` of a state or region must name one of its own direct \
+children.",
+        example: r#"
+state(Play):
+  start > Title
+  state(Running):
+    ...
+  end
+end
+"#,
+        fixed: r#"
+state(Play):
+  start > Running
+  state(Running):
+    ...
+  end
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "F110",
+        level: Error,
+        summary: "State mixes nested states and regions",
+        explanation: "A state either nests states (one active child) or splits into regions \
+(all active at once), not both. Move the nested states into a region.",
+        example: r#"
+state(Play):
+  start > Running
+  state(Running):
+    ...
+  end
+  region(Audio):
+    ...
+  end
+end
+"#,
+        fixed: r#"
+state(Play):
+  region(Game):
+    start > Running
+    state(Running):
+      ...
+    end
+  end
+  region(Audio):
+    ...
+  end
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "F111",
+        level: Error,
+        summary: "Transition crosses sibling regions",
+        explanation: "Regions of a parallel state run independently; a state in one region \
+cannot transition into a sibling region. Leave the parallel state instead, or emit an event \
+that the other region handles.",
+        example: r#"
+region(Game):
+  state(Running):
+    on input.key(m) > Muted
+  end
+end
+region(Audio):
+  state(Muted):
+    ...
+  end
+end
+"#,
+        fixed: r#"
+region(Audio):
+  state(Playing):
+    on input.key(m) > Muted
+  end
+  state(Muted):
+    ...
+  end
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "F112",
+        level: Error,
+        summary: "Duplicate region name in one state",
+        explanation: "Region names are unique within their parallel state.",
+        example: r#"
+region(Audio):
+  ...
+end
+region(Audio):
+  ...
+end
+"#,
+        fixed: r#"
+region(Audio):
+  ...
+end
+region(Input):
+  ...
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "F113",
+        level: Warning,
+        summary: "State unreachable from `start`",
+        explanation: "No chain of transitions that can fire leads from the flow's `start` to \
+this state. Transitions on events that nothing raises do not count. Reported as an error with \
+`@meta flow_analysis=strict`; `off` disables the check.",
+        example: r#"
+flow(Main):
+  start > Title
+  state(Title):
+    terminate
+  end
+  state(Secret):
+    terminate
+  end
+end
+"#,
+        fixed: r#"
+flow(Main):
+  start > Title
+  state(Title):
+    on input.key(s) > Secret
+    terminate
+  end
+  state(Secret):
+    terminate
+  end
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "F114",
+        level: Warning,
+        summary: "Leaf state with no way out",
+        explanation: "The state has no transition that can fire and neither terminates nor \
+returns, so the flow gets stuck once it is entered. Reported as an error with \
+`@meta flow_analysis=strict`.",
+        example: r#"
+state(GameOver):
+  ui.text("Game over")
+end
+"#,
+        fixed: r#"
+state(GameOver):
+  ui.text("Game over")
+  on input.key(enter) > Title
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "F115",
+        level: Warning,
+        summary: "Cycle with no path to `terminate` or `return`",
+        explanation: "The flow can finish somewhere, but once it enters these states it can \
+only move between them. Reported as an error with `@meta flow_analysis=strict`.",
+        example: r#"
+state(Ping):
+  on input.key(space) > Pong
+end
+state(Pong):
+  on input.key(space) > Ping
+end
+"#,
+        fixed: r#"
+state(Ping):
+  on input.key(space) > Pong
+  on input.key(esc) > Exit
+end
+state(Pong):
+  on input.key(space) > Ping
+end
+"#,
+    },
+    // Rules
+    DiagnosticCode {
+        code: "R201",
+        level: Error,
+        summary: "Duplicate rule name",
+        explanation: "Rule names are unique within a module; the generated runtime keys rules \
+by name.",
+        example: r#"
+rule(score):
+  on hit:: points += 1
+end
+rule(score):
+  on miss:: points += 0
+end
+"#,
+        fixed: r#"
+rule(scoreHit):
+  on hit:: points += 1
+end
+rule(scoreMiss):
+  on miss:: points += 0
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "R202",
+        level: Error,
+        summary: "Rule has no effect body",
+        explanation: "A rule must assign a variable or `emit` an event; an empty rule does \
+nothing.",
+        example: r#"
+rule(tick):
+  on input.tick:
+  end
+end
+"#,
+        fixed: r#"
+rule(tick):
+  on input.tick:
+    ticks += 1
+  end
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "R204",
+        level: Error,
+        summary: "Unsupported `when` expression",
+        explanation: "`when` conditions are comparisons (`>=`, `>`, `<`, `==`, `!=`) over \
+literals, state variables and arithmetic, combined with `and`, `or` and `not`.",
+        example: r#"
+rule(levelUp):
+  when score:
+    level += 1
+  end
+end
+"#,
+        fixed: r#"
+rule(levelUp):
+  when score >= 100:
+    level += 1
+  end
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "R205",
+        level: Error,
+        summary: "Invalid `emit` event name",
+        explanation: "Emitted events are plain identifiers, so that transitions and rules can \
+handle them with `on <event>`.",
+        example: r#"
+emit "level-done"
+"#,
+        fixed: r#"
+emit levelDone
+"#,
+    },
+    DiagnosticCode {
+        code: "R206",
+        level: Error,
+        summary: "`every(...)` interval is zero",
+        explanation: "A periodic rule needs an interval greater than zero.",
+        example: r#"
+rule(poll):
+  every(0ms):: polls += 1
+end
+"#,
+        fixed: r#"
+rule(poll):
+  every(500ms):: polls += 1
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "R210",
+        level: Warning,
+        summary: "Rules of equal priority assign the same variable in one cycle",
+        explanation: "Both rules can fire on the same event (or, for `when` rules, on the same \
+tick) while their scopes are active, and both assign the variable. Rules run in ascending \
+`@priority` and then source order, so the later one silently wins. Give the rule that should \
+win a higher `@priority`, or make the conditions exclusive. Two `+=` updates, or two \
+assignments of the same value, are not conflicts.",
+        example: r#"
+rule(faster):
+  on input.key(f):: speed = 2
+end
+rule(slower):
+  on input.key(f):: speed = 0
+end
+"#,
+        fixed: r#"
+rule(faster):
+  on input.key(f):: speed = 2
+end
+@priority(1)
+rule(slower):
+  on input.key(f):: speed = 0
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "R211",
+        level: Error,
+        summary: "Rules trigger each other through `emit` forever",
+        explanation: "Each rule in the cycle emits an event that triggers the next one, so a \
+single event never stops cascading. Break the cycle, or let one of the events drive a state \
+transition that leaves the rules' scope.",
+        example: r#"
+rule(ping):
+  on ping:: emit pong
+end
+rule(pong):
+  on pong:: emit ping
+end
+"#,
+        fixed: r#"
+rule(ping):
+  on ping:: emit pong
+end
+rule(pong):
+  on pong:: pongs += 1
+end
+"#,
+    },
+    // ND
+    DiagnosticCode {
+        code: "N301",
+        level: Error,
+        summary: "ND block has no `propose`",
+        explanation: "An `nd` block describes a solution space with `propose` and narrows it \
+with `satisfy`; the `propose` is required.",
+        example: r#"
+nd(layout):
+  satisfy(noOverlap())
+end
+"#,
+        fixed: r#"
+nd(layout):
+  propose layout(type: "rooms")
+  satisfy(noOverlap())
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "N303",
+        level: Error,
+        summary: "Empty `satisfy(...)`",
+        explanation: "`satisfy` lists at least one constraint; drop it or add constraints.",
+        example: r#"
+nd(layout):
+  propose layout(type: "rooms")
+  satisfy()
+end
+"#,
+        fixed: r#"
+nd(layout):
+  propose layout(type: "rooms")
+  satisfy(noOverlap())
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "N304",
+        level: Error,
+        summary: "Duplicate ND constraint",
+        explanation: "The same constraint appears twice in one `satisfy(...)`.",
+        example: r#"
+satisfy(noOverlap(), noOverlap())
+"#,
+        fixed: r#"
+satisfy(noOverlap(), reachablePathExists())
+"#,
+    },
+    DiagnosticCode {
+        code: "N305",
+        level: Error,
+        summary: "`nd_budget=0` with ND blocks",
+        explanation: "A budget of zero allows no non-determinism, so the module must not \
+contain `nd` blocks. Remove them or raise the budget.",
+        example: r#"
+@meta nd_budget=0
+module(App):
+  nd(layout):
+    ...
+  end
+end
+"#,
+        fixed: r#"
+@meta nd_budget=20
+module(App):
+  nd(layout):
+    ...
+  end
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "N307",
+        level: Error,
+        summary: "Invalid or duplicate soft define name",
+        explanation: "Soft define names are identifiers, optionally dotted \
+(`collision.stable`), and unique within their module or nd block.",
+        example: r#"
+define stable():
+  "Collision should feel stable."
+end
+define stable():
+  "Movement should feel stable."
+end
+"#,
+        fixed: r#"
+define collision.stable():
+  "Collision should feel stable."
+end
+define movement.stable():
+  "Movement should feel stable."
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "N308",
+        level: Error,
+        summary: "Soft define called with the wrong number of arguments",
+        explanation: "A `?name(...)` reference passes exactly as many arguments as the define \
+declares parameters.",
+        example: r#"
+define board.size(width, height):
+  "Board is {width}x{height}."
+end
+...
+satisfy(?board.size(10))
+"#,
+        fixed: r#"
+satisfy(?board.size(10, 8))
+"#,
+    },
+    DiagnosticCode {
+        code: "N309",
+        level: Error,
+        summary: "Unknown soft define",
+        explanation: "`?name(...)` must reference a `define` of the nd block or the module.",
+        example: r#"
+satisfy(?collision.stabel())
+"#,
+        fixed: r#"
+satisfy(?collision.stable())
+"#,
+    },
+    DiagnosticCode {
+        code: "N310",
+        level: Error,
+        summary: "Invalid inline prompt",
+        explanation: "An inline prompt `?\"...\"` is a single string literal.",
+        example: r#"
+satisfy(?"fast" "and calm")
+"#,
+        fixed: r#"
+satisfy(?"fast and calm")
+"#,
+    },
+    DiagnosticCode {
+        code: "N320",
+        level: Warning,
+        summary: "ND marker on a critical deterministic path",
+        explanation: "State statements, transitions and rules are the deterministic part of a \
+program. `?` markers there leave business logic to the model. Use deterministic symbols \
+instead. `@meta nd_critical_path=error` makes this an error, `off` disables it.",
+        example: r#"
+rule(approve):
+  on submit:: approved = ?"decide if the expense is fine"
+end
+"#,
+        fixed: r#"
+rule(approve):
+  on submit:: approved = amount < limit
+end
+"#,
+    },
+    // Runtime/Binding
+    DiagnosticCode {
+        code: "B401",
+        level: Error,
+        summary: "`run` references an unknown flow",
+        explanation: "`run Flow` must name a flow of the same module.",
+        example: r#"
+state(Ask):
+  run Confrim
+  on done > Next
+end
+"#,
+        fixed: r#"
+state(Ask):
+  run Confirm
+  on done > Next
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "B402",
+        level: Error,
+        summary: "Misplaced `terminate` or `return`",
+        explanation: "`terminate` and `return` end the flow, so they are the last statement \
+of their state, and `terminate` cannot be combined with other statements.",
+        example: r#"
+state(Done):
+  return
+  ui.text("Bye")
+end
+"#,
+        fixed: r#"
+state(Done):
+  ui.text("Bye")
+  return
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "B403",
+        level: Error,
+        summary: "Multiple `run` targets in one state",
+        explanation: "A state runs at most one sub-flow; split the runs into consecutive \
+states.",
+        example: r#"
+state(Setup):
+  run Login
+  run Profile
+  on done > Home
+end
+"#,
+        fixed: r#"
+state(Setup):
+  run Login
+  on done > ProfileSetup
+end
+state(ProfileSetup):
+  run Profile
+  on done > Home
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "B404",
+        level: Error,
+        summary: "`run` without `on done > ...`",
+        explanation: "When the sub-flow returns, the state needs a transition for `done` to \
+continue.",
+        example: r#"
+state(Ask):
+  run Confirm
+end
+"#,
+        fixed: r#"
+state(Ask):
+  run Confirm
+  on done > Next
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "B405",
+        level: Error,
+        summary: "`run` arguments do not match the flow parameters",
+        explanation: "A `run` passes every parameter of the flow exactly once, positionally or \
+by name, and no names the flow does not declare.",
+        example: r#"
+flow(Confirm, message: string):
+  ...
+end
+...
+run Confirm()
+"#,
+        fixed: r#"
+run Confirm("Delete file?")
+"#,
+    },
+    DiagnosticCode {
+        code: "B406",
+        level: Error,
+        summary: "`run ... -> result` on a flow without a result type",
+        explanation: "Only flows declared with `-> Type` return a value to bind.",
+        example: r#"
+flow(Confirm):
+  ...
+end
+...
+run Confirm -> answer
+"#,
+        fixed: r#"
+flow(Confirm) -> bool:
+  ...
+end
+...
+run Confirm -> answer
+"#,
+    },
+    DiagnosticCode {
+        code: "B407",
+        level: Error,
+        summary: "`return` value does not match the flow signature",
+        explanation: "A flow with a result type returns a value from every `return`; a flow \
+without one returns no value.",
+        example: r#"
+flow(Confirm) -> bool:
+  ...
+  state(Yes):
+    return
+  end
+end
+"#,
+        fixed: r#"
+flow(Confirm) -> bool:
+  ...
+  state(Yes):
+    return true
+  end
+end
+"#,
+    },
+    // Namespace/Scope
+    DiagnosticCode {
+        code: "NS501",
+        level: Error,
+        summary: "Invalid namespace segment or qualified name",
+        explanation: "Module names and qualified references are dot-separated identifiers, \
+without empty segments.",
+        example: r#"
+module(Billing..Invoices):
+"#,
+        fixed: r#"
+module(Billing.Invoices):
+"#,
+    },
+    DiagnosticCode {
+        code: "NS503",
+        level: Error,
+        summary: "Unknown qualified reference",
+        explanation: "A reference qualified with this module's name must resolve to a flow, \
+state, rule or global of the module.",
+        example: r#"
+module(App):
+  ...
+  ui.text(App.Main.Titel)
+"#,
+        fixed: r#"
+  ui.text(App.Main.Title)
+"#,
+    },
+    DiagnosticCode {
+        code: "NS504",
+        level: Error,
+        summary: "Cross-namespace reference without import",
+        explanation: "A qualified name that points into another namespace needs a `use(...)` \
+of a contract package or an `import(...)` of the module.",
+        example: r#"
+module(App):
+  ...
+  total = Billing.Rules.total(items)
+"#,
+        fixed: r#"
+module(App):
+  import(Billing.Rules) as Rules
+  ...
+  total = Rules.total(items)
+"#,
+    },
+    DiagnosticCode {
+        code: "NS505",
+        level: Error,
+        summary: "Forbidden shadowing in strict scope mode",
+        explanation: "With `@meta strict_scopes=true` a rule parameter may not reuse the name \
+of a global state variable.",
+        example: r#"
+@meta strict_scopes=true
+...
+state():
+  score = 0
+end
+rule(add, score):
+  ...
+"#,
+        fixed: r#"
+rule(add, points):
+  ...
+"#,
+    },
+    DiagnosticCode {
+        code: "NS506",
+        level: Error,
+        summary: "Ambiguous unqualified reference",
+        explanation: "The short name matches more than one symbol of the module, for example \
+states with the same name in two flows. Qualify it with its full path.",
+        example: r#"
+ui.text(Title)
+"#,
+        fixed: r#"
+ui.text(App.Main.Title)
+"#,
+    },
+    DiagnosticCode {
+        code: "NS507",
+        level: Error,
+        summary: "Reference to a symbol the imported module does not export",
+        explanation: "Project modules are private by default. Another module can only reference \
+what the imported module lists in `exports(...)`; states and rules inside flows stay internal.",
+        example: r#"
+module(Billing.Rules):
+  state():
+    limit = 1000
+  end
+end
+...
+  total = Rules.limit
+"#,
+        fixed: r#"
+module(Billing.Rules):
+  exports(limit)
+  state():
+    limit = 1000
+  end
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "NS508",
+        level: Error,
+        summary: "Unknown name in `exports(...)`",
+        explanation: "Only flows, top-level rules, state variables, defines and nd blocks of the \
+module itself can be exported.",
+        example: r#"
+exports(Mian)
+flow(Main):
+"#,
+        fixed: r#"
+exports(Main)
+flow(Main):
+"#,
+    },
+    // Use/Import
+    DiagnosticCode {
+        code: "U601",
+        level: Error,
+        summary: "Invalid `use` path",
+        explanation: "`use(...)` takes a dotted package path such as `cli.ui`.",
+        example: r#"
+use(cli..ui)
+"#,
+        fixed: r#"
+use(cli.ui)
+"#,
+    },
+    DiagnosticCode {
+        code: "U602",
+        level: Error,
+        summary: "Invalid `use` alias",
+        explanation: "The alias after `as` is a plain identifier.",
+        example: r#"
+use(cli.input) as in.put
+"#,
+        fixed: r#"
+use(cli.input) as input
+"#,
+    },
+    DiagnosticCode {
+        code: "U603",
+        level: Error,
+        summary: "Duplicate namespace root from `use`",
+        explanation: "Two `use` declarations expose the same root name. Alias one of them.",
+        example: r#"
+use(cli.ui)
+use(gui.ui)
+"#,
+        fixed: r#"
+use(cli.ui)
+use(gui.ui) as window
+"#,
+    },
+    DiagnosticCode {
+        code: "U604",
+        level: Error,
+        summary: "Invalid `import` alias or root",
+        explanation: "`import(...)` takes a dotted module path, and the alias after `as` is a \
+plain identifier.",
+        example: r#"
+import(Billing.Rules) as 2rules
+"#,
+        fixed: r#"
+import(Billing.Rules) as Rules
+"#,
+    },
+    DiagnosticCode {
+        code: "U605",
+        level: Error,
+        summary: "Duplicate namespace root from `import`",
+        explanation: "The import exposes a root name that a `use` or another `import` already \
+exposes. Alias one of them.",
+        example: r#"
+import(Billing.Rules)
+import(Shipping.Rules)
+"#,
+        fixed: r#"
+import(Billing.Rules) as BillingRules
+import(Shipping.Rules) as ShippingRules
+"#,
+    },
+    DiagnosticCode {
+        code: "U606",
+        level: Error,
+        summary: "Import cycle",
+        explanation: "Modules of a project cannot import each other in a circle, directly or \
+through other modules. Move what both sides need into a module that neither imports.",
+        example: r#"
+module(Billing.Rules):
+  import(Billing.Invoices) as Invoices
+...
+module(Billing.Invoices):
+  import(Billing.Rules) as Rules
+"#,
+        fixed: r#"
+module(Billing.Rules):
+  import(Billing.Shared) as Shared
+...
+module(Billing.Invoices):
+  import(Billing.Rules) as Rules
+"#,
+    },
+    DiagnosticCode {
+        code: "U607",
+        level: Error,
+        summary: "Unknown imported module",
+        explanation: "`import(...)` names a module by its `module(...)` name, and that module \
+must be listed in the project file's `modules`.",
+        example: r#"
+import(Billing.Rule) as Rules
+"#,
+        fixed: r#"
+import(Billing.Rules) as Rules
+"#,
+    },
+    DiagnosticCode {
+        code: "U610",
+        level: Error,
+        summary: "Legacy `key(...)` event shorthand",
+        explanation: "Bare `key(...)` events predate packages. Import the input package and use \
+`input.key(...)`. This is an error in every language edition.",
+        example: r#"
+on key(enter) > Game
+"#,
+        fixed: r#"
+use(cli.input) as input
+...
+on input.key(enter) > Game
+"#,
+    },
+    DiagnosticCode {
+        code: "U611",
+        level: Error,
+        summary: "Legacy `render` shorthand",
+        explanation: "Bare `render ...` statements predate packages. Use namespaced calls such \
+as `ui.text(...)`. This is an error in every language edition.",
+        example: r#"
+render text("Hello")
+"#,
+        fixed: r#"
+use(cli.ui)
+...
+ui.text("Hello")
+"#,
+    },
+    // Types
+    DiagnosticCode {
+        code: "T801",
+        level: Error,
+        summary: "Assigned value does not match the variable's type",
+        explanation: "A variable keeps the type it was declared or first assigned with. Add a \
+type annotation such as `value: any = 0` if it may hold different types. An `int` flag that is \
+also assigned `true`/`false` is only a warning in files pinned to language 1.0.",
+        example: r#"
+score: int = 0
+...
+score = "high"
+"#,
+        fixed: r#"
+score = 10
+"#,
+    },
+    DiagnosticCode {
+        code: "T802",
+        level: Error,
+        summary: "`+=` on a variable that is not a number or string",
+        explanation: "`+=` adds numbers or appends strings; the variable and the value must \
+have matching types.",
+        example: r#"
+ready = true
+...
+ready += 1
+"#,
+        fixed: r#"
+count = 0
+...
+count += 1
+"#,
+    },
+    DiagnosticCode {
+        code: "T803",
+        level: Error,
+        summary: "Operand types do not fit the operator",
+        explanation: "Comparisons and arithmetic work on numbers; `==` and `!=` compare values \
+of the same type.",
+        example: r#"
+when "a" > 3:
+"#,
+        fixed: r#"
+when score > 3:
+"#,
+    },
+    DiagnosticCode {
+        code: "T804",
+        level: Error,
+        summary: "Contract call argument has the wrong type",
+        explanation: "The target contract declares the argument types of its calls.",
+        example: r#"
+ui.text(42, color: true)
+"#,
+        fixed: r#"
+ui.text("42", color: "green")
+"#,
+    },
+    DiagnosticCode {
+        code: "T805",
+        level: Error,
+        summary: "Variable declared with two different types",
+        explanation: "Type annotations of one variable must agree everywhere it is declared.",
+        example: r#"
+state():
+  level: int = 1
+end
+...
+level: string = "easy"
+"#,
+        fixed: r#"
+level = 2
+"#,
+    },
+    DiagnosticCode {
+        code: "T806",
+        level: Error,
+        summary: "`run` argument does not fit the parameter type",
+        explanation: "Each argument of `run` must match the type of the flow parameter it \
+fills.",
+        example: r#"
+flow(Confirm, message: string):
+...
+run Confirm(42)
+"#,
+        fixed: r#"
+run Confirm("Proceed?")
+"#,
+    },
+    DiagnosticCode {
+        code: "T807",
+        level: Error,
+        summary: "`return` value does not fit the flow result type",
+        explanation: "The value of `return` must match the `-> Type` of the flow.",
+        example: r#"
+flow(Confirm) -> bool:
+  ...
+  return "yes"
+"#,
+        fixed: r#"
+  return true
+"#,
+    },
+    // Meta/Convergence controls
+    DiagnosticCode {
+        code: "M701",
+        level: Error,
+        summary: "Invalid `nd_budget`",
+        explanation: "`nd_budget` is an integer from 0 to 100.",
+        example: r#"
+@meta nd_budget=150
+"#,
+        fixed: r#"
+@meta nd_budget=35
+"#,
+    },
+    DiagnosticCode {
+        code: "M702",
+        level: Error,
+        summary: "Invalid `confidence`",
+        explanation: "`confidence` is a number from 0.0 to 1.0.",
+        example: r#"
+@meta confidence=95
+"#,
+        fixed: r#"
+@meta confidence=0.95
+"#,
+    },
+    DiagnosticCode {
+        code: "M703",
+        level: Error,
+        summary: "Invalid `max_iterations`",
+        explanation: "`max_iterations` is an integer from 1 to 10000.",
+        example: r#"
+@meta max_iterations=0
+"#,
+        fixed: r#"
+@meta max_iterations=3
+"#,
+    },
+    DiagnosticCode {
+        code: "M704",
+        level: Error,
+        summary: "Invalid `fallback`",
+        explanation: "`fallback` is one of `fail`, `stub` or `replay`.",
+        example: r#"
+@meta fallback=retry
+"#,
+        fixed: r#"
+@meta fallback=replay
+"#,
+    },
+    DiagnosticCode {
+        code: "M705",
+        level: Error,
+        summary: "Invalid `nd_policy`",
+        explanation: "The only supported `nd_policy` is `strict`.",
+        example: r#"
+@meta nd_policy=loose
+"#,
+        fixed: r#"
+@meta nd_policy=strict
+"#,
+    },
+    DiagnosticCode {
+        code: "M706",
+        level: Error,
+        summary: "Invalid `nd_critical_path`",
+        explanation: "`nd_critical_path` is one of `off`, `warn` or `error` and controls N320.",
+        example: r#"
+@meta nd_critical_path=strict
+"#,
+        fixed: r#"
+@meta nd_critical_path=error
+"#,
+    },
+    DiagnosticCode {
+        code: "M707",
+        level: Warning,
+        summary: "Unknown item attribute",
+        explanation: "Known attributes are `@owner`, `@deprecated`, `@critical` and \
+`@priority`. Unknown attributes are kept in the IR but have no meaning to the compiler.",
+        example: r#"
+@ownr(payments)
+flow(Checkout):
+"#,
+        fixed: r#"
+@owner(payments)
+flow(Checkout):
+"#,
+    },
+    DiagnosticCode {
+        code: "M708",
+        level: Error,
+        summary: "Invalid or repeated item attribute",
+        explanation: "`@owner` takes one name, `@deprecated` an optional message, `@critical` \
+no arguments and `@priority` one whole number, and only on rules. An item lists each \
+attribute once.",
+        example: r#"
+@priority("high")
+rule(reset):
+"#,
+        fixed: r#"
+@priority(1)
+rule(reset):
+"#,
+    },
+    DiagnosticCode {
+        code: "M709",
+        level: Warning,
+        summary: "Use of a deprecated flow or state",
+        explanation: "A `run` targets a `@deprecated` flow, or a transition enters a \
+`@deprecated` state. Uses from deprecated code are not reported.",
+        example: r#"
+@deprecated("use Checkout2")
+flow(Checkout):
+...
+run Checkout
+"#,
+        fixed: r#"
+run Checkout2
+"#,
+    },
+    DiagnosticCode {
+        code: "M710",
+        level: Error,
+        summary: "Invalid `language`",
+        explanation: "`language` pins the edition as `MAJOR.MINOR`.",
+        example: r#"
+@meta language=1
+"#,
+        fixed: r#"
+@meta language=1.1
+"#,
+    },
+    DiagnosticCode {
+        code: "M711",
+        level: Error,
+        summary: "Unsupported `language`",
+        explanation: "The pinned language is outside the range this compiler supports; \
+`sculpt target list` prints the range.",
+        example: r#"
+@meta language=2.0
+"#,
+        fixed: r#"
+@meta language=1.1
+"#,
+    },
+    DiagnosticCode {
+        code: "M712",
+        level: Error,
+        summary: "Invalid `flow_analysis`",
+        explanation: "`flow_analysis` is one of `off`, `warn` or `strict` and controls \
+F113-F115.",
+        example: r#"
+@meta flow_analysis=error
+"#,
+        fixed: r#"
+@meta flow_analysis=strict
+"#,
+    },
+    // Dataflow
+    DiagnosticCode {
+        code: "D001",
+        level: Warning,
+        summary: "Variable may be read before it is assigned",
+        explanation: "On some path through the flow the variable is read before anything \
+assigns it, or a `when` rule may read it before the `on`/`every` rule of the same state has \
+assigned it. Assign it in global `state()` or on every path into the state.",
+        example: r#"
+state(Menu):
+  on input.key(s) > Summary
+  on input.key(l) > Load
+end
+state(Load):
+  rows = 10
+  on input.key(s) > Summary
+end
+state(Summary):
+  ui.text("Rows: ${rows}")
+end
+"#,
+        fixed: r#"
+state():
+  rows = 0
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "D002",
+        level: Warning,
+        summary: "Variable assigned but never read",
+        explanation: "Nothing reads the variable, so the assignment has no effect. Globals in \
+`state()` are exempt because target runtimes read them; prefix a name with `_` to mark it as \
+intentionally unused.",
+        example: r#"
+state(Load):
+  scratch = compute()
+end
+"#,
+        fixed: r#"
+state(Load):
+  _scratch = compute()
+end
+"#,
+    },
+    DiagnosticCode {
+        code: "D003",
+        level: Warning,
+        summary: "`+=` on a variable that is never initialised",
+        explanation: "The variable is only ever updated with `+=`; give it a starting value.",
+        example: r#"
+rule(count):
+  on input.tick:: ticks += 1
+end
+"#,
+        fixed: r#"
+state():
+  ticks = 0
+end
+rule(count):
+  on input.tick:: ticks += 1
+end
+"#,
+    },
+    // Target contract validation
+    DiagnosticCode {
+        code: "C901",
+        level: Error,
+        summary: "Invalid `@meta` value for the target contract",
+        explanation: "The target contract declares the type and range of its meta keys; the \
+value does not fit.",
+        example: r#"
+@meta target=cli
+@meta contract_version=latest
+"#,
+        fixed: r#"
+@meta target=cli
+@meta contract_version=1
+"#,
+    },
+    DiagnosticCode {
+        code: "C902",
+        level: Error,
+        summary: "Required capability missing on the target",
+        explanation: "`@meta requires=...` lists a capability the selected target does not \
+provide. `sculpt target describe --target <name>` lists its capabilities.",
+        example: r#"
+@meta target=cli
+@meta requires=ui.modal.ok
+"#,
+        fixed: r#"
+@meta target=gui
+@meta requires=ui.modal.ok
+"#,
+    },
+    DiagnosticCode {
+        code: "C903",
+        level: Error,
+        summary: "Unknown `@meta` key for the target",
+        explanation: "The key is not declared in the target contract's meta schema. Prefix \
+private keys with `x_`.",
+        example: r#"
+@meta team=payments
+"#,
+        fixed: r#"
+@meta x_team=payments
+"#,
+    },
+    DiagnosticCode {
+        code: "C904",
+        level: Error,
+        summary: "`layout=explicit` without target support",
+        explanation: "Explicit layout needs the `layout.explicit` capability on the target.",
+        example: r#"
+@meta target=cli
+@meta layout=explicit
+"#,
+        fixed: r#"
+@meta target=gui
+@meta layout=explicit
+"#,
+    },
+    DiagnosticCode {
+        code: "C905",
+        level: Error,
+        summary: "Unknown package namespace",
+        explanation: "`use(...)` names a package the target contract does not provide. \
+`sculpt target packages --target <name>` lists them.",
+        example: r#"
+use(cli.audio)
+"#,
+        fixed: r#"
+use(cli.ui)
+"#,
+    },
+    DiagnosticCode {
+        code: "C906",
+        level: Error,
+        summary: "Symbol not exported by the package",
+        explanation: "The call uses a symbol the package does not export. `sculpt target \
+exports --target <name> --package <pkg>` lists the exports.",
+        example: r#"
+ui.txt("Hello")
+"#,
+        fixed: r#"
+ui.text("Hello")
+"#,
+    },
+    DiagnosticCode {
+        code: "C907",
+        level: Error,
+        summary: "Unknown unqualified deterministic call",
+        explanation: "On the CLI target, unqualified calls in deterministic code must be one \
+of the runtime helpers (`csvRead`, `writeJson`, ...). Qualify package calls with their \
+namespace.",
+        example: r#"
+rows = loadRows(path)
+"#,
+        fixed: r#"
+rows = csvRead(path)
+"#,
+    },
+    DiagnosticCode {
+        code: "C908",
+        level: Error,
+        summary: "Wrong number of arguments for a call",
+        explanation: "The target contract fixes the arity of the call.",
+        example: r#"
+rows = data.csvRead()
+"#,
+        fixed: r#"
+rows = data.csvRead(path)
+"#,
+    },
+    DiagnosticCode {
+        code: "C909",
+        level: Error,
+        summary: "Invalid call signature",
+        explanation: "An argument of a contract call has the wrong form, for example a path \
+that is neither a string nor an identifier, or a metric key that is not allowed.",
+        example: r#"
+rows = data.csvRead(42)
+"#,
+        fixed: r#"
+rows = data.csvRead("invoices.csv")
+"#,
+    },
+    DiagnosticCode {
+        code: "C910",
+        level: Error,
+        summary: "Implicit ND constraint or unverifiable required outputs",
+        explanation: "An ND constraint must be explicit: a soft define `?name(...)`, an inline \
+prompt `?\"...\"` or a namespaced contract call. The code is also reported when \
+`@meta required_outputs` is set but the build produced no runtime rules that could write the \
+outputs, or lists a file type other than `.json` and `.csv`.",
+        example: r#"
+satisfy(readableLayout())
+"#,
+        fixed: r#"
+use(cli.guide) as guide
+...
+satisfy(guide.readableLayout())
+"#,
+    },
+    DiagnosticCode {
+        code: "C911",
+        level: Error,
+        summary: "Unknown alias in ND constraint or unbacked required output",
+        explanation: "A namespaced ND constraint uses an alias that no `use(...)` declares. The \
+code is also reported when a `@meta required_outputs` entry is not written by a \
+deterministic writer call (`data.writeJson`, `data.writeCsv`) in the runtime rules.",
+        example: r#"
+satisfy(guide.readableLayout())
+"#,
+        fixed: r#"
+use(cli.guide) as guide
+...
+satisfy(guide.readableLayout())
+"#,
+    },
+    DiagnosticCode {
+        code: "C912",
+        level: Error,
+        summary: "Invalid `buildReportJson` field",
+        explanation: "A field of the report passed to `buildReportJson` has a value of the \
+wrong kind, for example text where a count is expected.",
+        example: r#"
+report = data.buildReportJson(..., processing_ms: "fast")
+"#,
+        fixed: r#"
+report = data.buildReportJson(..., processing_ms: elapsedMs)
+"#,
+    },
+    DiagnosticCode {
+        code: "C913",
+        level: Error,
+        summary: "Non-portable package under `profile=portable`",
+        explanation: "Portable modules only use the `ui`, `input` and `data` packages, which \
+every target provides.",
+        example: r#"
+@meta profile=portable
+...
+use(gui.window)
+"#,
+        fixed: r#"
+@meta profile=portable
+...
+use(gui.ui)
+"#,
+    },
+    DiagnosticCode {
+        code: "C914",
+        level: Error,
+        summary: "Non-portable symbol under `profile=portable`",
+        explanation: "Portable modules only call the symbols of `ui`, `input` and `data` that \
+every target exports. The message lists the allowed symbols.",
+        example: r#"
+@meta profile=portable
+...
+ui.gradient("blue")
+"#,
+        fixed: r#"
+ui.text("Hello")
+"#,
+    },
+    DiagnosticCode {
+        code: "C915",
+        level: Error,
+        summary: "`contract_version` does not match the target",
+        explanation: "The script pins a target contract version that differs from the \
+selected target's. Update the script for the new contract, or pin the matching version.",
+        example: r#"
+@meta contract_version=0
+"#,
+        fixed: r#"
+@meta contract_version=1
+"#,
+    },
+    DiagnosticCode {
+        code: "C916",
+        level: Error,
+        summary: "Language feature not supported by the target runtime",
+        explanation: "Some constructs need support in the generated runtime: string \
+interpolation in view text (`text.interpolation`). A target declares what its runtime \
+implements as contract capabilities (`sculpt target describe`); the construct is rejected on \
+targets that do not. Pick another target or rewrite the construct.",
+        example: r#"
+@meta target=gui
+...
+  ui.text("Total: ${total}")
+"#,
+        fixed: r#"
+@meta target=cli
+...
+  ui.text("Total: ${total}")
+"#,
+    },
+];
@@ -0,0 +1,787 @@
+use anyhow::Result;
+
+use crate::ast::*;
+use crate::lexer::{lex_comments, lex_recovering, Comment, Keyword, TokenKind};
+use crate::parser::{parse_source_recovering_with_path, SyntaxErrors};
+use std::path::Path;
+
+const INDENT: &str = "  ";
+
+/// Parses `input` and prints it in canonical layout (see [`format_module`]).
+///
+/// Fails on syntax warnings too: the ignored text after the module's `end` would be lost.
+pub fn format_source(input: &str, path: &Path) -> Result<String> {
+    let outcome = parse_source_recovering_with_path(input, path);
+    if !outcome.diagnostics.is_empty() {
+        return Err(SyntaxErrors(outcome.diagnostics).into());
+    }
+    Ok(format_module(&outcome.module))
+}
+
+/// Prints `module` in canonical layout: two-space indentation, one `@meta` key per line,
+/// uses/imports/exports first, a blank line between module items and between flow states.
+///
+/// Comments are taken from the source the module was parsed from and re-attached to the
+/// nearest following node (or the end of the line they trailed). Blank lines inside state,
+/// rule and nd bodies are kept, collapsed to one. Printing and parsing again yields the
+/// same module.
+pub fn format_module(module: &Module) -> String {
+    let text = module
+        .span
+        .source
+        .as_ref()
+        .map(|s| s.text.as_str())
+        .unwrap_or("");
+    let mut printer = Printer::new(text);
+    printer.module(module);
+    printer.finish()
+}
+
+struct Printer<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+    comments: Vec<Comment>,
+    next_comment: usize,
+    /// Source offset up to which everything has been printed.
+    cursor: usize,
+    out: String,
+    depth: usize,
+    /// Set after a block header until the first body line, to suppress blank lines there.
+    block_start: bool,
+}
+
+impl<'a> Printer<'a> {
+    fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            text,
+            line_starts,
+            comments: lex_comments(text),
+            next_comment: 0,
+            cursor: 0,
+            out: String::new(),
+            depth: 0,
+            block_start: true,
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.leading(usize::MAX, false);
+        self.out
+    }
+
+    fn module(&mut self, module: &Module) {
+        let mut meta: Vec<_> = module.meta.iter().collect();
+        meta.sort_by_key(|(key, _)| {
+            (
+                module.meta_spans.get(*key).map_or(usize::MAX, |s| s.start),
+                key.as_str(),
+            )
+        });
+        for (key, value) in meta {
+            let span = module.meta_spans.get(key);
+            self.open(span, true);
+            self.line(&format!("@meta {}={}", key, meta_value(value)), span);
+            self.close(span);
+        }
+
+        let header = self.module_keyword_offset();
+        if !module.meta.is_empty() {
+            self.blank();
+        }
+        if let Some(offset) = header {
+            self.leading(offset, true);
+            if self.blank_in_source(offset) {
+                self.blank();
+            }
+        }
+        self.line_at(&format!("module({}):", module.name), header);
+        self.enter();
+
+        for decl in &module.uses {
+            self.open(Some(&decl.span), false);
+            self.line(&use_line("use", &decl.path, &decl.alias), Some(&decl.span));
+            self.close(Some(&decl.span));
+        }
+        for decl in &module.imports {
+            self.open(Some(&decl.span), false);
+            self.line(
+                &use_line("import", &decl.path, &decl.alias),
+                Some(&decl.span),
+            );
+            self.close(Some(&decl.span));
+        }
+        for decl in &module.exports {
+            self.open(Some(&decl.span), false);
+            self.line(
+                &format!("exports({})", decl.names.join(", ")),
+                Some(&decl.span),
+            );
+            self.close(Some(&decl.span));
+        }
+        let mut separate =
+            !module.uses.is_empty() || !module.imports.is_empty() || !module.exports.is_empty();
+        for item in &module.items {
+            if separate {
+                self.blank();
+            }
+            separate = true;
+            match item {
+                Item::Flow(flow) => self.flow(flow),
+                Item::GlobalState(block) => self.state_block(block, "state():"),
+                Item::Rule(rule) => self.rule(rule),
+                Item::Nd(nd) => self.nd(nd),
+                Item::Define(define) => self.define(define),
+            }
+        }
+        self.exit(&module.span);
+    }
+
+    fn flow(&mut self, flow: &Flow) {
+        self.open(Some(&flow.span), false);
+        let header_span = self.attributes(&flow.annotations, &flow.span);
+        let mut header = flow.name.clone();
+        for param in &flow.params {
+            header.push_str(&format!(", {}: {}", param.name, param.ty));
+        }
+        let returns = match &flow.returns {
+            Some(ty) => format!(" -> {}", ty),
+            None => String::new(),
+        };
+        self.line(&format!("flow({}){}:", header, returns), Some(&header_span));
+        self.enter();
+        self.start(&flow.start, flow.start_span.as_ref());
+        self.flow_states(&flow.states);
+        self.exit(&flow.span);
+    }
+
+    fn flow_states(&mut self, states: &[StateBlock]) {
+        for state in states {
+            self.blank();
+            let header = format!("state({}):", state.name.as_deref().unwrap_or(""));
+            self.state_block(state, &header);
+        }
+    }
+
+    fn start(&mut self, start: &Option<String>, span: Option<&Span>) {
+        if let Some(start) = start {
+            self.open(span, false);
+            self.line(&format!("start > {}", start), span);
+            self.close(span);
+        }
+    }
+
+    fn state_block(&mut self, block: &StateBlock, header: &str) {
+        self.open(Some(&block.span), false);
+        let header_span = self.attributes(&block.annotations, &block.span);
+        self.line(header, Some(&header_span));
+        self.enter();
+        for stmt in &block.statements {
+            self.state_stmt(stmt);
+        }
+        self.start(&block.start, block.start_span.as_ref());
+        self.flow_states(&block.states);
+        for region in &block.regions {
+            self.blank();
+            self.open(Some(&region.span), false);
+            self.line(&format!("region({}):", region.name), Some(&region.span));
+            self.enter();
+            self.start(&region.start, region.start_span.as_ref());
+            self.flow_states(&region.states);
+            self.exit(&region.span);
+        }
+        self.exit(&block.span);
+    }
+
+    fn state_stmt(&mut self, stmt: &StateStmt) {
+        let span = stmt.span();
+        match stmt {
+            StateStmt::Rule(rule) if is_inline_on_rule(rule) => {
+                self.inline_on_rule(rule);
+                return;
+            }
+            StateStmt::Rule(rule) => {
+                self.open(span, true);
+                self.rule(rule);
+                return;
+            }
+            _ => {}
+        }
+        let text = match stmt {
+            StateStmt::On {
+                event,
+                guard: Some(guard),
+                target,
+                ..
+            } => format!(
+                "on {} when {} > {}",
+                call_text(event, false),
+                guard_text(guard),
+                target
+            ),
+            StateStmt::On { event, target, .. } => {
+                format!("on {} > {}", call_text(event, false), target)
+            }
+            StateStmt::After {
+                delay_ms, target, ..
+            } => format!("after({}) > {}", duration_text(*delay_ms), target),
+            StateStmt::Run {
+                flow, args, result, ..
+            } => {
+                let mut text = format!("run {}", flow);
+                if !args.is_empty() {
+                    text.push_str(&format!("({})", args_text(args)));
+                }
+                if let Some(result) = result {
+                    text.push_str(&format!(" -> {}", result));
+                }
+                text
+            }
+            StateStmt::Terminate => "terminate".to_string(),
+            StateStmt::Return { value: None, .. } => "return".to_string(),
+            StateStmt::Return {
+                value: Some(value), ..
+            } => format!("return {}", expr_text(value, 0)),
+            StateStmt::Assign {
+                target,
+                value: Expr::Null,
+                ty: Some(ty),
+                ..
+            } => format!("{}: {}", target, ty),
+            StateStmt::Assign {
+                target,
+                value,
+                ty: Some(ty),
+                ..
+            } => format!("{}: {} = {}", target, ty, expr_text(value, 0)),
+            StateStmt::Assign {
+                target, op, value, ..
+            } => assign_text(target, op, value),
+            StateStmt::Expr(call) => call_text(call, true),
+            StateStmt::Rule(_) => unreachable!("rules are printed above"),
+        };
+        self.open(span, true);
+        self.line(&text, span);
+        self.close(span);
+    }
+
+    fn inline_on_rule(&mut self, rule: &Rule) {
+        let RuleTrigger::On(event) = &rule.trigger else {
+            return;
+        };
+        self.open(Some(&rule.span), true);
+        if let [stmt] = rule.body.as_slice() {
+            let text = format!("on {}:: {}", call_text(event, false), rule_stmt_text(stmt));
+            self.line(&text, Some(&rule.span));
+            self.close(Some(&rule.span));
+            return;
+        }
+        self.line(
+            &format!("on {}:", call_text(event, false)),
+            Some(&rule.span),
+        );
+        self.enter();
+        self.rule_body(&rule.body);
+        self.exit(&rule.span);
+    }
+
+    fn rule(&mut self, rule: &Rule) {
+        self.open(Some(&rule.span), false);
+        let header_span = self.attributes(&rule.annotations, &rule.span);
+        let mut head = rule.name.clone();
+        for param in &rule.params {
+            head.push_str(", ");
+            head.push_str(param);
+        }
+        self.line(&format!("rule({}):", head), Some(&header_span));
+        self.enter();
+        let trigger = match &rule.trigger {
+            RuleTrigger::On(call) => format!("on {}:", call_text(call, false)),
+            RuleTrigger::When(expr) => format!("when {}:", expr_text(expr, 0)),
+            RuleTrigger::Every(ms) => format!("every({}):", duration_text(*ms)),
+        };
+        // The trigger line has no span of its own; it sits on the line after the header.
+        self.line(&trigger, None);
+        self.enter();
+        self.rule_body(&rule.body);
+        self.depth -= 1;
+        self.line("end", None);
+        self.exit(&rule.span);
+    }
+
+    fn rule_body(&mut self, body: &[RuleStmt]) {
+        for stmt in body {
+            let span = stmt.span();
+            self.open(Some(span), true);
+            self.line(&rule_stmt_text(stmt), Some(span));
+            self.close(Some(span));
+        }
+    }
+
+    fn nd(&mut self, nd: &NdBlock) {
+        self.open(Some(&nd.span), false);
+        let header_span = self.attributes(&nd.annotations, &nd.span);
+        let mut head = nd.name.clone();
+        for param in &nd.params {
+            head.push_str(", ");
+            head.push_str(param);
+        }
+        self.line(&format!("nd({}):", head), Some(&header_span));
+        self.enter();
+        for define in &nd.defines {
+            self.define(define);
+        }
+        if !nd.defines.is_empty() {
+            self.blank();
+        }
+        self.open(Some(&nd.propose.span), true);
+        self.line(
+            &format!("propose {}", call_text(&nd.propose, false)),
+            Some(&nd.propose.span),
+        );
+        self.close(Some(&nd.propose.span));
+        if !nd.constraints.is_empty() {
+            self.line("satisfy(", None);
+            self.enter();
+            let last = nd.constraints.len() - 1;
+            for (idx, constraint) in nd.constraints.iter().enumerate() {
+                let sep = if idx == last { "" } else { "," };
+                self.open(Some(&constraint.span), true);
+                self.line(
+                    &format!("{}{}", constraint_text(constraint), sep),
+                    Some(&constraint.span),
+                );
+                self.close(Some(&constraint.span));
+            }
+            self.depth -= 1;
+            self.line(")", None);
+        }
+        self.exit(&nd.span);
+    }
+
+    fn define(&mut self, define: &SoftDefine) {
+        self.open(Some(&define.span), false);
+        self.line(
+            &format!("define {}({}):", define.name, define.params.join(", ")),
+            Some(&define.span),
+        );
+        self.enter();
+        self.line(&string_literal(&define.template), None);
+        self.exit(&define.span);
+    }
+
+    /// Starts a node: prints the comments before it and, if `keep_blank`, one blank line
+    /// when the source had one.
+    fn open(&mut self, span: Option<&Span>, keep_blank: bool) {
+        let Some(span) = span.filter(|s| !s.is_dummy()) else {
+            return;
+        };
+        self.leading(span.start, keep_blank);
+        if keep_blank && self.blank_in_source(span.start) {
+            self.blank();
+        }
+        self.cursor = self.cursor.max(span.start);
+    }
+
+    fn close(&mut self, span: Option<&Span>) {
+        if let Some(span) = span.filter(|s| !s.is_dummy()) {
+            self.cursor = self.cursor.max(span.end);
+        }
+    }
+
+    fn enter(&mut self) {
+        self.depth += 1;
+        self.block_start = true;
+    }
+
+    /// Closes the block `span`: comments left inside it go above its `end`.
+    fn exit(&mut self, span: &Span) {
+        if !span.is_dummy() {
+            self.leading(span.end.saturating_sub("end".len()), true);
+        }
+        self.depth -= 1;
+        let end_line = (!span.is_dummy()).then(|| self.line_of(span.end));
+        self.emit("end", end_line);
+        self.close(Some(span));
+    }
+
+    /// Prints every pending comment that starts before `offset` on its own line.
+    fn leading(&mut self, offset: usize, keep_blank: bool) {
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.start >= offset {
+                break;
+            }
+            let (start, end, text) = (comment.start, comment.end, comment.text.clone());
+            if keep_blank && self.blank_in_source(start) {
+                self.blank();
+            }
+            self.next_comment += 1;
+            self.emit(&text, None);
+            self.cursor = self.cursor.max(end);
+        }
+    }
+
+    /// Prints `text` as the first line of the node at `span`, with any comment trailing
+    /// that source line.
+    fn line(&mut self, text: &str, span: Option<&Span>) {
+        let line = span
+            .filter(|s| !s.is_dummy())
+            .map(|s| self.line_of(s.start));
+        self.emit(text, line);
+    }
+
+    fn line_at(&mut self, text: &str, offset: Option<usize>) {
+        let line = offset.map(|o| self.line_of(o));
+        self.emit(text, line);
+        if let Some(offset) = offset {
+            self.cursor = self.cursor.max(offset);
+        }
+    }
+
+    fn emit(&mut self, text: &str, source_line: Option<usize>) {
+        for _ in 0..self.depth {
+            self.out.push_str(INDENT);
+        }
+        self.out.push_str(text);
+        if let Some(line) = source_line {
+            while let Some(comment) = self.comments.get(self.next_comment) {
+                if comment.line != line {
+                    break;
+                }
+                self.out.push_str("  ");
+                self.out.push_str(&comment.text);
+                self.cursor = self.cursor.max(comment.end);
+                self.next_comment += 1;
+            }
+        }
+        self.out.push('\n');
+        self.block_start = false;
+    }
+
+    fn blank(&mut self) {
+        if !self.block_start && !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    /// Whether the source has an empty line between the printed part and `offset`.
+    fn blank_in_source(&self, offset: usize) -> bool {
+        let from = self.cursor.min(self.text.len());
+        let to = offset.min(self.text.len());
+        if from >= to {
+            return false;
+        }
+        let gap = &self.text[from..to];
+        let mut lines = gap.split('\n');
+        lines.next();
+        let mut lines: Vec<_> = lines.collect();
+        lines.pop();
+        lines.iter().any(|l| l.trim().is_empty())
+    }
+
+    fn line_of(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(idx) => idx + 1,
+            Err(idx) => idx,
+        }
+    }
+
+    /// Prints the `@name(...)` lines of an item whose span is `span` and returns the span to
+    /// use for its header line. (Doc comments are comments and print as such.)
+    fn attributes(&mut self, annotations: &Annotations, span: &Span) -> Span {
+        for attr in &annotations.attrs {
+            self.open(Some(&attr.span), false);
+            self.line(&attribute_text(attr), Some(&attr.span));
+            self.close(Some(&attr.span));
+        }
+        let Some(last) = annotations.attrs.last().filter(|a| !a.span.is_dummy()) else {
+            return span.clone();
+        };
+        let header = self.code_after(last.span.end);
+        self.leading(header, false);
+        self.cursor = self.cursor.max(header);
+        Span {
+            start: header,
+            ..span.clone()
+        }
+    }
+
+    /// The first offset at or after `offset` that is neither whitespace nor a comment.
+    fn code_after(&self, mut offset: usize) -> usize {
+        let bytes = self.text.as_bytes();
+        while let Some(&byte) = bytes.get(offset) {
+            match byte {
+                b' ' | b'\t' | b'\r' | b'\n' => offset += 1,
+                b'#' => {
+                    while bytes.get(offset).is_some_and(|&b| b != b'\n') {
+                        offset += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+        offset
+    }
+
+    fn module_keyword_offset(&self) -> Option<usize> {
+        let (tokens, _) = lex_recovering(self.text);
+        tokens
+            .iter()
+            .find(|t| t.kind == TokenKind::Keyword(Keyword::Module))
+            .map(|t| t.start)
+    }
+}
+
+/// Inline `on event:: stmt` / `on event: ... end` handlers are parsed into rules with
+/// generated `__on_` names.
+fn is_inline_on_rule(rule: &Rule) -> bool {
+    rule.name.starts_with("__on_")
+        && rule.params.is_empty()
+        && matches!(rule.trigger, RuleTrigger::On(_))
+}
+
+pub(crate) fn attribute_text(attr: &Attribute) -> String {
+    if attr.args.is_empty() {
+        format!("@{}", attr.name)
+    } else {
+        format!("@{}({})", attr.name, args_text(&attr.args))
+    }
+}
+
+fn use_line(keyword: &str, path: &str, alias: &Option<String>) -> String {
+    match alias {
+        Some(alias) => format!("{}({}) as {}", keyword, path, alias),
+        None => format!("{}({})", keyword, path),
+    }
+}
+
+/// A guard ends at the first bare `>`, so guards containing a `>` comparison are parenthesized.
+fn guard_text(guard: &Expr) -> String {
+    fn has_bare_gt(expr: &Expr) -> bool {
+        match expr {
+            Expr::Binary {
+                op: BinaryOp::Gt, ..
+            } => true,
+            Expr::Binary { left, right, .. } => has_bare_gt(left) || has_bare_gt(right),
+            Expr::Unary { operand, .. } => has_bare_gt(operand),
+            _ => false,
+        }
+    }
+    if has_bare_gt(guard) {
+        format!("({})", expr_text(guard, 0))
+    } else {
+        expr_text(guard, 0)
+    }
+}
+
+fn assign_text(target: &str, op: &AssignOp, value: &Expr) -> String {
+    let op = match op {
+        AssignOp::Set => "=",
+        AssignOp::Add => "+=",
+    };
+    format!("{} {} {}", target, op, expr_text(value, 0))
+}
+
+fn rule_stmt_text(stmt: &RuleStmt) -> String {
+    match stmt {
+        RuleStmt::Assign {
+            target, op, value, ..
+        } => assign_text(target, op, value),
+        RuleStmt::Emit { event, .. } => format!("emit {}", event),
+    }
+}
+
+fn constraint_text(call: &Call) -> String {
+    if call.name == "?prompt" {
+        if let [CallArg {
+            name: None,
+            value: Expr::String(text),
+            ..
+        }] = call.args.as_slice()
+        {
+            return format!("?{}", string_literal(text));
+        }
+    }
+    call_text(call, true)
+}
+
+/// `name(args)`; without arguments the parentheses are printed only if `parens` is set.
+pub(crate) fn call_text(call: &Call, parens: bool) -> String {
+    if call.args.is_empty() && !parens {
+        return call.name.clone();
+    }
+    format!("{}({})", call.name, args_text(&call.args))
+}
+
+fn args_text(args: &[CallArg]) -> String {
+    let args: Vec<String> = args
+        .iter()
+        .map(|arg| match &arg.name {
+            Some(name) => format!("{}: {}", name, expr_text(&arg.value, 0)),
+            None => expr_text(&arg.value, 0),
+        })
+        .collect();
+    args.join(", ")
+}
+
+/// Source text of `expr`, parenthesised if its precedence is below `min_prec`.
+pub(crate) fn expr_text(expr: &Expr, min_prec: u8) -> String {
+    match expr {
+        Expr::Number(n) => n.to_string(),
+        Expr::Duration(ms) => duration_text(*ms),
+        Expr::String(s) => string_literal(s),
+        Expr::Interpolated(parts) => interpolated_literal(parts),
+        Expr::Bool(b) => b.to_string(),
+        Expr::Null => "null".to_string(),
+        Expr::Ident(name) => name.clone(),
+        Expr::Call(call) => call_text(call, true),
+        Expr::List(items) => {
+            let items: Vec<String> = items.iter().map(|item| expr_text(item, 0)).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Expr::Record(fields) if fields.is_empty() => "{}".to_string(),
+        Expr::Record(fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|field| format!("{}: {}", record_key(&field.key), expr_text(&field.value, 0)))
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+        Expr::Binary { left, op, right } => {
+            let prec = binary_prec(op);
+            // Comparisons do not chain; every other operator is left-associative.
+            let (left_prec, right_prec) = match op {
+                BinaryOp::Gte | BinaryOp::Gt | BinaryOp::Lt | BinaryOp::Eq | BinaryOp::Neq => {
+                    (prec + 1, prec + 1)
+                }
+                _ => (prec, prec + 1),
+            };
+            let text = format!(
+                "{} {} {}",
+                expr_text(left, left_prec),
+                binary_op_text(op),
+                expr_text(right, right_prec)
+            );
+            if prec < min_prec {
+                format!("({})", text)
+            } else {
+                text
+            }
+        }
+        Expr::Unary { op, operand } => {
+            let (prec, text) = match op {
+                UnaryOp::Not => (3, format!("not {}", expr_text(operand, 3))),
+                UnaryOp::Neg => (7, format!("-{}", expr_text(operand, 7))),
+            };
+            if prec < min_prec {
+                format!("({})", text)
+            } else {
+                text
+            }
+        }
+    }
+}
+
+fn binary_prec(op: &BinaryOp) -> u8 {
+    match op {
+        BinaryOp::Or => 1,
+        BinaryOp::And => 2,
+        // `not` is 3.
+        BinaryOp::Gte | BinaryOp::Gt | BinaryOp::Lt | BinaryOp::Eq | BinaryOp::Neq => 4,
+        BinaryOp::Add | BinaryOp::Sub => 5,
+        BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => 6,
+        // Unary `-` is 7.
+    }
+}
+
+pub(crate) fn binary_op_text(op: &BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Gte => ">=",
+        BinaryOp::Gt => ">",
+        BinaryOp::Lt => "<",
+        BinaryOp::Eq => "==",
+        BinaryOp::Neq => "!=",
+        BinaryOp::And => "and",
+        BinaryOp::Or => "or",
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::Mod => "%",
+    }
+}
+
+fn string_literal(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    push_string_text(&mut out, value);
+    out.push('"');
+    out
+}
+
+fn interpolated_literal(parts: &[StringPart]) -> String {
+    let mut out = String::from("\"");
+    for part in parts {
+        match part {
+            StringPart::Text(text) => push_string_text(&mut out, text),
+            StringPart::Expr(expr) => {
+                out.push_str("${");
+                out.push_str(&expr_text(expr, 0));
+                out.push('}');
+            }
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Escapes string text so the lexer reads it back unchanged, including a literal `${`.
+fn push_string_text(out: &mut String, value: &str) {
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '$' if chars.peek() == Some(&'{') => out.push_str("\\$"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            _ => out.push(c),
+        }
+    }
+}
+
+/// Record keys stay bare when they are identifier-shaped (keywords included), otherwise
+/// they are quoted.
+fn record_key(key: &str) -> String {
+    let bare = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if bare {
+        key.to_string()
+    } else {
+        string_literal(key)
+    }
+}
+
+/// Meta values stay bare when they lex back to the same text, otherwise they are quoted.
+fn meta_value(value: &str) -> String {
+    let bare_ident = value
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let bare_number = value.starts_with(|c: char| c.is_ascii_digit())
+        && value.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && value.parse::<f64>().is_ok_and(|n| n.to_string() == value);
+    if bare_ident || bare_number {
+        value.to_string()
+    } else {
+        string_literal(value)
+    }
+}
@@ -0,0 +1,382 @@
+//! Per-node hashes of a module, so a change can be traced to the flows, states, rules and nd
+//! blocks it touches instead of invalidating the whole lock.
+//!
+//! Every node has an interface hash (what other nodes depend on: names, signatures,
+//! transitions, triggers) and a semantic hash (its body: assignments, UI calls, rule
+//! statements, nd proposals). When the target IR is known, states and rules also get an output
+//! hash over the parts of the target IR generated for them. A flow's semantic hash covers the
+//! hashes of its states, and the module root covers all nodes plus the linked modules, Merkle
+//! style. The module node (`module:Name`) covers `@meta`, imports, exports, state variables
+//! and defines.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Digest;
+
+use crate::ast::{walk_states, NdBlock, Rule, StateBlock, StateStmt};
+use crate::ir::ids::rule_key;
+use crate::ir::{canonical_json, IrFlow, IrModule};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeHash {
+    /// Node key, as in [`crate::ir::ids`]: `flow:Main`, `state:Main.Idle`, `rule:total`.
+    pub key: String,
+    /// Stable ID of the node; nodes are matched by it when diffing, so renames are changes.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    pub interface: String,
+    pub semantic: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+impl NodeHash {
+    /// The hash that goes into the parent: all three parts.
+    pub fn combined(&self) -> String {
+        digest(&[
+            &self.interface,
+            &self.semantic,
+            self.output.as_deref().unwrap_or(""),
+        ])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleHashes {
+    pub module: String,
+    /// Merkle root over the nodes and the roots of the linked modules.
+    pub root: String,
+    /// Sorted by key.
+    pub nodes: Vec<NodeHash>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub linked: Vec<ModuleHashes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeChange {
+    Added,
+    Removed,
+    Changed {
+        interface: bool,
+        semantic: bool,
+        output: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeDiff {
+    pub module: String,
+    /// The current key; the old one for removed nodes.
+    pub key: String,
+    /// The old key of a renamed node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_key: Option<String>,
+    pub change: NodeChange,
+}
+
+/// Hashes `ir` and its linked modules node by node. With `target_ir`, states and rules of the
+/// generated flow also get output hashes.
+pub fn compute_node_hashes(ir: &IrModule, target_ir: Option<&Value>) -> Result<ModuleHashes> {
+    let linked = ir
+        .linked
+        .iter()
+        .map(|module| compute_node_hashes(module, None))
+        .collect::<Result<Vec<_>>>()?;
+    let id = |key: &str| ir.ids.get(key).cloned().unwrap_or_default();
+
+    let mut nodes = vec![NodeHash {
+        key: format!("module:{}", ir.name),
+        id: String::new(),
+        interface: value_hash(&json!({
+            "name": ir.name,
+            "meta": ir.meta,
+            "uses": ir.uses,
+            "imports": ir.imports,
+            "exports": ir.exports,
+        }))?,
+        semantic: value_hash(&json!({
+            "global_state": ir.global_state,
+            "soft_defines": ir.soft_defines,
+        }))?,
+        output: None,
+    }];
+    for (index, flow) in ir.flows.iter().enumerate() {
+        // The target IR describes the first flow.
+        let output = target_ir.filter(|_| index == 0);
+        let mut states = Vec::new();
+        for state in walk_states(&flow.states) {
+            let Some(name) = &state.name else {
+                continue;
+            };
+            let key = format!("state:{}.{}", flow.name, name);
+            states.push(state_hash(state, name, output, id(&key), key)?);
+        }
+        let key = format!("flow:{}", flow.name);
+        nodes.push(flow_hash(flow, &states, output, id(&key), key)?);
+        nodes.extend(states);
+    }
+    for rule in &ir.rules {
+        let key = rule_key(rule);
+        nodes.push(rule_hash(rule, target_ir, id(&key), key)?);
+    }
+    for nd in &ir.nd_blocks {
+        let key = format!("nd:{}", nd.name);
+        nodes.push(nd_hash(nd, id(&key), key)?);
+    }
+    nodes.sort_by(|a, b| a.key.cmp(&b.key));
+
+    let mut parts = vec![ir.name.clone()];
+    parts.extend(
+        nodes
+            .iter()
+            .map(|node| format!("{}={}", node.key, node.combined())),
+    );
+    parts.extend(linked.iter().map(|module| module.root.clone()));
+    let parts: Vec<&str> = parts.iter().map(String::as_str).collect();
+    Ok(ModuleHashes {
+        module: ir.name.clone(),
+        root: digest(&parts),
+        nodes,
+        linked,
+    })
+}
+
+fn flow_hash(
+    flow: &IrFlow,
+    states: &[NodeHash],
+    target_ir: Option<&Value>,
+    id: String,
+    key: String,
+) -> Result<NodeHash> {
+    let interface = value_hash(&json!({
+        "name": flow.name,
+        "params": flow.params,
+        "returns": flow.returns,
+        "start": flow.start,
+        "states": flow.states.iter().map(|state| &state.name).collect::<Vec<_>>(),
+        "annotations": flow.annotations,
+    }))?;
+    let combined: Vec<String> = states.iter().map(NodeHash::combined).collect();
+    let combined: Vec<&str> = combined.iter().map(String::as_str).collect();
+    let output = target_ir
+        .and_then(|target| target.pointer("/flow/start"))
+        .map(value_hash)
+        .transpose()?;
+    Ok(NodeHash {
+        key,
+        id,
+        interface,
+        semantic: digest(&combined),
+        output,
+    })
+}
+
+/// Transitions, `run`, `return` and `terminate` are the interface of a state; assignments and
+/// UI calls its body. Inline rules are nodes of their own.
+fn state_hash(
+    state: &StateBlock,
+    name: &str,
+    target_ir: Option<&Value>,
+    id: String,
+    key: String,
+) -> Result<NodeHash> {
+    let (body, exits): (Vec<&StateStmt>, Vec<&StateStmt>) = state
+        .statements
+        .iter()
+        .filter(|stmt| !matches!(stmt, StateStmt::Rule(_)))
+        .partition(|stmt| matches!(stmt, StateStmt::Assign { .. } | StateStmt::Expr(_)));
+    let interface = value_hash(&json!({
+        "name": name,
+        "start": state.start,
+        "states": state.states.iter().map(|child| &child.name).collect::<Vec<_>>(),
+        "regions": state.regions.iter().map(|region| {
+            json!({
+                "name": region.name,
+                "start": region.start,
+                "states": region.states.iter().map(|child| &child.name).collect::<Vec<_>>(),
+            })
+        }).collect::<Vec<_>>(),
+        "exits": exits,
+        "annotations": state.annotations,
+    }))?;
+    let output = match target_ir {
+        Some(target) => {
+            let parts = json!({
+                "view": target.pointer(&format!("/views/{}", name)),
+                "layout": target.pointer(&format!("/layout/{}", name)),
+                "transitions": target.pointer(&format!("/flow/transitions/{}", name)),
+                "guards": target.pointer(&format!("/flow/guards/{}", name)),
+                "timers": target.pointer(&format!("/flow/timers/{}", name)),
+            });
+            let generated = parts
+                .as_object()
+                .is_some_and(|parts| parts.values().any(|part| !part.is_null()));
+            generated.then(|| value_hash(&parts)).transpose()?
+        }
+        None => None,
+    };
+    Ok(NodeHash {
+        key,
+        id,
+        interface,
+        semantic: value_hash(&body)?,
+        output,
+    })
+}
+
+fn rule_hash(rule: &Rule, target_ir: Option<&Value>, id: String, key: String) -> Result<NodeHash> {
+    let interface = value_hash(&json!({
+        "name": rule.name,
+        "params": rule.params,
+        "scope_flow": rule.scope_flow,
+        "scope_state": rule.scope_state,
+        "trigger": rule.trigger,
+        "annotations": rule.annotations,
+    }))?;
+    let runtime_rule = target_ir
+        .and_then(|target| target.pointer("/extensions/runtimeRules"))
+        .and_then(Value::as_array)
+        .and_then(|rules| {
+            rules.iter().find(|runtime| {
+                runtime.get("name").and_then(Value::as_str) == Some(rule.name.as_str())
+                    && runtime.get("scopeFlow").and_then(Value::as_str)
+                        == rule.scope_flow.as_deref()
+                    && runtime.get("scopeState").and_then(Value::as_str)
+                        == rule.scope_state.as_deref()
+            })
+        });
+    Ok(NodeHash {
+        key,
+        id,
+        interface,
+        semantic: value_hash(&rule.body)?,
+        output: runtime_rule.map(value_hash).transpose()?,
+    })
+}
+
+fn nd_hash(nd: &NdBlock, id: String, key: String) -> Result<NodeHash> {
+    Ok(NodeHash {
+        key,
+        id,
+        interface: value_hash(&json!({
+            "name": nd.name,
+            "params": nd.params,
+            "defines": nd.defines,
+            "annotations": nd.annotations,
+        }))?,
+        semantic: value_hash(&json!({
+            "propose": nd.propose,
+            "constraints": nd.constraints,
+        }))?,
+        output: None,
+    })
+}
+
+/// The nodes whose hashes differ between `before` and `after`, in the order of `after` with
+/// removed nodes last. Nodes are matched by stable ID, then by key; linked modules by name.
+pub fn diff_node_hashes(before: &ModuleHashes, after: &ModuleHashes) -> Vec<NodeDiff> {
+    let mut diffs = Vec::new();
+    if before.root != after.root {
+        diff_module(before, after, &mut diffs);
+    }
+    let previous: HashMap<&str, &ModuleHashes> = before
+        .linked
+        .iter()
+        .map(|module| (module.module.as_str(), module))
+        .collect();
+    for module in &after.linked {
+        match previous.get(module.module.as_str()) {
+            Some(old) => diffs.extend(diff_node_hashes(old, module)),
+            None => diffs.extend(module.nodes.iter().map(|node| NodeDiff {
+                module: module.module.clone(),
+                key: node.key.clone(),
+                previous_key: None,
+                change: NodeChange::Added,
+            })),
+        }
+    }
+    for module in &before.linked {
+        if !after.linked.iter().any(|new| new.module == module.module) {
+            diffs.extend(module.nodes.iter().map(|node| NodeDiff {
+                module: module.module.clone(),
+                key: node.key.clone(),
+                previous_key: None,
+                change: NodeChange::Removed,
+            }));
+        }
+    }
+    diffs
+}
+
+fn diff_module(before: &ModuleHashes, after: &ModuleHashes, diffs: &mut Vec<NodeDiff>) {
+    let by_id: BTreeMap<&str, &NodeHash> = before
+        .nodes
+        .iter()
+        .filter(|node| !node.id.is_empty())
+        .map(|node| (node.id.as_str(), node))
+        .collect();
+    let by_key: BTreeMap<&str, &NodeHash> = before
+        .nodes
+        .iter()
+        .map(|node| (node.key.as_str(), node))
+        .collect();
+    let mut matched = Vec::new();
+    for node in &after.nodes {
+        let old = by_id
+            .get(node.id.as_str())
+            .or_else(|| by_key.get(node.key.as_str()));
+        let change = match old {
+            Some(old) => {
+                matched.push(old.key.as_str());
+                let interface = old.interface != node.interface;
+                let semantic = old.semantic != node.semantic;
+                let output = old.output != node.output;
+                if !(interface || semantic || output) {
+                    continue;
+                }
+                NodeChange::Changed {
+                    interface,
+                    semantic,
+                    output,
+                }
+            }
+            None => NodeChange::Added,
+        };
+        diffs.push(NodeDiff {
+            module: after.module.clone(),
+            key: node.key.clone(),
+            previous_key: old
+                .filter(|old| old.key != node.key)
+                .map(|old| old.key.clone()),
+            change,
+        });
+    }
+    for node in &before.nodes {
+        if !matched.contains(&node.key.as_str()) {
+            diffs.push(NodeDiff {
+                module: before.module.clone(),
+                key: node.key.clone(),
+                previous_key: None,
+                change: NodeChange::Removed,
+            });
+        }
+    }
+}
+
+fn value_hash<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    let canonical = canonical_json(&serde_json::to_value(value)?);
+    Ok(digest(&[&serde_json::to_string(&canonical)?]))
+}
+
+fn digest(parts: &[&str]) -> String {
+    let mut hasher = sha2::Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
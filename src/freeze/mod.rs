@@ -8,12 +8,19 @@ use sha2::Digest;
 use crate::ir::{to_canonical_string, IrModule};
 use serde_json::Value;
 
+pub mod merkle;
+
+use self::merkle::{compute_node_hashes, diff_node_hashes, ModuleHashes, NodeChange};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LockFile {
     pub provider: String,
     pub model: String,
     pub target: String,
     pub ir_hash: String,
+    /// Per-node hashes of the frozen IR; locks written before they existed have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_hashes: Option<ModuleHashes>,
     pub target_ir: Value,
 }
 
@@ -30,6 +37,7 @@ pub fn create_lock(
         model: model.to_string(),
         target: target.to_string(),
         ir_hash,
+        node_hashes: Some(compute_node_hashes(ir, Some(target_ir))?),
         target_ir: target_ir.clone(),
     })
 }
@@ -47,10 +55,46 @@ pub fn read_lock(path: &Path) -> Result<LockFile> {
 
 pub fn verify_lock(ir: &IrModule, lock: &LockFile) -> Result<()> {
     let hash = compute_ir_hash(ir)?;
-    if hash != lock.ir_hash {
+    if hash == lock.ir_hash {
+        return Ok(());
+    }
+    let Some(locked) = &lock.node_hashes else {
+        bail!("IR hash mismatch: lock {}, current {}", lock.ir_hash, hash);
+    };
+    let current = compute_node_hashes(ir, Some(&lock.target_ir))?;
+    let changed: Vec<String> = diff_node_hashes(locked, &current)
+        .into_iter()
+        .map(|diff| {
+            let what = match diff.change {
+                NodeChange::Added => "added".to_string(),
+                NodeChange::Removed => "removed".to_string(),
+                NodeChange::Changed {
+                    interface,
+                    semantic,
+                    output,
+                } => [
+                    (interface, "interface"),
+                    (semantic, "semantic"),
+                    (output, "output"),
+                ]
+                .iter()
+                .filter(|(changed, _)| *changed)
+                .map(|(_, part)| *part)
+                .collect::<Vec<_>>()
+                .join("+"),
+            };
+            format!("{} ({})", diff.key, what)
+        })
+        .collect();
+    if changed.is_empty() {
         bail!("IR hash mismatch: lock {}, current {}", lock.ir_hash, hash);
     }
-    Ok(())
+    bail!(
+        "IR hash mismatch: lock {}, current {}; changed nodes: {}",
+        lock.ir_hash,
+        hash,
+        changed.join(", ")
+    );
 }
 
 pub fn compute_ir_hash(ir: &IrModule) -> Result<String> {
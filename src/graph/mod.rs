@@ -0,0 +1,619 @@
+//! The typed semantic graph of a program: modules, flows, states, rules, nd blocks, state
+//! variables, packages and capabilities as nodes, connected by typed edges (`contains`,
+//! `imports`, `calls`, `reads`, `writes`, `transitions`, `requires`).
+//!
+//! The graph is built from the linked [`IrModule`] and, when the target is known, its
+//! contract, which names the package behind each `use(...)` and says whether a required
+//! capability is provided. Node IDs are the node kind plus the fully qualified name
+//! (`state:App.Main.Main.Idle`); nodes that have one also carry their stable ID. `sculpt graph`
+//! prints the graph as JSON, Graphviz DOT or Mermaid.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ast::{Call, Expr, Rule, RuleStmt, RuleTrigger, StateBlock, StateStmt, StringPart};
+use crate::contracts::{parse_capability_list, TargetContract};
+use crate::formatter::call_text;
+use crate::ir::ids::rule_key;
+use crate::ir::IrModule;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Module,
+    Flow,
+    State,
+    Rule,
+    Nd,
+    Variable,
+    Package,
+    Capability,
+}
+
+impl NodeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Module => "module",
+            Self::Flow => "flow",
+            Self::State => "state",
+            Self::Rule => "rule",
+            Self::Nd => "nd",
+            Self::Variable => "variable",
+            Self::Package => "package",
+            Self::Capability => "capability",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    Contains,
+    Imports,
+    Calls,
+    Reads,
+    Writes,
+    Transitions,
+    Requires,
+}
+
+impl EdgeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Contains => "contains",
+            Self::Imports => "imports",
+            Self::Calls => "calls",
+            Self::Reads => "reads",
+            Self::Writes => "writes",
+            Self::Transitions => "transitions",
+            Self::Requires => "requires",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphNode {
+    /// Kind and fully qualified name: `flow:App.Main.Main`, `variable:App.Main.global.count`.
+    pub id: String,
+    pub kind: NodeKind,
+    /// Name within its module (`Main.Idle`), or the package path or capability.
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stable_id: Option<String>,
+    /// `package` (contract package id) on packages, `provided` on capabilities.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attrs: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+    /// The event of a transition (`on input.key(Enter)`) or the symbol of a package call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SemanticGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Builds the graph of `ir` and the modules linked into it.
+pub fn build_graph(ir: &IrModule, contract: Option<&TargetContract>) -> SemanticGraph {
+    let mut builder = Builder {
+        contract,
+        graph: SemanticGraph::default(),
+        index: HashMap::new(),
+        edges: HashSet::new(),
+        linked: ir.linked.iter().map(|module| module.name.clone()).collect(),
+    };
+    for module in &ir.linked {
+        builder.module(module);
+    }
+    builder.module(ir);
+    builder.graph
+}
+
+impl SemanticGraph {
+    pub fn node(&self, id: &str) -> Option<&GraphNode> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    /// The node a user-facing name refers to: `Main.Idle` or `App.Main.Main.Idle` for a state
+    /// of the entry module `App.Main`. Flows win over states, states over the other kinds.
+    pub fn resolve(&self, name: &str, entry: &str) -> Option<&GraphNode> {
+        let qualified = format!("{}.{}", entry, name);
+        let order = [
+            NodeKind::Flow,
+            NodeKind::State,
+            NodeKind::Rule,
+            NodeKind::Nd,
+            NodeKind::Module,
+        ];
+        order.iter().find_map(|kind| {
+            self.nodes.iter().find(|node| {
+                node.kind == *kind
+                    && [&qualified, name]
+                        .iter()
+                        .any(|name| node.id == format!("{}:{}", kind.as_str(), name))
+            })
+        })
+    }
+
+    /// The node `id` with its direct neighbours and the edges that touch it.
+    pub fn focus(&self, id: &str) -> SemanticGraph {
+        let edges: Vec<GraphEdge> = self
+            .edges
+            .iter()
+            .filter(|edge| edge.from == id || edge.to == id)
+            .cloned()
+            .collect();
+        let kept: HashSet<&str> = edges
+            .iter()
+            .flat_map(|edge| [edge.from.as_str(), edge.to.as_str()])
+            .chain(std::iter::once(id))
+            .collect();
+        SemanticGraph {
+            nodes: self
+                .nodes
+                .iter()
+                .filter(|node| kept.contains(node.id.as_str()))
+                .cloned()
+                .collect(),
+            edges,
+        }
+    }
+
+    /// Graphviz DOT, one shape per node kind.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph sculpt {\n  rankdir=LR;\n");
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Module => "folder",
+                NodeKind::Flow => "box",
+                NodeKind::State => "ellipse",
+                NodeKind::Rule => "diamond",
+                NodeKind::Nd => "hexagon",
+                NodeKind::Variable => "note",
+                NodeKind::Package => "component",
+                NodeKind::Capability => "octagon",
+            };
+            out.push_str(&format!(
+                "  \"{}\" [label=\"{}\", shape={}];\n",
+                dot_escape(&node.id),
+                dot_escape(&format!("{} {}", node.kind.as_str(), node.label)),
+                shape
+            ));
+        }
+        for edge in &self.edges {
+            out.push_str(&format!(
+                "  \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                dot_escape(&edge.from),
+                dot_escape(&edge.to),
+                dot_escape(&edge_label(edge))
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// A Mermaid flowchart. Nodes are numbered (`n0`, `n1`, ...) since Mermaid IDs cannot
+    /// contain `:` or `.`.
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("flowchart LR\n");
+        let mut numbers = HashMap::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            numbers.insert(node.id.as_str(), index);
+            let label = mermaid_escape(&format!("{} {}", node.kind.as_str(), node.label));
+            let (open, close) = match node.kind {
+                NodeKind::Module => ("[/", "/]"),
+                NodeKind::Flow => ("[", "]"),
+                NodeKind::State => ("(", ")"),
+                NodeKind::Rule => ("{", "}"),
+                NodeKind::Nd => ("{{", "}}"),
+                NodeKind::Variable => ("[(", ")]"),
+                NodeKind::Package => ("[[", "]]"),
+                NodeKind::Capability => (">", "]"),
+            };
+            out.push_str(&format!("  n{}{}\"{}\"{}\n", index, open, label, close));
+        }
+        for edge in &self.edges {
+            let (Some(from), Some(to)) = (
+                numbers.get(edge.from.as_str()),
+                numbers.get(edge.to.as_str()),
+            ) else {
+                continue;
+            };
+            let arrow = match edge.kind {
+                EdgeKind::Contains => "-.->",
+                _ => "-->",
+            };
+            out.push_str(&format!(
+                "  n{} {}|\"{}\"| n{}\n",
+                from,
+                arrow,
+                mermaid_escape(&edge_label(edge)),
+                to
+            ));
+        }
+        out
+    }
+}
+
+fn edge_label(edge: &GraphEdge) -> String {
+    match &edge.label {
+        Some(label) => format!("{}: {}", edge.kind.as_str(), label),
+        None => edge.kind.as_str().to_string(),
+    }
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+struct Builder<'a> {
+    contract: Option<&'a TargetContract>,
+    graph: SemanticGraph,
+    index: HashMap<String, usize>,
+    edges: HashSet<(String, String, EdgeKind, Option<String>)>,
+    /// Names of the linked modules; references to them are fully qualified.
+    linked: Vec<String>,
+}
+
+/// What the statements of one module resolve names against.
+struct Scope<'a> {
+    module: &'a IrModule,
+    /// `use(...)` alias to package node.
+    packages: HashMap<String, String>,
+    variables: HashSet<String>,
+}
+
+impl Builder<'_> {
+    fn node(
+        &mut self,
+        kind: NodeKind,
+        fqn: &str,
+        label: &str,
+        stable_id: Option<&String>,
+    ) -> String {
+        let id = format!("{}:{}", kind.as_str(), fqn);
+        match self.index.get(&id) {
+            Some(&index) => {
+                let node = &mut self.graph.nodes[index];
+                if node.stable_id.is_none() {
+                    node.stable_id = stable_id.cloned();
+                }
+            }
+            None => {
+                self.index.insert(id.clone(), self.graph.nodes.len());
+                self.graph.nodes.push(GraphNode {
+                    id: id.clone(),
+                    kind,
+                    label: label.to_string(),
+                    stable_id: stable_id.cloned(),
+                    attrs: BTreeMap::new(),
+                });
+            }
+        }
+        id
+    }
+
+    fn edge(&mut self, from: &str, to: &str, kind: EdgeKind, label: Option<String>) {
+        let key = (from.to_string(), to.to_string(), kind, label.clone());
+        if self.edges.insert(key) {
+            self.graph.edges.push(GraphEdge {
+                from: from.to_string(),
+                to: to.to_string(),
+                kind,
+                label,
+            });
+        }
+    }
+
+    fn module(&mut self, ir: &IrModule) {
+        let module_id = self.node(NodeKind::Module, &ir.name, &ir.name, None);
+        for decl in &ir.imports {
+            let imported = self.node(NodeKind::Module, &decl.path, &decl.path, None);
+            self.edge(&module_id, &imported, EdgeKind::Imports, None);
+        }
+
+        let mut packages = HashMap::new();
+        for decl in &ir.uses {
+            let namespace = decl.path.rsplit('.').next().unwrap_or_default();
+            let package = self.node(NodeKind::Package, &decl.path, &decl.path, None);
+            if let Some(id) = self.contract.and_then(|c| c.package_id(namespace)) {
+                let index = self.index[&package];
+                self.graph.nodes[index]
+                    .attrs
+                    .insert("package".to_string(), id.to_string());
+            }
+            self.edge(&module_id, &package, EdgeKind::Imports, None);
+            let alias = decl.alias.clone().unwrap_or_else(|| namespace.to_string());
+            packages.insert(alias, package);
+        }
+
+        let mut capabilities = ir
+            .meta
+            .get("requires")
+            .map(|raw| parse_capability_list(raw))
+            .unwrap_or_default();
+        if ir
+            .meta
+            .get("layout")
+            .is_some_and(|layout| layout.trim().eq_ignore_ascii_case("explicit"))
+        {
+            capabilities.push("layout.explicit".to_string());
+        }
+        for capability in capabilities {
+            let node = self.node(NodeKind::Capability, &capability, &capability, None);
+            if let Some(contract) = self.contract {
+                let provided = contract.capabilities.contains(&capability);
+                let index = self.index[&node];
+                self.graph.nodes[index]
+                    .attrs
+                    .insert("provided".to_string(), provided.to_string());
+            }
+            self.edge(&module_id, &node, EdgeKind::Requires, None);
+        }
+
+        // Every assigned name is a state variable of the module.
+        let mut variables = HashSet::new();
+        let mut assigned = Vec::new();
+        for stmt in &ir.global_state {
+            if let StateStmt::Assign { target, .. } = stmt {
+                assigned.push(target.clone());
+            }
+        }
+        for flow in &ir.flows {
+            for state in crate::ast::walk_states(&flow.states) {
+                for stmt in &state.statements {
+                    if let StateStmt::Assign { target, .. } = stmt {
+                        assigned.push(target.clone());
+                    }
+                }
+            }
+        }
+        for rule in &ir.rules {
+            for stmt in &rule.body {
+                if let RuleStmt::Assign { target, .. } = stmt {
+                    assigned.push(target.clone());
+                }
+            }
+        }
+        for name in assigned {
+            if name.contains('.') || !variables.insert(name.clone()) {
+                continue;
+            }
+            let fqn = format!("{}.global.{}", ir.name, name);
+            let variable = self.node(
+                NodeKind::Variable,
+                &fqn,
+                &name,
+                ir.ids.get(&format!("global:{}", name)),
+            );
+            self.edge(&module_id, &variable, EdgeKind::Contains, None);
+        }
+
+        let scope = Scope {
+            module: ir,
+            packages,
+            variables,
+        };
+        for flow in &ir.flows {
+            let fqn = format!("{}.{}", ir.name, flow.name);
+            let flow_id = self.node(
+                NodeKind::Flow,
+                &fqn,
+                &flow.name,
+                ir.ids.get(&format!("flow:{}", flow.name)),
+            );
+            self.edge(&module_id, &flow_id, EdgeKind::Contains, None);
+            self.states(&scope, &flow.name, &flow_id, &flow.states);
+        }
+        for rule in &ir.rules {
+            self.rule(&scope, &module_id, rule);
+        }
+        for nd in &ir.nd_blocks {
+            let fqn = format!("{}.{}", ir.name, nd.name);
+            let nd_id = self.node(
+                NodeKind::Nd,
+                &fqn,
+                &nd.name,
+                ir.ids.get(&format!("nd:{}", nd.name)),
+            );
+            self.edge(&module_id, &nd_id, EdgeKind::Contains, None);
+            self.call(&scope, &nd_id, &nd.propose);
+            for constraint in &nd.constraints {
+                self.call(&scope, &nd_id, constraint);
+            }
+        }
+    }
+
+    fn state_node(&mut self, scope: &Scope, flow: &str, state: &str) -> String {
+        let fqn = format!("{}.{}.{}", scope.module.name, flow, state);
+        let label = format!("{}.{}", flow, state);
+        let stable_id = scope.module.ids.get(&format!("state:{}", label));
+        self.node(NodeKind::State, &fqn, &label, stable_id)
+    }
+
+    fn states(&mut self, scope: &Scope, flow: &str, parent: &str, states: &[StateBlock]) {
+        for state in states {
+            let Some(name) = &state.name else {
+                continue;
+            };
+            let state_id = self.state_node(scope, flow, name);
+            self.edge(parent, &state_id, EdgeKind::Contains, None);
+            for stmt in &state.statements {
+                match stmt {
+                    StateStmt::On {
+                        event,
+                        guard,
+                        target,
+                        ..
+                    } => {
+                        if let Some(guard) = guard {
+                            self.expr(scope, &state_id, guard);
+                        }
+                        let target = self.state_node(scope, flow, target);
+                        let label = format!("on {}", call_text(event, false));
+                        self.edge(&state_id, &target, EdgeKind::Transitions, Some(label));
+                    }
+                    StateStmt::After {
+                        delay_ms, target, ..
+                    } => {
+                        let target = self.state_node(scope, flow, target);
+                        let label = format!("after({})", crate::ast::duration_text(*delay_ms));
+                        self.edge(&state_id, &target, EdgeKind::Transitions, Some(label));
+                    }
+                    StateStmt::Run {
+                        flow: callee,
+                        args,
+                        result,
+                        ..
+                    } => {
+                        let fqn = if self.is_linked(callee) {
+                            callee.clone()
+                        } else {
+                            format!("{}.{}", scope.module.name, callee)
+                        };
+                        let label = fqn.rsplit('.').next().unwrap_or_default().to_string();
+                        let callee = self.node(NodeKind::Flow, &fqn, &label, None);
+                        self.edge(&state_id, &callee, EdgeKind::Calls, Some("run".to_string()));
+                        for arg in args {
+                            self.expr(scope, &state_id, &arg.value);
+                        }
+                        if let Some(result) = result {
+                            self.variable(scope, &state_id, result, EdgeKind::Writes);
+                        }
+                    }
+                    StateStmt::Return {
+                        value: Some(value), ..
+                    } => self.expr(scope, &state_id, value),
+                    StateStmt::Assign { target, value, .. } => {
+                        self.variable(scope, &state_id, target, EdgeKind::Writes);
+                        self.expr(scope, &state_id, value);
+                    }
+                    StateStmt::Expr(call) => self.call(scope, &state_id, call),
+                    // Inline rules are in `IrModule::rules`, scoped to this state.
+                    StateStmt::Rule(_) | StateStmt::Return { value: None, .. } => {}
+                    StateStmt::Terminate => {}
+                }
+            }
+            self.states(scope, flow, &state_id, &state.states);
+            for region in &state.regions {
+                self.states(scope, flow, &state_id, &region.states);
+            }
+        }
+    }
+
+    fn rule(&mut self, scope: &Scope, module_id: &str, rule: &Rule) {
+        let stable_id = scope.module.ids.get(&rule_key(rule));
+        let (parent, fqn, label) = match (&rule.scope_flow, &rule.scope_state) {
+            (Some(flow), Some(state)) => (
+                self.state_node(scope, flow, state),
+                format!("{}.{}.{}.{}", scope.module.name, flow, state, rule.name),
+                format!("{}.{}.{}", flow, state, rule.name),
+            ),
+            _ => (
+                module_id.to_string(),
+                format!("{}.{}", scope.module.name, rule.name),
+                rule.name.clone(),
+            ),
+        };
+        let rule_id = self.node(NodeKind::Rule, &fqn, &label, stable_id);
+        self.edge(&parent, &rule_id, EdgeKind::Contains, None);
+        match &rule.trigger {
+            RuleTrigger::On(event) => {
+                for arg in &event.args {
+                    self.expr(scope, &rule_id, &arg.value);
+                }
+            }
+            RuleTrigger::When(condition) => self.expr(scope, &rule_id, condition),
+            RuleTrigger::Every(_) => {}
+        }
+        for stmt in &rule.body {
+            if let RuleStmt::Assign { target, value, .. } = stmt {
+                self.variable(scope, &rule_id, target, EdgeKind::Writes);
+                self.expr(scope, &rule_id, value);
+            }
+        }
+    }
+
+    fn is_linked(&self, name: &str) -> bool {
+        self.linked
+            .iter()
+            .any(|module| name.starts_with(&format!("{}.", module)))
+    }
+
+    /// Adds a `reads` or `writes` edge if `name` (or the record it is a field of) is a state
+    /// variable of this module or of a linked one.
+    fn variable(&mut self, scope: &Scope, from: &str, name: &str, kind: EdgeKind) {
+        let linked = self.linked.iter().find_map(|module| {
+            let member = name.strip_prefix(module.as_str())?.strip_prefix('.')?;
+            let variable = member.split('.').next().unwrap_or(member);
+            Some(format!("variable:{}.global.{}", module, variable))
+        });
+        let local = || {
+            let variable = name.split('.').next().unwrap_or(name);
+            scope
+                .variables
+                .contains(variable)
+                .then(|| format!("variable:{}.global.{}", scope.module.name, variable))
+        };
+        if let Some(id) = linked.or_else(local) {
+            if self.index.contains_key(&id) {
+                self.edge(from, &id, kind, None);
+            }
+        }
+    }
+
+    /// Adds a `calls` edge for a package call and walks the arguments.
+    fn call(&mut self, scope: &Scope, from: &str, call: &Call) {
+        if let Some((alias, symbol)) = call.name.split_once('.') {
+            if let Some(package) = scope.packages.get(alias) {
+                self.edge(from, package, EdgeKind::Calls, Some(symbol.to_string()));
+            }
+        }
+        for arg in &call.args {
+            self.expr(scope, from, &arg.value);
+        }
+    }
+
+    fn expr(&mut self, scope: &Scope, from: &str, expr: &Expr) {
+        match expr {
+            Expr::Ident(name) => self.variable(scope, from, name, EdgeKind::Reads),
+            Expr::Call(call) => self.call(scope, from, call),
+            Expr::Binary { left, right, .. } => {
+                self.expr(scope, from, left);
+                self.expr(scope, from, right);
+            }
+            Expr::Unary { operand, .. } => self.expr(scope, from, operand),
+            Expr::Interpolated(parts) => {
+                for part in parts {
+                    if let StringPart::Expr(expr) = part {
+                        self.expr(scope, from, expr);
+                    }
+                }
+            }
+            Expr::List(items) => {
+                for item in items {
+                    self.expr(scope, from, item);
+                }
+            }
+            Expr::Record(fields) => {
+                for field in fields {
+                    self.expr(scope, from, &field.value);
+                }
+            }
+            Expr::Number(_) | Expr::Duration(_) | Expr::String(_) | Expr::Bool(_) | Expr::Null => {}
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Stable IDs for the nodes of a module: flows, states, transitions, rules, global state
+//! variables, nd blocks and soft defines.
+//!
+//! Every node has a key built from names, not positions: `flow:Main`, `state:Main.Idle`,
+//! `transition:Main.Idle/on input.key(Enter)`, `rule:total` (`rule:Main.Idle/on tick` for
+//! unnamed rules), `global:score`, `nd:layout`, `define:collision.stable`. A node without a
+//! pinned ID gets one derived from the module name and its key, so IDs are reproducible
+//! without any extra file. The sidecar `<script>.ids.json` next to the source pins them:
+//! `sculpt freeze` records the current IDs there, and `sculpt rename` moves a renamed
+//! node's entries to its new key so the node keeps its ID.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+use crate::ast::{duration_text, walk_states, Rule, RuleTrigger, StateStmt};
+use crate::formatter::{call_text, expr_text};
+use crate::ir::IrModule;
+
+/// The contents of an ID sidecar: node key to ID.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IdMap {
+    pub module: String,
+    pub ids: BTreeMap<String, String>,
+}
+
+impl IdMap {
+    /// Moves the ID of the node `from` (a key such as `state:Main.Idle`) to `to`, together
+    /// with the keys of everything inside it: the states, transitions and rules of a flow,
+    /// the transitions and rules of a state.
+    pub fn rename(&mut self, from: &str, to: &str) {
+        let (Some((kind, old)), Some((_, new))) = (from.split_once(':'), to.split_once(':')) else {
+            return;
+        };
+        let nested: &[(&str, char)] = match kind {
+            "flow" => &[("state", '.'), ("transition", '.'), ("rule", '.')],
+            "state" => &[("transition", '/'), ("rule", '.'), ("rule", '/')],
+            _ => &[],
+        };
+        let moved: Vec<(String, String)> = self
+            .ids
+            .keys()
+            .filter_map(|key| {
+                if key == from {
+                    return Some((key.clone(), to.to_string()));
+                }
+                let (key_kind, name) = key.split_once(':')?;
+                nested.iter().find_map(|(nested_kind, sep)| {
+                    let rest = name.strip_prefix(old)?.strip_prefix(*sep)?;
+                    (key_kind == *nested_kind)
+                        .then(|| (key.clone(), format!("{}:{}{}{}", key_kind, new, sep, rest)))
+                })
+            })
+            .collect();
+        for (old_key, new_key) in moved {
+            if let Some(id) = self.ids.remove(&old_key) {
+                self.ids.insert(new_key, id);
+            }
+        }
+    }
+}
+
+/// `app.sculpt` keeps its IDs in `app.ids.json`.
+pub fn sidecar_path(source: &Path) -> PathBuf {
+    source.with_extension("ids.json")
+}
+
+/// Reads a sidecar; a missing file is `None`.
+pub fn read_id_map(path: &Path) -> Result<Option<IdMap>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read ID map {}", path.display()))?;
+    let map = serde_json::from_str(&data)
+        .with_context(|| format!("Invalid ID map {}", path.display()))?;
+    Ok(Some(map))
+}
+
+pub fn write_id_map(path: &Path, map: &IdMap) -> Result<()> {
+    let json = serde_json::to_string_pretty(map)?;
+    fs::write(path, json + "\n")?;
+    Ok(())
+}
+
+/// The keys of all nodes of `ir`, in source order. Transitions and unnamed rules with the
+/// same trigger in one state are numbered from the second on (`...#2`).
+pub fn node_keys(ir: &IrModule) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut seen = HashMap::new();
+    let mut numbered = |key: String| {
+        let count = seen.entry(key.clone()).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            key
+        } else {
+            format!("{}#{}", key, count)
+        }
+    };
+
+    for flow in &ir.flows {
+        keys.push(format!("flow:{}", flow.name));
+        for state in walk_states(&flow.states) {
+            let Some(name) = &state.name else {
+                continue;
+            };
+            keys.push(format!("state:{}.{}", flow.name, name));
+            for stmt in &state.statements {
+                let trigger = match stmt {
+                    StateStmt::On { event, .. } => format!("on {}", call_text(event, false)),
+                    StateStmt::After { delay_ms, .. } => {
+                        format!("after({})", duration_text(*delay_ms))
+                    }
+                    _ => continue,
+                };
+                keys.push(numbered(format!(
+                    "transition:{}.{}/{}",
+                    flow.name, name, trigger
+                )));
+            }
+        }
+    }
+    for rule in &ir.rules {
+        keys.push(numbered(rule_key(rule)));
+    }
+    let mut globals = HashSet::new();
+    for stmt in &ir.global_state {
+        if let StateStmt::Assign { target, .. } = stmt {
+            if globals.insert(target) {
+                keys.push(format!("global:{}", target));
+            }
+        }
+    }
+    keys.extend(ir.nd_blocks.iter().map(|nd| format!("nd:{}", nd.name)));
+    keys.extend(
+        ir.soft_defines
+            .iter()
+            .map(|define| format!("define:{}", define.name)),
+    );
+    keys
+}
+
+pub(crate) fn rule_key(rule: &Rule) -> String {
+    let scope = match (&rule.scope_flow, &rule.scope_state) {
+        (Some(flow), Some(state)) => format!("{}.{}", flow, state),
+        _ => String::new(),
+    };
+    // Inline `on event: ...` rules are named by position; key them by their trigger.
+    if rule.name.starts_with("__on_") {
+        let trigger = match &rule.trigger {
+            RuleTrigger::On(call) => format!("on {}", call_text(call, false)),
+            RuleTrigger::When(expr) => format!("when {}", expr_text(expr, 0)),
+            RuleTrigger::Every(ms) => format!("every({})", duration_text(*ms)),
+        };
+        return format!("rule:{}/{}", scope, trigger);
+    }
+    if scope.is_empty() {
+        format!("rule:{}", rule.name)
+    } else {
+        format!("rule:{}.{}", scope, rule.name)
+    }
+}
+
+/// Sets `ir.ids` for every node: the pinned ID if there is one, a derived one otherwise.
+/// Derived IDs never repeat a pinned one.
+pub fn assign_ids(ir: &mut IrModule, pinned: &BTreeMap<String, String>) {
+    let mut used: HashSet<String> = pinned.values().cloned().collect();
+    let mut ids = BTreeMap::new();
+    for key in node_keys(ir) {
+        let id = match pinned.get(&key) {
+            Some(id) => id.clone(),
+            None => derived_id(&ir.name, &key, &mut used),
+        };
+        ids.insert(key, id);
+    }
+    ir.ids = ids;
+}
+
+fn derived_id(module: &str, key: &str, used: &mut HashSet<String>) -> String {
+    let prefix = match key.split_once(':').map(|(kind, _)| kind) {
+        Some("flow") => "fl",
+        Some("state") => "st",
+        Some("transition") => "tr",
+        Some("rule") => "ru",
+        Some("global") => "gv",
+        Some("nd") => "nd",
+        _ => "df",
+    };
+    let mut attempt = 0usize;
+    loop {
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(module.as_bytes());
+        hasher.update([0]);
+        hasher.update(key.as_bytes());
+        if attempt > 0 {
+            hasher.update(attempt.to_string().as_bytes());
+        }
+        let hash = format!("{:x}", hasher.finalize());
+        let id = format!("{}_{}", prefix, &hash[..12]);
+        if used.insert(id.clone()) {
+            return id;
+        }
+        attempt += 1;
+    }
+}
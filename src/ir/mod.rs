@@ -2,6 +2,9 @@ use crate::ast;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+pub mod ids;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IrModule {
@@ -9,23 +12,46 @@ pub struct IrModule {
     pub namespace: Vec<String>,
     pub fqns: Vec<String>,
     pub meta: std::collections::HashMap<String, String>,
+    /// Source locations of the `@meta` entries, for diagnostics.
+    #[serde(skip)]
+    pub meta_spans: std::collections::HashMap<String, ast::Span>,
     #[serde(default)]
     pub uses: Vec<ast::UseDecl>,
     #[serde(default)]
     pub imports: Vec<ast::ImportDecl>,
+    /// Names listed in `exports(...)`, in source order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exports: Vec<String>,
     pub flows: Vec<IrFlow>,
     pub global_state: Vec<ast::StateStmt>,
     pub rules: Vec<ast::Rule>,
     #[serde(default)]
     pub soft_defines: Vec<ast::SoftDefine>,
     pub nd_blocks: Vec<ast::NdBlock>,
+    /// The modules a project entry imports, directly or not, dependencies first. References
+    /// to them spell out the imported module's full name, whatever the import alias was.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub linked: Vec<IrModule>,
+    /// Stable ID of every node, by node key (see [`ids`]).
+    #[serde(default)]
+    pub ids: BTreeMap<String, String>,
+    #[serde(skip)]
+    pub span: ast::Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IrFlow {
     pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<ast::FlowParam>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub returns: Option<ast::TypeName>,
     pub start: Option<String>,
     pub states: Vec<ast::StateBlock>,
+    #[serde(default, skip_serializing_if = "ast::Annotations::is_empty")]
+    pub annotations: ast::Annotations,
+    #[serde(skip)]
+    pub span: ast::Span,
 }
 
 pub fn from_ast(module: ast::Module) -> IrModule {
@@ -34,7 +60,10 @@ pub fn from_ast(module: ast::Module) -> IrModule {
         meta,
         uses,
         imports,
+        exports,
         items,
+        span,
+        meta_spans,
     } = module;
     let module_name = name.clone();
     let mut flows = Vec::new();
@@ -50,7 +79,7 @@ pub fn from_ast(module: ast::Module) -> IrModule {
             ast::Item::Flow(flow) => {
                 let flow_fqn = format!("{}.{}", module_name, flow.name);
                 fqns.push(flow_fqn.clone());
-                for state in &flow.states {
+                for state in flow.all_states() {
                     if let Some(name) = &state.name {
                         let state_fqn = format!("{flow_fqn}.{}", name);
                         fqns.push(state_fqn.clone());
@@ -64,8 +93,12 @@ pub fn from_ast(module: ast::Module) -> IrModule {
                 }
                 flows.push(IrFlow {
                     name: flow.name,
+                    params: flow.params,
+                    returns: flow.returns,
                     start: flow.start,
                     states: flow.states,
+                    annotations: flow.annotations,
+                    span: flow.span,
                 });
             }
             ast::Item::GlobalState(state) => {
@@ -86,20 +119,29 @@ pub fn from_ast(module: ast::Module) -> IrModule {
     }
     fqns.sort();
     fqns.dedup();
+    // Rules run in this order; `@priority` moves a rule later so its assignments win.
+    rules.sort_by_key(|rule| rule.annotations.priority());
 
-    IrModule {
+    let mut ir = IrModule {
         name: name.clone(),
         namespace: name.split('.').map(|s| s.to_string()).collect(),
         fqns,
         meta,
+        meta_spans,
         uses,
         imports,
+        exports: exports.into_iter().flat_map(|decl| decl.names).collect(),
         flows,
         global_state,
         rules,
         soft_defines,
         nd_blocks,
-    }
+        linked: Vec::new(),
+        ids: BTreeMap::new(),
+        span,
+    };
+    ids::assign_ids(&mut ir, &BTreeMap::new());
+    ir
 }
 
 pub fn canonical_json(value: &Value) -> Value {
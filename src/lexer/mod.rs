@@ -4,18 +4,33 @@ use anyhow::{bail, Result};
 pub enum TokenKind {
     Identifier(String),
     Number(f64),
+    /// A duration literal such as `400ms` or `2s`, in milliseconds.
+    Duration(u64),
     String(String),
+    /// A string literal with `${...}` interpolation.
+    Template(Vec<TemplatePart>),
     Keyword(Keyword),
     At,
     Gt,
     Lt,
     PlusEq,
+    Plus,
+    Minus,
+    /// `->`
+    Arrow,
+    Star,
+    Slash,
+    Percent,
     Eq,
     EqEq,
     Neq,
     Gte,
     LParen,
     RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
     Comma,
     Colon,
     DoubleColon,
@@ -25,6 +40,13 @@ pub enum TokenKind {
     Eof,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplatePart {
+    Text(String),
+    /// The tokens between `${` and `}`, followed by `Eof`.
+    Expr(Vec<Token>),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Keyword {
     Module,
@@ -44,312 +66,475 @@ pub enum Keyword {
     Or,
     Run,
     Terminate,
+    Return,
     Start,
+    Region,
     End,
     Null,
+    True,
+    False,
 }
 
-#[derive(Debug, Clone)]
+/// A lexed token. `line`/`col` are 1-based and point at the first character;
+/// `start..end` is the byte range of the token in the input.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub kind: TokenKind,
     pub line: usize,
     pub col: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A character sequence the lexer could not turn into a token.
+#[derive(Debug, Clone)]
+pub struct LexError {
+    pub message: String,
+    pub line: usize,
+    pub col: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A `#` comment. Comments are not part of the token stream; the formatter re-attaches
+/// them by position. `text` includes the leading `#`.
+#[derive(Debug, Clone)]
+pub struct Comment {
+    pub text: String,
+    pub line: usize,
+    pub col: usize,
+    pub start: usize,
+    pub end: usize,
 }
 
 pub fn lex(input: &str) -> Result<Vec<Token>> {
-    let mut tokens = Vec::new();
-    let mut chars = input.chars().peekable();
-    let mut line = 1usize;
-    let mut col = 1usize;
+    let (tokens, errors) = lex_recovering(input);
+    if let Some(err) = errors.first() {
+        bail!("{} at {}:{}", err.message, err.line, err.col);
+    }
+    Ok(tokens)
+}
+
+/// Lexes the whole input, skipping over invalid characters instead of stopping at the
+/// first one. The token stream always ends with `Eof`.
+pub fn lex_recovering(input: &str) -> (Vec<Token>, Vec<LexError>) {
+    let lexer = Lexer::run(input);
+    (lexer.tokens, lexer.errors)
+}
+
+/// All comments of `input` in source order.
+pub fn lex_comments(input: &str) -> Vec<Comment> {
+    Lexer::run(input).comments
+}
+
+struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+    line: usize,
+    col: usize,
+    tokens: Vec<Token>,
+    errors: Vec<LexError>,
+    comments: Vec<Comment>,
+}
 
-    while let Some(ch) = chars.peek().cloned() {
+impl<'a> Lexer<'a> {
+    fn run(input: &'a str) -> Self {
+        let mut lexer = Lexer {
+            input,
+            pos: 0,
+            line: 1,
+            col: 1,
+            tokens: Vec::new(),
+            errors: Vec::new(),
+            comments: Vec::new(),
+        };
+        lexer.lex_all();
+        lexer
+    }
+
+    fn lex_all(&mut self) {
+        while let Some(ch) = self.peek() {
+            self.lex_token(ch);
+        }
+
+        let (pos, line, col) = (self.pos, self.line, self.col);
+        self.push_at(TokenKind::Eof, pos, line, col);
+    }
+
+    fn lex_token(&mut self, ch: char) {
+        let (start, line, col) = (self.pos, self.line, self.col);
         match ch {
             ' ' | '\t' | '\r' => {
-                chars.next();
-                col += 1;
+                self.bump();
             }
             '\n' => {
-                chars.next();
-                tokens.push(Token {
-                    kind: TokenKind::Newline,
-                    line,
-                    col,
-                });
-                line += 1;
-                col = 1;
+                self.bump();
+                self.push_at(TokenKind::Newline, start, line, col);
             }
             '#' => {
-                while let Some(c) = chars.next() {
+                while let Some(c) = self.peek() {
                     if c == '\n' {
-                        tokens.push(Token {
-                            kind: TokenKind::Newline,
-                            line,
-                            col,
-                        });
-                        line += 1;
-                        col = 1;
                         break;
                     }
+                    self.bump();
                 }
-            }
-            '@' => {
-                chars.next();
-                tokens.push(Token {
-                    kind: TokenKind::At,
+                self.comments.push(Comment {
+                    text: self.input[start..self.pos].trim_end().to_string(),
                     line,
                     col,
+                    start,
+                    end: self.pos,
                 });
-                col += 1;
+                if self.peek() == Some('\n') {
+                    let (nl_start, nl_line, nl_col) = (self.pos, self.line, self.col);
+                    self.bump();
+                    self.push_at(TokenKind::Newline, nl_start, nl_line, nl_col);
+                }
             }
+            '@' => self.single(TokenKind::At),
             '+' => {
-                chars.next();
-                if chars.peek() == Some(&'=') {
-                    chars.next();
-                    tokens.push(Token {
-                        kind: TokenKind::PlusEq,
-                        line,
-                        col,
-                    });
-                    col += 2;
+                self.bump();
+                if self.peek() == Some('=') {
+                    self.bump();
+                    self.push_at(TokenKind::PlusEq, start, line, col);
                 } else {
-                    bail!("Unexpected '+' at {}:{}", line, col);
+                    self.push_at(TokenKind::Plus, start, line, col);
                 }
             }
+            '-' => {
+                self.bump();
+                if self.peek() == Some('>') {
+                    self.bump();
+                    self.push_at(TokenKind::Arrow, start, line, col);
+                } else {
+                    self.push_at(TokenKind::Minus, start, line, col);
+                }
+            }
+            '*' => self.single(TokenKind::Star),
+            '/' => self.single(TokenKind::Slash),
+            '%' => self.single(TokenKind::Percent),
             '=' => {
-                chars.next();
-                if chars.peek() == Some(&'=') {
-                    chars.next();
-                    tokens.push(Token {
-                        kind: TokenKind::EqEq,
-                        line,
-                        col,
-                    });
-                    col += 2;
+                self.bump();
+                if self.peek() == Some('=') {
+                    self.bump();
+                    self.push_at(TokenKind::EqEq, start, line, col);
                 } else {
-                    tokens.push(Token {
-                        kind: TokenKind::Eq,
-                        line,
-                        col,
-                    });
-                    col += 1;
+                    self.push_at(TokenKind::Eq, start, line, col);
                 }
             }
             '!' => {
-                chars.next();
-                if chars.peek() == Some(&'=') {
-                    chars.next();
-                    tokens.push(Token {
-                        kind: TokenKind::Neq,
-                        line,
-                        col,
-                    });
-                    col += 2;
+                self.bump();
+                if self.peek() == Some('=') {
+                    self.bump();
+                    self.push_at(TokenKind::Neq, start, line, col);
                 } else {
-                    bail!("Unexpected '!' at {}:{}", line, col);
+                    self.error_at("Unexpected '!'", start, line, col);
                 }
             }
             '>' => {
-                chars.next();
-                if chars.peek() == Some(&'=') {
-                    chars.next();
-                    tokens.push(Token {
-                        kind: TokenKind::Gte,
-                        line,
-                        col,
-                    });
-                    col += 2;
+                self.bump();
+                if self.peek() == Some('=') {
+                    self.bump();
+                    self.push_at(TokenKind::Gte, start, line, col);
                 } else {
-                    tokens.push(Token {
-                        kind: TokenKind::Gt,
-                        line,
-                        col,
-                    });
-                    col += 1;
+                    self.push_at(TokenKind::Gt, start, line, col);
                 }
             }
-            '<' => {
-                chars.next();
-                tokens.push(Token {
-                    kind: TokenKind::Lt,
-                    line,
-                    col,
-                });
-                col += 1;
-            }
-            '(' => {
-                chars.next();
-                tokens.push(Token {
-                    kind: TokenKind::LParen,
-                    line,
-                    col,
-                });
-                col += 1;
-            }
-            ')' => {
-                chars.next();
-                tokens.push(Token {
-                    kind: TokenKind::RParen,
-                    line,
-                    col,
-                });
-                col += 1;
-            }
-            ',' => {
-                chars.next();
-                tokens.push(Token {
-                    kind: TokenKind::Comma,
-                    line,
-                    col,
-                });
-                col += 1;
-            }
-            ';' => {
-                chars.next();
-                tokens.push(Token {
-                    kind: TokenKind::Newline,
-                    line,
-                    col,
-                });
-                col += 1;
-            }
-            '.' => {
-                chars.next();
-                tokens.push(Token {
-                    kind: TokenKind::Dot,
-                    line,
-                    col,
-                });
-                col += 1;
-            }
+            '<' => self.single(TokenKind::Lt),
+            '(' => self.single(TokenKind::LParen),
+            ')' => self.single(TokenKind::RParen),
+            '[' => self.single(TokenKind::LBracket),
+            ']' => self.single(TokenKind::RBracket),
+            '{' => self.single(TokenKind::LBrace),
+            '}' => self.single(TokenKind::RBrace),
+            ',' => self.single(TokenKind::Comma),
+            ';' => self.single(TokenKind::Newline),
+            '.' => self.single(TokenKind::Dot),
             ':' => {
-                chars.next();
-                if chars.peek() == Some(&':') {
-                    chars.next();
-                    tokens.push(Token {
-                        kind: TokenKind::DoubleColon,
-                        line,
-                        col,
-                    });
-                    col += 2;
+                self.bump();
+                if self.peek() == Some(':') {
+                    self.bump();
+                    self.push_at(TokenKind::DoubleColon, start, line, col);
                 } else {
-                    tokens.push(Token {
-                        kind: TokenKind::Colon,
-                        line,
-                        col,
-                    });
-                    col += 1;
+                    self.push_at(TokenKind::Colon, start, line, col);
                 }
             }
-            '?' => {
-                chars.next();
-                tokens.push(Token {
-                    kind: TokenKind::Question,
-                    line,
-                    col,
-                });
-                col += 1;
-            }
-            '"' => {
-                chars.next();
-                col += 1;
-                let mut s = String::new();
-                while let Some(c) = chars.next() {
-                    col += 1;
-                    match c {
-                        '"' => break,
-                        '\\' => {
-                            if let Some(esc) = chars.next() {
-                                col += 1;
-                                match esc {
-                                    'n' => s.push('\n'),
-                                    't' => s.push('\t'),
-                                    '"' => s.push('"'),
-                                    '\\' => s.push('\\'),
-                                    _ => s.push(esc),
-                                }
-                            }
-                        }
-                        _ => s.push(c),
-                    }
-                }
-                tokens.push(Token {
-                    kind: TokenKind::String(s),
-                    line,
-                    col,
-                });
-            }
+            '?' => self.single(TokenKind::Question),
+            '"' => self.lex_string(start, line, col),
             c if c.is_ascii_digit() => {
-                let start_col = col;
-                let mut num = String::new();
-                while let Some(c2) = chars.peek().cloned() {
+                while let Some(c2) = self.peek() {
                     if c2.is_ascii_digit() || c2 == '.' {
-                        num.push(c2);
-                        chars.next();
-                        col += 1;
+                        self.bump();
                     } else {
                         break;
                     }
                 }
-                let value: f64 = num.parse()?;
-                tokens.push(Token {
-                    kind: TokenKind::Number(value),
-                    line,
-                    col: start_col,
-                });
+                let digits_end = self.pos;
+                let unit = self.lex_duration_unit();
+                match (self.input[start..digits_end].parse::<f64>(), unit) {
+                    (Ok(value), None) => self.push_at(TokenKind::Number(value), start, line, col),
+                    (Ok(value), Some(scale)) if (value * scale).fract() == 0.0 => self.push_at(
+                        TokenKind::Duration((value * scale) as u64),
+                        start,
+                        line,
+                        col,
+                    ),
+                    (Ok(value), Some(scale)) => {
+                        // Keep the (rounded) token so the parser does not report a
+                        // second error for the missing value.
+                        self.error_at(
+                            &format!(
+                                "Invalid duration '{}' (not a whole number of milliseconds)",
+                                &self.input[start..self.pos]
+                            ),
+                            start,
+                            line,
+                            col,
+                        );
+                        self.push_at(
+                            TokenKind::Duration((value * scale).round() as u64),
+                            start,
+                            line,
+                            col,
+                        );
+                    }
+                    (Err(_), _) => self.error_at(
+                        &format!("Invalid number '{}'", &self.input[start..self.pos]),
+                        start,
+                        line,
+                        col,
+                    ),
+                }
             }
             c if is_ident_start(c) => {
-                let start_col = col;
-                let mut ident = String::new();
-                while let Some(c2) = chars.peek().cloned() {
+                while let Some(c2) = self.peek() {
                     if is_ident_continue(c2) {
-                        ident.push(c2);
-                        chars.next();
-                        col += 1;
+                        self.bump();
                     } else {
                         break;
                     }
                 }
-                let kind = match ident.as_str() {
-                    "module" => TokenKind::Keyword(Keyword::Module),
-                    "use" => TokenKind::Keyword(Keyword::Use),
-                    "import" => TokenKind::Keyword(Keyword::Import),
-                    "flow" => TokenKind::Keyword(Keyword::Flow),
-                    "state" => TokenKind::Keyword(Keyword::State),
-                    "rule" => TokenKind::Keyword(Keyword::Rule),
-                    "nd" => TokenKind::Keyword(Keyword::Nd),
-                    "define" => TokenKind::Keyword(Keyword::Define),
-                    "propose" => TokenKind::Keyword(Keyword::Propose),
-                    "satisfy" => TokenKind::Keyword(Keyword::Satisfy),
-                    "on" => TokenKind::Keyword(Keyword::On),
-                    "when" => TokenKind::Keyword(Keyword::When),
-                    "emit" => TokenKind::Keyword(Keyword::Emit),
-                    "and" => TokenKind::Keyword(Keyword::And),
-                    "or" => TokenKind::Keyword(Keyword::Or),
-                    "run" => TokenKind::Keyword(Keyword::Run),
-                    "terminate" => TokenKind::Keyword(Keyword::Terminate),
-                    "start" => TokenKind::Keyword(Keyword::Start),
-                    "end" => TokenKind::Keyword(Keyword::End),
-                    "null" => TokenKind::Keyword(Keyword::Null),
-                    _ => TokenKind::Identifier(ident),
+                let ident = &self.input[start..self.pos];
+                let kind = match keyword(ident) {
+                    Some(kw) => TokenKind::Keyword(kw),
+                    None => TokenKind::Identifier(ident.to_string()),
                 };
-                tokens.push(Token {
-                    kind,
-                    line,
-                    col: start_col,
-                });
+                self.push_at(kind, start, line, col);
             }
             _ => {
-                bail!("Unexpected character '{}' at {}:{}", ch, line, col);
+                self.bump();
+                self.error_at(&format!("Unexpected character '{}'", ch), start, line, col);
             }
         }
     }
 
-    tokens.push(Token {
-        kind: TokenKind::Eof,
-        line,
-        col,
-    });
-    Ok(tokens)
+    /// A string literal. Without `${...}` it is a `String` token; with interpolation it is a
+    /// `Template` whose expression parts are lexed in place, so their offsets are absolute.
+    fn lex_string(&mut self, start: usize, line: usize, col: usize) {
+        self.bump();
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut terminated = false;
+        while let Some(c) = self.peek() {
+            let (c_start, c_line, c_col) = (self.pos, self.line, self.col);
+            self.bump();
+            match c {
+                '"' => {
+                    terminated = true;
+                    break;
+                }
+                '\\' => self.lex_escape(&mut text, c_start, c_line, c_col),
+                '$' if self.peek() == Some('{') => {
+                    self.bump();
+                    if !text.is_empty() {
+                        parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                    }
+                    let tokens = self.lex_interpolation(c_start, c_line, c_col);
+                    parts.push(TemplatePart::Expr(tokens));
+                }
+                _ => text.push(c),
+            }
+        }
+        if !terminated {
+            self.error_at("Unterminated string literal", start, line, col);
+        }
+        if parts.is_empty() {
+            self.push_at(TokenKind::String(text), start, line, col);
+        } else {
+            if !text.is_empty() {
+                parts.push(TemplatePart::Text(text));
+            }
+            self.push_at(TokenKind::Template(parts), start, line, col);
+        }
+    }
+
+    /// The escape after a backslash. Unknown escapes stand for the character itself.
+    fn lex_escape(&mut self, out: &mut String, start: usize, line: usize, col: usize) {
+        let Some(esc) = self.bump() else {
+            return;
+        };
+        match esc {
+            'n' => out.push('\n'),
+            't' => out.push('\t'),
+            'r' => out.push('\r'),
+            '0' => out.push('\0'),
+            'u' => match self.lex_unicode_escape() {
+                Some(c) => out.push(c),
+                None => self.error_at(
+                    "Invalid unicode escape; expected \\u{...} with 1-6 hex digits",
+                    start,
+                    line,
+                    col,
+                ),
+            },
+            _ => out.push(esc),
+        }
+    }
+
+    /// The `{hex}` after `\u`: one to six hex digits naming a valid code point.
+    fn lex_unicode_escape(&mut self) -> Option<char> {
+        if self.peek() != Some('{') {
+            return None;
+        }
+        self.bump();
+        let digits_start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+            self.bump();
+        }
+        let digits = &self.input[digits_start..self.pos];
+        if self.peek() != Some('}') {
+            return None;
+        }
+        self.bump();
+        if digits.is_empty() || digits.len() > 6 {
+            return None;
+        }
+        u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+    }
+
+    /// Tokens of one `${...}` part, up to the matching `}` on the same line, ending in `Eof`.
+    fn lex_interpolation(&mut self, start: usize, line: usize, col: usize) -> Vec<Token> {
+        let outer = std::mem::take(&mut self.tokens);
+        let mut depth = 0usize;
+        loop {
+            match self.peek() {
+                None | Some('\n') => {
+                    self.error_at("Unterminated string interpolation", start, line, col);
+                    break;
+                }
+                Some('}') if depth == 0 => {
+                    self.bump();
+                    break;
+                }
+                Some(ch) => {
+                    match ch {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                    self.lex_token(ch);
+                }
+            }
+        }
+        let (pos, eof_line, eof_col) = (self.pos, self.line, self.col);
+        self.push_at(TokenKind::Eof, pos, eof_line, eof_col);
+        std::mem::replace(&mut self.tokens, outer)
+    }
+
+    /// Consumes a duration unit (`ms`, `s`, `m`, `h`) directly after a number and returns
+    /// its length in milliseconds. Anything else after the digits is left alone.
+    fn lex_duration_unit(&mut self) -> Option<f64> {
+        let rest = &self.input[self.pos..];
+        let unit_len = rest
+            .find(|c: char| !is_ident_continue(c))
+            .unwrap_or(rest.len());
+        let scale = match &rest[..unit_len] {
+            "ms" => 1.0,
+            "s" => 1_000.0,
+            "m" => 60_000.0,
+            "h" => 3_600_000.0,
+            _ => return None,
+        };
+        for _ in 0..unit_len {
+            self.bump();
+        }
+        Some(scale)
+    }
+
+    fn error_at(&mut self, message: &str, start: usize, line: usize, col: usize) {
+        self.errors.push(LexError {
+            message: message.to_string(),
+            line,
+            col,
+            start,
+            end: self.pos,
+        });
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+
+    fn single(&mut self, kind: TokenKind) {
+        let (start, line, col) = (self.pos, self.line, self.col);
+        self.bump();
+        self.push_at(kind, start, line, col);
+    }
+
+    fn push_at(&mut self, kind: TokenKind, start: usize, line: usize, col: usize) {
+        self.tokens.push(Token {
+            kind,
+            line,
+            col,
+            start,
+            end: self.pos,
+        });
+    }
+}
+
+fn keyword(ident: &str) -> Option<Keyword> {
+    let kw = match ident {
+        "module" => Keyword::Module,
+        "use" => Keyword::Use,
+        "import" => Keyword::Import,
+        "flow" => Keyword::Flow,
+        "state" => Keyword::State,
+        "rule" => Keyword::Rule,
+        "nd" => Keyword::Nd,
+        "define" => Keyword::Define,
+        "propose" => Keyword::Propose,
+        "satisfy" => Keyword::Satisfy,
+        "on" => Keyword::On,
+        "when" => Keyword::When,
+        "emit" => Keyword::Emit,
+        "and" => Keyword::And,
+        "or" => Keyword::Or,
+        "run" => Keyword::Run,
+        "terminate" => Keyword::Terminate,
+        "return" => Keyword::Return,
+        "start" => Keyword::Start,
+        "region" => Keyword::Region,
+        "end" => Keyword::End,
+        "null" => Keyword::Null,
+        "true" => Keyword::True,
+        "false" => Keyword::False,
+        _ => return None,
+    };
+    Some(kw)
 }
 
 fn is_ident_start(c: char) -> bool {
@@ -5,11 +5,17 @@ pub mod cli;
 pub mod codegen;
 pub mod contracts;
 pub mod convergence;
+pub mod cst;
+pub mod diagnostics;
+pub mod formatter;
 pub mod freeze;
+pub mod graph;
 pub mod ir;
 pub mod lexer;
+pub mod linker;
 pub mod llm_ir;
 pub mod parser;
+pub mod refactor;
 pub mod report;
 pub mod semantics;
 pub mod target_ir;
@@ -0,0 +1,286 @@
+//! Project linking: the entry module of a `.sculpt.json` project and every module it
+//! imports, directly or through other modules, become one program IR.
+//!
+//! Imports resolve against the project's modules (`U607` for unknown ones, `U606` for
+//! cycles). Each module is validated with only its own imports in scope, and references
+//! through an import must name a symbol the imported module exports. The linker then rewrites
+//! those references to the imported module's full name, so the program spells every symbol
+//! one way whatever the aliases were. Imported modules end up in [`IrModule::linked`], which
+//! the lock hash covers together with the entry.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{bail, Result};
+
+use crate::ast::{
+    Call, Expr, ImportDecl, Item, Module, Rule, RuleStmt, RuleTrigger, StateBlock, StateStmt,
+    StringPart,
+};
+use crate::diagnostics::{has_errors, Diagnostic, DiagnosticsError};
+use crate::ir::{from_ast, IrModule};
+use crate::semantics::{module_symbols, validate_module_in_project, ImportedModule};
+
+/// Links `entry` with the modules it imports. Returns the program IR and the warnings of all
+/// linked modules; errors fail with a [`DiagnosticsError`].
+pub fn link_program(
+    entry: &str,
+    modules: &HashMap<String, Module>,
+) -> Result<(IrModule, Vec<Diagnostic>)> {
+    let Some(entry_module) = modules.get(entry) else {
+        bail!("Project entry module '{}' not found", entry);
+    };
+    let mut diagnostics = Vec::new();
+    let order = import_order(entry_module, modules, &mut diagnostics);
+
+    let mut linked = Vec::new();
+    for module in &order {
+        let imports = visible_imports(module, modules);
+        diagnostics.extend(validate_module_in_project(module, &imports));
+        let mut module = (*module).clone();
+        canonicalize_references(&mut module, &imports);
+        linked.push(from_ast(module));
+    }
+    if has_errors(&diagnostics) {
+        bail!(DiagnosticsError::with_heading(
+            "Semantic validation failed",
+            diagnostics
+        ));
+    }
+
+    // The entry is visited last: every module comes after the modules it imports.
+    let mut program = linked.pop().expect("import order includes the entry");
+    for module in &linked {
+        program.fqns.extend(module.fqns.iter().cloned());
+    }
+    program.fqns.sort();
+    program.fqns.dedup();
+    program.linked = linked;
+    Ok((program, diagnostics))
+}
+
+/// Modules reachable from `entry` through imports, each after the modules it imports.
+fn import_order<'a>(
+    entry: &'a Module,
+    modules: &'a HashMap<String, Module>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<&'a Module> {
+    struct Walk<'a> {
+        modules: &'a HashMap<String, Module>,
+        /// Modules being visited, with the import that led to the next one.
+        stack: Vec<(&'a str, Option<&'a ImportDecl>)>,
+        done: HashSet<&'a str>,
+        order: Vec<&'a Module>,
+    }
+
+    impl<'a> Walk<'a> {
+        fn visit(&mut self, module: &'a Module, diagnostics: &mut Vec<Diagnostic>) {
+            self.stack.push((module.name.as_str(), None));
+            for decl in &module.imports {
+                let Some(imported) = self.modules.get(&decl.path) else {
+                    diagnostics.push(
+                        Diagnostic::new("U607", format!("Unknown imported module '{}'", decl.path))
+                            .with_span(&decl.span)
+                            .did_you_mean(
+                                &decl.path,
+                                decl.span.find_word(&decl.path),
+                                self.modules.keys().map(String::as_str),
+                            ),
+                    );
+                    continue;
+                };
+                if let Some(pos) = self
+                    .stack
+                    .iter()
+                    .position(|(name, _)| *name == imported.name)
+                {
+                    let cycle: Vec<&str> = self.stack[pos..]
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .chain(std::iter::once(imported.name.as_str()))
+                        .collect();
+                    let mut diagnostic =
+                        Diagnostic::new("U606", format!("Import cycle: {}", cycle.join(" -> ")))
+                            .with_span(&decl.span);
+                    // The last module on the stack is this one; `decl` closes the cycle.
+                    for (_, via) in &self.stack[pos..self.stack.len() - 1] {
+                        if let Some(via) = via {
+                            diagnostic = diagnostic.with_label(&via.span, "imported here");
+                        }
+                    }
+                    diagnostics.push(diagnostic);
+                    continue;
+                }
+                if self.done.contains(imported.name.as_str()) {
+                    continue;
+                }
+                if let Some(last) = self.stack.last_mut() {
+                    last.1 = Some(decl);
+                }
+                self.visit(imported, diagnostics);
+            }
+            self.stack.pop();
+            self.done.insert(module.name.as_str());
+            self.order.push(module);
+        }
+    }
+
+    let mut walk = Walk {
+        modules,
+        stack: Vec::new(),
+        done: HashSet::new(),
+        order: Vec::new(),
+    };
+    walk.visit(entry, diagnostics);
+    walk.order
+}
+
+/// The imports of `module` that name a project module, with what they make visible.
+fn visible_imports(module: &Module, modules: &HashMap<String, Module>) -> Vec<ImportedModule> {
+    module
+        .imports
+        .iter()
+        .filter_map(|decl| {
+            let imported = modules.get(&decl.path)?;
+            Some(ImportedModule {
+                prefix: decl.alias.clone().unwrap_or_else(|| decl.path.clone()),
+                module: imported.name.clone(),
+                symbols: module_symbols(imported),
+                exports: imported
+                    .exports
+                    .iter()
+                    .flat_map(|decl| decl.names.iter().cloned())
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Rewrites `Alias.member` references to `Imported.Module.member`.
+fn canonicalize_references(module: &mut Module, imports: &[ImportedModule]) {
+    let renamer = Renamer { imports };
+    for item in &mut module.items {
+        match item {
+            Item::Flow(flow) => renamer.states(&mut flow.states),
+            Item::GlobalState(state) => renamer.statements(&mut state.statements),
+            Item::Rule(rule) => renamer.rule(rule),
+            Item::Nd(nd) => {
+                renamer.call(&mut nd.propose);
+                for constraint in &mut nd.constraints {
+                    renamer.call(constraint);
+                }
+            }
+            Item::Define(_) => {}
+        }
+    }
+}
+
+struct Renamer<'a> {
+    imports: &'a [ImportedModule],
+}
+
+impl Renamer<'_> {
+    fn name(&self, name: &mut String) {
+        let renamed = self.imports.iter().find_map(|import| {
+            name.strip_prefix(import.prefix.as_str())
+                .and_then(|rest| rest.strip_prefix('.'))
+                .map(|member| format!("{}.{}", import.module, member))
+        });
+        if let Some(renamed) = renamed {
+            *name = renamed;
+        }
+    }
+
+    fn states(&self, states: &mut [StateBlock]) {
+        for state in states {
+            self.statements(&mut state.statements);
+            self.states(&mut state.states);
+            for region in &mut state.regions {
+                self.states(&mut region.states);
+            }
+        }
+    }
+
+    fn statements(&self, statements: &mut [StateStmt]) {
+        for stmt in statements {
+            match stmt {
+                StateStmt::On { event, guard, .. } => {
+                    self.call(event);
+                    if let Some(guard) = guard {
+                        self.expr(guard);
+                    }
+                }
+                StateStmt::Run { flow, args, .. } => {
+                    self.name(flow);
+                    for arg in args {
+                        self.expr(&mut arg.value);
+                    }
+                }
+                StateStmt::Return {
+                    value: Some(value), ..
+                } => self.expr(value),
+                StateStmt::Assign { target, value, .. } => {
+                    self.name(target);
+                    self.expr(value);
+                }
+                StateStmt::Expr(call) => self.call(call),
+                StateStmt::Rule(rule) => self.rule(rule),
+                StateStmt::After { .. } | StateStmt::Return { value: None, .. } => {}
+                StateStmt::Terminate => {}
+            }
+        }
+    }
+
+    fn rule(&self, rule: &mut Rule) {
+        match &mut rule.trigger {
+            RuleTrigger::On(call) => self.call(call),
+            RuleTrigger::When(expr) => self.expr(expr),
+            RuleTrigger::Every(_) => {}
+        }
+        for stmt in &mut rule.body {
+            match stmt {
+                RuleStmt::Assign { target, value, .. } => {
+                    self.name(target);
+                    self.expr(value);
+                }
+                RuleStmt::Emit { event, .. } => self.name(event),
+            }
+        }
+    }
+
+    fn call(&self, call: &mut Call) {
+        self.name(&mut call.name);
+        for arg in &mut call.args {
+            self.expr(&mut arg.value);
+        }
+    }
+
+    fn expr(&self, expr: &mut Expr) {
+        match expr {
+            Expr::Ident(name) => self.name(name),
+            Expr::Call(call) => self.call(call),
+            Expr::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Unary { operand, .. } => self.expr(operand),
+            Expr::Interpolated(parts) => {
+                for part in parts {
+                    if let StringPart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+            }
+            Expr::List(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            Expr::Record(fields) => {
+                for field in fields {
+                    self.expr(&mut field.value);
+                }
+            }
+            Expr::Number(_) | Expr::Duration(_) | Expr::String(_) | Expr::Bool(_) | Expr::Null => {}
+        }
+    }
+}
@@ -59,6 +59,11 @@ fn print_help_tui() {
             " freeze     compile + lock deterministic output",
             " replay     build from sculpt.lock (no LLM)",
             " run        run last build output",
+            " fmt        format .sculpt sources canonically",
+            " explain    describe a diagnostic code",
+            " fix        apply safe diagnostic suggestions",
+            " rename     rename a symbol and keep its stable ID",
+            " graph      print the module graph as JSON, DOT or Mermaid",
             " target     list/describe targets",
             " auth       provider auth + conformance checks",
         ],
@@ -123,6 +128,8 @@ fn print_subcommand_help(cmd: &str) -> bool {
                     " --model <name>          model override",
                     " --strict-provider       fail if provider auth/config is missing",
                     " --debug[=compact|raw|all|json]",
+                    " --diagnostics-format=json|sarif",
+                    "                         also write dist/<script>/diagnostics.*",
                 ],
                 accent2,
                 c,
@@ -235,6 +242,8 @@ fn print_subcommand_help(cmd: &str) -> bool {
                     " --model <name>          model override",
                     " --strict-provider       fail if provider auth/config is missing",
                     " --debug[=compact|raw|all|json]",
+                    " --diagnostics-format=json|sarif",
+                    "                         also write dist/<script>/diagnostics.*",
                 ],
                 accent2,
                 c,
@@ -258,6 +267,16 @@ fn print_subcommand_help(cmd: &str) -> bool {
                 accent2,
                 c,
             );
+            print_box(
+                "Options",
+                &[
+                    " --target <name>         override target",
+                    " --diagnostics-format=json|sarif",
+                    "                         also write dist/<script>/diagnostics.*",
+                ],
+                accent2,
+                c,
+            );
             print_box(
                 "Behavior",
                 &[
@@ -338,6 +357,115 @@ fn print_subcommand_help(cmd: &str) -> bool {
             );
             true
         }
+        "fmt" => {
+            print_header();
+            print_box(
+                "Usage",
+                &[" sculpt fmt <file.sculpt|dir> ... [--check]"],
+                accent2,
+                c,
+            );
+            print_box(
+                "Behavior",
+                &[
+                    " Rewrites files in canonical layout; comments are kept.",
+                    " Directories are searched recursively for *.sculpt files.",
+                    " --check: list unformatted files and exit non-zero, write nothing.",
+                ],
+                accent2,
+                c,
+            );
+            true
+        }
+        "explain" => {
+            print_header();
+            print_box("Usage", &[" sculpt explain [<code>]"], accent2, c);
+            print_box(
+                "Behavior",
+                &[
+                    " Prints the level, summary and explanation of one code.",
+                    " Without a code, lists every registered diagnostic code.",
+                ],
+                accent2,
+                c,
+            );
+            print_box("Example", &[" sculpt explain F105"], accent2, c);
+            true
+        }
+        "fix" => {
+            print_header();
+            print_box(
+                "Usage",
+                &[" sculpt fix <file.sculpt|dir> ... [--target <name>] [--check]"],
+                accent2,
+                c,
+            );
+            print_box(
+                "Behavior",
+                &[
+                    " Applies the safe suggestions of semantic and contract checks.",
+                    " Directories are searched recursively for *.sculpt files.",
+                    " --target: contract to check against (default: @meta target).",
+                    " --check: list safe fixes and exit non-zero, write nothing.",
+                ],
+                accent2,
+                c,
+            );
+            true
+        }
+        "rename" => {
+            print_header();
+            print_box(
+                "Usage",
+                &[" sculpt rename <file.sculpt> <from> <to> [--kind <kind>]"],
+                accent2,
+                c,
+            );
+            print_box(
+                "Behavior",
+                &[
+                    " Renames a flow, state, rule, variable, nd or define and all",
+                    " references to it, then updates the <file>.ids.json sidecar.",
+                    " --kind flow|state|rule|global|nd|define: pick one if ambiguous.",
+                ],
+                accent2,
+                c,
+            );
+            print_box(
+                "Example",
+                &[" sculpt rename app.sculpt Main.Idle Waiting"],
+                accent2,
+                c,
+            );
+            true
+        }
+        "graph" => {
+            print_header();
+            print_box(
+                "Usage",
+                &[" sculpt graph <input.sculpt|project.sculpt.json> [options]"],
+                accent2,
+                c,
+            );
+            print_box(
+                "Options",
+                &[
+                    " --format <json|dot|mermaid>  output format (default: json)",
+                    " --focus <name>               one flow, state or rule and its",
+                    "                              neighbours, e.g. Main.Idle",
+                    " --target <name>              contract for packages/capabilities",
+                ],
+                accent2,
+                c,
+            );
+            print_box(
+                "Example",
+                &[" sculpt graph app.sculpt --format mermaid --focus Main.Idle"],
+                accent2,
+                c,
+            );
+            true
+        }
         "clean" => {
             print_header();
             print_box(
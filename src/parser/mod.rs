@@ -1,74 +1,303 @@
-use anyhow::{bail, Result};
+use anyhow::Result;
 
 use crate::ast::*;
-use crate::lexer::{lex, Keyword, Token, TokenKind};
+use crate::diagnostics::{format_diagnostics, has_errors, Diagnostic};
+use crate::lexer::{
+    lex_comments, lex_recovering, Keyword, LexError, TemplatePart, Token, TokenKind,
+};
+use crate::versioning::{module_language, Feature, LanguageVersion};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
 
+/// Parses a module. On failure the error is a [`SyntaxErrors`] listing every syntax error
+/// in the file, not just the first one. Syntax warnings are dropped; use
+/// [`parse_source_recovering`] to see them.
 pub fn parse_source(input: &str) -> Result<Module> {
-    let tokens = lex(input)?;
-    let mut parser = Parser {
-        tokens,
-        pos: 0,
-        anon_rule_counter: 0,
+    parse_source_file(SourceFile::new("<input>", input))
+}
+
+/// Parses `input` and records `path` in every span, so diagnostics can point at the file.
+pub fn parse_source_with_path(input: &str, path: &Path) -> Result<Module> {
+    parse_source_file(SourceFile::new(path.display().to_string(), input))
+}
+
+/// Result of an error-recovering parse: the module with every construct that parsed
+/// cleanly, plus one `S0xx` diagnostic per syntax error or warning.
+#[derive(Debug, Clone)]
+pub struct ParseOutcome {
+    pub module: Module,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Parses in recovery mode: after a syntax error the parser skips to the next statement or
+/// block boundary (newline / matching `end`) and carries on, so a single pass reports all
+/// syntax errors and the well-formed parts can still be validated.
+pub fn parse_source_recovering(input: &str) -> ParseOutcome {
+    parse_recovering_file(SourceFile::new("<input>", input))
+}
+
+pub fn parse_source_recovering_with_path(input: &str, path: &Path) -> ParseOutcome {
+    parse_recovering_file(SourceFile::new(path.display().to_string(), input))
+}
+
+/// All syntax errors of one file; displays like rendered semantic diagnostics.
+#[derive(Debug, thiserror::Error)]
+#[error("{}", format_diagnostics(.0))]
+pub struct SyntaxErrors(pub Vec<Diagnostic>);
+
+fn parse_source_file(source: Arc<SourceFile>) -> Result<Module> {
+    let outcome = parse_recovering_file(source);
+    if !has_errors(&outcome.diagnostics) {
+        Ok(outcome.module)
+    } else {
+        Err(SyntaxErrors(outcome.diagnostics).into())
+    }
+}
+
+fn parse_recovering_file(source: Arc<SourceFile>) -> ParseOutcome {
+    let (tokens, lex_errors) = lex_recovering(&source.text);
+    parse_tokens(tokens, lex_errors, source)
+}
+
+/// Parses an already lexed token stream of `source` (used by the CST, which owns the
+/// tokens). `lex_errors` are reported as `S007`.
+pub(crate) fn parse_tokens(
+    tokens: Vec<Token>,
+    lex_errors: Vec<LexError>,
+    source: Arc<SourceFile>,
+) -> ParseOutcome {
+    // Well-formed input never goes through the recovery heuristics (they lean on indentation).
+    let mut strict_error = None;
+    if lex_errors.is_empty() {
+        let mut parser = Parser::new(tokens.clone(), source.clone(), false);
+        match parser.parse_module() {
+            Ok(module) => {
+                // Edition gates (`S009`) and trailing-content warnings don't abort the parse.
+                return ParseOutcome {
+                    module,
+                    diagnostics: parser.diagnostics,
+                };
+            }
+            Err(err) => strict_error = Some(err),
+        }
+    }
+
+    let mut parser = Parser::new(tokens, source.clone(), true);
+    for err in lex_errors {
+        let span = Span::new(source.clone(), err.start, err.end, err.line, err.col);
+        parser
+            .diagnostics
+            .push(Diagnostic::new("S007", err.message).with_span(&span));
+    }
+    let module = match parser.parse_module() {
+        Ok(module) => module,
+        Err(err) => {
+            parser.record(err);
+            empty_module()
+        }
     };
-    parser.parse_module()
+    if !has_errors(&parser.diagnostics) {
+        // The recovering parse accepted what the strict one rejected; keep the strict error.
+        if let Some(err) = strict_error {
+            parser.record(err);
+        }
+    }
+    let mut diagnostics = parser.diagnostics;
+    diagnostics.sort_by_key(|d| d.span.as_ref().map_or(usize::MAX, |s| s.start));
+    ParseOutcome {
+        module,
+        diagnostics,
+    }
+}
+
+fn empty_module() -> Module {
+    Module {
+        name: String::new(),
+        meta: HashMap::new(),
+        uses: Vec::new(),
+        imports: Vec::new(),
+        exports: Vec::new(),
+        items: Vec::new(),
+        span: Span::default(),
+        meta_spans: HashMap::new(),
+    }
+}
+
+/// A single syntax error; recorded as a [`Diagnostic`] with the same code and span.
+#[derive(Debug, thiserror::Error)]
+#[error("{code}: {message} at {}:{}", .span.line, .span.col)]
+struct SyntaxError {
+    code: &'static str,
+    message: String,
+    span: Span,
 }
 
 struct Parser {
     tokens: Vec<Token>,
     pos: usize,
     anon_rule_counter: usize,
+    source: Arc<SourceFile>,
+    /// Report errors and resynchronize instead of stopping at the first one.
+    recovering: bool,
+    diagnostics: Vec<Diagnostic>,
+    /// `##` doc comment text by line, for comments that stand on a line of their own.
+    doc_lines: HashMap<usize, String>,
+    /// The language version from `@meta language=...`; decides which constructs parse.
+    language: LanguageVersion,
 }
 
 impl Parser {
+    fn new(tokens: Vec<Token>, source: Arc<SourceFile>, recovering: bool) -> Self {
+        let doc_lines = doc_comment_lines(&source.text);
+        Self {
+            tokens,
+            pos: 0,
+            anon_rule_counter: 0,
+            source,
+            recovering,
+            diagnostics: Vec::new(),
+            doc_lines,
+            language: LanguageVersion::default_version(),
+        }
+    }
+
     fn parse_module(&mut self) -> Result<Module> {
         self.consume_newlines();
-        let meta = self.parse_meta_headers()?;
-        self.expect_keyword(Keyword::Module)?;
-        self.expect(TokenKind::LParen)?;
-        let name = self.parse_qualified_ident()?;
-        self.expect(TokenKind::RParen)?;
-        self.expect(TokenKind::Colon)?;
+        let start = self.pos;
+        let (meta, meta_spans) = self.parse_meta_headers()?;
+        self.language = module_language(&meta);
+        let module_col = self.current_col();
+        let name = match self.parse_module_header() {
+            Ok(name) => name,
+            Err(err) if self.recovering => {
+                // Treat everything after a broken header as the module body.
+                self.record(err);
+                self.skip_line();
+                String::new()
+            }
+            Err(err) => return Err(err),
+        };
         let mut uses = Vec::new();
         let mut imports = Vec::new();
+        let mut exports = Vec::new();
         let mut items = Vec::new();
         self.consume_newlines();
+        let module_col = self.body_indent(module_col);
 
-        while !self.check_keyword(Keyword::End) && !self.is_eof() {
-            if self.check_keyword(Keyword::Use) {
-                uses.push(self.parse_use()?);
-            } else if self.check_keyword(Keyword::Import) {
-                imports.push(self.parse_import()?);
-            } else if self.check_keyword(Keyword::Flow) {
-                items.push(Item::Flow(self.parse_flow()?));
-            } else if self.check_keyword(Keyword::State) {
-                items.push(Item::GlobalState(self.parse_global_state()?));
-            } else if self.check_keyword(Keyword::Rule) {
-                items.push(Item::Rule(self.parse_rule(None, None)?));
-            } else if self.check_keyword(Keyword::Define) {
-                items.push(Item::Define(self.parse_define()?));
-            } else if self.check_keyword(Keyword::Nd) {
-                items.push(Item::Nd(self.parse_nd()?));
-            } else if self.check(TokenKind::Newline) {
-                self.consume_newlines();
-            } else {
-                let tok = self.peek().cloned();
-                bail!("Unexpected token {:?} at module level", tok.map(|t| t.kind));
-            }
+        while !self.at_block_end(module_col) {
+            let member_start = self.pos;
+            let result =
+                self.parse_module_member(&mut uses, &mut imports, &mut exports, &mut items);
+            self.recover(result, member_start)?;
             self.consume_newlines();
         }
 
-        self.expect_keyword(Keyword::End)?;
+        self.expect_block_end("module", &name, start, module_col)?;
+        let span = self.span_from(start);
+        self.check_trailing_content();
         Ok(Module {
             name,
             meta,
             uses,
             imports,
+            exports,
             items,
+            span,
+            meta_spans,
         })
     }
 
+    fn parse_module_header(&mut self) -> Result<String> {
+        if !self.check_keyword(Keyword::Module) {
+            return Err(self.error(
+                "S001",
+                format!(
+                    "Missing module root: expected 'module(<Name>):', found {}",
+                    self.describe_current()
+                ),
+            ));
+        }
+        self.expect_keyword(Keyword::Module)?;
+        self.expect(TokenKind::LParen)?;
+        let name = self.parse_qualified_ident()?;
+        self.expect(TokenKind::RParen)?;
+        self.expect(TokenKind::Colon)?;
+        Ok(name)
+    }
+
+    fn parse_module_member(
+        &mut self,
+        uses: &mut Vec<UseDecl>,
+        imports: &mut Vec<ImportDecl>,
+        exports: &mut Vec<ExportDecl>,
+        items: &mut Vec<Item>,
+    ) -> Result<()> {
+        if self.check_keyword(Keyword::Use) {
+            uses.push(self.parse_use()?);
+        } else if self.check_keyword(Keyword::Import) {
+            imports.push(self.parse_import()?);
+        } else if self.at_exports() {
+            exports.push(self.parse_exports()?);
+        } else if self.at_item(Keyword::Flow) {
+            items.push(Item::Flow(self.parse_flow()?));
+        } else if self.check_keyword(Keyword::State) {
+            items.push(Item::GlobalState(self.parse_global_state()?));
+        } else if self.at_item(Keyword::Rule) {
+            items.push(Item::Rule(self.parse_rule(None, None)?));
+        } else if self.check_keyword(Keyword::Define) {
+            items.push(Item::Define(self.parse_define()?));
+        } else if self.at_item(Keyword::Nd) {
+            items.push(Item::Nd(self.parse_nd()?));
+        } else if self.check(TokenKind::At) {
+            return Err(self.misplaced_attribute());
+        } else if self.check(TokenKind::Newline) {
+            self.consume_newlines();
+        } else {
+            return Err(self.error(
+                "S003",
+                format!(
+                    "Unexpected {} at module level (expected use, import, exports, flow, state, rule, define or nd)",
+                    self.describe_current()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Only blank lines, comments and surplus `end`s (accepted for compatibility) should
+    /// follow the module's closing `end`. Anything else is ignored, as it always was, but
+    /// reported as a warning (`S002` for a second module, `S010` otherwise).
+    fn check_trailing_content(&mut self) {
+        while self.check(TokenKind::Newline) || self.check_keyword(Keyword::End) {
+            self.advance();
+        }
+        if self.is_eof() {
+            return;
+        }
+        let warning = if self.check_keyword(Keyword::Module) {
+            Diagnostic::warn(
+                "S002",
+                "Multiple module roots: only the first module of a file is compiled",
+            )
+        } else {
+            Diagnostic::warn(
+                "S010",
+                format!(
+                    "Unexpected {} after the module's closing 'end' is ignored",
+                    self.describe_current()
+                ),
+            )
+        };
+        self.diagnostics
+            .push(warning.with_span(&self.current_span()));
+        while !self.is_eof() {
+            self.advance();
+        }
+    }
+
     fn parse_use(&mut self) -> Result<UseDecl> {
+        let start = self.pos;
         self.expect_keyword(Keyword::Use)?;
         self.expect(TokenKind::LParen)?;
         let path = self.parse_qualified_ident()?;
@@ -78,10 +307,15 @@ impl Parser {
             self.advance();
             alias = Some(self.expect_ident()?);
         }
-        Ok(UseDecl { path, alias })
+        Ok(UseDecl {
+            path,
+            alias,
+            span: self.span_from(start),
+        })
     }
 
     fn parse_import(&mut self) -> Result<ImportDecl> {
+        let start = self.pos;
         self.expect_keyword(Keyword::Import)?;
         self.expect(TokenKind::LParen)?;
         let path = self.parse_qualified_ident()?;
@@ -91,120 +325,275 @@ impl Parser {
             self.advance();
             alias = Some(self.expect_ident()?);
         }
-        Ok(ImportDecl { path, alias })
+        Ok(ImportDecl {
+            path,
+            alias,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_exports(&mut self) -> Result<ExportDecl> {
+        let start = self.pos;
+        self.advance();
+        self.expect(TokenKind::LParen)?;
+        let mut names = vec![self.expect_ident()?];
+        while self.check(TokenKind::Comma) {
+            self.advance();
+            names.push(self.expect_ident()?);
+        }
+        self.expect(TokenKind::RParen)?;
+        Ok(ExportDecl {
+            names,
+            span: self.span_from(start),
+        })
     }
 
-    fn parse_meta_headers(&mut self) -> Result<HashMap<String, String>> {
+    fn parse_meta_headers(&mut self) -> Result<(HashMap<String, String>, HashMap<String, Span>)> {
         let mut meta = HashMap::new();
+        let mut spans = HashMap::new();
         loop {
             self.consume_newlines();
             if !self.check(TokenKind::At) {
                 break;
             }
-            self.expect(TokenKind::At)?;
-            let tag = self.expect_ident()?;
-            if tag != "meta" {
-                bail!("Unknown directive @{}", tag);
+            let line_start = self.pos;
+            let result = self.parse_meta_line(&mut meta, &mut spans);
+            self.recover(result, line_start)?;
+            self.consume_newlines();
+        }
+        Ok((meta, spans))
+    }
+
+    fn parse_meta_line(
+        &mut self,
+        meta: &mut HashMap<String, String>,
+        spans: &mut HashMap<String, Span>,
+    ) -> Result<()> {
+        let start = self.pos;
+        self.expect(TokenKind::At)?;
+        let tag = self.expect_ident()?;
+        if tag != "meta" {
+            return Err(SyntaxError {
+                code: "S008",
+                message: format!("Unknown directive @{}", tag),
+                span: self.span_from(start),
             }
+            .into());
+        }
 
-            while !self.check(TokenKind::Newline) && !self.is_eof() {
-                let key = self.expect_ident()?.to_lowercase();
-                self.expect(TokenKind::Eq)?;
-                let value = self.expect_meta_value()?;
-                meta.insert(key, value);
+        while !self.check(TokenKind::Newline) && !self.is_eof() {
+            let pair_start = self.pos;
+            let key = self.expect_ident()?.to_lowercase();
+            self.expect(TokenKind::Eq)?;
+            let value = self.expect_meta_value()?;
+            spans.insert(key.clone(), self.span_from(pair_start));
+            meta.insert(key, value);
 
-                if self.check(TokenKind::Comma) {
-                    self.advance();
-                }
+            if self.check(TokenKind::Comma) {
+                self.advance();
             }
-            self.consume_newlines();
         }
-        Ok(meta)
+        Ok(())
     }
 
     fn expect_meta_value(&mut self) -> Result<String> {
-        if let Some(tok) = self.peek() {
-            match &tok.kind {
-                TokenKind::String(s) => {
-                    let out = s.clone();
-                    self.advance();
-                    Ok(out)
-                }
-                TokenKind::Number(n) => {
-                    let out = n.to_string();
-                    self.advance();
-                    Ok(out)
-                }
-                TokenKind::Identifier(s) => {
-                    let out = s.clone();
-                    self.advance();
-                    Ok(out)
-                }
-                TokenKind::Keyword(k) => {
-                    let out = format!("{:?}", k).to_lowercase();
-                    self.advance();
-                    Ok(out)
-                }
-                _ => bail!("Expected meta value"),
+        let out = match self.peek_kind() {
+            Some(TokenKind::String(s)) => s.clone(),
+            // Keep numbers as written, so `language=1.10` does not read as `1.1`.
+            Some(TokenKind::Number(_)) => {
+                let token = &self.tokens[self.pos];
+                self.source.text[token.start..token.end].to_string()
             }
-        } else {
-            bail!("Expected meta value")
-        }
+            Some(TokenKind::Identifier(s)) => s.clone(),
+            Some(TokenKind::Keyword(k)) => format!("{:?}", k).to_lowercase(),
+            _ => return Err(self.unexpected("meta value")),
+        };
+        self.advance();
+        Ok(out)
     }
 
     fn parse_flow(&mut self) -> Result<Flow> {
+        let flow_start = self.pos;
+        let annotations = self.parse_annotations()?;
+        let flow_col = self.current_col();
         self.expect_keyword(Keyword::Flow)?;
         self.expect(TokenKind::LParen)?;
         let name = self.parse_qualified_ident()?;
+        let mut params = Vec::new();
+        while self.check(TokenKind::Comma) {
+            self.advance();
+            let param_start = self.pos;
+            params.push(self.parse_flow_param()?);
+            self.require(Feature::FlowSignatures, param_start);
+        }
         self.expect(TokenKind::RParen)?;
+        let returns = if self.check(TokenKind::Arrow) {
+            let arrow = self.pos;
+            self.advance();
+            let returns = self.parse_type_name()?;
+            self.require(Feature::FlowSignatures, arrow);
+            Some(returns)
+        } else {
+            None
+        };
         self.expect(TokenKind::Colon)?;
         let mut start = None;
+        let mut start_span = None;
         let mut states = Vec::new();
         self.consume_newlines();
+        let flow_col = self.body_indent(flow_col);
 
-        while !self.check_keyword(Keyword::End) && !self.is_eof() {
-            if self.check_keyword(Keyword::Start) {
-                self.expect_keyword(Keyword::Start)?;
-                self.expect_transition()?;
-                let target = self.parse_qualified_ident()?;
-                start = Some(target);
-            } else if self.check_keyword(Keyword::State) {
-                states.push(self.parse_state_block(true, Some(name.as_str()))?);
-            } else if self.check(TokenKind::Newline) {
-                self.consume_newlines();
-            } else {
-                let tok = self.peek().cloned();
-                bail!("Unexpected token {:?} in flow", tok.map(|t| t.kind));
-            }
+        while !self.at_block_end(flow_col) {
+            let member_start = self.pos;
+            let result = self.parse_flow_member(
+                "flow",
+                &name,
+                &name,
+                &mut start,
+                &mut start_span,
+                &mut states,
+            );
+            self.recover(result, member_start)?;
             self.consume_newlines();
         }
 
-        self.expect_keyword(Keyword::End)?;
+        self.expect_block_end("flow", &name, flow_start, flow_col)?;
         Ok(Flow {
             name,
+            params,
+            returns,
             start,
             states,
+            annotations,
+            span: self.span_from(flow_start),
+            start_span,
+        })
+    }
+
+    /// `name: type` in a flow header.
+    fn parse_flow_param(&mut self) -> Result<FlowParam> {
+        let start = self.pos;
+        let name = self.expect_ident()?;
+        self.expect(TokenKind::Colon)?;
+        let ty = self.parse_type_name()?;
+        Ok(FlowParam {
+            name,
+            ty,
+            span: self.span_from(start),
+        })
+    }
+
+    /// A member of a flow or a region: `start > State` or a `state(...)` block.
+    fn parse_flow_member(
+        &mut self,
+        kind: &str,
+        name: &str,
+        flow_name: &str,
+        start: &mut Option<String>,
+        start_span: &mut Option<Span>,
+        states: &mut Vec<StateBlock>,
+    ) -> Result<()> {
+        if self.check_keyword(Keyword::Start) {
+            self.parse_start(start, start_span)?;
+        } else if self.at_item(Keyword::State) {
+            states.push(self.parse_state_block(true, Some(flow_name))?);
+        } else if self.check(TokenKind::At) {
+            return Err(self.misplaced_attribute());
+        } else if self.check(TokenKind::Newline) {
+            self.consume_newlines();
+        } else {
+            return Err(self.error(
+                "S006",
+                format!(
+                    "Unexpected {} in {} '{}' (expected start or state)",
+                    self.describe_current(),
+                    kind,
+                    name
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn parse_start(
+        &mut self,
+        start: &mut Option<String>,
+        start_span: &mut Option<Span>,
+    ) -> Result<()> {
+        let stmt_start = self.pos;
+        self.expect_keyword(Keyword::Start)?;
+        self.expect_transition()?;
+        let target = self.parse_qualified_ident()?;
+        *start = Some(target);
+        *start_span = Some(self.span_from(stmt_start));
+        Ok(())
+    }
+
+    fn parse_region(&mut self, flow_name: &str) -> Result<Region> {
+        let region_start = self.pos;
+        let region_col = self.current_col();
+        self.expect_keyword(Keyword::Region)?;
+        self.expect(TokenKind::LParen)?;
+        let name = self.expect_ident()?;
+        self.expect(TokenKind::RParen)?;
+        self.expect(TokenKind::Colon)?;
+        let mut start = None;
+        let mut start_span = None;
+        let mut states = Vec::new();
+        self.consume_newlines();
+        let region_col = self.body_indent(region_col);
+
+        while !self.at_block_end(region_col) {
+            let member_start = self.pos;
+            let result = self.parse_flow_member(
+                "region",
+                &name,
+                flow_name,
+                &mut start,
+                &mut start_span,
+                &mut states,
+            );
+            self.recover(result, member_start)?;
+            self.consume_newlines();
+        }
+
+        self.expect_block_end("region", &name, region_start, region_col)?;
+        Ok(Region {
+            name,
+            start,
+            states,
+            span: self.span_from(region_start),
+            start_span,
         })
     }
 
     fn parse_global_state(&mut self) -> Result<StateBlock> {
+        let start = self.pos;
+        let block_col = self.current_col();
         self.expect_keyword(Keyword::State)?;
         if self.check(TokenKind::LParen) {
             self.advance();
             if !self.check(TokenKind::RParen) {
-                bail!("Global state blocks must be state() with no name");
+                return Err(self.error("S006", "Global state blocks must be state() with no name"));
             }
             self.expect(TokenKind::RParen)?;
         } else {
-            bail!("Global state blocks must be state()");
+            return Err(self.error("S006", "Global state blocks must be state()"));
         }
         self.expect(TokenKind::Colon)?;
         self.consume_newlines();
-        let statements = self.parse_state_statements(false, None, None)?;
-        self.expect_keyword(Keyword::End)?;
+        let block_col = self.body_indent(block_col);
+        let statements = self.parse_state_statements(false, None, None, block_col)?;
+        self.expect_block_end("state", "", start, block_col)?;
         Ok(StateBlock {
             name: None,
             statements,
+            start: None,
+            states: Vec::new(),
+            regions: Vec::new(),
+            annotations: Annotations::default(),
+            span: self.span_from(start),
+            start_span: None,
         })
     }
 
@@ -213,6 +602,9 @@ impl Parser {
         requires_name: bool,
         flow_name: Option<&str>,
     ) -> Result<StateBlock> {
+        let start = self.pos;
+        let annotations = self.parse_annotations()?;
+        let block_col = self.current_col();
         self.expect_keyword(Keyword::State)?;
         let name = if self.check(TokenKind::LParen) {
             self.advance();
@@ -225,15 +617,78 @@ impl Parser {
                 Some(n)
             }
         } else if requires_name {
-            bail!("state(name) required inside flows");
+            return Err(self.error("S005", "state(name) required inside flows"));
         } else {
             None
         };
         self.expect(TokenKind::Colon)?;
         self.consume_newlines();
-        let statements = self.parse_state_statements(true, flow_name, name.as_deref())?;
-        self.expect_keyword(Keyword::End)?;
-        Ok(StateBlock { name, statements })
+        let block_col = self.body_indent(block_col);
+        let mut block = StateBlock {
+            name,
+            statements: Vec::new(),
+            start: None,
+            states: Vec::new(),
+            regions: Vec::new(),
+            annotations,
+            span: Span::default(),
+            start_span: None,
+        };
+        while !self.at_block_end(block_col) {
+            if self.check(TokenKind::Newline) {
+                self.consume_newlines();
+                continue;
+            }
+            let member_start = self.pos;
+            let result = self.parse_state_member(flow_name, &mut block);
+            self.recover(result, member_start)?;
+            self.consume_newlines();
+        }
+        self.expect_block_end(
+            "state",
+            block.name.as_deref().unwrap_or(""),
+            start,
+            block_col,
+        )?;
+        block.span = self.span_from(start);
+        Ok(block)
+    }
+
+    /// A statement of a flow state, or one of its nested `start`/`state`/`region` members.
+    fn parse_state_member(
+        &mut self,
+        flow_name: Option<&str>,
+        block: &mut StateBlock,
+    ) -> Result<()> {
+        match flow_name {
+            Some(flow_name) if self.at_item(Keyword::State) => {
+                let member_start = self.pos;
+                let child = self.parse_state_block(true, Some(flow_name))?;
+                self.require(Feature::NestedStates, member_start);
+                block.states.push(child);
+            }
+            Some(flow_name) if self.check_keyword(Keyword::Region) => {
+                let member_start = self.pos;
+                let region = self.parse_region(flow_name)?;
+                self.require(Feature::NestedStates, member_start);
+                block.regions.push(region);
+            }
+            Some(_) if self.check_keyword(Keyword::Start) => {
+                let member_start = self.pos;
+                self.parse_start(&mut block.start, &mut block.start_span)?;
+                self.require(Feature::NestedStates, member_start);
+            }
+            _ => {
+                let state_name = block.name.clone();
+                self.parse_state_statement(
+                    true,
+                    flow_name,
+                    state_name.as_deref(),
+                    &mut block.statements,
+                )?;
+            }
+        }
+        Ok(())
     }
 
     fn parse_state_statements(
@@ -241,80 +696,179 @@ impl Parser {
         allow_actions: bool,
         flow_name: Option<&str>,
         state_name: Option<&str>,
+        block_col: usize,
     ) -> Result<Vec<StateStmt>> {
         let mut statements = Vec::new();
-        while !self.check_keyword(Keyword::End) && !self.is_eof() {
+        while !self.at_block_end(block_col) {
             if self.check(TokenKind::Newline) {
                 self.consume_newlines();
                 continue;
             }
-            if allow_actions && self.check_keyword(Keyword::On) {
-                self.expect_keyword(Keyword::On)?;
-                let event = self.parse_call()?;
-                if self.check(TokenKind::Gt) {
-                    self.expect_transition()?;
-                    let target = self.parse_qualified_ident()?;
-                    statements.push(StateStmt::On { event, target });
-                } else if self.check(TokenKind::DoubleColon) {
-                    self.advance();
-                    let body_stmt = self.parse_rule_stmt()?;
-                    statements.push(StateStmt::Rule(self.build_inline_on_rule(
-                        event,
-                        vec![body_stmt],
-                        flow_name,
-                        state_name,
-                    )));
-                } else if self.check(TokenKind::Colon) {
-                    self.expect(TokenKind::Colon)?;
-                    self.consume_newlines();
-                    let mut body = Vec::new();
-                    while !self.check_keyword(Keyword::End) && !self.is_eof() {
-                        if self.check(TokenKind::Newline) {
-                            self.consume_newlines();
-                            continue;
-                        }
-                        body.push(self.parse_rule_stmt()?);
-                        self.consume_newlines();
-                    }
-                    self.expect_keyword(Keyword::End)?;
-                    statements.push(StateStmt::Rule(
-                        self.build_inline_on_rule(event, body, flow_name, state_name),
-                    ));
-                } else {
-                    bail!("Expected '>' or ':' or '::' after on-event");
-                }
-            } else if allow_actions && self.check_keyword(Keyword::Rule) {
-                statements.push(StateStmt::Rule(self.parse_rule(flow_name, state_name)?));
-            } else if allow_actions && self.check_keyword(Keyword::Run) {
-                self.expect_keyword(Keyword::Run)?;
-                let flow = self.parse_qualified_ident()?;
-                statements.push(StateStmt::Run { flow });
-            } else if allow_actions && self.check_keyword(Keyword::Terminate) {
-                self.expect_keyword(Keyword::Terminate)?;
-                statements.push(StateStmt::Terminate);
+            let stmt_start = self.pos;
+            let result =
+                self.parse_state_statement(allow_actions, flow_name, state_name, &mut statements);
+            self.recover(result, stmt_start)?;
+            self.consume_newlines();
+        }
+        Ok(statements)
+    }
+
+    fn parse_state_statement(
+        &mut self,
+        allow_actions: bool,
+        flow_name: Option<&str>,
+        state_name: Option<&str>,
+        statements: &mut Vec<StateStmt>,
+    ) -> Result<()> {
+        let stmt_start = self.pos;
+        let stmt_col = self.current_col();
+        if allow_actions && self.check_keyword(Keyword::On) {
+            self.expect_keyword(Keyword::On)?;
+            let event = self.parse_call()?;
+            let guard = if self.check_keyword(Keyword::When) {
+                let guard_start = self.pos;
+                let guard = self.parse_transition_guard()?;
+                self.require(Feature::TransitionGuards, guard_start);
+                Some(guard)
+            } else {
+                None
+            };
+            if guard.is_some() || self.check(TokenKind::Gt) {
+                self.expect_transition()?;
+                let target = self.parse_qualified_ident()?;
+                statements.push(StateStmt::On {
+                    event,
+                    guard,
+                    target,
+                    span: self.span_from(stmt_start),
+                });
+            } else if self.check(TokenKind::DoubleColon) {
+                self.advance();
+                let body_stmt = self.parse_rule_stmt()?;
+                statements.push(StateStmt::Rule(self.build_inline_on_rule(
+                    event,
+                    vec![body_stmt],
+                    flow_name,
+                    state_name,
+                    stmt_start,
+                )));
+            } else if self.check(TokenKind::Colon) {
+                self.expect(TokenKind::Colon)?;
+                self.consume_newlines();
+                let stmt_col = self.body_indent(stmt_col);
+                let body = self.parse_rule_body(stmt_col)?;
+                self.expect_block_end("on-block", &event.name, stmt_start, stmt_col)?;
+                statements.push(StateStmt::Rule(
+                    self.build_inline_on_rule(event, body, flow_name, state_name, stmt_start),
+                ));
             } else {
-                let stmt = self.parse_assignment_or_expr()?;
-                statements.push(stmt);
+                return Err(self.unexpected("'>', ':' or '::' after on-event"));
             }
+        } else if allow_actions && self.at_timer("after") {
+            let delay_ms = self.parse_timer_delay()?;
+            self.expect_transition()?;
+            let target = self.parse_qualified_ident()?;
+            statements.push(StateStmt::After {
+                delay_ms,
+                target,
+                span: self.span_from(stmt_start),
+            });
+        } else if allow_actions && self.at_item(Keyword::Rule) {
+            statements.push(StateStmt::Rule(self.parse_rule(flow_name, state_name)?));
+        } else if self.check(TokenKind::At) {
+            return Err(self.misplaced_attribute());
+        } else if allow_actions && self.check_keyword(Keyword::Run) {
+            self.expect_keyword(Keyword::Run)?;
+            let flow = self.parse_qualified_ident()?;
+            let args = if self.check(TokenKind::LParen) {
+                self.parse_arg_list()?
+            } else {
+                Vec::new()
+            };
+            let result = if self.check(TokenKind::Arrow) {
+                self.advance();
+                Some(self.expect_ident()?)
+            } else {
+                None
+            };
+            if !args.is_empty() || result.is_some() {
+                self.require(Feature::FlowSignatures, stmt_start);
+            }
+            statements.push(StateStmt::Run {
+                flow,
+                args,
+                result,
+                span: self.span_from(stmt_start),
+            });
+        } else if allow_actions && self.check_keyword(Keyword::Terminate) {
+            self.expect_keyword(Keyword::Terminate)?;
+            statements.push(StateStmt::Terminate);
+        } else if allow_actions && self.check_keyword(Keyword::Return) {
+            self.expect_keyword(Keyword::Return)?;
+            let value = if self.check(TokenKind::Newline)
+                || self.check_keyword(Keyword::End)
+                || self.is_eof()
+            {
+                None
+            } else {
+                Some(self.parse_expr()?)
+            };
+            self.require(Feature::FlowSignatures, stmt_start);
+            statements.push(StateStmt::Return {
+                value,
+                span: self.span_from(stmt_start),
+            });
+        } else {
+            let stmt = self.parse_assignment_or_expr()?;
+            statements.push(stmt);
+        }
+        Ok(())
+    }
+
+    /// Rule statements up to (not including) the `end` of the block opened in `block_col`.
+    fn parse_rule_body(&mut self, block_col: usize) -> Result<Vec<RuleStmt>> {
+        let mut body = Vec::new();
+        while !self.at_block_end(block_col) {
+            if self.check(TokenKind::Newline) {
+                self.consume_newlines();
+                continue;
+            }
+            let stmt_start = self.pos;
+            let result = self.parse_rule_stmt().map(|stmt| body.push(stmt));
+            self.recover(result, stmt_start)?;
             self.consume_newlines();
         }
-        Ok(statements)
+        Ok(body)
     }
 
     fn parse_rule(&mut self, scope_flow: Option<&str>, scope_state: Option<&str>) -> Result<Rule> {
+        let start = self.pos;
+        let annotations = self.parse_annotations()?;
+        let rule_col = self.current_col();
         self.expect_keyword(Keyword::Rule)?;
         let (name, params) = self.parse_named_param_list()?;
         self.expect(TokenKind::Colon)?;
         self.consume_newlines();
 
+        let rule_col = self.body_indent(rule_col);
+        let trigger_start = self.pos;
+        let trigger_col = self.current_col();
         let trigger = if self.check_keyword(Keyword::On) {
             self.expect_keyword(Keyword::On)?;
             RuleTrigger::On(self.parse_call()?)
         } else if self.check_keyword(Keyword::When) {
             self.expect_keyword(Keyword::When)?;
             RuleTrigger::When(self.parse_expr()?)
+        } else if self.at_timer("every") {
+            RuleTrigger::Every(self.parse_timer_delay()?)
         } else {
-            bail!("rule must start with on/when");
+            return Err(self.error(
+                "S006",
+                format!(
+                    "rule must start with on/when/every, found {}",
+                    self.describe_current()
+                ),
+            ));
         };
         let body = if self.check(TokenKind::DoubleColon) {
             self.advance();
@@ -322,21 +876,14 @@ impl Parser {
         } else {
             self.expect(TokenKind::Colon)?;
             self.consume_newlines();
-            let mut body = Vec::new();
-            while !self.check_keyword(Keyword::End) && !self.is_eof() {
-                if self.check(TokenKind::Newline) {
-                    self.consume_newlines();
-                    continue;
-                }
-                body.push(self.parse_rule_stmt()?);
-                self.consume_newlines();
-            }
-            self.expect_keyword(Keyword::End)?; // end trigger
+            let trigger_col = self.body_indent(trigger_col);
+            let body = self.parse_rule_body(trigger_col)?;
+            self.expect_block_end("trigger of rule", &name, trigger_start, trigger_col)?;
             body
         };
 
         self.consume_newlines();
-        self.expect_keyword(Keyword::End)?; // end rule
+        self.expect_block_end("rule", &name, start, rule_col)?;
 
         Ok(Rule {
             name,
@@ -345,10 +892,15 @@ impl Parser {
             scope_state: scope_state.map(str::to_string),
             trigger,
             body,
+            annotations,
+            span: self.span_from(start),
         })
     }
 
     fn parse_nd(&mut self) -> Result<NdBlock> {
+        let start = self.pos;
+        let annotations = self.parse_annotations()?;
+        let nd_col = self.current_col();
         self.expect_keyword(Keyword::Nd)?;
         let (name, params) = self.parse_named_param_list()?;
         self.expect(TokenKind::Colon)?;
@@ -357,70 +909,112 @@ impl Parser {
         let mut defines = Vec::new();
         let mut propose: Option<Call> = None;
         let mut constraints: Option<Vec<Call>> = None;
+        let nd_col = self.body_indent(nd_col);
 
-        while !self.check_keyword(Keyword::End) && !self.is_eof() {
+        while !self.at_block_end(nd_col) {
             if self.check(TokenKind::Newline) {
                 self.consume_newlines();
                 continue;
             }
-            if self.check_keyword(Keyword::Define) {
-                defines.push(self.parse_define()?);
-                self.consume_newlines();
-                continue;
-            }
-            if self.check_keyword(Keyword::Propose) {
-                if propose.is_some() {
-                    bail!("ND block '{}' has duplicate propose", name);
-                }
-                self.expect_keyword(Keyword::Propose)?;
-                propose = Some(self.parse_call()?);
-                self.consume_newlines();
-                continue;
-            }
-            if self.check_keyword(Keyword::Satisfy) {
-                if constraints.is_some() {
-                    bail!("ND block '{}' has duplicate satisfy", name);
+            let member_start = self.pos;
+            let result = self.parse_nd_member(&name, &mut defines, &mut propose, &mut constraints);
+            self.recover(result, member_start)?;
+            self.consume_newlines();
+        }
+        self.expect_block_end("nd", &name, start, nd_col)?;
+
+        let propose = match propose {
+            Some(call) => call,
+            None => {
+                let err = SyntaxError {
+                    code: "S006",
+                    message: format!("ND block '{}' is missing propose", name),
+                    span: self.span_from(start),
+                };
+                if !self.recovering {
+                    return Err(err.into());
                 }
-                self.expect_keyword(Keyword::Satisfy)?;
-                self.expect(TokenKind::LParen)?;
-                let mut out = Vec::new();
-                self.consume_newlines();
-                if !self.check(TokenKind::RParen) {
-                    loop {
-                        self.consume_newlines();
-                        out.push(self.parse_constraint_call()?);
-                        self.consume_newlines();
-                        if self.check(TokenKind::Comma) {
-                            self.advance();
-                            self.consume_newlines();
-                            continue;
-                        }
-                        break;
-                    }
+                self.record(err.into());
+                Call {
+                    name: String::new(),
+                    args: Vec::new(),
+                    span: Span::default(),
                 }
-                self.expect(TokenKind::RParen)?;
-                constraints = Some(out);
-                self.consume_newlines();
-                continue;
             }
-            bail!(
-                "Expected define/propose/satisfy in nd '{}', got {:?}",
-                name,
-                self.peek_kind()
-            );
-        }
-        self.expect_keyword(Keyword::End)?;
+        };
 
         Ok(NdBlock {
             name,
             params,
             defines,
-            propose: propose.ok_or_else(|| anyhow::anyhow!("ND block is missing propose"))?,
+            propose,
             constraints: constraints.unwrap_or_default(),
+            annotations,
+            span: self.span_from(start),
         })
     }
 
+    fn parse_nd_member(
+        &mut self,
+        name: &str,
+        defines: &mut Vec<SoftDefine>,
+        propose: &mut Option<Call>,
+        constraints: &mut Option<Vec<Call>>,
+    ) -> Result<()> {
+        if self.check_keyword(Keyword::Define) {
+            defines.push(self.parse_define()?);
+            return Ok(());
+        }
+        if self.check_keyword(Keyword::Propose) {
+            if propose.is_some() {
+                return Err(
+                    self.error("S006", format!("ND block '{}' has duplicate propose", name))
+                );
+            }
+            self.expect_keyword(Keyword::Propose)?;
+            *propose = Some(self.parse_call()?);
+            return Ok(());
+        }
+        if self.check_keyword(Keyword::Satisfy) {
+            if constraints.is_some() {
+                return Err(
+                    self.error("S006", format!("ND block '{}' has duplicate satisfy", name))
+                );
+            }
+            self.expect_keyword(Keyword::Satisfy)?;
+            self.expect(TokenKind::LParen)?;
+            let mut out = Vec::new();
+            self.consume_newlines();
+            if !self.check(TokenKind::RParen) {
+                loop {
+                    self.consume_newlines();
+                    out.push(self.parse_constraint_call()?);
+                    self.consume_newlines();
+                    if self.check(TokenKind::Comma) {
+                        self.advance();
+                        self.consume_newlines();
+                        continue;
+                    }
+                    break;
+                }
+            }
+            self.expect(TokenKind::RParen)?;
+            *constraints = Some(out);
+            return Ok(());
+        }
+        Err(self.error(
+            "S006",
+            format!(
+                "Expected define/propose/satisfy in nd '{}', found {}",
+                name,
+                self.describe_current()
+            ),
+        ))
+    }
+
     fn parse_define(&mut self) -> Result<SoftDefine> {
+        let start = self.pos;
+        let define_col = self.current_col();
         self.expect_keyword(Keyword::Define)?;
         let name = self.parse_qualified_ident()?;
         let mut params = Vec::new();
@@ -445,42 +1039,77 @@ impl Parser {
                 self.advance();
                 s
             }
-            _ => bail!("define '{}' expects a string template body", name),
+            _ => {
+                return Err(self.error(
+                    "S006",
+                    format!("define '{}' expects a string template body", name),
+                ))
+            }
         };
         self.consume_newlines();
-        self.expect_keyword(Keyword::End)?;
+        self.expect_block_end("define", &name, start, define_col)?;
         Ok(SoftDefine {
             name,
             params,
             template,
+            span: self.span_from(start),
         })
     }
 
     fn parse_constraint_call(&mut self) -> Result<Call> {
+        let start = self.pos;
         let magic = self.check(TokenKind::Question);
         if magic {
             self.advance();
             if let Some(TokenKind::String(s)) = self.peek_kind().cloned() {
+                let arg_start = self.pos;
                 self.advance();
                 return Ok(Call {
                     name: "?prompt".to_string(),
                     args: vec![CallArg {
                         name: None,
                         value: Expr::String(s),
+                        span: self.span_from(arg_start),
                     }],
+                    span: self.span_from(start),
                 });
             }
         }
         let mut call = self.parse_call()?;
         if magic {
             call.name = format!("?{}", call.name);
+            call.span = self.span_from(start);
         }
         Ok(call)
     }
 
     fn parse_assignment_or_expr(&mut self) -> Result<StateStmt> {
         let checkpoint = self.pos;
+        let start = self.pos;
         if let Some(TokenKind::Identifier(name)) = self.peek_kind().cloned() {
+            if self.check_n(1, TokenKind::Colon) {
+                self.advance();
+                self.advance();
+                let ty = self.parse_type_name()?;
+                let value = if self.check(TokenKind::Eq) {
+                    self.advance();
+                    self.parse_expr()?
+                } else if self.check(TokenKind::PlusEq) {
+                    return Err(self.error(
+                        "S006",
+                        format!("Typed declaration of '{}' must use '=', not '+='", name),
+                    ));
+                } else {
+                    Expr::Null
+                };
+                return Ok(StateStmt::Assign {
+                    target: name,
+                    op: AssignOp::Set,
+                    value,
+                    ty: Some(ty),
+                    span: self.span_from(start),
+                });
+            }
             if self.check_n(1, TokenKind::Eq) || self.check_n(1, TokenKind::PlusEq) {
                 self.advance();
                 let op = if self.check(TokenKind::Eq) {
@@ -495,6 +1124,8 @@ impl Parser {
                     target: name,
                     op,
                     value,
+                    ty: None,
+                    span: self.span_from(start),
                 });
             }
         }
@@ -503,7 +1134,32 @@ impl Parser {
         Ok(StateStmt::Expr(call))
     }
 
+    /// `int`, `float`, `string`, `bool`, `record`, `any`, `list` or `list<T>`.
+    fn parse_type_name(&mut self) -> Result<TypeName> {
+        let Some(TokenKind::Identifier(name)) = self.peek_kind().cloned() else {
+            return Err(self.unexpected("type name"));
+        };
+        let Some(ty) = TypeName::from_keyword(&name) else {
+            return Err(self.error(
+                "S006",
+                format!(
+                    "Unknown type '{}' (expected int, float, string, bool, record, any, list or list<T>)",
+                    name
+                ),
+            ));
+        };
+        self.advance();
+        if matches!(ty, TypeName::List(_)) && self.check(TokenKind::Lt) {
+            self.advance();
+            let item = self.parse_type_name()?;
+            self.expect(TokenKind::Gt)?;
+            return Ok(TypeName::List(Box::new(item)));
+        }
+        Ok(ty)
+    }
+
     fn parse_rule_assignment(&mut self) -> Result<RuleStmt> {
+        let start = self.pos;
         if let Some(TokenKind::Identifier(name)) = self.peek_kind().cloned() {
             if self.check_n(1, TokenKind::Eq) || self.check_n(1, TokenKind::PlusEq) {
                 self.advance();
@@ -519,17 +1175,28 @@ impl Parser {
                     target: name,
                     op,
                     value,
+                    span: self.span_from(start),
                 });
             }
         }
-        bail!("rule body only supports assignments or emit");
+        Err(self.error(
+            "S006",
+            format!(
+                "rule body only supports assignments or emit, found {}",
+                self.describe_current()
+            ),
+        ))
     }
 
     fn parse_rule_stmt(&mut self) -> Result<RuleStmt> {
         if self.check_keyword(Keyword::Emit) {
+            let start = self.pos;
             self.expect_keyword(Keyword::Emit)?;
             let event = self.parse_qualified_ident()?;
-            return Ok(RuleStmt::Emit { event });
+            return Ok(RuleStmt::Emit {
+                event,
+                span: self.span_from(start),
+            });
         }
         self.parse_rule_assignment()
     }
@@ -540,10 +1207,11 @@ impl Parser {
         body: Vec<RuleStmt>,
         scope_flow: Option<&str>,
         scope_state: Option<&str>,
+        start: usize,
     ) -> Rule {
         self.anon_rule_counter += 1;
-        let line = self.peek().map(|t| t.line).unwrap_or(0);
-        let mut name = format!("__on_{}_{}", line, self.anon_rule_counter);
+        // Numbered by position only, so reformatting a file keeps the generated names.
+        let mut name = format!("__on_{}", self.anon_rule_counter);
         if let Some(state) = scope_state {
             name.push('_');
             name.push_str(state);
@@ -555,6 +1223,8 @@ impl Parser {
             scope_state: scope_state.map(str::to_string),
             trigger: RuleTrigger::On(event),
             body,
+            annotations: Annotations::default(),
+            span: self.span_from(start),
         }
     }
 
@@ -577,10 +1247,10 @@ impl Parser {
     }
 
     fn parse_and_expr(&mut self) -> Result<Expr> {
-        let mut left = self.parse_comparison_expr()?;
+        let mut left = self.parse_not_expr()?;
         while self.check_keyword(Keyword::And) {
             self.expect_keyword(Keyword::And)?;
-            let right = self.parse_comparison_expr()?;
+            let right = self.parse_not_expr()?;
             left = Expr::Binary {
                 left: Box::new(left),
                 op: BinaryOp::And,
@@ -590,8 +1260,21 @@ impl Parser {
         Ok(left)
     }
 
+    /// `not` binds looser than comparisons: `not a == b` is `not (a == b)`.
+    fn parse_not_expr(&mut self) -> Result<Expr> {
+        if self.at_not_operator() {
+            self.advance();
+            let operand = self.parse_not_expr()?;
+            return Ok(Expr::Unary {
+                op: UnaryOp::Not,
+                operand: Box::new(operand),
+            });
+        }
+        self.parse_comparison_expr()
+    }
+
     fn parse_comparison_expr(&mut self) -> Result<Expr> {
-        let mut left = self.parse_primary()?;
+        let mut left = self.parse_additive_expr()?;
         if self.check(TokenKind::Gte)
             || self.check(TokenKind::Gt)
             || self.check(TokenKind::Lt)
@@ -614,7 +1297,28 @@ impl Parser {
                 self.expect(TokenKind::Neq)?;
                 BinaryOp::Neq
             };
-            let right = self.parse_primary()?;
+            let right = self.parse_additive_expr()?;
+            left = Expr::Binary {
+                left: Box::new(left),
+                op,
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_additive_expr(&mut self) -> Result<Expr> {
+        let mut left = self.parse_multiplicative_expr()?;
+        loop {
+            let op = if self.check(TokenKind::Plus) {
+                BinaryOp::Add
+            } else if self.check(TokenKind::Minus) {
+                BinaryOp::Sub
+            } else {
+                break;
+            };
+            self.advance();
+            let right = self.parse_multiplicative_expr()?;
             left = Expr::Binary {
                 left: Box::new(left),
                 op,
@@ -624,12 +1328,53 @@ impl Parser {
         Ok(left)
     }
 
+    fn parse_multiplicative_expr(&mut self) -> Result<Expr> {
+        let mut left = self.parse_unary_expr()?;
+        loop {
+            let op = if self.check(TokenKind::Star) {
+                BinaryOp::Mul
+            } else if self.check(TokenKind::Slash) {
+                BinaryOp::Div
+            } else if self.check(TokenKind::Percent) {
+                BinaryOp::Mod
+            } else {
+                break;
+            };
+            self.advance();
+            let right = self.parse_unary_expr()?;
+            left = Expr::Binary {
+                left: Box::new(left),
+                op,
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_unary_expr(&mut self) -> Result<Expr> {
+        if self.check(TokenKind::Minus) {
+            self.advance();
+            let operand = self.parse_unary_expr()?;
+            return Ok(Expr::Unary {
+                op: UnaryOp::Neg,
+                operand: Box::new(operand),
+            });
+        }
+        self.parse_primary()
+    }
+
     fn parse_primary(&mut self) -> Result<Expr> {
         match self.peek_kind().cloned() {
             Some(TokenKind::Number(n)) => {
                 self.advance();
                 Ok(Expr::Number(n))
             }
+            Some(TokenKind::Duration(ms)) => {
+                let start = self.pos;
+                self.advance();
+                self.require(Feature::Timers, start);
+                Ok(Expr::Duration(ms))
+            }
             Some(TokenKind::String(s)) => {
                 self.advance();
                 Ok(Expr::String(s))
@@ -638,6 +1383,29 @@ impl Parser {
                 self.advance();
                 Ok(Expr::Null)
             }
+            Some(TokenKind::Template(parts)) => {
+                self.advance();
+                let mut out = Vec::with_capacity(parts.len());
+                for part in parts {
+                    out.push(match part {
+                        TemplatePart::Text(text) => StringPart::Text(text),
+                        TemplatePart::Expr(tokens) => {
+                            StringPart::Expr(self.parse_interpolation(tokens)?)
+                        }
+                    });
+                }
+                Ok(Expr::Interpolated(out))
+            }
+            Some(TokenKind::Keyword(Keyword::True)) => {
+                self.advance();
+                Ok(Expr::Bool(true))
+            }
+            Some(TokenKind::Keyword(Keyword::False)) => {
+                self.advance();
+                Ok(Expr::Bool(false))
+            }
+            Some(TokenKind::LBracket) => self.parse_list(),
+            Some(TokenKind::LBrace) => self.parse_record(),
             Some(TokenKind::Identifier(_)) => {
                 let call_or_ident = self.parse_call_or_ident()?;
                 Ok(call_or_ident)
@@ -653,37 +1421,150 @@ impl Parser {
                 self.expect(TokenKind::RParen)?;
                 Ok(expr)
             }
-            _ => {
-                bail!("Unexpected token in expression: {:?}", self.peek_kind());
+            _ => Err(self.unexpected("expression")),
+        }
+    }
+
+    /// The expression of one `${...}` part. Its tokens carry absolute offsets, so errors
+    /// point into the string.
+    fn parse_interpolation(&self, tokens: Vec<Token>) -> Result<Expr> {
+        let mut inner = Parser::new(tokens, self.source.clone(), false);
+        if inner.is_eof() {
+            return Err(inner.error("S006", "Empty string interpolation '${}'"));
+        }
+        let expr = inner.parse_expr()?;
+        if !inner.is_eof() {
+            return Err(inner.unexpected("'}' to close the interpolation"));
+        }
+        Ok(expr)
+    }
+
+    /// `when <cond>` of a guarded transition. The condition ends at the first `>` outside
+    /// brackets, so a `>` comparison inside a guard must be parenthesized.
+    fn parse_transition_guard(&mut self) -> Result<Expr> {
+        self.expect_keyword(Keyword::When)?;
+        let mut depth = 0usize;
+        let mut end = self.pos;
+        while let Some(token) = self.tokens.get(end) {
+            match token.kind {
+                TokenKind::LParen | TokenKind::LBracket | TokenKind::LBrace => depth += 1,
+                TokenKind::RParen | TokenKind::RBracket | TokenKind::RBrace => {
+                    depth = depth.saturating_sub(1)
+                }
+                TokenKind::Gt if depth == 0 => break,
+                TokenKind::Newline | TokenKind::Eof => break,
+                _ => {}
             }
+            end += 1;
+        }
+        if end == self.pos {
+            return Err(self.error("S006", "Empty transition guard after 'when'"));
+        }
+        let mut tokens = self.tokens[self.pos..end].to_vec();
+        if let Some(stop) = self.tokens.get(end) {
+            tokens.push(Token {
+                kind: TokenKind::Eof,
+                ..stop.clone()
+            });
+        }
+        let mut inner = Parser::new(tokens, self.source.clone(), false);
+        let guard = inner.parse_expr()?;
+        if !inner.is_eof() {
+            return Err(inner.unexpected("transition '>' after the guard"));
         }
+        self.pos = end;
+        Ok(guard)
+    }
+
+    /// `[a, b]`. Items may span lines; a trailing comma is allowed.
+    fn parse_list(&mut self) -> Result<Expr> {
+        self.expect(TokenKind::LBracket)?;
+        let mut items = Vec::new();
+        self.consume_newlines();
+        while !self.check(TokenKind::RBracket) {
+            items.push(self.parse_expr()?);
+            self.consume_newlines();
+            if !self.check(TokenKind::Comma) {
+                break;
+            }
+            self.advance();
+            self.consume_newlines();
+        }
+        self.expect(TokenKind::RBracket)?;
+        Ok(Expr::List(items))
+    }
+
+    /// `{ key: value }`. Keys are identifiers, keywords or strings; fields may span lines.
+    fn parse_record(&mut self) -> Result<Expr> {
+        self.expect(TokenKind::LBrace)?;
+        let mut fields = Vec::new();
+        self.consume_newlines();
+        while !self.check(TokenKind::RBrace) {
+            let key = match self.peek_kind().cloned() {
+                Some(TokenKind::String(key)) => {
+                    self.advance();
+                    key
+                }
+                Some(TokenKind::Keyword(kw)) => {
+                    self.advance();
+                    keyword_text(kw)
+                }
+                _ => self.expect_ident()?,
+            };
+            self.expect(TokenKind::Colon)?;
+            let value = self.parse_expr()?;
+            fields.push(RecordField { key, value });
+            self.consume_newlines();
+            if !self.check(TokenKind::Comma) {
+                break;
+            }
+            self.advance();
+            self.consume_newlines();
+        }
+        self.expect(TokenKind::RBrace)?;
+        Ok(Expr::Record(fields))
     }
 
     fn parse_call_or_ident(&mut self) -> Result<Expr> {
+        let start = self.pos;
         let name = self.parse_qualified_ident()?;
         if self.check(TokenKind::LParen) {
             let args = self.parse_arg_list()?;
-            Ok(Expr::Call(Call { name, args }))
+            Ok(Expr::Call(Call {
+                name,
+                args,
+                span: self.span_from(start),
+            }))
         } else {
             Ok(Expr::Ident(name))
         }
     }
 
     fn parse_call(&mut self) -> Result<Call> {
+        let start = self.pos;
         let name = self.parse_qualified_ident()?;
         let args = if self.check(TokenKind::LParen) {
             self.parse_arg_list()?
         } else {
             Vec::new()
         };
-        Ok(Call { name, args })
+        Ok(Call {
+            name,
+            args,
+            span: self.span_from(start),
+        })
     }
 
     fn parse_command_call(&mut self) -> Result<Call> {
+        let start = self.pos;
         let name = self.parse_qualified_ident()?;
         if self.check(TokenKind::LParen) {
             let args = self.parse_arg_list()?;
-            return Ok(Call { name, args });
+            return Ok(Call {
+                name,
+                args,
+                span: self.span_from(start),
+            });
         }
         let mut args = Vec::new();
         while !self.check(TokenKind::Newline)
@@ -691,23 +1572,31 @@ impl Parser {
             && !self.check_keyword(Keyword::On)
             && !self.check_keyword(Keyword::Run)
             && !self.check_keyword(Keyword::Terminate)
+            && !self.check_keyword(Keyword::Return)
             && !self.check_keyword(Keyword::State)
             && !self.check_keyword(Keyword::Flow)
             && !self.check_keyword(Keyword::Rule)
             && !self.check_keyword(Keyword::Nd)
             && !self.check_keyword(Keyword::Start)
+            && !self.check_keyword(Keyword::Region)
             && !self.is_eof()
         {
+            let arg_start = self.pos;
             let expr = self.parse_expr()?;
             args.push(CallArg {
                 name: None,
                 value: expr,
+                span: self.span_from(arg_start),
             });
             if self.check(TokenKind::Comma) {
                 self.advance();
             }
         }
-        Ok(Call { name, args })
+        Ok(Call {
+            name,
+            args,
+            span: self.span_from(start),
+        })
     }
 
     fn parse_named_param_list(&mut self) -> Result<(String, Vec<String>)> {
@@ -759,6 +1648,7 @@ impl Parser {
     }
 
     fn parse_arg(&mut self) -> Result<CallArg> {
+        let start = self.pos;
         if let Some(TokenKind::Identifier(name)) = self.peek_kind().cloned() {
             if self.check_n(1, TokenKind::Colon) {
                 self.advance();
@@ -767,11 +1657,16 @@ impl Parser {
                 return Ok(CallArg {
                     name: Some(name),
                     value,
+                    span: self.span_from(start),
                 });
             }
         }
         let value = self.parse_expr()?;
-        Ok(CallArg { name: None, value })
+        Ok(CallArg {
+            name: None,
+            value,
+            span: self.span_from(start),
+        })
     }
 
     fn expect_ident(&mut self) -> Result<String> {
@@ -780,7 +1675,10 @@ impl Parser {
                 self.advance();
                 Ok(name)
             }
-            _ => bail!("Expected identifier"),
+            _ => Err(self.error(
+                "S005",
+                format!("Expected identifier, found {}", self.describe_current()),
+            )),
         }
     }
 
@@ -790,7 +1688,7 @@ impl Parser {
                 self.advance();
                 Ok(())
             }
-            _ => bail!("Expected keyword {:?}", kw),
+            _ => Err(self.unexpected(&format!("'{}'", keyword_text(kw)))),
         }
     }
 
@@ -799,8 +1697,118 @@ impl Parser {
             self.advance();
             Ok(())
         } else {
-            bail!("Expected {:?}", kind)
+            Err(self.unexpected(&describe_kind(&kind)))
+        }
+    }
+
+    /// Whether the current token starts item `kw`, possibly behind `@attribute` lines.
+    fn at_item(&self, kw: Keyword) -> bool {
+        let mut i = self.pos;
+        while matches!(self.tokens.get(i).map(|t| &t.kind), Some(TokenKind::At)) {
+            // `@`, the name, then an optional argument list up to the end of the line.
+            i += 2;
+            while !matches!(
+                self.tokens.get(i).map(|t| &t.kind),
+                None | Some(TokenKind::Newline | TokenKind::Eof)
+            ) {
+                i += 1;
+            }
+            while matches!(
+                self.tokens.get(i).map(|t| &t.kind),
+                Some(TokenKind::Newline)
+            ) {
+                i += 1;
+            }
+        }
+        matches!(self.tokens.get(i).map(|t| &t.kind), Some(TokenKind::Keyword(k)) if *k == kw)
+    }
+
+    /// The `##` doc comment directly above the current line and the `@name(...)` lines
+    /// that precede a flow, state, rule or nd block.
+    fn parse_annotations(&mut self) -> Result<Annotations> {
+        let mut doc = Vec::new();
+        let mut line = self.peek().map_or(0, |t| t.line);
+        while line > 1 {
+            line -= 1;
+            match self.doc_lines.get(&line) {
+                Some(text) => doc.push(text.clone()),
+                None => break,
+            }
+        }
+        doc.reverse();
+        let mut attrs = Vec::new();
+        while self.check(TokenKind::At) {
+            let start = self.pos;
+            self.advance();
+            let name = self.expect_ident()?;
+            let args = if self.check(TokenKind::LParen) {
+                self.parse_arg_list()?
+            } else {
+                Vec::new()
+            };
+            attrs.push(Attribute {
+                name,
+                args,
+                span: self.span_from(start),
+            });
+            self.require(Feature::ItemAttributes, start);
+            if !self.check(TokenKind::Newline) {
+                return Err(self.unexpected("end of line after attribute"));
+            }
+            self.consume_newlines();
+        }
+        Ok(Annotations {
+            doc: (!doc.is_empty()).then(|| doc.join("\n")),
+            attrs,
+        })
+    }
+
+    /// Reports a construct that the declared language version does not have yet. Parsing
+    /// goes on, so the rest of the file is still checked.
+    fn require(&mut self, feature: Feature, start: usize) {
+        if self.language.has(feature) {
+            return;
         }
+        let span = self.span_from(start);
+        self.diagnostics.push(
+            Diagnostic::new(
+                "S009",
+                format!(
+                    "{} require language {} or newer; this file declares language={}",
+                    feature.description(),
+                    feature.since(),
+                    self.language
+                ),
+            )
+            .with_span(&span),
+        );
+    }
+
+    fn misplaced_attribute(&self) -> anyhow::Error {
+        self.error(
+            "S008",
+            "Attributes must be followed by a flow, state, rule or nd block",
+        )
+    }
+
+    /// `after` and `every` are contextual: they start a timer only when `(` follows.
+    fn at_timer(&self, word: &str) -> bool {
+        matches!(self.peek_kind(), Some(TokenKind::Identifier(name)) if name == word)
+            && self.check_n(1, TokenKind::LParen)
+    }
+
+    /// `after(<duration>)` or `every(<duration>)`; returns the delay in milliseconds.
+    fn parse_timer_delay(&mut self) -> Result<u64> {
+        let start = self.pos;
+        self.advance();
+        self.expect(TokenKind::LParen)?;
+        let Some(TokenKind::Duration(ms)) = self.peek_kind().cloned() else {
+            return Err(self.unexpected("duration such as 500ms or 2s"));
+        };
+        self.advance();
+        self.expect(TokenKind::RParen)?;
+        self.require(Feature::Timers, start);
+        Ok(ms)
     }
 
     fn expect_transition(&mut self) -> Result<()> {
@@ -808,7 +1816,226 @@ impl Parser {
             self.advance();
             return Ok(());
         }
-        bail!("Expected transition '>'");
+        Err(self.unexpected("transition '>'"))
+    }
+
+    /// Consumes the `end` of the block whose header is token `start` in column `block_col`.
+    ///
+    /// When recovering, a missing `end` is reported as S004 instead of failing, provided
+    /// the parser already stands on an outer boundary (an outdented line or end of file).
+    fn expect_block_end(
+        &mut self,
+        kind: &str,
+        name: &str,
+        start: usize,
+        block_col: usize,
+    ) -> Result<()> {
+        if self.check_keyword(Keyword::End) && !self.outdented(block_col) {
+            self.advance();
+            return Ok(());
+        }
+        if !self.recovering || !(self.is_eof() || self.outdented(block_col)) {
+            return Err(self.error(
+                "S004",
+                format!(
+                    "Expected 'end' to close {}, found {}",
+                    block_label(kind, name),
+                    self.describe_current()
+                ),
+            ));
+        }
+        let span = self.line_span(start);
+        self.diagnostics.push(
+            Diagnostic::new(
+                "S004",
+                format!("Missing 'end' for {}", block_label(kind, name)),
+            )
+            .with_span(&span),
+        );
+        Ok(())
+    }
+
+    /// True at the end of the block opened in `block_col`: its `end`, end of file or, when
+    /// recovering, a line indented no deeper than the block header.
+    fn at_block_end(&self, block_col: usize) -> bool {
+        self.check_keyword(Keyword::End) || self.is_eof() || self.outdented(block_col)
+    }
+
+    /// A line that can no longer belong to the block opened in `block_col`: it starts left of
+    /// the header, or in the header's column with anything but the block's own `end`.
+    fn outdented(&self, block_col: usize) -> bool {
+        if !self.recovering || !self.starts_line() {
+            return false;
+        }
+        let col = self.current_col();
+        col < block_col || (col == block_col && !self.check_keyword(Keyword::End))
+    }
+
+    /// The column that bounds a block whose header sits in `header_col`, checked at the
+    /// first body line. Blocks whose body is not indented get no indentation heuristics (0).
+    fn body_indent(&self, header_col: usize) -> usize {
+        if self.current_col() > header_col || self.check_keyword(Keyword::End) {
+            header_col
+        } else {
+            0
+        }
+    }
+
+    fn starts_line(&self) -> bool {
+        match (self.pos.checked_sub(1), self.peek()) {
+            (Some(prev), Some(tok)) => self.tokens[prev].line != tok.line,
+            _ => true,
+        }
+    }
+
+    fn current_col(&self) -> usize {
+        self.peek().map_or(1, |t| t.col)
+    }
+
+    /// When recovering, records the error of `result` and skips the construct that began
+    /// at token `start`; otherwise hands the error back to the caller.
+    fn recover(&mut self, result: Result<()>, start: usize) -> Result<()> {
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) if self.recovering => {
+                self.record(err);
+                self.synchronize(start);
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn record(&mut self, err: anyhow::Error) {
+        let diagnostic = match err.downcast::<SyntaxError>() {
+            Ok(err) => Diagnostic::new(err.code, err.message).with_span(&err.span),
+            Err(err) => Diagnostic::new("S006", err.to_string()).with_span(&self.current_span()),
+        };
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Skips the construct that began at token `start`: the rest of its logical line and,
+    /// if that line opened a block (`:` without a matching `end`), every deeper indented
+    /// line up to and including the `end` in the header's column.
+    fn synchronize(&mut self, start: usize) {
+        let block_col = self.tokens[start].col;
+        self.pos = start;
+        let mut depth = 0isize;
+        let mut parens = 0usize;
+        while !self.is_eof() {
+            match self.peek_kind() {
+                Some(TokenKind::LParen) => parens += 1,
+                Some(TokenKind::RParen) => parens = parens.saturating_sub(1),
+                Some(TokenKind::Colon) if parens == 0 => depth += 1,
+                Some(TokenKind::Keyword(Keyword::End)) => {
+                    if depth == 0 && self.pos > start {
+                        // Closes an enclosing block; leave it to the caller.
+                        break;
+                    }
+                    depth -= 1;
+                }
+                // Calls never span lines, so an unclosed `(` ends with its line.
+                Some(TokenKind::Newline) => {
+                    self.advance();
+                    break;
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+
+        // A header whose `:` went missing still owns the deeper indented lines below it.
+        if depth > 0 || self.next_line_col().is_some_and(|col| col > block_col) {
+            while !self.is_eof() {
+                if self.starts_line() && !self.check(TokenKind::Newline) {
+                    let col = self.current_col();
+                    if col == block_col && self.check_keyword(Keyword::End) {
+                        self.advance();
+                        break;
+                    }
+                    if col <= block_col {
+                        break;
+                    }
+                }
+                self.advance();
+            }
+        }
+
+        if self.pos == start {
+            self.advance();
+        }
+    }
+
+    fn next_line_col(&self) -> Option<usize> {
+        self.tokens[self.pos..]
+            .iter()
+            .find(|t| t.kind != TokenKind::Newline)
+            .filter(|t| t.kind != TokenKind::Eof)
+            .map(|t| t.col)
+    }
+
+    fn skip_line(&mut self) {
+        let start = self.pos;
+        while !self.check(TokenKind::Newline) && !self.is_eof() {
+            self.advance();
+        }
+        if self.pos == start {
+            self.advance();
+        }
+    }
+
+    fn error(&self, code: &'static str, message: impl Into<String>) -> anyhow::Error {
+        SyntaxError {
+            code,
+            message: message.into(),
+            span: self.current_span(),
+        }
+        .into()
+    }
+
+    fn unexpected(&self, expected: &str) -> anyhow::Error {
+        self.error(
+            "S006",
+            format!("Expected {}, found {}", expected, self.describe_current()),
+        )
+    }
+
+    fn describe_current(&self) -> String {
+        self.peek_kind()
+            .map_or_else(|| "end of file".to_string(), describe_kind)
+    }
+
+    fn current_span(&self) -> Span {
+        let tok = &self.tokens[self.pos.min(self.tokens.len() - 1)];
+        Span::new(self.source.clone(), tok.start, tok.end, tok.line, tok.col)
+    }
+
+    /// Span of the header line of the construct starting at token `start`.
+    fn line_span(&self, start: usize) -> Span {
+        let first = &self.tokens[start.min(self.tokens.len() - 1)];
+        let end = self.tokens[start..]
+            .iter()
+            .take_while(|t| t.line == first.line && t.kind != TokenKind::Newline)
+            .last()
+            .map_or(first.end, |t| t.end);
+        Span::new(self.source.clone(), first.start, end, first.line, first.col)
+    }
+
+    /// Span from the token at index `start` up to the last consumed token.
+    fn span_from(&self, start: usize) -> Span {
+        let first = &self.tokens[start.min(self.tokens.len() - 1)];
+        let end = if self.pos > start {
+            self.tokens[self.pos - 1].end
+        } else {
+            first.end
+        };
+        Span::new(
+            self.source.clone(),
+            first.start,
+            end.max(first.start),
+            first.line,
+            first.col,
+        )
     }
 
     fn check(&self, kind: TokenKind) -> bool {
@@ -819,6 +2046,34 @@ impl Parser {
         matches!(self.peek_kind(), Some(TokenKind::Keyword(k)) if *k == kw)
     }
 
+    /// `not` is contextual so existing names like `not.valid` keep parsing: it is the
+    /// operator only when an operand follows.
+    fn at_not_operator(&self) -> bool {
+        matches!(self.peek_kind(), Some(TokenKind::Identifier(name)) if name == "not")
+            && matches!(
+                self.tokens.get(self.pos + 1).map(|t| &t.kind),
+                Some(
+                    TokenKind::Identifier(_)
+                        | TokenKind::Number(_)
+                        | TokenKind::Duration(_)
+                        | TokenKind::String(_)
+                        | TokenKind::Template(_)
+                        | TokenKind::Keyword(Keyword::Null | Keyword::True | Keyword::False)
+                        | TokenKind::Question
+                        | TokenKind::LBracket
+                        | TokenKind::LBrace
+                        | TokenKind::LParen
+                        | TokenKind::Minus
+                )
+            )
+    }
+
+    /// `exports` is contextual as well: a module-level `exports(` starts an export list.
+    fn at_exports(&self) -> bool {
+        matches!(self.peek_kind(), Some(TokenKind::Identifier(name)) if name == "exports")
+            && self.check_n(1, TokenKind::LParen)
+    }
+
     fn check_n(&self, n: usize, kind: TokenKind) -> bool {
         self.tokens.get(self.pos + n).map(|t| t.kind.clone()) == Some(kind)
     }
@@ -844,7 +2099,76 @@ impl Parser {
     }
 
     fn is_eof(&self) -> bool {
-        matches!(self.peek_kind(), Some(TokenKind::Eof))
+        matches!(self.peek_kind(), Some(TokenKind::Eof) | None)
+    }
+}
+
+fn block_label(kind: &str, name: &str) -> String {
+    if name.is_empty() {
+        kind.to_string()
+    } else {
+        format!("{} '{}'", kind, name)
+    }
+}
+
+/// Text of the `##` comments that stand on a line of their own, by line number.
+fn doc_comment_lines(source: &str) -> HashMap<usize, String> {
+    let lines: Vec<&str> = source.lines().collect();
+    lex_comments(source)
+        .into_iter()
+        .filter(|comment| comment.text.starts_with("##"))
+        .filter(|comment| {
+            lines
+                .get(comment.line - 1)
+                .is_some_and(|line| line.trim_start().starts_with("##"))
+        })
+        .map(|comment| {
+            let text = comment.text["##".len()..].trim_end();
+            let text = text.strip_prefix(' ').unwrap_or(text);
+            (comment.line, text.to_string())
+        })
+        .collect()
+}
+
+fn keyword_text(kw: Keyword) -> String {
+    format!("{:?}", kw).to_lowercase()
+}
+
+fn describe_kind(kind: &TokenKind) -> String {
+    match kind {
+        TokenKind::Identifier(name) => format!("identifier '{}'", name),
+        TokenKind::Number(n) => format!("number {}", n),
+        TokenKind::Duration(ms) => format!("duration {}", duration_text(*ms)),
+        TokenKind::String(s) => format!("string \"{}\"", s),
+        TokenKind::Template(_) => "interpolated string".to_string(),
+        TokenKind::Keyword(kw) => format!("'{}'", keyword_text(*kw)),
+        TokenKind::At => "'@'".to_string(),
+        TokenKind::Gt => "'>'".to_string(),
+        TokenKind::Lt => "'<'".to_string(),
+        TokenKind::PlusEq => "'+='".to_string(),
+        TokenKind::Plus => "'+'".to_string(),
+        TokenKind::Minus => "'-'".to_string(),
+        TokenKind::Arrow => "'->'".to_string(),
+        TokenKind::Star => "'*'".to_string(),
+        TokenKind::Slash => "'/'".to_string(),
+        TokenKind::Percent => "'%'".to_string(),
+        TokenKind::Eq => "'='".to_string(),
+        TokenKind::EqEq => "'=='".to_string(),
+        TokenKind::Neq => "'!='".to_string(),
+        TokenKind::Gte => "'>='".to_string(),
+        TokenKind::LParen => "'('".to_string(),
+        TokenKind::RParen => "')'".to_string(),
+        TokenKind::LBracket => "'['".to_string(),
+        TokenKind::RBracket => "']'".to_string(),
+        TokenKind::LBrace => "'{'".to_string(),
+        TokenKind::RBrace => "'}'".to_string(),
+        TokenKind::Comma => "','".to_string(),
+        TokenKind::Colon => "':'".to_string(),
+        TokenKind::DoubleColon => "'::'".to_string(),
+        TokenKind::Question => "'?'".to_string(),
+        TokenKind::Dot => "'.'".to_string(),
+        TokenKind::Newline => "end of line".to_string(),
+        TokenKind::Eof => "end of file".to_string(),
     }
 }
 
@@ -0,0 +1,447 @@
+//! Source refactorings. [`rename_symbol`] renames a flow, state, rule, global state variable,
+//! nd block or soft define of a module together with its references in the same file.
+//!
+//! Renaming works on the token stream, so strings, comments and other kinds of names that
+//! happen to be spelled the same stay untouched: a state rename only changes `state(...)`
+//! headers and `> Target` transitions inside its flow, a variable rename skips event names,
+//! call names and named arguments. Qualified references (`App.Main.Idle`,
+//! `App.global.score`) are renamed for every kind.
+
+use std::ops::Range;
+
+use anyhow::{bail, Result};
+
+use crate::ast::{Item, Module, Rule, Span, StateStmt};
+use crate::lexer::{lex, Keyword, TemplatePart, Token, TokenKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Flow,
+    State,
+    Rule,
+    Global,
+    Nd,
+    Define,
+}
+
+impl SymbolKind {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "flow" => Some(Self::Flow),
+            "state" => Some(Self::State),
+            "rule" => Some(Self::Rule),
+            "global" => Some(Self::Global),
+            "nd" => Some(Self::Nd),
+            "define" => Some(Self::Define),
+            _ => None,
+        }
+    }
+
+    /// Also the kind part of the node keys in [`crate::ir::ids`].
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Flow => "flow",
+            Self::State => "state",
+            Self::Rule => "rule",
+            Self::Global => "global",
+            Self::Nd => "nd",
+            Self::Define => "define",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Rename {
+    pub kind: SymbolKind,
+    /// The module source with the rename applied.
+    pub text: String,
+    pub edits: usize,
+    /// Node keys before and after the rename (see [`crate::ir::ids`]).
+    pub old_key: String,
+    pub new_key: String,
+}
+
+/// What is being renamed.
+struct Target {
+    kind: SymbolKind,
+    /// The name as declared, split at dots (only defines have more than one segment).
+    old: Vec<String>,
+    /// The path after the module name in qualified references: `Main.Idle` for a state,
+    /// `global.score` for a variable.
+    qualified: Vec<String>,
+    /// Where the declaration is; for states also where transitions to it are.
+    scope: Range<usize>,
+    old_key: String,
+    new_key: String,
+}
+
+/// Renames the symbol `from` of `module` (parsed from `source`) to `to`. States are named
+/// `Flow.State`, rules inside states `Flow.State.rule`. `kind` picks one symbol when several
+/// kinds share the name.
+pub fn rename_symbol(
+    module: &Module,
+    source: &str,
+    from: &str,
+    to: &str,
+    kind: Option<SymbolKind>,
+) -> Result<Rename> {
+    let mut targets: Vec<Target> = find_targets(module, from, to)
+        .into_iter()
+        .filter(|target| kind.is_none_or(|kind| kind == target.kind))
+        .collect();
+    let target = match targets.len() {
+        0 => bail!(
+            "No flow, state, rule, global, nd block or define named '{}'",
+            from
+        ),
+        1 => targets.remove(0),
+        _ => bail!(
+            "'{}' names more than one symbol ({}); pick one with --kind",
+            from,
+            targets
+                .iter()
+                .map(|t| t.kind.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let segments: Vec<&str> = to.split('.').collect();
+    if segments.len() > 1 && target.kind != SymbolKind::Define
+        || !segments.iter().all(|segment| is_plain_ident(segment))
+    {
+        bail!("'{}' is not a valid {} name", to, target.kind.as_str());
+    }
+    if taken(module, &target, to) {
+        bail!("A {} named '{}' already exists", target.kind.as_str(), to);
+    }
+
+    let mut tokens = Vec::new();
+    flatten(lex(source)?, &mut tokens);
+    let edits = token_edits(&tokens, &module.name, &target);
+    let mut text = source.to_string();
+    for range in edits.iter().rev() {
+        text.replace_range(range.clone(), to);
+    }
+    Ok(Rename {
+        kind: target.kind,
+        text,
+        edits: edits.len(),
+        old_key: target.old_key,
+        new_key: target.new_key,
+    })
+}
+
+fn find_targets(module: &Module, from: &str, to: &str) -> Vec<Target> {
+    let whole = Span {
+        start: 0,
+        end: usize::MAX,
+        ..Span::default()
+    };
+    let target = |kind: SymbolKind, qualified: Vec<String>, scope: &Span, key: &str| {
+        let new_key = match key.rsplit_once('.') {
+            Some((parent, _)) if kind != SymbolKind::Define => format!("{}.{}", parent, to),
+            _ => to.to_string(),
+        };
+        let old = match kind {
+            SymbolKind::Define => from.split('.').map(str::to_string).collect(),
+            _ => vec![qualified.last().cloned().unwrap_or_default()],
+        };
+        Target {
+            kind,
+            old,
+            qualified,
+            scope: scope.start..scope.end,
+            old_key: format!("{}:{}", kind.as_str(), key),
+            new_key: format!("{}:{}", kind.as_str(), new_key),
+        }
+    };
+    let mut out = Vec::new();
+    for item in &module.items {
+        match item {
+            Item::Flow(flow) => {
+                if flow.name == from {
+                    out.push(target(
+                        SymbolKind::Flow,
+                        vec![flow.name.clone()],
+                        &whole,
+                        from,
+                    ));
+                }
+                for state in flow.all_states() {
+                    let Some(state_name) = &state.name else {
+                        continue;
+                    };
+                    let state_path = format!("{}.{}", flow.name, state_name);
+                    if state_path == from {
+                        out.push(target(
+                            SymbolKind::State,
+                            vec![flow.name.clone(), state_name.clone()],
+                            &flow.span,
+                            from,
+                        ));
+                    }
+                    for stmt in &state.statements {
+                        if let StateStmt::Rule(rule) = stmt {
+                            if format!("{}.{}", state_path, rule.name) == from {
+                                out.push(target(
+                                    SymbolKind::Rule,
+                                    vec![flow.name.clone(), state_name.clone(), rule.name.clone()],
+                                    &rule.span,
+                                    from,
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+            Item::Rule(rule) if rule.name == from => {
+                out.push(target(
+                    SymbolKind::Rule,
+                    vec![rule.name.clone()],
+                    &rule.span,
+                    from,
+                ));
+            }
+            Item::GlobalState(state) => {
+                let declared = state
+                    .statements
+                    .iter()
+                    .any(|stmt| matches!(stmt, StateStmt::Assign { target, .. } if target == from));
+                if declared && !out.iter().any(|t: &Target| t.kind == SymbolKind::Global) {
+                    out.push(target(
+                        SymbolKind::Global,
+                        vec!["global".to_string(), from.to_string()],
+                        &whole,
+                        from,
+                    ));
+                }
+            }
+            Item::Nd(nd) if nd.name == from => {
+                out.push(target(
+                    SymbolKind::Nd,
+                    vec![nd.name.clone()],
+                    &nd.span,
+                    from,
+                ));
+            }
+            Item::Define(define) if define.name == from => {
+                out.push(target(
+                    SymbolKind::Define,
+                    from.split('.').map(str::to_string).collect(),
+                    &whole,
+                    from,
+                ));
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Whether `to` already names a symbol of the same kind in the same scope.
+fn taken(module: &Module, target: &Target, to: &str) -> bool {
+    let flows = module.items.iter().filter_map(|item| match item {
+        Item::Flow(flow) => Some(flow),
+        _ => None,
+    });
+    let named = |rule: &Rule| rule.name == to;
+    match target.kind {
+        SymbolKind::Flow => flows.clone().any(|flow| flow.name == to),
+        SymbolKind::State => flows
+            .filter(|flow| flow.name == target.qualified[0])
+            .flat_map(|flow| flow.all_states())
+            .any(|state| state.name.as_deref() == Some(to)),
+        SymbolKind::Rule if target.qualified.len() == 3 => flows
+            .filter(|flow| flow.name == target.qualified[0])
+            .flat_map(|flow| flow.all_states())
+            .filter(|state| state.name.as_deref() == Some(target.qualified[1].as_str()))
+            .flat_map(|state| &state.statements)
+            .any(|stmt| matches!(stmt, StateStmt::Rule(rule) if named(rule))),
+        SymbolKind::Rule => module
+            .items
+            .iter()
+            .any(|item| matches!(item, Item::Rule(rule) if named(rule))),
+        SymbolKind::Global => module.items.iter().any(|item| match item {
+            Item::GlobalState(state) => state
+                .statements
+                .iter()
+                .any(|stmt| matches!(stmt, StateStmt::Assign { target, .. } if target == to)),
+            _ => false,
+        }),
+        SymbolKind::Nd => module
+            .items
+            .iter()
+            .any(|item| matches!(item, Item::Nd(nd) if nd.name == to)),
+        SymbolKind::Define => module
+            .items
+            .iter()
+            .any(|item| matches!(item, Item::Define(define) if define.name == to)),
+    }
+}
+
+/// Replaces template strings by the tokens of their `${...}` parts, which carry absolute
+/// offsets.
+fn flatten(tokens: Vec<Token>, out: &mut Vec<Token>) {
+    for token in tokens {
+        if let TokenKind::Template(parts) = token.kind {
+            for part in parts {
+                if let TemplatePart::Expr(inner) = part {
+                    flatten(inner, out);
+                }
+            }
+        } else {
+            out.push(token);
+        }
+    }
+}
+
+/// The surroundings of one unqualified occurrence of a name.
+struct Occurrence<'a> {
+    offset: usize,
+    prev: Option<&'a TokenKind>,
+    /// The token before `prev`.
+    prev2: Option<&'a TokenKind>,
+    next: Option<&'a TokenKind>,
+    /// Open brackets around the name.
+    depth: usize,
+    in_exports: bool,
+}
+
+impl Occurrence<'_> {
+    fn after(&self, keyword: Keyword) -> bool {
+        matches!(self.prev, Some(TokenKind::Keyword(k)) if *k == keyword)
+    }
+
+    /// Whether the name is the first argument of `keyword(...)`.
+    fn declared_by(&self, keyword: Keyword) -> bool {
+        matches!(self.prev, Some(TokenKind::LParen))
+            && matches!(self.prev2, Some(TokenKind::Keyword(k)) if *k == keyword)
+    }
+
+    /// Whether this occurrence refers to `target`.
+    fn refers_to(&self, target: &Target) -> bool {
+        let in_scope = target.scope.contains(&self.offset);
+        let ends_line = matches!(self.next, None | Some(TokenKind::Newline | TokenKind::Eof));
+        let transition_target = matches!(self.prev, Some(TokenKind::Gt)) && ends_line;
+        match target.kind {
+            SymbolKind::Flow => {
+                self.declared_by(Keyword::Flow) || self.after(Keyword::Run) || self.in_exports
+            }
+            SymbolKind::State => {
+                in_scope && (self.declared_by(Keyword::State) || transition_target)
+            }
+            SymbolKind::Rule => {
+                (in_scope && self.declared_by(Keyword::Rule))
+                    || (self.in_exports && target.qualified.len() == 1)
+            }
+            SymbolKind::Nd => (in_scope && self.declared_by(Keyword::Nd)) || self.in_exports,
+            SymbolKind::Define => {
+                self.after(Keyword::Define)
+                    || matches!(self.next, Some(TokenKind::LParen))
+                    || self.in_exports
+            }
+            SymbolKind::Global => {
+                let other_kind = [
+                    Keyword::Flow,
+                    Keyword::State,
+                    Keyword::Rule,
+                    Keyword::Nd,
+                    Keyword::Region,
+                    Keyword::Use,
+                    Keyword::Import,
+                ]
+                .into_iter()
+                .any(|keyword| self.declared_by(keyword))
+                    || [Keyword::Run, Keyword::On, Keyword::Emit, Keyword::Define]
+                        .into_iter()
+                        .any(|keyword| self.after(keyword));
+                let named_arg = matches!(self.next, Some(TokenKind::Colon)) && self.depth > 0;
+                let call = matches!(self.next, Some(TokenKind::LParen));
+                let marker = matches!(self.prev, Some(TokenKind::At | TokenKind::Question));
+                !(other_kind || named_arg || call || marker || transition_target)
+            }
+        }
+    }
+}
+
+/// Byte ranges to replace, in source order.
+fn token_edits(tokens: &[Token], module_name: &str, target: &Target) -> Vec<Range<usize>> {
+    let module: Vec<&str> = module_name.split('.').collect();
+    let old: Vec<&str> = target.old.iter().map(String::as_str).collect();
+    let kind_at = |i: Option<usize>| i.and_then(|i| tokens.get(i)).map(|t| &t.kind);
+    let ident_at = |i: usize| match kind_at(Some(i)) {
+        Some(TokenKind::Identifier(name)) => Some(name.as_str()),
+        _ => None,
+    };
+
+    let mut edits = Vec::new();
+    let mut depth = 0usize;
+    let mut in_exports = false;
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i].kind {
+            TokenKind::LParen | TokenKind::LBracket | TokenKind::LBrace => depth += 1,
+            TokenKind::RParen | TokenKind::RBracket | TokenKind::RBrace => {
+                depth = depth.saturating_sub(1);
+                in_exports = false;
+            }
+            _ => {}
+        }
+        let Some(first) = ident_at(i) else {
+            i += 1;
+            continue;
+        };
+        // A dotted name is a chain of identifiers joined by `.` tokens.
+        let mut chain = vec![i];
+        let mut segments = vec![first];
+        let mut last = i;
+        while let (Some(TokenKind::Dot), Some(segment)) =
+            (kind_at(Some(last + 1)), ident_at(last + 2))
+        {
+            last += 2;
+            chain.push(last);
+            segments.push(segment);
+        }
+        if segments == ["exports"] && matches!(kind_at(Some(last + 1)), Some(TokenKind::LParen)) {
+            in_exports = true;
+        }
+
+        let qualified_len = module.len() + target.qualified.len();
+        let replace = if segments.len() >= qualified_len
+            && segments[..module.len()] == module[..]
+            && segments[module.len()..qualified_len]
+                .iter()
+                .eq(target.qualified.iter())
+        {
+            Some((qualified_len - old.len(), qualified_len - 1))
+        } else if segments == old {
+            let occurrence = Occurrence {
+                offset: tokens[i].start,
+                prev: kind_at(i.checked_sub(1)),
+                prev2: kind_at(i.checked_sub(2)),
+                next: kind_at(Some(last + 1)),
+                depth,
+                in_exports,
+            };
+            occurrence.refers_to(target).then(|| (0, old.len() - 1))
+        } else {
+            None
+        };
+        if let Some((from, to)) = replace {
+            edits.push(tokens[chain[from]].start..tokens[chain[to]].end);
+        }
+        i = last + 1;
+    }
+    edits
+}
+
+fn is_plain_ident(name: &str) -> bool {
+    let mut tokens = match lex(name) {
+        Ok(tokens) => tokens.into_iter(),
+        Err(_) => return false,
+    };
+    matches!(
+        (tokens.next().map(|t| t.kind), tokens.next().map(|t| t.kind)),
+        (Some(TokenKind::Identifier(_)), None | Some(TokenKind::Eof))
+    )
+}
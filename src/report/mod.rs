@@ -1,4 +1,5 @@
-use crate::ast::{Call, Expr};
+use crate::ast::{Annotations, Call, Expr, StateBlock};
+use crate::formatter::{attribute_text, expr_text};
 use crate::ir::IrModule;
 
 pub fn generate_report(ir: &IrModule) -> String {
@@ -84,6 +85,31 @@ pub fn generate_report(ir: &IrModule) -> String {
         out.push('\n');
     }
 
+    let annotated = annotated_items(ir);
+    if !annotated.is_empty() {
+        out.push_str("Annotations\n");
+        out.push_str("-----------\n");
+        for (item, annotations) in annotated {
+            let attrs = annotations
+                .attrs
+                .iter()
+                .map(attribute_text)
+                .collect::<Vec<_>>();
+            if attrs.is_empty() {
+                out.push_str(&format!("{}\n", item));
+            } else {
+                out.push_str(&format!("{}: {}\n", item, attrs.join(" ")));
+            }
+            if let Some(doc) = &annotations.doc {
+                for line in doc.lines() {
+                    out.push_str(format!("  {}", line).trim_end());
+                    out.push('\n');
+                }
+            }
+        }
+        out.push('\n');
+    }
+
     let overall_nd = if block_scores.is_empty() {
         0.0
     } else {
@@ -105,6 +131,40 @@ pub fn generate_report(ir: &IrModule) -> String {
     out
 }
 
+/// Every flow, state, rule and nd block carrying doc comments or attributes, in source order.
+fn annotated_items(ir: &IrModule) -> Vec<(String, &Annotations)> {
+    let mut out = Vec::new();
+    for flow in &ir.flows {
+        out.push((format!("flow {}", flow.name), &flow.annotations));
+        for state in &flow.states {
+            annotated_states(&flow.name, state, &mut out);
+        }
+    }
+    for rule in &ir.rules {
+        out.push((format!("rule {}", rule.name), &rule.annotations));
+    }
+    for nd in &ir.nd_blocks {
+        out.push((format!("nd {}", nd.name), &nd.annotations));
+    }
+    out.retain(|(_, annotations)| !annotations.is_empty());
+    out
+}
+
+fn annotated_states<'a>(
+    path: &str,
+    state: &'a StateBlock,
+    out: &mut Vec<(String, &'a Annotations)>,
+) {
+    let Some(name) = &state.name else {
+        return;
+    };
+    let path = format!("{}.{}", path, name);
+    out.push((format!("state {}", path), &state.annotations));
+    for child in state.children() {
+        annotated_states(&path, child, out);
+    }
+}
+
 fn estimate_nd_score(constraints: usize, measurable: usize) -> f64 {
     if constraints == 0 {
         return 100.0;
@@ -153,8 +213,12 @@ fn format_expr(expr: &Expr) -> String {
         Expr::Null => "null".to_string(),
         Expr::Ident(s) => s.clone(),
         Expr::Call(c) => format_call(c),
-        Expr::Binary { left, op: _, right } => {
-            format!("{} >= {}", format_expr(left), format_expr(right))
-        }
+        Expr::Duration(_)
+        | Expr::Interpolated(_)
+        | Expr::Bool(_)
+        | Expr::List(_)
+        | Expr::Record(_)
+        | Expr::Binary { .. }
+        | Expr::Unary { .. } => expr_text(expr, 0),
     }
 }
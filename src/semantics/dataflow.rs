@@ -0,0 +1,601 @@
+//! Definite-assignment analysis of state variables: reads that may happen before any
+//! assignment (`D001`), variables that are written but never read (`D002`) and `+=` on a
+//! variable that is never initialised (`D003`).
+//!
+//! Global `state()` blocks run once at startup, in order. Each flow state then starts with
+//! the variables assigned on every path into it: the intersection over its incoming
+//! transitions of what the source state had assigned, plus the statements of the target's
+//! parent states. A transition on an event that rules `emit` also carries what those rules
+//! assign before emitting, and `on done` carries the result of the state's `run`. Rule bodies
+//! run at unknown times, so their assignments only count through such `emit`s. Rules inside
+//! a state read what that state has assigned; module rules read what the startup state of the
+//! first flow has assigned. Unreachable states are left to `F113`.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{
+    AssignOp, Call, Expr, Flow, Item, Module, Rule, RuleStmt, RuleTrigger, Span, StateBlock,
+    StateStmt, StringPart,
+};
+use crate::diagnostics::Diagnostic;
+
+pub(super) fn analyze_dataflow(
+    module: &Module,
+    flows: &[&Flow],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let globals: Vec<&StateStmt> = module
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::GlobalState(block) => Some(block.statements.iter()),
+            _ => None,
+        })
+        .flatten()
+        .collect();
+    let module_rules: Vec<&Rule> = module
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Rule(rule) => Some(rule),
+            _ => None,
+        })
+        .collect();
+    let facts = Facts::collect(module, flows);
+    let mut checker = Checker {
+        facts: &facts,
+        reported: HashSet::new(),
+        diagnostics,
+    };
+
+    let mut startup: HashSet<&str> = HashSet::new();
+    checker.statements(&globals, &mut startup, "global state");
+
+    let mut module_rule_scope = None;
+    for (idx, flow) in flows.iter().enumerate() {
+        let mut base = startup.clone();
+        base.extend(flow.params.iter().map(|p| p.name.as_str()));
+        let Some(entries) = flow_entries(flow, &base, &facts) else {
+            continue;
+        };
+        for state in flow.all_states() {
+            let Some(entry) = state.name.as_deref().and_then(|name| entries.get(name)) else {
+                continue;
+            };
+            let context = format!(
+                "{}.{}",
+                flow.name,
+                state.name.as_deref().unwrap_or("<unnamed>")
+            );
+            let mut assigned = entry.clone();
+            let statements: Vec<&StateStmt> = state.statements.iter().collect();
+            checker.statements(&statements, &mut assigned, &context);
+            for stmt in &state.statements {
+                match stmt {
+                    StateStmt::On {
+                        guard: Some(guard),
+                        span,
+                        ..
+                    } => checker.reads(guard, &assigned, &format!("guard in '{}'", context), span),
+                    StateStmt::Rule(rule) => {
+                        checker.rule(rule, &assigned, &format!("in '{}'", context))
+                    }
+                    _ => {}
+                }
+            }
+            checker.stale_reads(state, &context);
+            if idx == 0 && flow.start.as_deref() == state.name.as_deref() {
+                module_rule_scope = Some(assigned);
+            }
+        }
+    }
+
+    let scope = module_rule_scope.unwrap_or(startup);
+    for rule in module_rules {
+        checker.rule(rule, &scope, "at module level");
+    }
+
+    // Global state is visible to the target runtime (HUDs, game loops), which may read it.
+    let public: HashSet<&str> = statement_assigns_of(globals.iter().copied()).collect();
+    for (name, span) in &facts.written {
+        if !facts.read.contains(name) && !public.contains(name) && !name.starts_with('_') {
+            checker.diagnostics.push(
+                Diagnostic::warn(
+                    "D002",
+                    format!("Variable '{}' is assigned but never read", name),
+                )
+                .with_span(span),
+            );
+        }
+    }
+}
+
+/// Module-wide facts the path analysis needs.
+struct Facts<'a> {
+    /// Every variable with a value: assignment targets, flow parameters and `run` results.
+    vars: HashSet<&'a str>,
+    /// Variables that get a value somewhere other than through `+=`.
+    initialised: HashSet<&'a str>,
+    /// Variables that are read anywhere (`+=` on itself does not count).
+    read: HashSet<&'a str>,
+    /// Assigned variables (not parameters), with their first assignment, in source order.
+    written: Vec<(&'a str, &'a Span)>,
+    /// For each emitted event, what every emitting rule has assigned before its `emit`.
+    emit_assigns: HashMap<&'a str, HashSet<&'a str>>,
+}
+
+impl<'a> Facts<'a> {
+    fn collect(module: &'a Module, flows: &[&'a Flow]) -> Self {
+        let mut facts = Facts {
+            vars: HashSet::new(),
+            initialised: HashSet::new(),
+            read: HashSet::new(),
+            written: Vec::new(),
+            emit_assigns: HashMap::new(),
+        };
+        let mut reads = Vec::new();
+        let mut state_blocks: Vec<&StateBlock> = Vec::new();
+        let mut rules: Vec<&Rule> = Vec::new();
+        for item in &module.items {
+            match item {
+                Item::GlobalState(block) => state_blocks.push(block),
+                Item::Rule(rule) => rules.push(rule),
+                Item::Nd(nd) => {
+                    call_reads(&nd.propose, &mut reads);
+                    for call in &nd.constraints {
+                        call_reads(call, &mut reads);
+                    }
+                }
+                Item::Flow(_) | Item::Define(_) => {}
+            }
+        }
+        for flow in flows {
+            for param in &flow.params {
+                facts.vars.insert(&param.name);
+                facts.initialised.insert(&param.name);
+            }
+            state_blocks.extend(flow.all_states());
+        }
+        for block in state_blocks {
+            for stmt in &block.statements {
+                match stmt {
+                    StateStmt::Assign {
+                        target,
+                        op,
+                        value,
+                        span,
+                        ..
+                    } => {
+                        facts.write(target, op, span);
+                        expr_reads(value, &mut reads);
+                    }
+                    StateStmt::Expr(call) => call_reads(call, &mut reads),
+                    StateStmt::On {
+                        guard: Some(guard), ..
+                    } => expr_reads(guard, &mut reads),
+                    StateStmt::Run {
+                        args, result, span, ..
+                    } => {
+                        for arg in args {
+                            expr_reads(&arg.value, &mut reads);
+                        }
+                        if let Some(result) = result {
+                            facts.write(result, &AssignOp::Set, span);
+                        }
+                    }
+                    StateStmt::Return {
+                        value: Some(value), ..
+                    } => expr_reads(value, &mut reads),
+                    StateStmt::Rule(rule) => rules.push(rule),
+                    _ => {}
+                }
+            }
+        }
+        let mut emitters: HashMap<&str, Vec<HashSet<&str>>> = HashMap::new();
+        for rule in rules {
+            if let RuleTrigger::When(expr) = &rule.trigger {
+                expr_reads(expr, &mut reads);
+            }
+            let mut assigned = HashSet::new();
+            for stmt in &rule.body {
+                match stmt {
+                    RuleStmt::Assign {
+                        target,
+                        op,
+                        value,
+                        span,
+                    } => {
+                        facts.write(target, op, span);
+                        expr_reads(value, &mut reads);
+                        assigned.insert(target.as_str());
+                    }
+                    RuleStmt::Emit { event, .. } => {
+                        emitters
+                            .entry(event.as_str())
+                            .or_default()
+                            .push(assigned.clone());
+                    }
+                }
+            }
+        }
+        facts.emit_assigns = emitters
+            .into_iter()
+            .map(|(event, sets)| (event, intersect_all(sets)))
+            .collect();
+        let read = reads
+            .into_iter()
+            .filter_map(|name| facts.variable(name))
+            .collect();
+        facts.read = read;
+        facts
+    }
+
+    fn write(&mut self, target: &'a str, op: &AssignOp, span: &'a Span) {
+        if self.vars.insert(target) {
+            self.written.push((target, span));
+        }
+        if matches!(op, AssignOp::Set) {
+            self.initialised.insert(target);
+        }
+    }
+
+    /// The variable an identifier reads: `total`, or `invoice` for `invoice.amount`.
+    fn variable(&self, name: &str) -> Option<&'a str> {
+        let root = name.split('.').next().unwrap_or(name);
+        self.vars.get(name).or_else(|| self.vars.get(root)).copied()
+    }
+}
+
+/// Variables assigned on entry to each reachable state of `flow`, or `None` when the flow
+/// has no usable `start`.
+fn flow_entries<'a>(
+    flow: &'a Flow,
+    base: &HashSet<&'a str>,
+    facts: &Facts<'a>,
+) -> Option<HashMap<&'a str, HashSet<&'a str>>> {
+    let states: HashMap<&str, &StateBlock> = flow
+        .all_states()
+        .into_iter()
+        .filter_map(|state| Some((state.name.as_deref()?, state)))
+        .collect();
+    let start = flow.start.as_deref().filter(|s| states.contains_key(s))?;
+    let mut parents: HashMap<&str, &str> = HashMap::new();
+    for (name, state) in &states {
+        for child in state.children() {
+            if let Some(child_name) = child.name.as_deref() {
+                parents.entry(child_name).or_insert(name);
+            }
+        }
+    }
+    let ancestor_assigns = |state: &str| {
+        let mut out = HashSet::new();
+        let mut current = state;
+        let mut seen = HashSet::new();
+        while let Some(parent) = parents.get(current) {
+            if !seen.insert(*parent) {
+                break;
+            }
+            out.extend(statement_assigns(&states[parent].statements));
+            current = parent;
+        }
+        out
+    };
+
+    let mut entries: HashMap<&str, HashSet<&str>> = HashMap::new();
+    entries.insert(start, base.clone());
+    let mut queue = vec![start];
+    while let Some(name) = queue.pop() {
+        let state = states[name];
+        let mut out = entries[name].clone();
+        out.extend(statement_assigns(&state.statements));
+
+        let mut edges: Vec<(&str, HashSet<&str>)> = Vec::new();
+        for child in state
+            .start
+            .iter()
+            .chain(state.regions.iter().filter_map(|r| r.start.as_ref()))
+        {
+            edges.push((child, out.clone()));
+        }
+        for stmt in &state.statements {
+            let (target, event) = match stmt {
+                StateStmt::On { event, target, .. } => (target, Some(event.name.as_str())),
+                StateStmt::After { target, .. } => (target, None),
+                _ => continue,
+            };
+            let mut carried = out.clone();
+            if let Some(event) = event {
+                if let Some(assigns) = facts.emit_assigns.get(event) {
+                    carried.extend(assigns.iter().copied());
+                }
+                if event == "done" {
+                    carried.extend(state.statements.iter().filter_map(|stmt| match stmt {
+                        StateStmt::Run {
+                            result: Some(result),
+                            ..
+                        } => Some(result.as_str()),
+                        _ => None,
+                    }));
+                }
+            }
+            edges.push((target, carried));
+        }
+
+        for (target, mut carried) in edges {
+            let Some(target) = states.get_key_value(target).map(|(k, _)| *k) else {
+                continue;
+            };
+            carried.extend(ancestor_assigns(target));
+            let changed = match entries.get_mut(target) {
+                Some(entry) => {
+                    let before = entry.len();
+                    entry.retain(|var| carried.contains(var));
+                    entry.len() != before
+                }
+                None => {
+                    entries.insert(target, carried);
+                    true
+                }
+            };
+            if changed {
+                queue.push(target);
+            }
+        }
+    }
+    Some(entries)
+}
+
+struct Checker<'f, 'a, 'd> {
+    facts: &'f Facts<'a>,
+    /// Reads already reported, by variable and context, so one problem is reported once.
+    reported: HashSet<(String, String)>,
+    diagnostics: &'d mut Vec<Diagnostic>,
+}
+
+impl<'a> Checker<'_, 'a, '_> {
+    /// Checks state statements in order, adding their assignments to `assigned`.
+    fn statements(
+        &mut self,
+        statements: &[&'a StateStmt],
+        assigned: &mut HashSet<&'a str>,
+        context: &str,
+    ) {
+        for stmt in statements {
+            match stmt {
+                StateStmt::Assign {
+                    target,
+                    op,
+                    value,
+                    span,
+                    ..
+                } => {
+                    self.reads(value, assigned, &format!("in '{}'", context), span);
+                    self.add_assign(target, op, assigned, &format!("in '{}'", context), span);
+                    assigned.insert(target);
+                }
+                StateStmt::Expr(call) => {
+                    self.call_reads(call, assigned, &format!("in '{}'", context))
+                }
+                StateStmt::Run { args, span, .. } => {
+                    for arg in args {
+                        self.reads(&arg.value, assigned, &format!("in '{}'", context), span);
+                    }
+                }
+                StateStmt::Return {
+                    value: Some(value),
+                    span,
+                } => self.reads(value, assigned, &format!("in '{}'", context), span),
+                _ => {}
+            }
+        }
+    }
+
+    fn rule(&mut self, rule: &'a Rule, scope: &HashSet<&'a str>, place: &str) {
+        let context = format!("rule '{}' {}", rule.name, place);
+        if let RuleTrigger::When(expr) = &rule.trigger {
+            self.reads(expr, scope, &context, &rule.span);
+        }
+        let mut assigned = scope.clone();
+        for stmt in &rule.body {
+            if let RuleStmt::Assign {
+                target,
+                op,
+                value,
+                span,
+            } = stmt
+            {
+                self.reads(value, &assigned, &context, span);
+                self.add_assign(target, op, &assigned, &context, span);
+                assigned.insert(target);
+            }
+        }
+    }
+
+    /// A `when` rule of a state is checked as soon as the state is entered, so it can see a
+    /// variable before the state's `on`/`every` rules have assigned it.
+    fn stale_reads(&mut self, state: &'a StateBlock, context: &str) {
+        let entry: HashSet<&str> = statement_assigns(&state.statements).collect();
+        let rules: Vec<&Rule> = state
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                StateStmt::Rule(rule) => Some(rule),
+                _ => None,
+            })
+            .collect();
+        let mut producers: HashMap<&str, &str> = HashMap::new();
+        for rule in &rules {
+            if matches!(rule.trigger, RuleTrigger::When(_)) {
+                continue;
+            }
+            for stmt in &rule.body {
+                let RuleStmt::Assign {
+                    target, op, value, ..
+                } = stmt
+                else {
+                    continue;
+                };
+                // Accumulators (`n += 1`, `n = dec(n)`) build on the value from before.
+                let mut inputs = Vec::new();
+                expr_reads(value, &mut inputs);
+                let accumulates = matches!(op, AssignOp::Add) || inputs.contains(&target.as_str());
+                if !accumulates && !entry.contains(target.as_str()) {
+                    producers.entry(target).or_insert(&rule.name);
+                }
+            }
+        }
+        for rule in &rules {
+            let RuleTrigger::When(expr) = &rule.trigger else {
+                continue;
+            };
+            let mut names = Vec::new();
+            expr_reads(expr, &mut names);
+            for name in names {
+                let Some(var) = self.facts.variable(name) else {
+                    continue;
+                };
+                let Some(producer) = producers.get(var) else {
+                    continue;
+                };
+                let rule_context = format!("rule '{}' in '{}'", rule.name, context);
+                if !self
+                    .reported
+                    .insert((var.to_string(), rule_context.clone()))
+                {
+                    continue;
+                }
+                self.diagnostics.push(
+                    Diagnostic::warn(
+                        "D001",
+                        format!(
+                            "Variable '{}' may be read by {} before rule '{}' assigns it",
+                            var, rule_context, producer
+                        ),
+                    )
+                    .with_span(&rule.span),
+                );
+            }
+        }
+    }
+
+    fn add_assign(
+        &mut self,
+        target: &str,
+        op: &AssignOp,
+        assigned: &HashSet<&str>,
+        context: &str,
+        span: &Span,
+    ) {
+        if !matches!(op, AssignOp::Add) {
+            return;
+        }
+        if !self.facts.initialised.contains(target) {
+            self.diagnostics.push(
+                Diagnostic::warn(
+                    "D003",
+                    format!(
+                        "'+=' on '{}' {}, but '{}' is never initialised",
+                        target, context, target
+                    ),
+                )
+                .with_span(span),
+            );
+        } else {
+            self.read(target, assigned, context, span);
+        }
+    }
+
+    fn reads(&mut self, expr: &Expr, assigned: &HashSet<&str>, context: &str, span: &Span) {
+        let mut names = Vec::new();
+        expr_reads(expr, &mut names);
+        for name in names {
+            self.read(name, assigned, context, span);
+        }
+    }
+
+    fn call_reads(&mut self, call: &Call, assigned: &HashSet<&str>, context: &str) {
+        for arg in &call.args {
+            self.reads(&arg.value, assigned, context, &arg.span);
+        }
+    }
+
+    fn read(&mut self, name: &str, assigned: &HashSet<&str>, context: &str, span: &Span) {
+        let Some(var) = self.facts.variable(name) else {
+            return;
+        };
+        // `+=`-only variables are reported once as D003.
+        if assigned.contains(var) || !self.facts.initialised.contains(var) {
+            return;
+        }
+        if !self.reported.insert((var.to_string(), context.to_string())) {
+            return;
+        }
+        self.diagnostics.push(
+            Diagnostic::warn(
+                "D001",
+                format!(
+                    "Variable '{}' may be read before it is assigned ({})",
+                    var, context
+                ),
+            )
+            .with_span(span),
+        );
+    }
+}
+
+/// Targets of `=` and `+=` statements (`+=` leaves a value behind as well).
+fn statement_assigns(statements: &[StateStmt]) -> impl Iterator<Item = &str> {
+    statement_assigns_of(statements.iter())
+}
+
+fn statement_assigns_of<'a>(
+    statements: impl IntoIterator<Item = &'a StateStmt>,
+) -> impl Iterator<Item = &'a str> {
+    statements.into_iter().filter_map(|stmt| match stmt {
+        StateStmt::Assign { target, .. } => Some(target.as_str()),
+        _ => None,
+    })
+}
+
+fn intersect_all(sets: Vec<HashSet<&str>>) -> HashSet<&str> {
+    let mut sets = sets.into_iter();
+    let first = sets.next().unwrap_or_default();
+    sets.fold(first, |acc, set| acc.intersection(&set).copied().collect())
+}
+
+/// Identifiers read by `expr`. Call names and argument names are not reads.
+pub(super) fn expr_reads<'a>(expr: &'a Expr, out: &mut Vec<&'a str>) {
+    match expr {
+        Expr::Ident(name) => out.push(name),
+        Expr::Call(call) => call_reads(call, out),
+        Expr::Binary { left, right, .. } => {
+            expr_reads(left, out);
+            expr_reads(right, out);
+        }
+        Expr::Unary { operand, .. } => expr_reads(operand, out),
+        Expr::Interpolated(parts) => {
+            for part in parts {
+                if let StringPart::Expr(expr) = part {
+                    expr_reads(expr, out);
+                }
+            }
+        }
+        Expr::List(items) => {
+            for item in items {
+                expr_reads(item, out);
+            }
+        }
+        Expr::Record(fields) => {
+            for field in fields {
+                expr_reads(&field.value, out);
+            }
+        }
+        Expr::Number(_) | Expr::Duration(_) | Expr::String(_) | Expr::Bool(_) | Expr::Null => {}
+    }
+}
+
+fn call_reads<'a>(call: &'a Call, out: &mut Vec<&'a str>) {
+    for arg in &call.args {
+        expr_reads(&arg.value, out);
+    }
+}
@@ -0,0 +1,346 @@
+//! Graph analysis of flows: unreachable states (`F113`), dead-end states (`F114`) and
+//! cycles that can never reach an exit (`F115`).
+//!
+//! Nodes are the named states of a flow. A state leads to the targets of its own
+//! transitions and of its ancestors (events bubble up), to its `start` children and region
+//! starts (entering it enters them), and, inside a parallel state, to wherever any region can
+//! leave that state. Only transitions that can fire count: external events (`input.key(...)`,
+//! anything namespaced or with arguments, the runtime `tick`) and timers always can, `done`
+//! needs a `run` below the handling state or a rule that emits it, and other bare events need
+//! a rule `emit`.
+//! Exits are states that `terminate` or `return`.
+//!
+//! Findings are warnings; `@meta flow_analysis=strict` makes them errors and `off` skips the
+//! pass.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{walk_states, Call, Flow, Module, Rule, RuleStmt, StateBlock, StateStmt};
+use crate::diagnostics::Diagnostic;
+
+pub(super) fn analyze_flows(
+    module: &Module,
+    flows: &[&Flow],
+    rules: &[&Rule],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mode = module
+        .meta
+        .get("flow_analysis")
+        .map(|v| v.trim().to_ascii_lowercase())
+        .unwrap_or_else(|| "warn".to_string());
+    let strict = match mode.as_str() {
+        "off" => return,
+        "warn" => false,
+        "strict" => true,
+        _ => {
+            diagnostics.push(
+                Diagnostic::new(
+                    "M712",
+                    format!("Invalid flow_analysis '{}': expected off|warn|strict", mode),
+                )
+                .with_meta_span(&module.meta_spans, "flow_analysis"),
+            );
+            return;
+        }
+    };
+    let report = |code: &'static str, message: String| {
+        if strict {
+            Diagnostic::new(code, message)
+        } else {
+            Diagnostic::warn(code, message)
+        }
+    };
+
+    let emitted: HashSet<&str> = rules
+        .iter()
+        .flat_map(|rule| &rule.body)
+        .filter_map(|stmt| match stmt {
+            RuleStmt::Emit { event, .. } => Some(event.as_str()),
+            RuleStmt::Assign { .. } => None,
+        })
+        .collect();
+
+    for flow in flows {
+        let Some(graph) = FlowGraph::new(flow, &emitted) else {
+            continue;
+        };
+        let Some(start) = flow
+            .start
+            .as_deref()
+            .filter(|s| graph.states.contains_key(s))
+        else {
+            // A missing or unknown start is reported as F102/F103.
+            continue;
+        };
+
+        let reachable = graph.reachable_from(start);
+        for (name, state) in &graph.order {
+            if !reachable.contains(name) {
+                diagnostics.push(
+                    report(
+                        "F113",
+                        format!(
+                            "State '{}' in flow '{}' is unreachable from start",
+                            name, flow.name
+                        ),
+                    )
+                    .with_span(&state.span),
+                );
+            }
+        }
+
+        let exits = graph.reaches_exit();
+        let mut dead_ends = HashSet::new();
+        for (name, state) in &graph.order {
+            if reachable.contains(name)
+                && state.children().next().is_none()
+                && !graph.exits.contains(name)
+                && graph.successors(name).is_empty()
+            {
+                dead_ends.insert(*name);
+                diagnostics.push(
+                    report(
+                        "F114",
+                        format!(
+                            "State '{}' in flow '{}' has no way out (no transition that can fire, and no terminate or return)",
+                            name, flow.name
+                        ),
+                    )
+                    .with_span(&state.span),
+                );
+            }
+        }
+
+        // Traps only make sense when the flow can finish at all.
+        if graph.exits.is_empty() {
+            continue;
+        }
+        let stuck: HashSet<&str> = reachable
+            .iter()
+            .copied()
+            .filter(|name| !exits.contains(name) && !dead_ends.contains(name))
+            .collect();
+        for cycle in graph.cycles_within(&stuck) {
+            let first = graph.states[cycle[0]];
+            diagnostics.push(
+                report(
+                    "F115",
+                    format!(
+                        "States {} in flow '{}' form a cycle with no path to terminate or return",
+                        cycle
+                            .iter()
+                            .map(|name| format!("'{}'", name))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        flow.name
+                    ),
+                )
+                .with_span(&first.span),
+            );
+        }
+    }
+}
+
+struct FlowGraph<'a> {
+    /// Named states in source order; the first definition wins for duplicates (F104).
+    order: Vec<(&'a str, &'a StateBlock)>,
+    states: HashMap<&'a str, &'a StateBlock>,
+    parents: HashMap<&'a str, &'a str>,
+    /// Targets of the transitions declared directly on each state that can fire.
+    targets: HashMap<&'a str, Vec<&'a str>>,
+    exits: HashSet<&'a str>,
+}
+
+impl<'a> FlowGraph<'a> {
+    fn new(flow: &'a Flow, emitted: &HashSet<&str>) -> Option<Self> {
+        let mut graph = FlowGraph {
+            order: Vec::new(),
+            states: HashMap::new(),
+            parents: HashMap::new(),
+            targets: HashMap::new(),
+            exits: HashSet::new(),
+        };
+        for state in flow.all_states() {
+            let Some(name) = state.name.as_deref() else {
+                continue;
+            };
+            if graph.states.contains_key(name) {
+                continue;
+            }
+            graph.order.push((name, state));
+            graph.states.insert(name, state);
+            for child in state.children() {
+                if let Some(child_name) = child.name.as_deref() {
+                    graph.parents.entry(child_name).or_insert(name);
+                }
+            }
+        }
+        if graph.order.is_empty() {
+            return None;
+        }
+
+        for (name, state) in &graph.order {
+            let runs_flow = walk_states(std::slice::from_ref(*state))
+                .into_iter()
+                .any(|s| {
+                    s.statements
+                        .iter()
+                        .any(|stmt| matches!(stmt, StateStmt::Run { .. }))
+                });
+            let mut targets = Vec::new();
+            for stmt in &state.statements {
+                match stmt {
+                    StateStmt::On { event, target, .. } if can_fire(event, runs_flow, emitted) => {
+                        targets.push(target.as_str());
+                    }
+                    StateStmt::After { target, .. } => targets.push(target.as_str()),
+                    StateStmt::Terminate | StateStmt::Return { .. } => {
+                        graph.exits.insert(*name);
+                    }
+                    _ => {}
+                }
+            }
+            // Unknown targets are reported as F105.
+            targets.retain(|target| graph.states.contains_key(target));
+            graph.targets.insert(*name, targets);
+        }
+        Some(graph)
+    }
+
+    fn ancestors(&self, state: &'a str) -> Vec<&'a str> {
+        let mut out = Vec::new();
+        let mut current = state;
+        while let Some(parent) = self.parents.get(current) {
+            if out.contains(parent) {
+                break;
+            }
+            out.push(*parent);
+            current = parent;
+        }
+        out
+    }
+
+    fn in_subtree(&self, state: &str, root: &str) -> bool {
+        state == root || self.ancestors(state).contains(&root)
+    }
+
+    /// States that become active after `state`: transition targets of it and its ancestors,
+    /// the children it enters, and the exits of enclosing parallel states.
+    fn successors(&self, state: &'a str) -> Vec<&'a str> {
+        let mut out = Vec::new();
+        let ancestors = self.ancestors(state);
+        for source in std::iter::once(state).chain(ancestors.iter().copied()) {
+            out.extend(self.targets[source].iter().copied());
+        }
+        let block = self.states[state];
+        if let Some(start) = block.start.as_deref() {
+            out.push(start);
+        }
+        out.extend(block.regions.iter().filter_map(|r| r.start.as_deref()));
+        out.retain(|target| self.states.contains_key(target));
+
+        for parallel in ancestors
+            .iter()
+            .filter(|a| !self.states[**a].regions.is_empty())
+        {
+            for (name, _) in &self.order {
+                if self.in_subtree(name, parallel) {
+                    out.extend(
+                        self.targets[name]
+                            .iter()
+                            .filter(|target| !self.in_subtree(target, parallel)),
+                    );
+                }
+            }
+        }
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+
+    /// Every state that can become active, ancestors of entered states included.
+    fn reachable_from(&self, start: &'a str) -> HashSet<&'a str> {
+        let mut seen = HashSet::new();
+        let mut queue = vec![start];
+        while let Some(state) = queue.pop() {
+            if !seen.insert(state) {
+                continue;
+            }
+            queue.extend(self.ancestors(state));
+            queue.extend(self.successors(state));
+        }
+        seen
+    }
+
+    /// States from which some exit state can become active.
+    fn reaches_exit(&self) -> HashSet<&'a str> {
+        let mut out: HashSet<&str> = self.exits.clone();
+        loop {
+            let before = out.len();
+            for (name, _) in &self.order {
+                if !out.contains(name) && self.successors(name).iter().any(|s| out.contains(s)) {
+                    out.insert(*name);
+                }
+            }
+            if out.len() == before {
+                return out;
+            }
+        }
+    }
+
+    /// Cycles (strongly connected groups, or a state leading to itself) made only of
+    /// `states`, each listed in source order.
+    fn cycles_within(&self, states: &HashSet<&'a str>) -> Vec<Vec<&'a str>> {
+        let reach = |from: &'a str| {
+            let mut seen = HashSet::new();
+            let mut queue: Vec<&str> = self
+                .successors(from)
+                .into_iter()
+                .filter(|s| states.contains(s))
+                .collect();
+            while let Some(state) = queue.pop() {
+                if seen.insert(state) {
+                    queue.extend(
+                        self.successors(state)
+                            .into_iter()
+                            .filter(|s| states.contains(s)),
+                    );
+                }
+            }
+            seen
+        };
+        let reach: HashMap<&str, HashSet<&str>> =
+            states.iter().map(|state| (*state, reach(state))).collect();
+
+        let mut grouped = HashSet::new();
+        let mut cycles = Vec::new();
+        for (name, _) in &self.order {
+            if !states.contains(name) || grouped.contains(name) || !reach[name].contains(name) {
+                continue;
+            }
+            let cycle: Vec<&str> = self
+                .order
+                .iter()
+                .map(|(other, _)| *other)
+                .filter(|other| {
+                    states.contains(other)
+                        && reach[name].contains(other)
+                        && reach[other].contains(name)
+                })
+                .collect();
+            grouped.extend(cycle.iter().copied());
+            cycles.push(cycle);
+        }
+        cycles
+    }
+}
+
+/// Whether anything can raise `event` while the handling state is active.
+fn can_fire(event: &Call, runs_flow: bool, emitted: &HashSet<&str>) -> bool {
+    // `tick` is raised by the runtime ticker, `key(...)` is the legacy input event.
+    let external = event.name.contains('.')
+        || !event.args.is_empty()
+        || matches!(event.name.as_str(), "key" | "tick");
+    external || emitted.contains(event.name.as_str()) || (event.name == "done" && runs_flow)
+}
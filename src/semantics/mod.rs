@@ -1,55 +1,104 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+mod dataflow;
+mod flow_analysis;
+mod rule_conflicts;
+mod types;
+
+use crate::formatter::expr_text;
+use crate::versioning::{LanguageVersion, LANGUAGE_SUPPORT_RANGE};
+
+pub use crate::diagnostics::{format_diagnostics, has_errors, Diagnostic, DiagnosticLevel, Label};
 
 use crate::ast::{
-    BinaryOp, Call, Expr, Flow, Item, Module, NdBlock, Rule, RuleStmt, RuleTrigger, SoftDefine,
-    StateBlock, StateStmt,
+    duration_text, Annotations, BinaryOp, Call, CallArg, Expr, Flow, Item, Module, NdBlock, Rule,
+    RuleStmt, RuleTrigger, SoftDefine, Span, StateBlock, StateStmt, StringPart, UnaryOp,
 };
 
-#[derive(Debug, Clone)]
-pub struct Diagnostic {
-    pub code: &'static str,
-    pub message: String,
-    pub level: DiagnosticLevel,
+pub fn validate_module(module: &Module) -> Vec<Diagnostic> {
+    let additional_imported_roots = HashSet::new();
+    validate_module_with_imports(module, &additional_imported_roots)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum DiagnosticLevel {
-    Error,
-    Warning,
+pub fn validate_module_with_imports(
+    module: &Module,
+    additional_imported_roots: &HashSet<String>,
+) -> Vec<Diagnostic> {
+    validate_module_in_scope(module, additional_imported_roots, &[])
 }
 
-impl Diagnostic {
-    fn new(code: &'static str, message: impl Into<String>) -> Self {
-        Self {
-            code,
-            message: message.into(),
-            level: DiagnosticLevel::Error,
-        }
-    }
+/// A module made visible by an `import(...)`, as the importing module sees it.
+#[derive(Debug, Clone)]
+pub struct ImportedModule {
+    /// How references spell the module: the import alias, or the full module path.
+    pub prefix: String,
+    pub module: String,
+    /// Names the module defines, relative to its namespace (see [`module_symbols`]).
+    pub symbols: BTreeSet<String>,
+    /// The subset listed in the module's `exports(...)`; the rest is private to it.
+    pub exports: BTreeSet<String>,
+}
 
-    fn warn(code: &'static str, message: impl Into<String>) -> Self {
-        Self {
-            code,
-            message: message.into(),
-            level: DiagnosticLevel::Warning,
-        }
-    }
+/// Validates one module of a linked project. References through an import must name a
+/// symbol the imported module exports; only the module's own imports are visible.
+pub fn validate_module_in_project(module: &Module, imports: &[ImportedModule]) -> Vec<Diagnostic> {
+    validate_module_in_scope(module, &HashSet::new(), imports)
 }
 
-pub fn validate_module(module: &Module) -> Vec<Diagnostic> {
-    let additional_imported_roots = HashSet::new();
-    validate_module_with_imports(module, &additional_imported_roots)
+/// What other modules can reference after importing `module`: flows (`Main`), states
+/// (`Main.Idle`), rules (`total`, or `Main.Idle.onKey` inside a state), state variables,
+/// defines and nd blocks.
+pub fn module_symbols(module: &Module) -> BTreeSet<String> {
+    let mut symbols = BTreeSet::new();
+    for item in &module.items {
+        match item {
+            Item::Flow(flow) => {
+                symbols.insert(flow.name.clone());
+                for state in flow.all_states() {
+                    let Some(state_name) = &state.name else {
+                        continue;
+                    };
+                    symbols.insert(format!("{}.{}", flow.name, state_name));
+                    for stmt in &state.statements {
+                        if let StateStmt::Rule(rule) = stmt {
+                            symbols.insert(format!("{}.{}.{}", flow.name, state_name, rule.name));
+                        }
+                    }
+                }
+            }
+            Item::GlobalState(state) => {
+                for stmt in &state.statements {
+                    if let StateStmt::Assign { target, .. } = stmt {
+                        symbols.insert(target.clone());
+                    }
+                }
+            }
+            Item::Rule(rule) => {
+                symbols.insert(rule.name.clone());
+            }
+            Item::Nd(nd) => {
+                symbols.insert(nd.name.clone());
+            }
+            Item::Define(define) => {
+                symbols.insert(define.name.clone());
+            }
+        }
+    }
+    symbols
 }
 
-pub fn validate_module_with_imports(
+fn validate_module_in_scope(
     module: &Module,
     additional_imported_roots: &HashSet<String>,
+    imports: &[ImportedModule],
 ) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
 
     validate_module_name(module, &mut diagnostics);
+    validate_language_meta(module, &mut diagnostics);
     let mut imported_roots = validate_use_decls(module, &mut diagnostics);
     validate_import_decls(module, &mut imported_roots, &mut diagnostics);
+    validate_export_decls(module, &mut diagnostics);
     imported_roots.extend(additional_imported_roots.iter().cloned());
 
     let flows: Vec<&Flow> = module
@@ -69,7 +118,7 @@ pub fn validate_module_with_imports(
         })
         .collect();
     for flow in &flows {
-        for state in &flow.states {
+        for state in flow.all_states() {
             for stmt in &state.statements {
                 if let StateStmt::Rule(rule) = stmt {
                     rules.push(rule);
@@ -93,7 +142,7 @@ pub fn validate_module_with_imports(
     validate_nd_blocks(&nd_blocks, &module_defines, &mut diagnostics);
     validate_convergence_meta(module, &nd_blocks, &mut diagnostics);
     validate_nd_guardrails(module, &flows, &rules, &mut diagnostics);
-    validate_state_execution(&flows, &mut diagnostics);
+    validate_state_execution(&flows, &imported_roots, &mut diagnostics);
     validate_legacy_shorthand(module, &flows, &rules, &mut diagnostics);
     validate_symbol_references(
         module,
@@ -101,106 +150,129 @@ pub fn validate_module_with_imports(
         &rules,
         &known_fqns,
         &imported_roots,
+        imports,
         &mut diagnostics,
     );
     validate_shadowing(module, &rules, &mut diagnostics);
+    validate_annotations(&flows, &rules, &nd_blocks, &mut diagnostics);
+    validate_deprecated_uses(&flows, &mut diagnostics);
+    flow_analysis::analyze_flows(module, &flows, &rules, &mut diagnostics);
+    dataflow::analyze_dataflow(module, &flows, &mut diagnostics);
+    rule_conflicts::analyze_rules(&flows, &rules, &mut diagnostics);
+    types::validate_types(module, &flows, &rules, &mut diagnostics);
 
     diagnostics
 }
 
+/// Legacy `key(...)` events and `render` calls, rejected in every edition.
 fn validate_legacy_shorthand(
-    _module: &Module,
+    module: &Module,
     flows: &[&Flow],
     rules: &[&Rule],
     diagnostics: &mut Vec<Diagnostic>,
 ) {
+    let report = |code: &'static str, message: String, span: &Span| {
+        Diagnostic::new(code, message).with_span(span)
+    };
+    // Rewriting `key(...)` to `input.key(...)` is only safe once `input` is imported.
+    let input_imported = module.uses.iter().any(|decl| {
+        decl.alias
+            .as_deref()
+            .unwrap_or_else(|| decl.path.rsplit('.').next().unwrap_or(""))
+            == "input"
+    });
+    let modern_key = |diagnostic: Diagnostic, event: &Call| match event.span.find_word("key") {
+        Some(name) => diagnostic.with_suggestion(
+            &name,
+            "input.key",
+            if input_imported {
+                "use 'input.key(...)'"
+            } else {
+                "use 'input.key(...)' and import the input package with use(...)"
+            },
+            input_imported,
+        ),
+        None => diagnostic,
+    };
     for flow in flows {
-        for state in &flow.states {
+        for state in flow.all_states() {
             let state_name = state.name.as_deref().unwrap_or("<unnamed>");
             for stmt in &state.statements {
                 match stmt {
-                    StateStmt::On { event, .. } => {
-                        if event.name == "key" {
-                            diagnostics.push(Diagnostic::new(
-                                "U610",
-                                format!(
-                                    "Legacy event shorthand 'key(...)' in {}.{}; use 'input.key(...)' with use(...) import",
-                                    flow.name, state_name
-                                ),
-                            ));
-                        }
-                    }
-                    StateStmt::Expr(call) => {
-                        if call.name == "render" {
-                            diagnostics.push(Diagnostic::new(
-                                "U611",
-                                format!(
-                                    "Legacy render shorthand in {}.{}; use namespaced calls like 'ui.text(...)'",
-                                    flow.name, state_name
-                                ),
-                            ));
-                        }
+                    StateStmt::On { event, .. } if event.name == "key" => {
+                        let diagnostic = report(
+                            "U610",
+                            format!(
+                                "Legacy event shorthand 'key(...)' in {}.{}; use 'input.key(...)' with use(...) import",
+                                flow.name, state_name
+                            ),
+                            &event.span,
+                        );
+                        diagnostics.push(modern_key(diagnostic, event));
                     }
-                    StateStmt::Rule(rule) => {
-                        if let RuleTrigger::On(call) = &rule.trigger {
-                            if call.name == "key" {
-                                diagnostics.push(Diagnostic::new(
-                                    "U610",
-                                    format!(
-                                        "Legacy event shorthand in rule '{}'; use 'input.key(...)' with use(...) import",
-                                        rule.name
-                                    ),
-                                ));
-                            }
+                    StateStmt::Expr(call) if call.name == "render" => {
+                        let mut diagnostic = report(
+                            "U611",
+                            format!(
+                                "Legacy render shorthand in {}.{}; use namespaced calls like 'ui.text(...)'",
+                                flow.name, state_name
+                            ),
+                            &call.span,
+                        );
+                        // `render text(...)` becomes `ui.text(...)`; whether `ui` exports the
+                        // call is up to the target, so the rewrite is only a hint.
+                        if let Some(inner) = call
+                            .span
+                            .text()
+                            .strip_prefix("render")
+                            .map(str::trim_start)
+                            .filter(|inner| !inner.is_empty())
+                        {
+                            diagnostic = diagnostic.with_suggestion(
+                                &call.span,
+                                format!("ui.{}", inner),
+                                format!("use the namespaced call 'ui.{}'", inner),
+                                false,
+                            );
                         }
+                        diagnostics.push(diagnostic);
                     }
-                    StateStmt::Run { .. } | StateStmt::Terminate | StateStmt::Assign { .. } => {}
+                    _ => {}
                 }
             }
         }
     }
 
+    // `rules` already holds the rules declared inside states.
     for rule in rules {
         if let RuleTrigger::On(call) = &rule.trigger {
             if call.name == "key" {
-                diagnostics.push(Diagnostic::new(
+                let diagnostic = report(
                     "U610",
                     format!(
                         "Legacy event shorthand in rule '{}'; use 'input.key(...)' with use(...) import",
                         rule.name
                     ),
-                ));
+                    &call.span,
+                );
+                diagnostics.push(modern_key(diagnostic, call));
             }
         }
     }
 }
 
-pub fn format_diagnostics(diags: &[Diagnostic]) -> String {
-    diags
-        .iter()
-        .map(|d| match d.level {
-            DiagnosticLevel::Error => format!("{}: {}", d.code, d.message),
-            DiagnosticLevel::Warning => format!("{} [warn]: {}", d.code, d.message),
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
-}
-
-pub fn has_errors(diags: &[Diagnostic]) -> bool {
-    diags.iter().any(|d| d.level == DiagnosticLevel::Error)
-}
-
 fn validate_module_name(module: &Module, diagnostics: &mut Vec<Diagnostic>) {
     if module.name.is_empty() {
-        diagnostics.push(Diagnostic::new("NS501", "Module namespace is empty"));
+        diagnostics
+            .push(Diagnostic::new("NS501", "Module namespace is empty").with_span(&module.span));
         return;
     }
     for segment in module.name.split('.') {
         if !is_valid_ident(segment) {
-            diagnostics.push(Diagnostic::new(
-                "NS501",
-                format!("Invalid namespace segment '{}'", segment),
-            ));
+            diagnostics.push(
+                Diagnostic::new("NS501", format!("Invalid namespace segment '{}'", segment))
+                    .with_span(&module.span),
+            );
         }
     }
 }
@@ -209,10 +281,10 @@ fn validate_use_decls(module: &Module, diagnostics: &mut Vec<Diagnostic>) -> Has
     let mut roots = HashSet::new();
     for decl in &module.uses {
         if !is_valid_qualified_ident(&decl.path) {
-            diagnostics.push(Diagnostic::new(
-                "U601",
-                format!("Invalid use path '{}'", decl.path),
-            ));
+            diagnostics.push(
+                Diagnostic::new("U601", format!("Invalid use path '{}'", decl.path))
+                    .with_span(&decl.span),
+            );
             continue;
         }
         let exposed = decl
@@ -221,17 +293,23 @@ fn validate_use_decls(module: &Module, diagnostics: &mut Vec<Diagnostic>) -> Has
             .cloned()
             .unwrap_or_else(|| decl.path.rsplit('.').next().unwrap_or("").to_string());
         if exposed.is_empty() || !is_valid_ident(&exposed) {
-            diagnostics.push(Diagnostic::new(
-                "U602",
-                format!("Invalid use alias '{}' for path '{}'", exposed, decl.path),
-            ));
+            diagnostics.push(
+                Diagnostic::new(
+                    "U602",
+                    format!("Invalid use alias '{}' for path '{}'", exposed, decl.path),
+                )
+                .with_span(&decl.span),
+            );
             continue;
         }
         if !roots.insert(exposed.clone()) {
-            diagnostics.push(Diagnostic::new(
-                "U603",
-                format!("Duplicate imported namespace root '{}'", exposed),
-            ));
+            diagnostics.push(
+                Diagnostic::new(
+                    "U603",
+                    format!("Duplicate imported namespace root '{}'", exposed),
+                )
+                .with_span(&decl.span),
+            );
         }
     }
     roots
@@ -249,20 +327,57 @@ fn validate_import_decls(
             .cloned()
             .unwrap_or_else(|| decl.path.split('.').next().unwrap_or("").trim().to_string());
         if exposed.is_empty() || !is_valid_ident(&exposed) {
-            diagnostics.push(Diagnostic::new(
-                "U604",
-                format!(
-                    "Invalid import alias/root '{}' for path '{}'",
-                    exposed, decl.path
-                ),
-            ));
+            diagnostics.push(
+                Diagnostic::new(
+                    "U604",
+                    format!(
+                        "Invalid import alias/root '{}' for path '{}'",
+                        exposed, decl.path
+                    ),
+                )
+                .with_span(&decl.span),
+            );
             continue;
         }
         if !imported_roots.insert(exposed.clone()) {
-            diagnostics.push(Diagnostic::new(
-                "U605",
-                format!("Duplicate imported namespace root '{}'", exposed),
-            ));
+            diagnostics.push(
+                Diagnostic::new(
+                    "U605",
+                    format!("Duplicate imported namespace root '{}'", exposed),
+                )
+                .with_span(&decl.span),
+            );
+        }
+    }
+}
+
+/// `exports(...)` may list the flows, top-level rules, state variables, defines and nd
+/// blocks of the module.
+fn validate_export_decls(module: &Module, diagnostics: &mut Vec<Diagnostic>) {
+    if module.exports.is_empty() {
+        return;
+    }
+    let exportable: BTreeSet<String> = module_symbols(module)
+        .into_iter()
+        .filter(|symbol| !symbol.contains('.'))
+        .collect();
+    for decl in &module.exports {
+        for name in &decl.names {
+            if exportable.contains(name) {
+                continue;
+            }
+            let span = decl.span.find_word(name);
+            diagnostics.push(
+                Diagnostic::new(
+                    "NS508",
+                    format!(
+                        "exports(...) names '{}', which is not a flow, rule, state variable, define or nd block of module '{}'",
+                        name, module.name
+                    ),
+                )
+                .with_span(span.as_ref().unwrap_or(&decl.span))
+                .did_you_mean(name, span.clone(), exportable.iter().map(String::as_str)),
+            );
         }
     }
 }
@@ -274,7 +389,7 @@ fn collect_known_fqns(module: &Module, flows: &[&Flow], rules: &[&Rule]) -> Hash
     for flow in flows {
         let flow_fqn = format!("{}.{}", module.name, flow.name);
         fqns.insert(flow_fqn.clone());
-        for state in &flow.states {
+        for state in flow.all_states() {
             if let Some(state_name) = &state.name {
                 fqns.insert(format!("{flow_fqn}.{state_name}"));
             }
@@ -303,49 +418,216 @@ fn collect_known_fqns(module: &Module, flows: &[&Flow], rules: &[&Rule]) -> Hash
 }
 
 fn validate_flows(flows: &[&Flow], diagnostics: &mut Vec<Diagnostic>) {
-    let mut flow_names = HashSet::new();
+    let mut flow_names: HashMap<String, &Span> = HashMap::new();
     for flow in flows {
-        if !flow_names.insert(flow.name.clone()) {
-            diagnostics.push(Diagnostic::new(
-                "F101",
-                format!("Duplicate flow '{}'", flow.name),
-            ));
+        if let Some(first) = flow_names.get(&flow.name) {
+            diagnostics.push(
+                Diagnostic::new("F101", format!("Duplicate flow '{}'", flow.name))
+                    .with_span(&flow.span)
+                    .with_label(first, "first defined here"),
+            );
+        } else {
+            flow_names.insert(flow.name.clone(), &flow.span);
         }
     }
 
     for flow in flows {
         if flow.start.is_none() {
-            diagnostics.push(Diagnostic::new(
-                "F102",
-                format!("Flow '{}' is missing start", flow.name),
-            ));
+            diagnostics.push(
+                Diagnostic::new("F102", format!("Flow '{}' is missing start", flow.name))
+                    .with_span(&flow.span),
+            );
         }
 
-        let named_states: Vec<&StateBlock> =
-            flow.states.iter().filter(|s| s.name.is_some()).collect();
+        let named_states: Vec<&StateBlock> = flow
+            .all_states()
+            .into_iter()
+            .filter(|s| s.name.is_some())
+            .collect();
         let mut state_names = HashSet::new();
+        let mut first_states: HashMap<&str, &Span> = HashMap::new();
         for state in &named_states {
             let name = state.name.as_ref().expect("state name exists");
             if !state_names.insert(name.clone()) {
-                diagnostics.push(Diagnostic::new(
+                let mut diag = Diagnostic::new(
                     "F104",
                     format!("Duplicate state '{}' in flow '{}'", name, flow.name),
-                ));
+                )
+                .with_span(&state.span);
+                if let Some(first) = first_states.get(name.as_str()) {
+                    diag = diag.with_label(first, "first defined here");
+                }
+                diagnostics.push(diag);
+            } else {
+                first_states.insert(name.as_str(), &state.span);
             }
         }
 
         if let Some(start) = &flow.start {
             if !state_names.contains(start) {
-                diagnostics.push(Diagnostic::new(
-                    "F103",
-                    format!("Unknown start state '{}' in flow '{}'", start, flow.name),
-                ));
+                diagnostics.push(
+                    Diagnostic::new(
+                        "F103",
+                        format!("Unknown start state '{}' in flow '{}'", start, flow.name),
+                    )
+                    .with_span(flow.start_span.as_ref().unwrap_or(&flow.span)),
+                );
             }
         }
 
+        let tree = StateTree::new(flow);
         for state in &named_states {
-            validate_state_transitions(flow, state, &state_names, diagnostics);
+            validate_state_hierarchy(flow, state, diagnostics);
+            validate_state_transitions(flow, state, &state_names, &tree, diagnostics);
+        }
+    }
+}
+
+/// Parent links of a flow's state tree. A region is a node of its own, named `State/Region`.
+struct StateTree {
+    parents: HashMap<String, String>,
+    parallel: HashSet<String>,
+}
+
+impl StateTree {
+    fn new(flow: &Flow) -> Self {
+        let mut tree = StateTree {
+            parents: HashMap::new(),
+            parallel: HashSet::new(),
+        };
+        for state in flow.all_states() {
+            let Some(name) = &state.name else {
+                continue;
+            };
+            // Duplicate states are reported as F104; the first definition keeps its place.
+            for child in &state.states {
+                if let Some(child_name) = &child.name {
+                    tree.parents
+                        .entry(child_name.clone())
+                        .or_insert_with(|| name.clone());
+                }
+            }
+            for region in &state.regions {
+                let node = format!("{}/{}", name, region.name);
+                tree.parallel.insert(name.clone());
+                tree.parents.insert(node.clone(), name.clone());
+                for child in &region.states {
+                    if let Some(child_name) = &child.name {
+                        tree.parents
+                            .entry(child_name.clone())
+                            .or_insert_with(|| node.clone());
+                    }
+                }
+            }
+        }
+        tree
+    }
+
+    fn ancestors(&self, state: &str) -> Vec<&str> {
+        let mut out = Vec::new();
+        let mut current = state;
+        while let Some(parent) = self.parents.get(current) {
+            if out.contains(&parent.as_str()) {
+                break;
+            }
+            out.push(parent.as_str());
+            current = parent;
         }
+        out
+    }
+
+    /// The parallel state whose regions a transition from `from` to `to` would cut across.
+    fn crossed_parallel(&self, from: &str, to: &str) -> Option<&str> {
+        let target_ancestors = self.ancestors(to);
+        self.ancestors(from)
+            .into_iter()
+            .find(|ancestor| target_ancestors.contains(ancestor))
+            .filter(|common| self.parallel.contains(*common))
+    }
+}
+
+fn validate_state_hierarchy(flow: &Flow, state: &StateBlock, diagnostics: &mut Vec<Diagnostic>) {
+    let state_name = state.name.as_deref().unwrap_or("<unnamed>");
+    if !state.states.is_empty() && !state.regions.is_empty() {
+        diagnostics.push(
+            Diagnostic::new(
+                "F110",
+                format!(
+                    "State '{}' in flow '{}' mixes nested states and regions",
+                    state_name, flow.name
+                ),
+            )
+            .with_span(&state.span),
+        );
+    }
+    let owner = format!("state '{}' in flow '{}'", state_name, flow.name);
+    if !state.states.is_empty() || state.start.is_some() {
+        validate_nested_start(
+            &owner,
+            state.start.as_deref(),
+            &state.states,
+            state.start_span.as_ref().unwrap_or(&state.span),
+            diagnostics,
+        );
+    }
+
+    let mut region_names: HashMap<&str, &Span> = HashMap::new();
+    for region in &state.regions {
+        if let Some(first) = region_names.insert(region.name.as_str(), &region.span) {
+            diagnostics.push(
+                Diagnostic::new(
+                    "F112",
+                    format!(
+                        "Duplicate region '{}' in state '{}' of flow '{}'",
+                        region.name, state_name, flow.name
+                    ),
+                )
+                .with_span(&region.span)
+                .with_label(first, "first defined here"),
+            );
+        }
+        validate_nested_start(
+            &format!("region '{}' of {}", region.name, owner),
+            region.start.as_deref(),
+            &region.states,
+            region.start_span.as_ref().unwrap_or(&region.span),
+            diagnostics,
+        );
+    }
+}
+
+/// A state with nested states, and every region, enters one of its own children first.
+fn validate_nested_start(
+    owner: &str,
+    start: Option<&str>,
+    children: &[StateBlock],
+    span: &Span,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let Some(start) = start else {
+        diagnostics.push(
+            Diagnostic::new(
+                "F108",
+                format!("Missing start for nested states of {}", owner),
+            )
+            .with_span(span),
+        );
+        return;
+    };
+    if !children
+        .iter()
+        .any(|child| child.name.as_deref() == Some(start))
+    {
+        diagnostics.push(
+            Diagnostic::new(
+                "F109",
+                format!(
+                    "Start state '{}' of {} is not one of its nested states",
+                    start, owner
+                ),
+            )
+            .with_span(span),
+        );
     }
 }
 
@@ -353,70 +635,153 @@ fn validate_state_transitions(
     flow: &Flow,
     state: &StateBlock,
     known_states: &HashSet<String>,
+    tree: &StateTree,
     diagnostics: &mut Vec<Diagnostic>,
 ) {
     let state_name = state.name.as_deref().unwrap_or("<unnamed>");
-    let mut handlers: HashMap<String, String> = HashMap::new();
+    let mut handlers: HashMap<String, (String, &Span)> = HashMap::new();
 
-    for stmt in &state.statements {
-        if let StateStmt::On { event, target } = stmt {
-            if !known_states.contains(target) {
-                diagnostics.push(Diagnostic::new(
+    let check_target = |target: &str, span: &Span, diagnostics: &mut Vec<Diagnostic>| {
+        if !known_states.contains(target) {
+            diagnostics.push(
+                Diagnostic::new(
                     "F105",
                     format!(
                         "Unknown transition target '{}' in flow '{}' state '{}'",
                         target, flow.name, state_name
                     ),
-                ));
-            }
+                )
+                .with_span(span)
+                .did_you_mean(
+                    target,
+                    span.rfind_word(target),
+                    known_states.iter().map(String::as_str),
+                ),
+            );
+        } else if let Some(parallel) = tree.crossed_parallel(state_name, target) {
+            diagnostics.push(
+                Diagnostic::new(
+                    "F111",
+                    format!(
+                        "Transition from '{}' to '{}' in flow '{}' crosses regions of parallel state '{}'",
+                        state_name, target, flow.name, parallel
+                    ),
+                )
+                .with_span(span),
+            );
+        }
+    };
 
-            let signature = call_signature(event);
-            if let Some(existing_target) = handlers.insert(signature.clone(), target.clone()) {
-                diagnostics.push(Diagnostic::new(
+    for stmt in &state.statements {
+        let (signature, target, span) = match stmt {
+            StateStmt::On {
+                event,
+                guard,
+                target,
+                span,
+            } => {
+                if let Some(guard) = guard {
+                    if !is_supported_when_expr(guard) {
+                        diagnostics.push(
+                            Diagnostic::new(
+                                "F107",
+                                format!(
+                                    "Unsupported guard on '{}' in flow '{}' state '{}' (expected comparisons combined with and/or/not)",
+                                    call_signature(event),
+                                    flow.name,
+                                    state_name
+                                ),
+                            )
+                            .with_span(span),
+                        );
+                    }
+                }
+                let signature = match guard {
+                    Some(guard) => {
+                        format!("{} when {}", call_signature(event), expr_text(guard, 0))
+                    }
+                    None => call_signature(event),
+                };
+                (signature, target, span)
+            }
+            StateStmt::After {
+                delay_ms,
+                target,
+                span,
+            } => (format!("after({})", duration_text(*delay_ms)), target, span),
+            _ => continue,
+        };
+        check_target(target, span, diagnostics);
+
+        if let Some((existing_target, first)) =
+            handlers.insert(signature.clone(), (target.clone(), span))
+        {
+            diagnostics.push(
+                Diagnostic::new(
                     "F106",
                     format!(
-            "Duplicate event handler '{}' in flow '{}' state '{}' (targets '{}' and '{}')",
-            signature, flow.name, state_name, existing_target, target
-          ),
-                ));
-            }
+                        "Duplicate event handler '{}' in flow '{}' state '{}' (targets '{}' and '{}')",
+                        signature, flow.name, state_name, existing_target, target
+                    ),
+                )
+                .with_span(span)
+                .with_label(first, "first handler here"),
+            );
         }
     }
 }
 
 fn validate_rules(rules: &[&Rule], diagnostics: &mut Vec<Diagnostic>) {
-    let mut rule_names = HashSet::new();
+    let mut rule_names: HashMap<String, &Span> = HashMap::new();
     for rule in rules {
-        if !rule_names.insert(rule.name.clone()) {
-            diagnostics.push(Diagnostic::new(
-                "R201",
-                format!("Duplicate rule '{}'", rule.name),
-            ));
+        if let Some(first) = rule_names.get(&rule.name) {
+            diagnostics.push(
+                Diagnostic::new("R201", format!("Duplicate rule '{}'", rule.name))
+                    .with_span(&rule.span)
+                    .with_label(first, "first defined here"),
+            );
+        } else {
+            rule_names.insert(rule.name.clone(), &rule.span);
         }
         if rule.body.is_empty() {
-            diagnostics.push(Diagnostic::new(
-                "R202",
-                format!("Rule '{}' has no effect body", rule.name),
-            ));
+            diagnostics.push(
+                Diagnostic::new("R202", format!("Rule '{}' has no effect body", rule.name))
+                    .with_span(&rule.span),
+            );
         }
         if let RuleTrigger::When(expr) = &rule.trigger {
             if !is_supported_when_expr(expr) {
-                diagnostics.push(Diagnostic::new(
-                    "R204",
-                    format!(
-                        "Rule '{}' uses 'when' without a supported expression (expected comparisons >=, >, <, ==, != and optional and/or)",
-                        rule.name
-                    ),
-                ));
+                diagnostics.push(
+                    Diagnostic::new(
+                        "R204",
+                        format!(
+                            "Rule '{}' uses 'when' without a supported expression (expected comparisons >=, >, <, ==, != over values and arithmetic, combined with and/or/not)",
+                            rule.name
+                        ),
+                    )
+                    .with_span(&rule.span),
+                );
             }
         }
+        if let RuleTrigger::Every(0) = rule.trigger {
+            diagnostics.push(
+                Diagnostic::new(
+                    "R206",
+                    format!("Rule '{}' uses 'every' with a zero interval", rule.name),
+                )
+                .with_span(&rule.span),
+            );
+        }
         for stmt in &rule.body {
-            if let RuleStmt::Emit { event } = stmt {
+            if let RuleStmt::Emit { event, span } = stmt {
                 if !is_valid_ident(event) {
-                    diagnostics.push(Diagnostic::new(
-                        "R205",
-                        format!("Rule '{}' emits invalid event name '{}'", rule.name, event),
-                    ));
+                    diagnostics.push(
+                        Diagnostic::new(
+                            "R205",
+                            format!("Rule '{}' emits invalid event name '{}'", rule.name, event),
+                        )
+                        .with_span(span),
+                    );
                 }
             }
         }
@@ -429,19 +794,22 @@ fn collect_module_defines(
 ) -> HashMap<String, usize> {
     let mut out = HashMap::new();
     for item in &module.items {
-        if let Item::Define(SoftDefine { name, params, .. }) = item {
+        if let Item::Define(SoftDefine {
+            name, params, span, ..
+        }) = item
+        {
             if !is_valid_qualified_ident(name) {
-                diagnostics.push(Diagnostic::new(
-                    "N307",
-                    format!("Invalid module define name '{}'", name),
-                ));
+                diagnostics.push(
+                    Diagnostic::new("N307", format!("Invalid module define name '{}'", name))
+                        .with_span(span),
+                );
                 continue;
             }
             if out.insert(name.clone(), params.len()).is_some() {
-                diagnostics.push(Diagnostic::new(
-                    "N307",
-                    format!("Duplicate module define '{}'", name),
-                ));
+                diagnostics.push(
+                    Diagnostic::new("N307", format!("Duplicate module define '{}'", name))
+                        .with_span(span),
+                );
             }
         }
     }
@@ -455,67 +823,85 @@ fn validate_nd_blocks(
 ) {
     for nd in nd_blocks {
         if nd.propose.name.is_empty() {
-            diagnostics.push(Diagnostic::new(
-                "N301",
-                format!("ND '{}' has no propose call", nd.name),
-            ));
+            diagnostics.push(
+                Diagnostic::new("N301", format!("ND '{}' has no propose call", nd.name))
+                    .with_span(&nd.span),
+            );
         }
         if nd.constraints.is_empty() {
-            diagnostics.push(Diagnostic::new(
-                "N303",
-                format!("ND '{}' has empty satisfy()", nd.name),
-            ));
+            diagnostics.push(
+                Diagnostic::new("N303", format!("ND '{}' has empty satisfy()", nd.name))
+                    .with_span(&nd.span),
+            );
         }
         let mut local_defines: HashMap<String, usize> = HashMap::new();
         for d in &nd.defines {
             if !is_valid_qualified_ident(&d.name) {
-                diagnostics.push(Diagnostic::new(
-                    "N307",
-                    format!("ND '{}' has invalid define name '{}'", nd.name, d.name),
-                ));
+                diagnostics.push(
+                    Diagnostic::new(
+                        "N307",
+                        format!("ND '{}' has invalid define name '{}'", nd.name, d.name),
+                    )
+                    .with_span(&d.span),
+                );
                 continue;
             }
             if !local_defines
                 .insert(d.name.clone(), d.params.len())
                 .is_none()
             {
-                diagnostics.push(Diagnostic::new(
-                    "N307",
-                    format!("ND '{}' has duplicate define '{}'", nd.name, d.name),
-                ));
+                diagnostics.push(
+                    Diagnostic::new(
+                        "N307",
+                        format!("ND '{}' has duplicate define '{}'", nd.name, d.name),
+                    )
+                    .with_span(&d.span),
+                );
             }
         }
-        let mut signatures = HashSet::new();
+        let mut signatures: HashMap<String, &Span> = HashMap::new();
         for constraint in &nd.constraints {
             let signature = call_signature(constraint);
-            if !signatures.insert(signature.clone()) {
-                diagnostics.push(Diagnostic::new(
-                    "N304",
-                    format!(
-                        "ND '{}' has duplicate satisfy constraint '{}'",
-                        nd.name, signature
-                    ),
-                ));
+            if let Some(first) = signatures.get(&signature) {
+                diagnostics.push(
+                    Diagnostic::new(
+                        "N304",
+                        format!(
+                            "ND '{}' has duplicate satisfy constraint '{}'",
+                            nd.name, signature
+                        ),
+                    )
+                    .with_span(&constraint.span)
+                    .with_label(first, "first listed here"),
+                );
+            } else {
+                signatures.insert(signature.clone(), &constraint.span);
             }
             if constraint.name == "?prompt" {
                 if constraint.args.len() != 1 {
-                    diagnostics.push(Diagnostic::new(
-                        "N310",
-                        format!(
-                            "ND '{}' inline prompt '?\"...\"' expects exactly 1 string argument",
-                            nd.name
-                        ),
-                    ));
+                    diagnostics.push(
+                        Diagnostic::new(
+                            "N310",
+                            format!(
+                                "ND '{}' inline prompt '?\"...\"' expects exactly 1 string argument",
+                                nd.name
+                            ),
+                        )
+                        .with_span(&constraint.span),
+                    );
                     continue;
                 }
                 if !matches!(constraint.args[0].value, Expr::String(_)) {
-                    diagnostics.push(Diagnostic::new(
-                        "N310",
-                        format!(
-                            "ND '{}' inline prompt '?\"...\"' must be a string literal",
-                            nd.name
-                        ),
-                    ));
+                    diagnostics.push(
+                        Diagnostic::new(
+                            "N310",
+                            format!(
+                                "ND '{}' inline prompt '?\"...\"' must be a string literal",
+                                nd.name
+                            ),
+                        )
+                        .with_span(&constraint.span),
+                    );
                 }
                 continue;
             }
@@ -527,31 +913,74 @@ fn validate_nd_blocks(
                 match expected_arity {
                     Some(expected) => {
                         if expected != constraint.args.len() {
-                            diagnostics.push(Diagnostic::new(
-                                "N308",
-                                format!(
-                                    "ND '{}' soft define '?{}' expects {} arg(s), got {}",
-                                    nd.name,
-                                    raw_name,
-                                    expected,
-                                    constraint.args.len()
-                                ),
-                            ));
+                            diagnostics.push(
+                                Diagnostic::new(
+                                    "N308",
+                                    format!(
+                                        "ND '{}' soft define '?{}' expects {} arg(s), got {}",
+                                        nd.name,
+                                        raw_name,
+                                        expected,
+                                        constraint.args.len()
+                                    ),
+                                )
+                                .with_span(&constraint.span),
+                            );
                         }
                     }
-                    None => diagnostics.push(Diagnostic::new(
-                        "N309",
-                        format!(
-                            "ND '{}' references unknown soft define '?{}'",
-                            nd.name, raw_name
+                    None => diagnostics.push(
+                        Diagnostic::new(
+                            "N309",
+                            format!(
+                                "ND '{}' references unknown soft define '?{}'",
+                                nd.name, raw_name
+                            ),
+                        )
+                        .with_span(&constraint.span)
+                        .did_you_mean(
+                            raw_name,
+                            constraint.span.find_word(raw_name),
+                            local_defines
+                                .keys()
+                                .chain(module_defines.keys())
+                                .map(String::as_str),
                         ),
-                    )),
+                    ),
                 }
             }
         }
     }
 }
 
+fn validate_language_meta(module: &Module, diagnostics: &mut Vec<Diagnostic>) {
+    let Some(raw) = module.meta.get("language") else {
+        return;
+    };
+    match LanguageVersion::parse(raw) {
+        None => diagnostics.push(
+            Diagnostic::new(
+                "M710",
+                format!(
+                    "Invalid language '{}': expected MAJOR.MINOR such as 1.1",
+                    raw
+                ),
+            )
+            .with_meta_span(&module.meta_spans, "language"),
+        ),
+        Some(version) if !version.is_supported() => diagnostics.push(
+            Diagnostic::new(
+                "M711",
+                format!(
+                    "Language {} is not supported by this compiler (supported: {})",
+                    version, LANGUAGE_SUPPORT_RANGE
+                ),
+            )
+            .with_meta_span(&module.meta_spans, "language"),
+        ),
+        Some(_) => {}
+    }
+}
+
 fn validate_convergence_meta(
     module: &Module,
     nd_blocks: &[&NdBlock],
@@ -574,10 +1003,13 @@ fn validate_convergence_meta(
 
     if let Some(raw) = nd_policy {
         if raw != "strict" {
-            diagnostics.push(Diagnostic::new(
-                "M705",
-                format!("Invalid nd_policy '{}': expected strict", raw),
-            ));
+            diagnostics.push(
+                Diagnostic::new(
+                    "M705",
+                    format!("Invalid nd_policy '{}': expected strict", raw),
+                )
+                .with_meta_span(&module.meta_spans, "nd_policy"),
+            );
         }
     }
 
@@ -585,65 +1017,84 @@ fn validate_convergence_meta(
         match raw.parse::<i32>() {
             Ok(value) if (0..=100).contains(&value) => {
                 if !nd_blocks.is_empty() && value == 0 {
-                    diagnostics.push(Diagnostic::new(
-                        "N305",
-                        "nd_budget=0 is incompatible with ND blocks; remove ND or increase budget",
-                    ));
+                    diagnostics.push(
+                        Diagnostic::new(
+                            "N305",
+                            "nd_budget=0 is incompatible with ND blocks; remove ND or increase budget",
+                        )
+                        .with_meta_span(&module.meta_spans, "nd_budget"),
+                    );
                 }
             }
-            _ => diagnostics.push(Diagnostic::new(
-                "M701",
-                format!(
-                    "Invalid nd_budget '{}': expected integer in range 0..100",
-                    raw
-                ),
-            )),
+            _ => diagnostics.push(
+                Diagnostic::new(
+                    "M701",
+                    format!(
+                        "Invalid nd_budget '{}': expected integer in range 0..100",
+                        raw
+                    ),
+                )
+                .with_meta_span(&module.meta_spans, "nd_budget"),
+            ),
         }
     }
 
     if let Some(raw) = confidence {
         match raw.parse::<f64>() {
             Ok(value) if (0.0..=1.0).contains(&value) => {}
-            _ => diagnostics.push(Diagnostic::new(
-                "M702",
-                format!(
-                    "Invalid confidence '{}': expected number in range 0.0..1.0",
-                    raw
-                ),
-            )),
+            _ => diagnostics.push(
+                Diagnostic::new(
+                    "M702",
+                    format!(
+                        "Invalid confidence '{}': expected number in range 0.0..1.0",
+                        raw
+                    ),
+                )
+                .with_meta_span(&module.meta_spans, "confidence"),
+            ),
         }
     }
 
     if let Some(raw) = max_iterations {
         match raw.parse::<u32>() {
             Ok(value) if (1..=10_000).contains(&value) => {}
-            _ => diagnostics.push(Diagnostic::new(
-                "M703",
-                format!(
-                    "Invalid max_iterations '{}': expected integer in range 1..10000",
-                    raw
-                ),
-            )),
+            _ => diagnostics.push(
+                Diagnostic::new(
+                    "M703",
+                    format!(
+                        "Invalid max_iterations '{}': expected integer in range 1..10000",
+                        raw
+                    ),
+                )
+                .with_meta_span(&module.meta_spans, "max_iterations"),
+            ),
         }
     }
 
     if let Some(raw) = fallback {
         if !matches!(raw.as_str(), "fail" | "stub" | "replay") {
-            diagnostics.push(Diagnostic::new(
-                "M704",
-                format!(
-                    "Invalid fallback '{}': expected one of fail|stub|replay",
-                    raw
-                ),
-            ));
+            diagnostics.push(
+                Diagnostic::new(
+                    "M704",
+                    format!(
+                        "Invalid fallback '{}': expected one of fail|stub|replay",
+                        raw
+                    ),
+                )
+                .with_meta_span(&module.meta_spans, "fallback"),
+            );
         }
     }
 }
 
-fn validate_state_execution(flows: &[&Flow], diagnostics: &mut Vec<Diagnostic>) {
-    let known_flows: HashSet<String> = flows.iter().map(|f| f.name.clone()).collect();
+fn validate_state_execution(
+    flows: &[&Flow],
+    imported_roots: &HashSet<String>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let known_flows: HashMap<&str, &Flow> = flows.iter().map(|f| (f.name.as_str(), *f)).collect();
     for flow in flows {
-        for state in &flow.states {
+        for state in flow.all_states() {
             let state_name = state.name.as_deref().unwrap_or("<unnamed>");
             let mut run_targets = Vec::new();
             let mut has_done_handler = false;
@@ -651,24 +1102,45 @@ fn validate_state_execution(flows: &[&Flow], diagnostics: &mut Vec<Diagnostic>)
             for (idx, stmt) in state.statements.iter().enumerate() {
                 if let StateStmt::Terminate = stmt {
                     if idx + 1 != state.statements.len() {
-                        diagnostics.push(Diagnostic::new(
-                            "B402",
-                            format!(
-                                "terminate must be the last statement in '{}.{}'",
-                                flow.name, state_name
-                            ),
-                        ));
+                        diagnostics.push(
+                            Diagnostic::new(
+                                "B402",
+                                format!(
+                                    "terminate must be the last statement in '{}.{}'",
+                                    flow.name, state_name
+                                ),
+                            )
+                            .with_span(&state.span),
+                        );
                     }
                     if state.statements.len() > 1 {
-                        diagnostics.push(Diagnostic::new(
-                            "B402",
-                            format!(
-                                "terminate cannot be combined with other statements in '{}.{}'",
-                                flow.name, state_name
-                            ),
-                        ));
+                        diagnostics.push(
+                            Diagnostic::new(
+                                "B402",
+                                format!(
+                                    "terminate cannot be combined with other statements in '{}.{}'",
+                                    flow.name, state_name
+                                ),
+                            )
+                            .with_span(&state.span),
+                        );
                     }
                 }
+                if let StateStmt::Return { value, span } = stmt {
+                    if idx + 1 != state.statements.len() {
+                        diagnostics.push(
+                            Diagnostic::new(
+                                "B402",
+                                format!(
+                                    "return must be the last statement in '{}.{}'",
+                                    flow.name, state_name
+                                ),
+                            )
+                            .with_span(span),
+                        );
+                    }
+                    validate_return(flow, state_name, value.is_some(), span, diagnostics);
+                }
                 if let StateStmt::On { event, .. } = stmt {
                     if event.name == "done" {
                         has_done_handler = true;
@@ -676,43 +1148,189 @@ fn validate_state_execution(flows: &[&Flow], diagnostics: &mut Vec<Diagnostic>)
                 }
             }
 
+            let mut run_spans = Vec::new();
             for stmt in &state.statements {
-                if let StateStmt::Run { flow: run_target } = stmt {
+                if let StateStmt::Run {
+                    flow: run_target,
+                    args,
+                    result,
+                    span,
+                } = stmt
+                {
                     run_targets.push(run_target.clone());
-                    if !known_flows.contains(run_target) {
-                        diagnostics.push(Diagnostic::new(
-                            "B401",
-                            format!(
-                                "Unknown flow '{}' referenced by run in '{}.{}'",
-                                run_target, flow.name, state_name
+                    run_spans.push(span);
+                    if let Some(target) = known_flows.get(run_target.as_str()) {
+                        validate_run_call(target, args, result.as_deref(), span, diagnostics);
+                    } else if !run_target
+                        .split('.')
+                        .next()
+                        .is_some_and(|root| imported_roots.contains(root))
+                    {
+                        diagnostics.push(
+                            Diagnostic::new(
+                                "B401",
+                                format!(
+                                    "Unknown flow '{}' referenced by run in '{}.{}'",
+                                    run_target, flow.name, state_name
+                                ),
+                            )
+                            .with_span(span)
+                            .did_you_mean(
+                                run_target,
+                                span.find_word(run_target),
+                                known_flows.keys().copied(),
                             ),
-                        ));
+                        );
                     }
                 }
             }
 
             if run_targets.len() > 1 {
-                diagnostics.push(Diagnostic::new(
-                    "B403",
-                    format!(
-                        "State '{}.{}' has multiple run targets ({})",
-                        flow.name,
-                        state_name,
-                        run_targets.join(", ")
-                    ),
-                ));
+                diagnostics.push(
+                    Diagnostic::new(
+                        "B403",
+                        format!(
+                            "State '{}.{}' has multiple run targets ({})",
+                            flow.name,
+                            state_name,
+                            run_targets.join(", ")
+                        ),
+                    )
+                    .with_span(run_spans[1])
+                    .with_label(run_spans[0], "first run here"),
+                );
             }
             if !run_targets.is_empty() && !has_done_handler {
-                diagnostics.push(Diagnostic::new(
-                    "B404",
+                diagnostics.push(
+                    Diagnostic::new(
+                        "B404",
+                        format!(
+                            "State '{}.{}' uses run without an explicit on done > ... transition",
+                            flow.name, state_name
+                        ),
+                    )
+                    .with_span(run_spans[0]),
+                );
+            }
+        }
+    }
+}
+
+/// Checks a `run` call site against the parameters and result type of the target flow.
+fn validate_run_call(
+    target: &Flow,
+    args: &[CallArg],
+    result: Option<&str>,
+    span: &Span,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if args.len() > target.params.len() {
+        diagnostics.push(
+            Diagnostic::new(
+                "B405",
+                format!(
+                    "Flow '{}' takes {} argument(s), got {}",
+                    target.name,
+                    target.params.len(),
+                    args.len()
+                ),
+            )
+            .with_span(span)
+            .with_label(&target.span, "flow declared here"),
+        );
+    }
+    let mut bound: HashSet<&str> = HashSet::new();
+    let mut position = 0;
+    for arg in args {
+        let param = match &arg.name {
+            Some(name) => match target.params.iter().find(|p| &p.name == name) {
+                Some(param) => param,
+                None => {
+                    diagnostics.push(
+                        Diagnostic::new(
+                            "B405",
+                            format!("Flow '{}' has no parameter '{}'", target.name, name),
+                        )
+                        .with_span(&arg.span),
+                    );
+                    continue;
+                }
+            },
+            None => {
+                position += 1;
+                match target.params.get(position - 1) {
+                    Some(param) => param,
+                    None => continue,
+                }
+            }
+        };
+        if !bound.insert(param.name.as_str()) {
+            diagnostics.push(
+                Diagnostic::new(
+                    "B405",
                     format!(
-                        "State '{}.{}' uses run without an explicit on done > ... transition",
-                        flow.name, state_name
+                        "Parameter '{}' of flow '{}' is passed twice",
+                        param.name, target.name
                     ),
-                ));
-            }
+                )
+                .with_span(&arg.span),
+            );
         }
     }
+    let missing: Vec<&str> = target
+        .params
+        .iter()
+        .map(|p| p.name.as_str())
+        .filter(|name| !bound.contains(name))
+        .collect();
+    if !missing.is_empty() {
+        diagnostics.push(
+            Diagnostic::new(
+                "B405",
+                format!(
+                    "run {} is missing argument(s) for {}",
+                    target.name,
+                    missing.join(", ")
+                ),
+            )
+            .with_span(span)
+            .with_label(&target.span, "flow declared here"),
+        );
+    }
+    if let (Some(result), None) = (result, &target.returns) {
+        diagnostics.push(
+            Diagnostic::new(
+                "B406",
+                format!(
+                    "run {} binds '{}', but flow '{}' returns no value",
+                    target.name, result, target.name
+                ),
+            )
+            .with_span(span)
+            .with_label(&target.span, "flow declared here"),
+        );
+    }
+}
+
+fn validate_return(
+    flow: &Flow,
+    state_name: &str,
+    has_value: bool,
+    span: &Span,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let message = match (&flow.returns, has_value) {
+        (Some(ty), false) => format!(
+            "return in '{}.{}' needs a value: flow '{}' returns {}",
+            flow.name, state_name, flow.name, ty
+        ),
+        (None, true) => format!(
+            "return in '{}.{}' has a value, but flow '{}' declares no return type",
+            flow.name, state_name, flow.name
+        ),
+        _ => return,
+    };
+    diagnostics.push(Diagnostic::new("B407", message).with_span(span));
 }
 
 fn validate_nd_guardrails(
@@ -730,64 +1348,98 @@ fn validate_nd_guardrails(
         return;
     }
     if mode != "warn" && mode != "error" {
-        diagnostics.push(Diagnostic::new(
-            "M706",
-            format!(
-                "Invalid nd_critical_path '{}': expected off|warn|error",
-                mode
-            ),
-        ));
+        diagnostics.push(
+            Diagnostic::new(
+                "M706",
+                format!(
+                    "Invalid nd_critical_path '{}': expected off|warn|error",
+                    mode
+                ),
+            )
+            .with_meta_span(&module.meta_spans, "nd_critical_path"),
+        );
         return;
     }
 
-    let mut report = |context: String| {
+    let mut report = |context: String, span: &Span| {
         let msg = format!(
             "Critical deterministic path contains ND marker in {} (use deterministic symbols only)",
             context
         );
         if mode == "error" {
-            diagnostics.push(Diagnostic::new("N320", msg));
+            diagnostics.push(Diagnostic::new("N320", msg).with_span(span));
         } else {
-            diagnostics.push(Diagnostic::warn("N320", msg));
+            diagnostics.push(Diagnostic::warn("N320", msg).with_span(span));
         }
     };
 
     for flow in flows {
-        for state in &flow.states {
+        for state in flow.all_states() {
             let state_name = state.name.as_deref().unwrap_or("<unnamed>");
             for stmt in &state.statements {
                 match stmt {
-                    StateStmt::Assign { value, .. } => {
+                    StateStmt::Assign { value, span, .. } => {
                         if expr_has_nd_marker(value) {
-                            report(format!(
-                                "flow '{}', state '{}', assignment",
-                                flow.name, state_name
-                            ));
+                            report(
+                                format!("flow '{}', state '{}', assignment", flow.name, state_name),
+                                span,
+                            );
                         }
                     }
                     StateStmt::Expr(call) => {
                         if call_has_nd_marker(call) {
-                            report(format!(
-                                "flow '{}', state '{}', expression '{}'",
-                                flow.name, state_name, call.name
-                            ));
+                            report(
+                                format!(
+                                    "flow '{}', state '{}', expression '{}'",
+                                    flow.name, state_name, call.name
+                                ),
+                                &call.span,
+                            );
                         }
                     }
                     StateStmt::Rule(rule) => {
                         if let RuleTrigger::When(expr) = &rule.trigger {
                             if expr_has_nd_marker(expr) {
-                                report(format!("rule '{}' when-condition", rule.name));
+                                report(format!("rule '{}' when-condition", rule.name), &rule.span);
                             }
                         }
                         for body in &rule.body {
-                            if let RuleStmt::Assign { value, .. } = body {
+                            if let RuleStmt::Assign { value, span, .. } = body {
                                 if expr_has_nd_marker(value) {
-                                    report(format!("rule '{}' assignment", rule.name));
+                                    report(format!("rule '{}' assignment", rule.name), span);
                                 }
                             }
                         }
                     }
-                    StateStmt::On { .. } | StateStmt::Run { .. } | StateStmt::Terminate => {}
+                    StateStmt::Run { args, span, .. } => {
+                        if args.iter().any(|arg| expr_has_nd_marker(&arg.value)) {
+                            report(
+                                format!(
+                                    "flow '{}', state '{}', run argument",
+                                    flow.name, state_name
+                                ),
+                                span,
+                            );
+                        }
+                    }
+                    StateStmt::Return {
+                        value: Some(value),
+                        span,
+                    } => {
+                        if expr_has_nd_marker(value) {
+                            report(
+                                format!(
+                                    "flow '{}', state '{}', return value",
+                                    flow.name, state_name
+                                ),
+                                span,
+                            );
+                        }
+                    }
+                    StateStmt::On { .. }
+                    | StateStmt::After { .. }
+                    | StateStmt::Return { .. }
+                    | StateStmt::Terminate => {}
                 }
             }
         }
@@ -796,13 +1448,16 @@ fn validate_nd_guardrails(
     for rule in rules {
         if let RuleTrigger::When(expr) = &rule.trigger {
             if expr_has_nd_marker(expr) {
-                report(format!("module rule '{}' when-condition", rule.name));
+                report(
+                    format!("module rule '{}' when-condition", rule.name),
+                    &rule.span,
+                );
             }
         }
         for body in &rule.body {
-            if let RuleStmt::Assign { value, .. } = body {
+            if let RuleStmt::Assign { value, span, .. } = body {
                 if expr_has_nd_marker(value) {
-                    report(format!("module rule '{}' assignment", rule.name));
+                    report(format!("module rule '{}' assignment", rule.name), span);
                 }
             }
         }
@@ -814,7 +1469,13 @@ fn expr_has_nd_marker(expr: &Expr) -> bool {
         Expr::Ident(id) => id.starts_with('?'),
         Expr::Call(call) => call_has_nd_marker(call),
         Expr::Binary { left, right, .. } => expr_has_nd_marker(left) || expr_has_nd_marker(right),
-        Expr::Number(_) | Expr::String(_) | Expr::Null => false,
+        Expr::Unary { operand, .. } => expr_has_nd_marker(operand),
+        Expr::Interpolated(parts) => parts
+            .iter()
+            .any(|part| matches!(part, StringPart::Expr(expr) if expr_has_nd_marker(expr))),
+        Expr::List(items) => items.iter().any(expr_has_nd_marker),
+        Expr::Record(fields) => fields.iter().any(|f| expr_has_nd_marker(&f.value)),
+        Expr::Number(_) | Expr::Duration(_) | Expr::String(_) | Expr::Bool(_) | Expr::Null => false,
     }
 }
 
@@ -831,6 +1492,7 @@ fn validate_symbol_references(
     rules: &[&Rule],
     known_fqns: &HashSet<String>,
     imported_roots: &HashSet<String>,
+    imports: &[ImportedModule],
     diagnostics: &mut Vec<Diagnostic>,
 ) {
     let mut short_counts: HashMap<String, usize> = HashMap::new();
@@ -840,17 +1502,64 @@ fn validate_symbol_references(
         }
     }
 
-    let mut check_ident = |ident: &str, context: &str| {
+    let mut check_ident = |ident: &str, context: &str, span: &Span| {
         if ident.starts_with('?') {
             // Explicit ND-magic identifier (prefixed with '?') is always allowed.
             return;
         }
         if ident.contains('.') {
             if !is_valid_qualified_ident(ident) {
-                diagnostics.push(Diagnostic::new(
-                    "NS501",
-                    format!("Invalid qualified identifier '{}' in {}", ident, context),
-                ));
+                diagnostics.push(
+                    Diagnostic::new(
+                        "NS501",
+                        format!("Invalid qualified identifier '{}' in {}", ident, context),
+                    )
+                    .with_span(span),
+                );
+                return;
+            }
+            if let Some((import, member)) = imports.iter().find_map(|import| {
+                ident
+                    .strip_prefix(import.prefix.as_str())
+                    .and_then(|rest| rest.strip_prefix('.'))
+                    .map(|member| (import, member))
+            }) {
+                if import.exports.contains(member) {
+                    return;
+                }
+                if import.symbols.contains(member) {
+                    diagnostics.push(
+                        Diagnostic::new(
+                            "NS507",
+                            format!(
+                                "Reference '{}' in {}: module '{}' does not export '{}'",
+                                ident, context, import.module, member
+                            ),
+                        )
+                        .with_span(span),
+                    );
+                } else {
+                    let candidates: Vec<String> = import
+                        .exports
+                        .iter()
+                        .map(|symbol| format!("{}.{}", import.prefix, symbol))
+                        .collect();
+                    diagnostics.push(
+                        Diagnostic::new(
+                            "NS503",
+                            format!(
+                                "Unknown qualified reference '{}' in {}: module '{}' defines no '{}'",
+                                ident, context, import.module, member
+                            ),
+                        )
+                        .with_span(span)
+                        .did_you_mean(
+                            ident,
+                            span.find_word(ident),
+                            candidates.iter().map(String::as_str),
+                        ),
+                    );
+                }
                 return;
             }
             if let Some(root) = ident.split('.').next() {
@@ -860,38 +1569,66 @@ fn validate_symbol_references(
             }
             let module_prefix = format!("{}.", module.name);
             if !ident.starts_with(&module_prefix) && ident != module.name {
-                diagnostics.push(Diagnostic::new(
-                    "NS504",
-                    format!(
-                        "Illegal cross-namespace reference '{}' in {} (missing contract/import)",
-                        ident, context
-                    ),
-                ));
+                diagnostics.push(
+                    Diagnostic::new(
+                        "NS504",
+                        format!(
+                            "Illegal cross-namespace reference '{}' in {} (missing contract/import)",
+                            ident, context
+                        ),
+                    )
+                    .with_span(span),
+                );
                 return;
             }
             if !known_fqns.contains(ident) {
-                diagnostics.push(Diagnostic::new(
-                    "NS503",
-                    format!("Unknown qualified reference '{}' in {}", ident, context),
-                ));
+                diagnostics.push(
+                    Diagnostic::new(
+                        "NS503",
+                        format!("Unknown qualified reference '{}' in {}", ident, context),
+                    )
+                    .with_span(span)
+                    .did_you_mean(
+                        ident,
+                        span.find_word(ident),
+                        known_fqns.iter().map(String::as_str),
+                    ),
+                );
             }
         } else if short_counts.get(ident).copied().unwrap_or(0) > 1 {
-            diagnostics.push(Diagnostic::new(
-                "NS506",
-                format!("Ambiguous unqualified reference '{}' in {}", ident, context),
-            ));
+            diagnostics.push(
+                Diagnostic::new(
+                    "NS506",
+                    format!("Ambiguous unqualified reference '{}' in {}", ident, context),
+                )
+                .with_span(span),
+            );
         }
     };
 
     for flow in flows {
-        for state in &flow.states {
+        for state in flow.all_states() {
             let state_name = state.name.as_deref().unwrap_or("<unnamed>");
             for stmt in &state.statements {
                 match stmt {
-                    StateStmt::On { event, target } => {
+                    StateStmt::On {
+                        event,
+                        guard,
+                        target,
+                        span,
+                    } => {
+                        if let Some(guard) = guard {
+                            walk_expr_idents(
+                                guard,
+                                &format!("transition guard {}.{}", flow.name, state_name),
+                                span,
+                                &mut check_ident,
+                            );
+                        }
                         check_ident(
                             target,
                             &format!("state transition {}.{}", flow.name, state_name),
+                            span,
                         );
                         walk_call_idents(
                             event,
@@ -899,17 +1636,50 @@ fn validate_symbol_references(
                             &mut check_ident,
                         );
                     }
-                    StateStmt::Run { flow: target_flow } => {
-                        check_ident(target_flow, &format!("run {}.{}", flow.name, state_name));
+                    StateStmt::After { target, span, .. } => check_ident(
+                        target,
+                        &format!("timer transition {}.{}", flow.name, state_name),
+                        span,
+                    ),
+                    StateStmt::Run {
+                        flow: target_flow,
+                        args,
+                        result,
+                        span,
+                    } => {
+                        let context = format!("run {}.{}", flow.name, state_name);
+                        check_ident(target_flow, &context, span);
+                        for arg in args {
+                            walk_expr_idents(&arg.value, &context, &arg.span, &mut check_ident);
+                        }
+                        if let Some(result) = result {
+                            check_ident(result, &context, span);
+                        }
                     }
-                    StateStmt::Assign { target, value, .. } => {
+                    StateStmt::Return {
+                        value: Some(value),
+                        span,
+                    } => walk_expr_idents(
+                        value,
+                        &format!("return {}.{}", flow.name, state_name),
+                        span,
+                        &mut check_ident,
+                    ),
+                    StateStmt::Assign {
+                        target,
+                        value,
+                        span,
+                        ..
+                    } => {
                         check_ident(
                             target,
                             &format!("assignment target {}.{}", flow.name, state_name),
+                            span,
                         );
                         walk_expr_idents(
                             value,
                             &format!("assignment value {}.{}", flow.name, state_name),
+                            span,
                             &mut check_ident,
                         );
                     }
@@ -936,18 +1706,26 @@ fn validate_symbol_references(
                                     "state rule trigger {}.{}.{}",
                                     flow.name, state_name, rule.name
                                 ),
+                                &rule.span,
                                 &mut check_ident,
                             ),
+                            RuleTrigger::Every(_) => {}
                         }
                         for stmt in &rule.body {
                             match stmt {
-                                RuleStmt::Assign { target, value, .. } => {
+                                RuleStmt::Assign {
+                                    target,
+                                    value,
+                                    span,
+                                    ..
+                                } => {
                                     check_ident(
                                         target,
                                         &format!(
                                             "state rule assignment target {}.{}.{}",
                                             flow.name, state_name, rule.name
                                         ),
+                                        span,
                                     );
                                     walk_expr_idents(
                                         value,
@@ -955,22 +1733,24 @@ fn validate_symbol_references(
                                             "state rule assignment value {}.{}.{}",
                                             flow.name, state_name, rule.name
                                         ),
+                                        span,
                                         &mut check_ident,
                                     );
                                 }
-                                RuleStmt::Emit { event } => {
+                                RuleStmt::Emit { event, span } => {
                                     check_ident(
                                         event,
                                         &format!(
                                             "state rule emit {}.{}.{}",
                                             flow.name, state_name, rule.name
                                         ),
+                                        span,
                                     );
                                 }
                             }
                         }
                     }
-                    StateStmt::Terminate => {}
+                    StateStmt::Return { value: None, .. } | StateStmt::Terminate => {}
                 }
             }
         }
@@ -986,21 +1766,33 @@ fn validate_symbol_references(
             crate::ast::RuleTrigger::When(expr) => walk_expr_idents(
                 expr,
                 &format!("rule trigger {}", rule.name),
+                &rule.span,
                 &mut check_ident,
             ),
+            crate::ast::RuleTrigger::Every(_) => {}
         }
         for stmt in &rule.body {
             match stmt {
-                crate::ast::RuleStmt::Assign { target, value, .. } => {
-                    check_ident(target, &format!("rule assignment target {}", rule.name));
+                crate::ast::RuleStmt::Assign {
+                    target,
+                    value,
+                    span,
+                    ..
+                } => {
+                    check_ident(
+                        target,
+                        &format!("rule assignment target {}", rule.name),
+                        span,
+                    );
                     walk_expr_idents(
                         value,
                         &format!("rule assignment value {}", rule.name),
+                        span,
                         &mut check_ident,
                     );
                 }
-                crate::ast::RuleStmt::Emit { event } => {
-                    check_ident(event, &format!("rule emit {}", rule.name));
+                crate::ast::RuleStmt::Emit { event, span } => {
+                    check_ident(event, &format!("rule emit {}", rule.name), span);
                 }
             }
         }
@@ -1032,13 +1824,200 @@ fn validate_shadowing(module: &Module, rules: &[&Rule], diagnostics: &mut Vec<Di
     for rule in rules {
         for param in &rule.params {
             if global_vars.contains(param) {
-                diagnostics.push(Diagnostic::new(
-                    "NS505",
+                diagnostics.push(
+                    Diagnostic::new(
+                        "NS505",
+                        format!(
+                            "Rule '{}' parameter '{}' shadows global symbol in strict scope mode",
+                            rule.name, param
+                        ),
+                    )
+                    .with_span(&rule.span),
+                );
+            }
+        }
+    }
+}
+
+fn validate_annotations(
+    flows: &[&Flow],
+    rules: &[&Rule],
+    nd_blocks: &[&NdBlock],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut items: Vec<(String, &Annotations, bool)> = Vec::new();
+    for flow in flows {
+        items.push((format!("flow '{}'", flow.name), &flow.annotations, false));
+        for state in flow.all_states() {
+            let state_name = state.name.as_deref().unwrap_or("<unnamed>");
+            items.push((
+                format!("state '{}.{}'", flow.name, state_name),
+                &state.annotations,
+                false,
+            ));
+        }
+    }
+    for rule in rules {
+        items.push((format!("rule '{}'", rule.name), &rule.annotations, true));
+    }
+    for nd in nd_blocks {
+        items.push((format!("nd '{}'", nd.name), &nd.annotations, false));
+    }
+
+    for (item, annotations, is_rule) in items {
+        let mut seen: HashMap<&str, &Span> = HashMap::new();
+        for attr in &annotations.attrs {
+            if let Some(first) = seen.insert(attr.name.as_str(), &attr.span) {
+                diagnostics.push(
+                    Diagnostic::new(
+                        "M708",
+                        format!("Duplicate attribute '@{}' on {}", attr.name, item),
+                    )
+                    .with_span(&attr.span)
+                    .with_label(first, "first attribute here"),
+                );
+                continue;
+            }
+            if attr.name == "priority" && !is_rule {
+                diagnostics.push(
+                    Diagnostic::new(
+                        "M708",
+                        format!("Attribute '@priority' only applies to rules, not {}", item),
+                    )
+                    .with_span(&attr.span),
+                );
+                continue;
+            }
+            let positional_text = |max: usize| {
+                attr.args.len() <= max
+                    && attr.args.iter().all(|arg| {
+                        arg.name.is_none() && matches!(arg.value, Expr::String(_) | Expr::Ident(_))
+                    })
+            };
+            let expected = match attr.name.as_str() {
+                "owner" if attr.args.len() != 1 || !positional_text(1) => "a single owner name",
+                "deprecated" if !positional_text(1) => "an optional message string",
+                "critical" if !attr.args.is_empty() => "no arguments",
+                "priority" if attr.int_arg().is_none() => "a single whole number",
+                "owner" | "deprecated" | "critical" | "priority" => continue,
+                _ => {
+                    diagnostics.push(
+                        Diagnostic::warn(
+                            "M707",
+                            format!(
+                                "Unknown attribute '@{}' on {} (expected @owner, @deprecated, @critical or @priority)",
+                                attr.name, item
+                            ),
+                        )
+                        .with_span(&attr.span),
+                    );
+                    continue;
+                }
+            };
+            diagnostics.push(
+                Diagnostic::new(
+                    "M708",
                     format!(
-                        "Rule '{}' parameter '{}' shadows global symbol in strict scope mode",
-                        rule.name, param
+                        "Invalid arguments for '@{}' on {} (expected {})",
+                        attr.name, item, expected
                     ),
-                ));
+                )
+                .with_span(&attr.span),
+            );
+        }
+    }
+}
+
+/// Warns when a `run` or a transition reaches a `@deprecated` flow or state. Deprecated code
+/// may keep using other deprecated code without warnings.
+fn validate_deprecated_uses(flows: &[&Flow], diagnostics: &mut Vec<Diagnostic>) {
+    let known_flows: HashMap<&str, &Flow> = flows.iter().map(|f| (f.name.as_str(), *f)).collect();
+    let deprecation = |kind: &str, name: &str, message: &str| {
+        if message.is_empty() {
+            format!("{} '{}' is deprecated", kind, name)
+        } else {
+            format!("{} '{}' is deprecated: {}", kind, name, message)
+        }
+    };
+    for flow in flows {
+        if flow.annotations.deprecated().is_some() {
+            continue;
+        }
+        let states = flow.all_states();
+        let deprecated_states: HashMap<&str, (&Span, String)> = states
+            .iter()
+            .filter_map(|state| {
+                let attr = state.annotations.attr("deprecated")?;
+                let message = state.annotations.deprecated()?;
+                Some((state.name.as_deref()?, (&attr.span, message)))
+            })
+            .collect();
+        let check_target = |target: &str, span: &Span, diagnostics: &mut Vec<Diagnostic>| {
+            if let Some((attr_span, message)) = deprecated_states.get(target) {
+                diagnostics.push(
+                    Diagnostic::warn(
+                        "M709",
+                        format!(
+                            "{} (transition in flow '{}')",
+                            deprecation("State", target, message),
+                            flow.name
+                        ),
+                    )
+                    .with_span(span)
+                    .with_label(attr_span, "deprecated here"),
+                );
+            }
+        };
+        if let (Some(start), Some(span)) = (&flow.start, &flow.start_span) {
+            check_target(start, span, diagnostics);
+        }
+        for state in &states {
+            if state.annotations.deprecated().is_some() {
+                continue;
+            }
+            let state_name = state.name.as_deref().unwrap_or("<unnamed>");
+            if let (Some(start), Some(span)) = (&state.start, &state.start_span) {
+                check_target(start, span, diagnostics);
+            }
+            for region in &state.regions {
+                if let (Some(start), Some(span)) = (&region.start, &region.start_span) {
+                    check_target(start, span, diagnostics);
+                }
+            }
+            for stmt in &state.statements {
+                match stmt {
+                    StateStmt::On { target, span, .. } | StateStmt::After { target, span, .. } => {
+                        check_target(target, span, diagnostics)
+                    }
+                    StateStmt::Run {
+                        flow: run_target,
+                        span,
+                        ..
+                    } => {
+                        let Some(target) = known_flows.get(run_target.as_str()) else {
+                            continue;
+                        };
+                        if let (Some(attr), Some(message)) = (
+                            target.annotations.attr("deprecated"),
+                            target.annotations.deprecated(),
+                        ) {
+                            diagnostics.push(
+                                Diagnostic::warn(
+                                    "M709",
+                                    format!(
+                                        "{} (run in '{}.{}')",
+                                        deprecation("Flow", run_target, &message),
+                                        flow.name,
+                                        state_name
+                                    ),
+                                )
+                                .with_span(span)
+                                .with_label(&attr.span, "deprecated here"),
+                            );
+                        }
+                    }
+                    _ => {}
+                }
             }
         }
     }
@@ -1063,11 +2042,37 @@ fn call_signature(call: &Call) -> String {
 fn expr_kind(expr: &Expr) -> String {
     match expr {
         Expr::Number(n) => format!("number:{n}"),
+        Expr::Duration(ms) => format!("duration:{ms}"),
         Expr::String(s) => format!("string:{s}"),
+        Expr::Interpolated(parts) => format!(
+            "template:[{}]",
+            parts
+                .iter()
+                .map(|part| match part {
+                    StringPart::Text(text) => format!("text:{text}"),
+                    StringPart::Expr(expr) => expr_kind(expr),
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        Expr::Bool(b) => format!("bool:{b}"),
         Expr::Null => "null".to_string(),
         Expr::Ident(s) => format!("ident:{s}"),
         Expr::Call(c) => format!("call:{}", call_signature(c)),
         Expr::Binary { op, .. } => format!("binary:{op:?}"),
+        Expr::Unary { op, .. } => format!("unary:{op:?}"),
+        Expr::List(items) => format!(
+            "list:[{}]",
+            items.iter().map(expr_kind).collect::<Vec<_>>().join(",")
+        ),
+        Expr::Record(fields) => format!(
+            "record:{{{}}}",
+            fields
+                .iter()
+                .map(|f| format!("{}={}", f.key, expr_kind(&f.value)))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
     }
 }
 
@@ -1078,13 +2083,34 @@ fn is_supported_when_expr(expr: &Expr) -> bool {
                 is_supported_when_expr(left) && is_supported_when_expr(right)
             }
             BinaryOp::Gte | BinaryOp::Gt | BinaryOp::Lt | BinaryOp::Eq | BinaryOp::Neq => {
-                matches!(**left, Expr::Ident(_))
-                    && matches!(
-                        **right,
-                        Expr::Number(_) | Expr::String(_) | Expr::Null | Expr::Ident(_)
-                    )
+                is_runtime_value_expr(left) && is_runtime_value_expr(right)
             }
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => false,
         },
+        Expr::Unary {
+            op: UnaryOp::Not,
+            operand,
+        } => is_supported_when_expr(operand),
+        _ => false,
+    }
+}
+
+/// Operands a `when` comparison can evaluate at runtime: literals, state variables and
+/// arithmetic over them.
+fn is_runtime_value_expr(expr: &Expr) -> bool {
+    match expr {
+        Expr::Number(_) | Expr::String(_) | Expr::Bool(_) | Expr::Null | Expr::Ident(_) => true,
+        Expr::Binary { op, left, right } => {
+            matches!(
+                op,
+                BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod
+            ) && is_runtime_value_expr(left)
+                && is_runtime_value_expr(right)
+        }
+        Expr::Unary {
+            op: UnaryOp::Neg,
+            operand,
+        } => is_runtime_value_expr(operand),
         _ => false,
     }
 }
@@ -1107,28 +2133,48 @@ fn is_valid_qualified_ident(name: &str) -> bool {
 
 fn walk_call_idents<F>(call: &Call, context: &str, on_ident: &mut F)
 where
-    F: FnMut(&str, &str),
+    F: FnMut(&str, &str, &Span),
 {
-    on_ident(&call.name, context);
+    on_ident(&call.name, context, &call.span);
     for arg in &call.args {
         if let Some(name) = &arg.name {
-            on_ident(name, context);
+            on_ident(name, context, &arg.span);
         }
-        walk_expr_idents(&arg.value, context, on_ident);
+        walk_expr_idents(&arg.value, context, &arg.span, on_ident);
     }
 }
 
-fn walk_expr_idents<F>(expr: &Expr, context: &str, on_ident: &mut F)
+/// Visits identifiers in `expr`. Expressions carry no spans of their own, so identifiers
+/// are reported at `span`, the innermost enclosing node that has one.
+fn walk_expr_idents<F>(expr: &Expr, context: &str, span: &Span, on_ident: &mut F)
 where
-    F: FnMut(&str, &str),
+    F: FnMut(&str, &str, &Span),
 {
     match expr {
-        Expr::Ident(name) => on_ident(name, context),
+        Expr::Ident(name) => on_ident(name, context, span),
         Expr::Call(call) => walk_call_idents(call, context, on_ident),
         Expr::Binary { left, right, .. } => {
-            walk_expr_idents(left, context, on_ident);
-            walk_expr_idents(right, context, on_ident);
+            walk_expr_idents(left, context, span, on_ident);
+            walk_expr_idents(right, context, span, on_ident);
+        }
+        Expr::Unary { operand, .. } => walk_expr_idents(operand, context, span, on_ident),
+        Expr::Interpolated(parts) => {
+            for part in parts {
+                if let StringPart::Expr(expr) = part {
+                    walk_expr_idents(expr, context, span, on_ident);
+                }
+            }
+        }
+        Expr::List(items) => {
+            for item in items {
+                walk_expr_idents(item, context, span, on_ident);
+            }
+        }
+        Expr::Record(fields) => {
+            for field in fields {
+                walk_expr_idents(&field.value, context, span, on_ident);
+            }
         }
-        Expr::Number(_) | Expr::String(_) | Expr::Null => {}
+        Expr::Number(_) | Expr::Duration(_) | Expr::String(_) | Expr::Bool(_) | Expr::Null => {}
     }
 }
@@ -0,0 +1,412 @@
+//! Interactions between rules: two rules that can fire in the same cycle and assign the same
+//! variable (`R210`), and rules whose `emit`s trigger each other in a loop (`R211`).
+//!
+//! Rules run in ascending `@priority` order and in source order among equal priorities, so
+//! the last writer wins. A conflict is only reported between rules of equal priority; giving
+//! one of them a higher `@priority` states which assignment is meant to win.
+//!
+//! Two rules can fire together when their scopes can be active at the same time (a module
+//! rule, the same state, a state and one of its ancestors, or states in different regions of
+//! a parallel state) and their triggers match: the same `on` event, `when` rules whose
+//! conditions are not plainly exclusive, or a `when` rule and an `on tick` rule, since `when`
+//! conditions are checked on every tick. `every(...)` rules each have their own timer and
+//! never share a cycle with another rule.
+
+use std::collections::{HashMap, HashSet};
+
+use super::dataflow::expr_reads;
+use crate::ast::{
+    AssignOp, BinaryOp, Call, Expr, Flow, Rule, RuleStmt, RuleTrigger, Span, StateBlock, StateStmt,
+};
+use crate::diagnostics::Diagnostic;
+use crate::formatter::expr_text;
+
+pub(super) fn analyze_rules(flows: &[&Flow], rules: &[&Rule], diagnostics: &mut Vec<Diagnostic>) {
+    let scopes = Scopes::new(flows);
+    let mut ordered: Vec<&Rule> = rules.to_vec();
+    ordered.sort_by_key(|rule| (rule.annotations.priority(), rule.span.start));
+
+    for (idx, first) in ordered.iter().enumerate() {
+        for second in &ordered[idx + 1..] {
+            if first.annotations.priority() != second.annotations.priority()
+                || !triggers_overlap(&first.trigger, &second.trigger)
+                || !scopes.overlap(first, second)
+            {
+                continue;
+            }
+            report_write_conflicts(first, second, diagnostics);
+        }
+    }
+
+    report_emit_cycles(flows, &ordered, &scopes, diagnostics);
+}
+
+fn report_write_conflicts(first: &Rule, second: &Rule, diagnostics: &mut Vec<Diagnostic>) {
+    let mut reported = HashSet::new();
+    for stmt in &second.body {
+        let RuleStmt::Assign {
+            target, op, value, ..
+        } = stmt
+        else {
+            continue;
+        };
+        if reported.contains(target.as_str()) {
+            continue;
+        }
+        let earlier: Vec<_> = assigns_of(first, target).collect();
+        let Some((_, _, first_span)) = earlier.first() else {
+            continue;
+        };
+        if !earlier.iter().any(|(first_op, first_value, _)| {
+            order_dependent(target, (first_op, first_value), (op, value))
+        }) {
+            continue;
+        }
+        reported.insert(target.as_str());
+        diagnostics.push(
+            Diagnostic::warn(
+                "R210",
+                format!(
+                    "Rules '{}' and '{}' can fire in the same cycle and both assign '{}'; '{}' runs last and wins (use @priority to choose)",
+                    first.name, second.name, target, second.name
+                ),
+            )
+            .with_span(stmt_span(stmt))
+            .with_label(first_span, format!("'{}' assigns it here", first.name)),
+        );
+    }
+}
+
+fn assigns_of<'a>(
+    rule: &'a Rule,
+    var: &'a str,
+) -> impl Iterator<Item = (&'a AssignOp, &'a Expr, &'a Span)> + 'a {
+    rule.body.iter().filter_map(move |stmt| match stmt {
+        RuleStmt::Assign {
+            target,
+            op,
+            value,
+            span,
+        } if target == var => Some((op, value, span)),
+        _ => None,
+    })
+}
+
+/// Whether the final value of `var` depends on which of two writes runs last: not when both
+/// update it (`+=`, or a value computed from `var` itself), since both updates apply, and
+/// not when both set the same value.
+fn order_dependent(
+    var: &str,
+    (op_a, value_a): (&AssignOp, &Expr),
+    (op_b, value_b): (&AssignOp, &Expr),
+) -> bool {
+    let updates = |op: &AssignOp, value: &Expr| {
+        let mut reads = Vec::new();
+        expr_reads(value, &mut reads);
+        matches!(op, AssignOp::Add) || reads.contains(&var)
+    };
+    if updates(op_a, value_a) && updates(op_b, value_b) {
+        return false;
+    }
+    !(matches!((op_a, op_b), (AssignOp::Set, AssignOp::Set))
+        && expr_text(value_a, 0) == expr_text(value_b, 0))
+}
+
+fn stmt_span(stmt: &RuleStmt) -> &Span {
+    match stmt {
+        RuleStmt::Assign { span, .. } | RuleStmt::Emit { span, .. } => span,
+    }
+}
+
+fn triggers_overlap(a: &RuleTrigger, b: &RuleTrigger) -> bool {
+    match (a, b) {
+        (RuleTrigger::On(a), RuleTrigger::On(b)) => event_text(a) == event_text(b),
+        (RuleTrigger::When(a), RuleTrigger::When(b)) => !exclusive(a, b),
+        (RuleTrigger::On(event), RuleTrigger::When(_))
+        | (RuleTrigger::When(_), RuleTrigger::On(event)) => is_tick(event),
+        (RuleTrigger::Every(_), _) | (_, RuleTrigger::Every(_)) => false,
+    }
+}
+
+fn event_text(event: &Call) -> String {
+    expr_text(&Expr::Call(event.clone()), 0)
+}
+
+fn is_tick(event: &Call) -> bool {
+    event.args.is_empty() && matches!(event.name.as_str(), "tick" | "input.tick")
+}
+
+/// Conditions that can never hold together: comparisons of the same variable with literals
+/// that leave no common value (`x == 1` and `x == 2`, `hits >= 1` and `hits < 1`), also as
+/// one side of an `and`.
+fn exclusive(a: &Expr, b: &Expr) -> bool {
+    if let Expr::Binary {
+        left,
+        op: BinaryOp::And,
+        right,
+    } = a
+    {
+        return exclusive(left, b) || exclusive(right, b);
+    }
+    if let Expr::Binary {
+        op: BinaryOp::And, ..
+    } = b
+    {
+        return exclusive(b, a);
+    }
+    match (comparison(a), comparison(b)) {
+        (Some((var_a, range_a)), Some((var_b, range_b))) => {
+            var_a == var_b && range_a.disjoint(&range_b)
+        }
+        _ => false,
+    }
+}
+
+/// The values a comparison like `x >= 3` or `mode == "fast"` allows for its variable.
+enum Allowed {
+    Numbers {
+        low: Option<(f64, bool)>,
+        high: Option<(f64, bool)>,
+    },
+    Literal(String),
+}
+
+impl Allowed {
+    fn disjoint(&self, other: &Allowed) -> bool {
+        match (self, other) {
+            (Allowed::Literal(a), Allowed::Literal(b)) => a != b,
+            (
+                Allowed::Numbers { low, high },
+                Allowed::Numbers {
+                    low: other_low,
+                    high: other_high,
+                },
+            ) => below(*high, *other_low) || below(*other_high, *low),
+            _ => false,
+        }
+    }
+}
+
+/// Whether every value up to `high` lies under every value from `low` on.
+fn below(high: Option<(f64, bool)>, low: Option<(f64, bool)>) -> bool {
+    match (high, low) {
+        (Some((high, high_inclusive)), Some((low, low_inclusive))) => {
+            high < low || (high == low && !(high_inclusive && low_inclusive))
+        }
+        _ => false,
+    }
+}
+
+fn comparison(expr: &Expr) -> Option<(&str, Allowed)> {
+    let Expr::Binary { left, op, right } = expr else {
+        return None;
+    };
+    // `3 > x` reads as `x < 3`: with the variable on the right, the bound flips sides.
+    let (var, literal, flipped) = match (left.as_ref(), right.as_ref()) {
+        (Expr::Ident(var), literal) => (var, literal, false),
+        (literal, Expr::Ident(var)) => (var, literal, true),
+        _ => return None,
+    };
+    let allowed = match (literal, op) {
+        (Expr::Number(n), BinaryOp::Eq) => Allowed::Numbers {
+            low: Some((*n, true)),
+            high: Some((*n, true)),
+        },
+        (Expr::Number(n), BinaryOp::Gte | BinaryOp::Gt | BinaryOp::Lt) => {
+            let bound = Some((*n, matches!(op, BinaryOp::Gte)));
+            if matches!(op, BinaryOp::Lt) != flipped {
+                Allowed::Numbers {
+                    low: None,
+                    high: bound,
+                }
+            } else {
+                Allowed::Numbers {
+                    low: bound,
+                    high: None,
+                }
+            }
+        }
+        (Expr::String(_) | Expr::Bool(_), BinaryOp::Eq) => Allowed::Literal(expr_text(literal, 0)),
+        _ => return None,
+    };
+    Some((var.as_str(), allowed))
+}
+
+/// Rules that trigger each other through `emit`, in a loop. Only `on` rules take part:
+/// a `when` rule fires at most once per dispatched event. A loop is left alone when one of
+/// its events also drives a state transition, which can deactivate the rules involved.
+fn report_emit_cycles(
+    flows: &[&Flow],
+    rules: &[&Rule],
+    scopes: &Scopes,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let transition_events: HashSet<&str> = flows
+        .iter()
+        .flat_map(|flow| flow.all_states())
+        .flat_map(|state| &state.statements)
+        .filter_map(|stmt| match stmt {
+            StateStmt::On { event, .. } if event.args.is_empty() => Some(event.name.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    // edges[i] holds (j, event): rule i emits `event`, which triggers rule j.
+    let edges: Vec<Vec<(usize, &str)>> = rules
+        .iter()
+        .map(|rule| {
+            let mut out = Vec::new();
+            for stmt in &rule.body {
+                let RuleStmt::Emit { event, .. } = stmt else {
+                    continue;
+                };
+                if transition_events.contains(event.as_str()) {
+                    continue;
+                }
+                for (idx, other) in rules.iter().enumerate() {
+                    if let RuleTrigger::On(call) = &other.trigger {
+                        if call.args.is_empty()
+                            && call.name == *event
+                            && scopes.overlap(rule, other)
+                        {
+                            out.push((idx, event.as_str()));
+                        }
+                    }
+                }
+            }
+            out
+        })
+        .collect();
+
+    let reach: Vec<HashSet<usize>> = (0..rules.len())
+        .map(|from| {
+            let mut seen = HashSet::new();
+            let mut queue: Vec<usize> = edges[from].iter().map(|(to, _)| *to).collect();
+            while let Some(idx) = queue.pop() {
+                if seen.insert(idx) {
+                    queue.extend(edges[idx].iter().map(|(to, _)| *to));
+                }
+            }
+            seen
+        })
+        .collect();
+
+    let mut grouped = HashSet::new();
+    let mut by_source: Vec<usize> = (0..rules.len()).collect();
+    by_source.sort_by_key(|idx| rules[*idx].span.start);
+    for idx in by_source.iter().copied() {
+        if grouped.contains(&idx) || !reach[idx].contains(&idx) {
+            continue;
+        }
+        let cycle: Vec<usize> = by_source
+            .iter()
+            .copied()
+            .filter(|other| reach[idx].contains(other) && reach[*other].contains(&idx))
+            .collect();
+        grouped.extend(cycle.iter().copied());
+
+        let mut events: Vec<&str> = cycle
+            .iter()
+            .flat_map(|from| &edges[*from])
+            .filter(|(to, _)| cycle.contains(to))
+            .map(|(_, event)| *event)
+            .collect();
+        events.sort_unstable();
+        events.dedup();
+        let names = cycle
+            .iter()
+            .map(|idx| format!("'{}'", rules[*idx].name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut diagnostic = Diagnostic::new(
+            "R211",
+            format!(
+                "Rules {} trigger each other through emit ({}) and can loop forever",
+                names,
+                events
+                    .iter()
+                    .map(|event| format!("'{}'", event))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        )
+        .with_span(&rules[idx].span);
+        for member in &cycle {
+            for stmt in &rules[*member].body {
+                if let RuleStmt::Emit { event, span } = stmt {
+                    if events.contains(&event.as_str()) {
+                        diagnostic = diagnostic.with_label(span, format!("emits '{}'", event));
+                    }
+                }
+            }
+        }
+        diagnostics.push(diagnostic);
+    }
+}
+
+/// The chain of states from the top level of a flow down to a state, each with the index of
+/// the parent region it belongs to.
+type StatePath<'a> = Vec<(&'a str, Option<usize>)>;
+
+/// Where each named state sits in its flow, keyed by flow and state name.
+struct Scopes<'a> {
+    paths: HashMap<(&'a str, &'a str), StatePath<'a>>,
+}
+
+impl<'a> Scopes<'a> {
+    fn new(flows: &[&'a Flow]) -> Self {
+        fn walk<'a>(
+            flow: &'a str,
+            states: &'a [StateBlock],
+            region: Option<usize>,
+            prefix: &[(&'a str, Option<usize>)],
+            paths: &mut HashMap<(&'a str, &'a str), StatePath<'a>>,
+        ) {
+            for state in states {
+                let Some(name) = state.name.as_deref() else {
+                    continue;
+                };
+                let mut path = prefix.to_vec();
+                path.push((name, region));
+                walk(flow, &state.states, None, &path, paths);
+                for (idx, child_region) in state.regions.iter().enumerate() {
+                    walk(flow, &child_region.states, Some(idx), &path, paths);
+                }
+                paths.entry((flow, name)).or_insert(path);
+            }
+        }
+
+        let mut paths = HashMap::new();
+        for flow in flows {
+            walk(&flow.name, &flow.states, None, &[], &mut paths);
+        }
+        Scopes { paths }
+    }
+
+    /// Whether both rules can be active at the same time.
+    fn overlap(&self, a: &Rule, b: &Rule) -> bool {
+        let (Some(state_a), Some(state_b)) = (a.scope_state.as_deref(), b.scope_state.as_deref())
+        else {
+            return true;
+        };
+        if a.scope_flow != b.scope_flow {
+            return false;
+        }
+        let flow = a.scope_flow.as_deref().unwrap_or_default();
+        let (Some(path_a), Some(path_b)) = (
+            self.paths.get(&(flow, state_a)),
+            self.paths.get(&(flow, state_b)),
+        ) else {
+            return false;
+        };
+        match path_a.iter().zip(path_b).position(|(x, y)| x.0 != y.0) {
+            // One state contains the other.
+            None => true,
+            // Siblings are exclusive unless they sit in different regions of a parallel state.
+            Some(idx) => match (path_a[idx].1, path_b[idx].1) {
+                (Some(x), Some(y)) => x != y,
+                _ => false,
+            },
+        }
+    }
+}
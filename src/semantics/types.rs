@@ -0,0 +1,614 @@
+//! Static type checking of state variables.
+//!
+//! Variables take their type from a `name: type` declaration or, when unannotated, from the
+//! values assigned to them (numbers widen from `int` to `float`, `null` fits any type).
+//! Statements are visited in source order: global `state()` blocks, flow states, then rules.
+//! Flow parameters are declared variables; `run Flow(args) -> result` checks the arguments
+//! against them and assigns the flow's return type to `result`.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::ast::{
+    AssignOp, BinaryOp, Call, CallArg, Expr, Flow, Item, Module, Rule, RuleStmt, RuleTrigger, Span,
+    StateStmt, StringPart, TypeName, UnaryOp,
+};
+use crate::contracts::contract_param_types;
+use crate::diagnostics::Diagnostic;
+use crate::formatter::binary_op_text;
+use crate::versioning::{module_language, Feature};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Ty {
+    Int,
+    Float,
+    String,
+    Bool,
+    Record,
+    List(Box<Ty>),
+    Null,
+    /// `any`, or a value the checker cannot see into (calls, unknown names).
+    Unknown,
+}
+
+impl Ty {
+    fn from_name(name: &TypeName) -> Self {
+        match name {
+            TypeName::Int => Ty::Int,
+            TypeName::Float => Ty::Float,
+            TypeName::String => Ty::String,
+            TypeName::Bool => Ty::Bool,
+            TypeName::Record => Ty::Record,
+            TypeName::List(item) => Ty::List(Box::new(Ty::from_name(item))),
+            TypeName::Any => Ty::Unknown,
+        }
+    }
+
+    /// Parses a contract parameter type such as `string` or `list|string`.
+    fn from_spec(spec: &str) -> Vec<Self> {
+        spec.split('|')
+            .filter_map(TypeName::from_keyword)
+            .map(|name| Ty::from_name(&name))
+            .collect()
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, Ty::Int | Ty::Float)
+    }
+
+    fn is_numeric_or_unknown(&self) -> bool {
+        matches!(self, Ty::Int | Ty::Float | Ty::Unknown)
+    }
+
+    /// Whether a value of type `self` may be stored where `target` is expected.
+    fn fits(&self, target: &Ty) -> bool {
+        match (self, target) {
+            (Ty::Unknown, _) | (_, Ty::Unknown) | (Ty::Null, _) => true,
+            (Ty::Int, Ty::Float) => true,
+            (Ty::List(a), Ty::List(b)) => a.fits(b),
+            (a, b) => a == b,
+        }
+    }
+
+    /// Combines the types of two assignments to an unannotated variable; `None` on conflict.
+    fn join(&self, other: &Ty) -> Option<Ty> {
+        match (self, other) {
+            (Ty::Null, t) | (t, Ty::Null) => Some(t.clone()),
+            (Ty::Unknown, _) => Some(Ty::Unknown),
+            (t, Ty::Unknown) => Some(t.clone()),
+            (a, b) if a.is_numeric() && b.is_numeric() => {
+                Some(if a == b { a.clone() } else { Ty::Float })
+            }
+            (Ty::List(a), Ty::List(b)) => Some(Ty::List(Box::new(a.join(b)?))),
+            (a, b) if a == b => Some(a.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ty::Int => f.write_str("int"),
+            Ty::Float => f.write_str("float"),
+            Ty::String => f.write_str("string"),
+            Ty::Bool => f.write_str("bool"),
+            Ty::Record => f.write_str("record"),
+            Ty::List(item) if **item == Ty::Unknown => f.write_str("list"),
+            Ty::List(item) => write!(f, "list<{item}>"),
+            Ty::Null => f.write_str("null"),
+            Ty::Unknown => f.write_str("any"),
+        }
+    }
+}
+
+/// An `int` and a `bool` assigned to the same variable: the pre-bool flag idiom.
+fn is_int_flag(a: &Ty, b: &Ty) -> bool {
+    matches!((a, b), (Ty::Int, Ty::Bool) | (Ty::Bool, Ty::Int))
+}
+
+struct Var {
+    ty: Ty,
+    declared: bool,
+    span: Span,
+}
+
+struct Checker<'a> {
+    vars: HashMap<String, Var>,
+    flows: HashMap<&'a str, &'a Flow>,
+    /// `use` alias -> contract namespace (`use(cli.ui) as screen` maps `screen` to `ui`).
+    namespaces: HashMap<String, String>,
+    /// Whether an `int` flag assigned `true`/`false` is an error ([`Feature::StrictFlagTypes`]).
+    strict_flags: bool,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+pub(super) fn validate_types<'a>(
+    module: &Module,
+    flows: &[&'a Flow],
+    rules: &[&Rule],
+    diagnostics: &'a mut Vec<Diagnostic>,
+) {
+    let namespaces = module
+        .uses
+        .iter()
+        .map(|u| {
+            let namespace = u.path.rsplit('.').next().unwrap_or_default().to_string();
+            (
+                u.alias.clone().unwrap_or_else(|| namespace.clone()),
+                namespace,
+            )
+        })
+        .collect();
+    let mut checker = Checker {
+        vars: HashMap::new(),
+        flows: flows.iter().map(|f| (f.name.as_str(), *f)).collect(),
+        namespaces,
+        strict_flags: module_language(&module.meta).has(Feature::StrictFlagTypes),
+        diagnostics,
+    };
+
+    let global_statements = module.items.iter().filter_map(|item| match item {
+        Item::GlobalState(block) => Some(&block.statements),
+        _ => None,
+    });
+    let state_statements = flows
+        .iter()
+        .flat_map(|flow| flow.all_states().into_iter().map(|state| &state.statements));
+    let blocks: Vec<&Vec<StateStmt>> = global_statements.chain(state_statements).collect();
+
+    for param in flows.iter().flat_map(|flow| flow.params.iter()) {
+        checker.declare(&param.name, Ty::from_name(&param.ty), &param.span);
+    }
+    for stmt in blocks.iter().flat_map(|b| b.iter()) {
+        if let StateStmt::Assign {
+            target,
+            ty: Some(ty),
+            span,
+            ..
+        } = stmt
+        {
+            checker.declare(target, Ty::from_name(ty), span);
+        }
+    }
+    for stmt in blocks.iter().flat_map(|b| b.iter()) {
+        match stmt {
+            StateStmt::Assign {
+                target,
+                value,
+                ty: Some(ty),
+                span,
+                ..
+            } => checker.declaration(target, &Ty::from_name(ty), value, span),
+            StateStmt::Assign {
+                target,
+                op,
+                value,
+                span,
+                ..
+            } => checker.assign(target, op, value, span),
+            StateStmt::Expr(call) => checker.check_call(call),
+            StateStmt::On {
+                guard: Some(guard),
+                span,
+                ..
+            } => {
+                checker.expr(guard, span);
+            }
+            StateStmt::Run {
+                flow,
+                args,
+                result,
+                span,
+            } => checker.run(flow, args, result.as_deref(), span),
+            StateStmt::On { .. }
+            | StateStmt::After { .. }
+            | StateStmt::Rule(_)
+            | StateStmt::Return { .. } => {}
+            StateStmt::Terminate => {}
+        }
+    }
+    for flow in flows {
+        for state in flow.all_states() {
+            for stmt in &state.statements {
+                if let StateStmt::Return {
+                    value: Some(value),
+                    span,
+                } = stmt
+                {
+                    checker.return_value(flow, value, span);
+                }
+            }
+        }
+    }
+    for rule in rules {
+        if let RuleTrigger::When(expr) = &rule.trigger {
+            checker.expr(expr, &rule.span);
+        }
+        for stmt in &rule.body {
+            if let RuleStmt::Assign {
+                target,
+                op,
+                value,
+                span,
+            } = stmt
+            {
+                checker.assign(target, op, value, span);
+            }
+        }
+    }
+}
+
+impl Checker<'_> {
+    fn declare(&mut self, name: &str, ty: Ty, span: &Span) {
+        if let Some(prev) = self.vars.get(name) {
+            if prev.ty != ty {
+                self.diagnostics.push(
+                    Diagnostic::new(
+                        "T805",
+                        format!("'{}' is declared as {} and as {}", name, prev.ty, ty),
+                    )
+                    .with_span(span)
+                    .with_label(&prev.span, "first declared here"),
+                );
+            }
+            return;
+        }
+        self.vars.insert(
+            name.to_string(),
+            Var {
+                ty,
+                declared: true,
+                span: span.clone(),
+            },
+        );
+    }
+
+    /// Checks the initial value of `target: ty = value` against its own annotation.
+    fn declaration(&mut self, target: &str, ty: &Ty, value: &Expr, span: &Span) {
+        let value_ty = self.expr(value, span);
+        if !value_ty.fits(ty) {
+            self.diagnostics.push(
+                Diagnostic::new(
+                    "T801",
+                    format!(
+                        "Cannot assign {} to '{}', which is declared as {}",
+                        value_ty, target, ty
+                    ),
+                )
+                .with_span(span),
+            );
+        }
+    }
+
+    fn assign(&mut self, target: &str, op: &AssignOp, value: &Expr, span: &Span) {
+        let value_ty = self.expr(value, span);
+        match op {
+            AssignOp::Set => self.assign_set(target, value_ty, span),
+            AssignOp::Add => self.assign_add(target, value_ty, span),
+        }
+    }
+
+    fn assign_set(&mut self, target: &str, value_ty: Ty, span: &Span) {
+        let Some(var) = self.vars.get_mut(target) else {
+            self.vars.insert(
+                target.to_string(),
+                Var {
+                    ty: value_ty,
+                    declared: false,
+                    span: span.clone(),
+                },
+            );
+            return;
+        };
+        if var.declared {
+            if !value_ty.fits(&var.ty) {
+                self.diagnostics.push(
+                    Diagnostic::new(
+                        "T801",
+                        format!(
+                            "Cannot assign {} to '{}', which is declared as {}",
+                            value_ty, target, var.ty
+                        ),
+                    )
+                    .with_span(span)
+                    .with_label(&var.span, "declared here"),
+                );
+            }
+            return;
+        }
+        match var.ty.join(&value_ty) {
+            Some(ty) => var.ty = ty,
+            // Before bool literals, flags were ints set to 0 and 1. Language 1.0 keeps
+            // accepting a flag that is also set to `true`/`false`.
+            None if !self.strict_flags && is_int_flag(&var.ty, &value_ty) => {
+                self.diagnostics.push(
+                    Diagnostic::warn(
+                        "T801",
+                        format!(
+                            "'{}' is used as an int flag and assigned {}; use true/false throughout (an error from language 1.1 on)",
+                            target, value_ty
+                        ),
+                    )
+                    .with_span(span)
+                    .with_label(&var.span, format!("'{}' first assigned {} here", target, var.ty)),
+                );
+                var.ty = Ty::Unknown;
+            }
+            None => self.diagnostics.push(
+                Diagnostic::new(
+                    "T801",
+                    format!(
+                        "Cannot assign {} to '{}', which holds {} (add a type annotation if it may hold both)",
+                        value_ty, target, var.ty
+                    ),
+                )
+                .with_span(span)
+                .with_label(&var.span, format!("'{}' first assigned {} here", target, var.ty)),
+            ),
+        }
+    }
+
+    fn assign_add(&mut self, target: &str, value_ty: Ty, span: &Span) {
+        let Some(var) = self.vars.get(target) else {
+            return;
+        };
+        let ok = match &var.ty {
+            Ty::Unknown => true,
+            Ty::Int => matches!(value_ty, Ty::Int | Ty::Unknown),
+            Ty::Float => value_ty.is_numeric_or_unknown(),
+            Ty::String => !matches!(value_ty, Ty::List(_) | Ty::Record),
+            _ => false,
+        };
+        if !ok {
+            let message = if matches!(var.ty, Ty::Int | Ty::Float | Ty::String) {
+                format!(
+                    "Cannot add {} to '{}' ({}) with '+='",
+                    value_ty, target, var.ty
+                )
+            } else {
+                format!(
+                    "'+=' needs a number or string, but '{}' is {}",
+                    target, var.ty
+                )
+            };
+            self.diagnostics.push(
+                Diagnostic::new("T802", message)
+                    .with_span(span)
+                    .with_label(&var.span, "type comes from here"),
+            );
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr, span: &Span) -> Ty {
+        match expr {
+            Expr::Number(n) if n.fract() == 0.0 => Ty::Int,
+            Expr::Number(_) => Ty::Float,
+            Expr::Duration(_) => Ty::Int,
+            Expr::String(_) => Ty::String,
+            Expr::Interpolated(parts) => {
+                for part in parts {
+                    if let StringPart::Expr(e) = part {
+                        self.expr(e, span);
+                    }
+                }
+                Ty::String
+            }
+            Expr::Bool(_) => Ty::Bool,
+            Expr::Null => Ty::Null,
+            Expr::Ident(name) => self
+                .vars
+                .get(name)
+                .map(|v| v.ty.clone())
+                .unwrap_or(Ty::Unknown),
+            Expr::Call(call) => {
+                self.check_call(call);
+                Ty::Unknown
+            }
+            Expr::List(items) => {
+                let mut item_ty = Ty::Null;
+                for item in items {
+                    let ty = self.expr(item, span);
+                    item_ty = item_ty.join(&ty).unwrap_or(Ty::Unknown);
+                }
+                if item_ty == Ty::Null {
+                    item_ty = Ty::Unknown;
+                }
+                Ty::List(Box::new(item_ty))
+            }
+            Expr::Record(fields) => {
+                for field in fields {
+                    self.expr(&field.value, span);
+                }
+                Ty::Record
+            }
+            Expr::Unary { op, operand } => {
+                let ty = self.expr(operand, span);
+                match op {
+                    UnaryOp::Neg => {
+                        if !ty.is_numeric_or_unknown() {
+                            self.operand_error(
+                                format!("Operator '-' needs a number, got {}", ty),
+                                span,
+                            );
+                        }
+                        if ty.is_numeric() {
+                            ty
+                        } else {
+                            Ty::Unknown
+                        }
+                    }
+                    UnaryOp::Not => Ty::Bool,
+                }
+            }
+            Expr::Binary { left, op, right } => {
+                let l = self.expr(left, span);
+                let r = self.expr(right, span);
+                self.binary(op, l, r, span)
+            }
+        }
+    }
+
+    fn binary(&mut self, op: &BinaryOp, l: Ty, r: Ty, span: &Span) -> Ty {
+        let symbol = binary_op_text(op);
+        match op {
+            BinaryOp::And | BinaryOp::Or => Ty::Bool,
+            BinaryOp::Eq | BinaryOp::Neq => {
+                if !l.fits(&r) && !r.fits(&l) {
+                    self.operand_error(
+                        format!("Cannot compare {} with {} using '{}'", l, r, symbol),
+                        span,
+                    );
+                }
+                Ty::Bool
+            }
+            BinaryOp::Gt | BinaryOp::Gte | BinaryOp::Lt => {
+                if !l.is_numeric_or_unknown() || !r.is_numeric_or_unknown() {
+                    self.operand_error(
+                        format!("Cannot compare {} with {} using '{}'", l, r, symbol),
+                        span,
+                    );
+                }
+                Ty::Bool
+            }
+            BinaryOp::Add if l == Ty::String || r == Ty::String => {
+                let other = if l == Ty::String { &r } else { &l };
+                if matches!(other, Ty::List(_) | Ty::Record) {
+                    self.operand_error(format!("Cannot add {} to {} with '+'", r, l), span);
+                }
+                Ty::String
+            }
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
+                if !l.is_numeric_or_unknown() || !r.is_numeric_or_unknown() {
+                    self.operand_error(
+                        format!("Operator '{}' needs numbers, got {} and {}", symbol, l, r),
+                        span,
+                    );
+                    return Ty::Unknown;
+                }
+                match (&l, &r, op) {
+                    (Ty::Unknown, _, _) | (_, Ty::Unknown, _) => Ty::Unknown,
+                    (_, _, BinaryOp::Div) => Ty::Float,
+                    (Ty::Int, Ty::Int, _) => Ty::Int,
+                    _ => Ty::Float,
+                }
+            }
+        }
+    }
+
+    fn operand_error(&mut self, message: String, span: &Span) {
+        self.diagnostics
+            .push(Diagnostic::new("T803", message).with_span(span));
+    }
+
+    fn run(&mut self, target: &str, args: &[CallArg], result: Option<&str>, span: &Span) {
+        let arg_types: Vec<Ty> = args
+            .iter()
+            .map(|arg| self.expr(&arg.value, &arg.span))
+            .collect();
+        let Some(flow) = self.flows.get(target).copied() else {
+            return;
+        };
+        let bound = bind_args(args, &flow.params, |param| &param.name);
+        for ((arg, ty), param) in args.iter().zip(arg_types).zip(bound) {
+            let Some(param) = param else {
+                continue;
+            };
+            let expected = Ty::from_name(&param.ty);
+            if !ty.fits(&expected) {
+                self.diagnostics.push(
+                    Diagnostic::new(
+                        "T806",
+                        format!(
+                            "Argument '{}' of run {} expects {}, got {}",
+                            param.name, target, expected, ty
+                        ),
+                    )
+                    .with_span(&arg.span)
+                    .with_label(&param.span, "parameter declared here"),
+                );
+            }
+        }
+        if let (Some(result), Some(returns)) = (result, &flow.returns) {
+            self.assign_set(result, Ty::from_name(returns), span);
+        }
+    }
+
+    fn return_value(&mut self, flow: &Flow, value: &Expr, span: &Span) {
+        let Some(returns) = &flow.returns else {
+            return;
+        };
+        let expected = Ty::from_name(returns);
+        let ty = self.expr(value, span);
+        if !ty.fits(&expected) {
+            self.diagnostics.push(
+                Diagnostic::new(
+                    "T807",
+                    format!(
+                        "Flow '{}' returns {}, but this return gives {}",
+                        flow.name, expected, ty
+                    ),
+                )
+                .with_span(span),
+            );
+        }
+    }
+
+    fn check_call(&mut self, call: &Call) {
+        let arg_types: Vec<Ty> = call
+            .args
+            .iter()
+            .map(|arg| self.expr(&arg.value, &arg.span))
+            .collect();
+        let Some((root, symbol)) = call.name.split_once('.') else {
+            return;
+        };
+        let Some(params) = self
+            .namespaces
+            .get(root)
+            .and_then(|namespace| contract_param_types(namespace, symbol))
+        else {
+            return;
+        };
+        let bound = bind_args(&call.args, params, |(param, _)| param);
+        for ((arg, ty), param) in call.args.iter().zip(arg_types).zip(bound) {
+            let Some((param, spec)) = param else {
+                continue;
+            };
+            let expected = Ty::from_spec(spec);
+            if !expected.iter().any(|e| ty.fits(e)) {
+                self.diagnostics.push(
+                    Diagnostic::new(
+                        "T804",
+                        format!(
+                            "Argument '{}' of '{}' expects {}, got {}",
+                            param,
+                            call.name,
+                            spec.replace('|', " or "),
+                            ty
+                        ),
+                    )
+                    .with_span(&arg.span),
+                );
+            }
+        }
+    }
+}
+
+/// The parameter each argument binds: named arguments by name, the others by position.
+fn bind_args<'p, P>(
+    args: &[CallArg],
+    params: &'p [P],
+    name: impl Fn(&P) -> &str,
+) -> Vec<Option<&'p P>> {
+    let mut position = 0;
+    args.iter()
+        .map(|arg| match &arg.name {
+            Some(arg_name) => params.iter().find(|param| name(param) == arg_name),
+            None => {
+                position += 1;
+                params.get(position - 1)
+            }
+        })
+        .collect()
+}
@@ -26,6 +26,35 @@ pub struct TargetIr {
 pub struct Flow {
     pub start: String,
     pub transitions: HashMap<String, HashMap<String, String>>,
+    /// Guarded transitions per state and event, tried in source order before `transitions`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub guards: HashMap<String, HashMap<String, Vec<GuardedTransition>>>,
+    /// Parent of each nested state and region. A region is named `State/Region`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub parents: HashMap<String, String>,
+    /// State entered first when a composite state or region is entered.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub initial: HashMap<String, String>,
+    /// Orthogonal regions of each parallel state, in source order.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub regions: HashMap<String, Vec<String>>,
+    /// `after(...)` transitions per state, armed when the state is entered.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub timers: HashMap<String, Vec<TimerTransition>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuardedTransition {
+    /// Runtime condition, in the same form as `when` of runtime rules.
+    pub when: Value,
+    pub target: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimerTransition {
+    #[serde(rename = "afterMs")]
+    pub after_ms: u64,
+    pub target: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +67,13 @@ pub struct RenderItem {
     pub action: Option<String>,
     pub style: Option<String>,
     pub css: Option<Value>,
+    /// `{text}` / `{expr}` parts of an interpolated `text`, rendered against runtime state.
+    #[serde(
+        default,
+        rename = "textTemplate",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub text_template: Option<Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -278,11 +278,18 @@ let package = Package(
     swift.push_str("  let color: String?\n");
     swift.push_str("  let action: String?\n");
     swift.push_str("  let style: String?\n");
-    swift.push_str("  enum CodingKeys: String, CodingKey { case kind, text, color, action, style }\n");
+    swift.push_str(
+        "  enum CodingKeys: String, CodingKey { case kind, text, color, action, style }\n",
+    );
+    swift.push_str("}\n\n");
+    swift.push_str("struct TimerData: Codable {\n");
+    swift.push_str("  let afterMs: Int\n");
+    swift.push_str("  let target: String\n");
     swift.push_str("}\n\n");
     swift.push_str("struct FlowData: Codable {\n");
     swift.push_str("  let start: String\n");
     swift.push_str("  let transitions: [String: [String: String]]\n");
+    swift.push_str("  var timers: [String: [TimerData]]? = nil\n");
     swift.push_str("}\n\n");
     swift.push_str("struct TargetData: Codable {\n");
     swift.push_str("  let views: [String: [RenderItem]]\n");
@@ -310,7 +317,9 @@ let package = Package(
     swift.push_str("    if chars == \"\\r\" { return \"enter\" }\n");
     swift.push_str("    if chars == \"\\u{1b}\" { return \"esc\" }\n");
     swift.push_str("    if chars == \" \" { return \"space\" }\n");
-    swift.push_str("    let t = chars.trimmingCharacters(in: .whitespacesAndNewlines).lowercased()\n");
+    swift.push_str(
+        "    let t = chars.trimmingCharacters(in: .whitespacesAndNewlines).lowercased()\n",
+    );
     swift.push_str("    return t.isEmpty ? nil : t\n");
     swift.push_str("  }\n");
     swift.push_str("}\n\n");
@@ -332,20 +341,40 @@ let package = Package(
     swift.push_str("  let targetData: TargetData\n");
     swift.push_str("  @State private var currentState: String\n");
     swift.push_str("  @State private var showAlert = false\n");
+    swift.push_str("  @State private var timerEpoch = 0\n");
     swift.push_str("  init() {\n");
     swift.push_str("    let json = \"");
     swift.push_str(&escape_swift(&target_json));
     swift.push_str("\"\n");
-    swift.push_str("    let decoded = (try? JSONDecoder().decode(TargetData.self, from: Data(json.utf8)))\n");
-    swift.push_str("      ?? TargetData(views: [:], flow: FlowData(start: \"\", transitions: [:]))\n");
+    swift.push_str(
+        "    let decoded = (try? JSONDecoder().decode(TargetData.self, from: Data(json.utf8)))\n",
+    );
+    swift.push_str(
+        "      ?? TargetData(views: [:], flow: FlowData(start: \"\", transitions: [:]))\n",
+    );
     swift.push_str("    self.targetData = decoded\n");
     swift.push_str("    _currentState = State(initialValue: decoded.flow.start)\n");
     swift.push_str("  }\n\n");
     swift.push_str("  var activeItems: [RenderItem] { targetData.views[currentState] ?? [] }\n\n");
+    swift.push_str("  func go(_ next: String) {\n");
+    swift.push_str("    currentState = next\n");
+    swift.push_str("    if next.lowercased() == \"exit\" { NSApp.keyWindow?.close(); return }\n");
+    swift.push_str("    armTimers(next)\n");
+    swift.push_str("  }\n\n");
+    swift.push_str("  func armTimers(_ state: String) {\n");
+    swift.push_str("    timerEpoch += 1\n");
+    swift.push_str("    let epoch = timerEpoch\n");
+    swift.push_str("    for timer in targetData.flow.timers?[state] ?? [] {\n");
+    swift.push_str(
+        "      DispatchQueue.main.asyncAfter(deadline: .now() + .milliseconds(timer.afterMs)) {\n",
+    );
+    swift.push_str("        if timerEpoch == epoch { go(timer.target) }\n");
+    swift.push_str("      }\n");
+    swift.push_str("    }\n");
+    swift.push_str("  }\n\n");
     swift.push_str("  func dispatch(_ event: String) {\n");
     swift.push_str("    if let next = targetData.flow.transitions[currentState]?[event] {\n");
-    swift.push_str("      currentState = next\n");
-    swift.push_str("      if next.lowercased() == \"exit\" { NSApp.keyWindow?.close() }\n");
+    swift.push_str("      go(next)\n");
     swift.push_str("      return\n");
     swift.push_str("    }\n");
     swift.push_str("    if event == \"key(esc)\" { NSApp.keyWindow?.close() }\n");
@@ -454,8 +483,11 @@ let package = Package(
     swift.push_str("      .padding(12)\n");
     swift.push_str("    }\n");
     swift.push_str("    .background(KeyCapture { key in dispatch(\"key(\\(key))\") }.frame(width: 0, height: 0))\n");
-    swift.push_str("    .alert(\"OK\", isPresented: $showAlert) { Button(\"OK\", role: .cancel) { } }\n");
+    swift.push_str(
+        "    .alert(\"OK\", isPresented: $showAlert) { Button(\"OK\", role: .cancel) { } }\n",
+    );
     swift.push_str("    .onExitCommand { dispatch(\"key(esc)\") }\n");
+    swift.push_str("    .onAppear { armTimers(currentState) }\n");
     swift.push_str("  }\n");
     swift.push_str("}\n\n");
     swift.push_str("@main struct SculptGuiApp: App {\n");
@@ -496,7 +528,8 @@ fn emit_gui_tkinter_state_machine(out_dir: &Path, target: Option<&TargetIr>) ->
     let target_json = target
         .map(|t| serde_json::to_string(t).unwrap_or_else(|_| "{}".to_string()))
         .unwrap_or_else(|| "{}".to_string());
-    let target_json_literal = serde_json::to_string(&target_json).unwrap_or_else(|_| "\"{}\"".to_string());
+    let target_json_literal =
+        serde_json::to_string(&target_json).unwrap_or_else(|_| "\"{}\"".to_string());
     let mut py = String::new();
     py.push_str("import json\n");
     py.push_str("import tkinter as tk\n");
@@ -505,7 +538,9 @@ fn emit_gui_tkinter_state_machine(out_dir: &Path, target: Option<&TargetIr>) ->
     py.push_str("VIEWS = TARGET.get('views', {})\n");
     py.push_str("FLOW = TARGET.get('flow', {})\n");
     py.push_str("TRANSITIONS = FLOW.get('transitions', {})\n");
-    py.push_str("state = FLOW.get('start', '')\n\n");
+    py.push_str("TIMERS = FLOW.get('timers', {})\n");
+    py.push_str("state = FLOW.get('start', '')\n");
+    py.push_str("timer_jobs = []\n\n");
     py.push_str("root = tk.Tk()\n");
     py.push_str("window = TARGET.get('window') or {}\n");
     py.push_str("root.title(window.get('title') or 'SCULPT')\n");
@@ -534,15 +569,24 @@ fn emit_gui_tkinter_state_machine(out_dir: &Path, target: Option<&TargetIr>) ->
     py.push_str("    if s == 'subtitle': return ('Menlo', 13, 'bold')\n");
     py.push_str("    if s == 'caption': return ('Menlo', 11)\n");
     py.push_str("    return ('Menlo', 13)\n\n");
-    py.push_str("def dispatch(event):\n");
+    py.push_str("def arm_timers():\n");
+    py.push_str("    for job in timer_jobs:\n");
+    py.push_str("        root.after_cancel(job)\n");
+    py.push_str("    timer_jobs.clear()\n");
+    py.push_str("    for timer in TIMERS.get(state, []):\n");
+    py.push_str("        timer_jobs.append(root.after(int(timer.get('afterMs') or 0), lambda target=timer.get('target'): go(target)))\n\n");
+    py.push_str("def go(next_state):\n");
     py.push_str("    global state\n");
+    py.push_str("    state = next_state\n");
+    py.push_str("    if str(state).lower() == 'exit':\n");
+    py.push_str("        root.destroy()\n");
+    py.push_str("        return\n");
+    py.push_str("    arm_timers()\n");
+    py.push_str("    render()\n\n");
+    py.push_str("def dispatch(event):\n");
     py.push_str("    next_state = (TRANSITIONS.get(state) or {}).get(event)\n");
     py.push_str("    if next_state:\n");
-    py.push_str("        state = next_state\n");
-    py.push_str("        if str(state).lower() == 'exit':\n");
-    py.push_str("            root.destroy()\n");
-    py.push_str("            return\n");
-    py.push_str("        render()\n");
+    py.push_str("        go(next_state)\n");
     py.push_str("    elif event == 'key(esc)':\n");
     py.push_str("        root.destroy()\n\n");
     py.push_str("def current_button_action():\n");
@@ -605,6 +649,7 @@ fn emit_gui_tkinter_state_machine(out_dir: &Path, target: Option<&TargetIr>) ->
     py.push_str("root.bind('<Escape>', lambda _e: dispatch('key(esc)'))\n");
     py.push_str("root.bind('<Return>', on_primary)\n");
     py.push_str("root.bind('<KP_Enter>', on_primary)\n");
+    py.push_str("arm_timers()\n");
     py.push_str("render()\n");
     py.push_str("root.mainloop()\n");
 
@@ -930,8 +975,11 @@ pub fn describe_target(name: &str) -> Result<Value> {
             Some(vec![
                 "runtime.web",
                 "render.text",
+                "text.interpolation",
                 "input.key",
                 "flow.state_machine",
+                "flow.guards",
+                "flow.hierarchy",
                 "web.profile",
                 "web.adapter",
             ]),
@@ -1059,9 +1107,13 @@ pub fn describe_target(name: &str) -> Result<Value> {
                 "runtime.cli",
                 "runtime.rules",
                 "runtime.when.logic",
+                "rules.every",
                 "render.text",
+                "text.interpolation",
                 "input.key",
                 "flow.state_machine",
+                "flow.guards",
+                "flow.hierarchy",
             ]),
             Some(json!({
               "layout": { "type": "enum", "values": ["default"] }
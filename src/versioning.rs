@@ -1,6 +1,121 @@
-pub const LANGUAGE_DEFAULT: &str = "1.0";
+use std::collections::HashMap;
+use std::fmt;
+
+pub const LANGUAGE_DEFAULT: &str = "1.1";
 pub const LANGUAGE_SUPPORT_RANGE: &str = ">=1.0 <2.0";
 
 pub fn language_line() -> String {
     format!("SCULPT language {}", LANGUAGE_DEFAULT)
 }
+
+/// A `MAJOR.MINOR` language version, as declared by `@meta language=1.1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LanguageVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl LanguageVersion {
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+
+    /// Parses `MAJOR.MINOR`. A bare `MAJOR` is rejected so pins are always explicit.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (major, minor) = text.trim().split_once('.')?;
+        let number = |part: &str| {
+            if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            part.parse::<u32>().ok()
+        };
+        Some(Self::new(number(major)?, number(minor)?))
+    }
+
+    pub fn default_version() -> Self {
+        Self::parse(LANGUAGE_DEFAULT).expect("LANGUAGE_DEFAULT is MAJOR.MINOR")
+    }
+
+    /// Whether this compiler accepts sources pinned to this version.
+    pub fn is_supported(self) -> bool {
+        in_range(self, LANGUAGE_SUPPORT_RANGE)
+    }
+
+    pub fn has(self, feature: Feature) -> bool {
+        self >= feature.since()
+    }
+}
+
+impl fmt::Display for LanguageVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// The language version a module is compiled with: its `language` meta when that is valid
+/// and supported, otherwise [`LANGUAGE_DEFAULT`]. Semantics reports bad pins (`M710`/`M711`).
+pub fn module_language(meta: &HashMap<String, String>) -> LanguageVersion {
+    meta.get("language")
+        .and_then(|value| LanguageVersion::parse(value))
+        .filter(|version| version.is_supported())
+        .unwrap_or_else(LanguageVersion::default_version)
+}
+
+/// Edition-dependent parts of the language. Each feature is available from
+/// [`Feature::since`] on; older pins keep the previous behavior.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    TransitionGuards,
+    NestedStates,
+    FlowSignatures,
+    Timers,
+    ItemAttributes,
+    /// An `int` variable assigned `true`/`false` (the pre-bool flag idiom) is an error
+    /// instead of a warning.
+    StrictFlagTypes,
+}
+
+impl Feature {
+    pub fn since(self) -> LanguageVersion {
+        match self {
+            Feature::TransitionGuards
+            | Feature::NestedStates
+            | Feature::FlowSignatures
+            | Feature::Timers
+            | Feature::ItemAttributes
+            | Feature::StrictFlagTypes => LanguageVersion::new(1, 1),
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Feature::TransitionGuards => "Transition guards",
+            Feature::NestedStates => "Nested states and regions",
+            Feature::FlowSignatures => "Flow parameters, run arguments and return",
+            Feature::Timers => "Durations and timers",
+            Feature::ItemAttributes => "Item attributes",
+            Feature::StrictFlagTypes => "Rejecting int flags assigned a bool",
+        }
+    }
+}
+
+/// Evaluates a space-separated list of `>=`, `>`, `<=`, `<` and `=` comparators.
+fn in_range(version: LanguageVersion, range: &str) -> bool {
+    range.split_whitespace().all(|comparator| {
+        let (op, bound) = match comparator.find(|c: char| c.is_ascii_digit()) {
+            Some(idx) => comparator.split_at(idx),
+            None => return false,
+        };
+        let Some(bound) = LanguageVersion::parse(bound) else {
+            return false;
+        };
+        match op {
+            ">=" => version >= bound,
+            ">" => version > bound,
+            "<=" => version <= bound,
+            "<" => version < bound,
+            "=" | "" => version == bound,
+            _ => false,
+        }
+    })
+}
@@ -45,6 +45,7 @@ fn build_resolves_recursive_imports() {
         dir.join("modules").join("shared.sculpt"),
         r#"module(Company.Shared):
   import(Company.Deep) as Deep
+  exports(root)
   state():
     root = 1
   end
@@ -202,3 +203,206 @@ end
     assert!(text.contains("\"a.sculpt\""));
     assert!(text.contains("\"b.sculpt\""));
 }
+
+#[test]
+fn build_writes_sarif_diagnostics_on_semantic_failure() {
+    let dir = temp_dir("diagnostics_sarif");
+    fs::write(
+        dir.join("main.sculpt"),
+        r#"@meta target=cli
+module(App.Main):
+  flow(Main):
+    start > A
+    state(A):
+      on tick > Missing
+    end
+  end
+end
+"#,
+    )
+    .expect("write main");
+
+    let out = Command::new(sculpt_bin())
+        .args([
+            "build",
+            "main.sculpt",
+            "--target",
+            "cli",
+            "--provider",
+            "stub",
+        ])
+        .arg("--diagnostics-format=sarif")
+        .current_dir(&dir)
+        .output()
+        .expect("run");
+    assert!(!out.status.success(), "build unexpectedly succeeded");
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("Semantic validation failed"));
+    assert!(stderr.contains("F105"));
+
+    let sarif: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(dir.join("dist/main/diagnostics.sarif")).expect("sarif written"),
+    )
+    .expect("valid json");
+    let results = sarif["runs"][0]["results"].as_array().expect("results");
+    let f105 = results
+        .iter()
+        .find(|r| r["ruleId"] == "F105")
+        .expect("F105 result");
+    let location = &f105["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "main.sculpt");
+    assert_eq!(location["region"]["startLine"], 6);
+}
+
+#[test]
+fn fix_applies_safe_suggestions() {
+    let dir = temp_dir("fix_safe");
+    let main = dir.join("main.sculpt");
+    fs::write(
+        &main,
+        r#"@meta target=cli
+module(App.Main):
+  use(cli.ui)
+  flow(Main):
+    start > A
+    state(A):
+      ui.txt("hello")
+      on tick > Don
+    end
+    state(Done):
+      terminate
+    end
+  end
+end
+"#,
+    )
+    .expect("write main");
+
+    let out = Command::new(sculpt_bin())
+        .args(["fix", "main.sculpt", "--check"])
+        .current_dir(&dir)
+        .output()
+        .expect("run");
+    assert!(!out.status.success(), "check should report fixable issues");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("main.sculpt:7:7: C906 did you mean 'ui.text'?"));
+    assert!(stdout.contains("main.sculpt:8:17: F105 did you mean 'Done'?"));
+
+    let out = Command::new(sculpt_bin())
+        .args(["fix", "main.sculpt"])
+        .current_dir(&dir)
+        .output()
+        .expect("run");
+    assert!(
+        out.status.success(),
+        "stderr={}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let fixed = fs::read_to_string(&main).expect("read main");
+    assert!(fixed.contains("      ui.text(\"hello\")\n      on tick > Done\n"));
+
+    let out = Command::new(sculpt_bin())
+        .args(["fix", "main.sculpt", "--check"])
+        .current_dir(&dir)
+        .output()
+        .expect("run");
+    assert!(out.status.success(), "nothing left to fix");
+}
+
+#[test]
+fn rename_updates_source_and_id_sidecar() {
+    let dir = temp_dir("rename");
+    let main = dir.join("main.sculpt");
+    fs::write(
+        &main,
+        r#"module(App.Main):
+  flow(Main):
+    start > Idle
+    state(Idle):
+      on tick > Idle
+    end
+  end
+end
+"#,
+    )
+    .expect("write main");
+
+    let out = Command::new(sculpt_bin())
+        .args(["rename", "main.sculpt", "Main.Idle", "Waiting"])
+        .current_dir(&dir)
+        .output()
+        .expect("run");
+    assert!(
+        out.status.success(),
+        "stderr={}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let renamed = fs::read_to_string(&main).expect("read main");
+    assert!(renamed.contains("start > Waiting\n    state(Waiting):\n      on tick > Waiting\n"));
+    let sidecar = fs::read_to_string(dir.join("main.ids.json")).expect("read sidecar");
+    assert!(sidecar.contains("\"state:Main.Waiting\": \"st_"));
+    assert!(!sidecar.contains("Main.Idle"));
+
+    let out = Command::new(sculpt_bin())
+        .args([
+            "rename",
+            "main.sculpt",
+            "Main",
+            "Waiting",
+            "--kind",
+            "state",
+        ])
+        .current_dir(&dir)
+        .output()
+        .expect("run");
+    assert!(!out.status.success(), "no state named 'Main'");
+}
+
+#[test]
+fn graph_prints_focused_mermaid() {
+    let dir = temp_dir("graph");
+    fs::write(
+        dir.join("main.sculpt"),
+        r#"module(App.Main):
+  flow(Main):
+    start > Idle
+    state(Idle):
+      on tick > Done
+    end
+    state(Done):
+      terminate
+    end
+  end
+end
+"#,
+    )
+    .expect("write main");
+
+    let out = Command::new(sculpt_bin())
+        .args([
+            "graph",
+            "main.sculpt",
+            "--format",
+            "mermaid",
+            "--focus",
+            "Main.Done",
+        ])
+        .current_dir(&dir)
+        .output()
+        .expect("run");
+    assert!(
+        out.status.success(),
+        "stderr={}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.starts_with("flowchart LR\n"));
+    assert!(stdout.contains("-->|\"transitions: on tick\"|"));
+
+    let out = Command::new(sculpt_bin())
+        .args(["graph", "main.sculpt", "--focus", "Main.Missing"])
+        .current_dir(&dir)
+        .output()
+        .expect("run");
+    assert!(!out.status.success(), "unknown focus must fail");
+}
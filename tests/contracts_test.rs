@@ -1,5 +1,8 @@
-use sculpt::contracts::{parse_target_contract, validate_module_against_contract};
-use sculpt::ir::from_ast;
+use sculpt::contracts::{
+    check_module_against_contract, parse_target_contract, validate_module_against_contract,
+};
+use sculpt::diagnostics::DiagnosticsError;
+use sculpt::ir::{from_ast, IrModule};
 use sculpt::parser::parse_source;
 use sculpt::targets::describe_target;
 
@@ -67,6 +70,50 @@ end
     validate_module_against_contract(&ir, "gui", &contract).expect("must pass");
 }
 
+#[test]
+fn every_target_accepts_a_language_pin() {
+    let src = r#"@meta language=1.0
+module(App.Core):
+  flow(Main):
+    start > A
+    state(A):
+      terminate
+    end
+  end
+end
+"#;
+    let ir = from_ast(parse_source(src).expect("parse"));
+    for target in ["cli", "web", "gui"] {
+        assert_eq!(
+            builtin_contract_codes(&ir, target),
+            Vec::<&str>::new(),
+            "{target}"
+        );
+    }
+}
+
+#[test]
+fn every_target_accepts_a_flow_analysis_mode() {
+    let src = r#"@meta flow_analysis=strict
+module(App.Core):
+  flow(Main):
+    start > A
+    state(A):
+      terminate
+    end
+  end
+end
+"#;
+    let ir = from_ast(parse_source(src).expect("parse"));
+    for target in ["cli", "web", "gui"] {
+        assert_eq!(
+            builtin_contract_codes(&ir, target),
+            Vec::<&str>::new(),
+            "{target}"
+        );
+    }
+}
+
 #[test]
 fn rejects_unknown_use_package_namespace_for_target() {
     let src = r#"@meta target=web
@@ -334,6 +381,68 @@ end
     validate_module_against_contract(&ir, "cli", &contract).expect("must pass");
 }
 
+#[test]
+fn accepts_list_literal_column_arguments() {
+    let src = r#"@meta target=cli
+module(App.Core):
+  use(cli.input) as input
+  use(cli.data) as data
+  flow(Main):
+    start > A
+    state(A):
+      rows = data.csvRead("invoices.csv")
+      ok = data.csvHasColumns(rows, ["invoice_id", "customer_id"])
+      sorted = data.sortBy(rows, ["due_date"])
+      on input.key(esc) > Exit
+    end
+    state(Exit):
+      terminate
+    end
+  end
+  state():
+    rows = []
+    ok = false
+    sorted = []
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse");
+    let ir = from_ast(module);
+    let spec = describe_target("cli").expect("describe");
+    let contract = parse_target_contract(&spec).expect("contract");
+    validate_module_against_contract(&ir, "cli", &contract).expect("must pass");
+}
+
+#[test]
+fn rejects_non_string_or_empty_column_lists() {
+    let src = r#"@meta target=cli
+module(App.Core):
+  use(cli.input) as input
+  use(cli.data) as data
+  flow(Main):
+    start > A
+    state(A):
+      rows = data.csvRead("invoices.csv")
+      ok = data.csvHasColumns(rows, [1, true])
+      missing = data.csvMissingColumns(rows, [])
+      on input.key(esc) > Exit
+    end
+    state(Exit):
+      terminate
+    end
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse");
+    let ir = from_ast(module);
+    let spec = describe_target("cli").expect("describe");
+    let contract = parse_target_contract(&spec).expect("contract");
+    let err = validate_module_against_contract(&ir, "cli", &contract).expect_err("must fail");
+    let msg = format!("{err}");
+    assert!(msg.contains("arg2 list items must be column name strings"));
+    assert!(msg.contains("arg2 column list is empty"));
+}
+
 #[test]
 fn rejects_namespaced_data_call_wrong_arity() {
     let src = r#"@meta target=cli
@@ -481,3 +590,177 @@ end
     let msg = format!("{err}");
     assert!(msg.contains("C914"));
 }
+
+#[test]
+fn contract_errors_are_structured_diagnostics() {
+    let src = r#"@meta target=cli
+@meta requires="ui.modal.ok"
+module(App.Core):
+  flow(Main):
+    start > A
+    state(A):
+      report = loadRows("x.csv")
+      terminate
+    end
+  end
+end
+"#;
+    let ir = from_ast(parse_source(src).expect("parse"));
+    let spec = describe_target("cli").expect("describe");
+    let contract = parse_target_contract(&spec).expect("contract");
+    let diagnostics = check_module_against_contract(&ir, "cli", &contract);
+    let codes: Vec<_> = diagnostics.iter().map(|d| d.code).collect();
+    assert_eq!(codes, vec!["C902", "C907"]);
+    let lines: Vec<_> = diagnostics
+        .iter()
+        .map(|d| d.span.as_ref().expect("span").line)
+        .collect();
+    assert_eq!(lines, vec![2, 7]);
+    assert!(!diagnostics[1].message.starts_with("C907"));
+
+    let err = validate_module_against_contract(&ir, "cli", &contract).expect_err("must fail");
+    let failed = err.downcast_ref::<DiagnosticsError>().expect("diagnostics");
+    assert_eq!(failed.diagnostics.len(), 2);
+    assert!(format!("{err}").contains("C907: Unknown unqualified deterministic call 'loadRows'"));
+}
+
+#[test]
+fn unknown_exports_suggest_the_closest_export() {
+    let src = r#"@meta target=cli
+module(App.Core):
+  use(cli.ui) as ui
+  flow(Main):
+    start > A
+    state(A):
+      ui.txt("hello")
+      terminate
+    end
+  end
+end
+"#;
+    let ir = from_ast(parse_source(src).expect("parse"));
+    let spec = describe_target("cli").expect("describe");
+    let contract = parse_target_contract(&spec).expect("contract");
+    let diagnostics = check_module_against_contract(&ir, "cli", &contract);
+    let c906 = diagnostics.iter().find(|d| d.code == "C906").expect("C906");
+    let suggestion = &c906.suggestions[0];
+    assert_eq!(suggestion.span.text(), "ui.txt");
+    assert_eq!(suggestion.replacement, "ui.text");
+    assert!(suggestion.safe);
+}
+
+/// Codes reported when `ir` is checked against the built-in contract of `target`.
+fn builtin_contract_codes(ir: &IrModule, target: &str) -> Vec<&'static str> {
+    let contract =
+        parse_target_contract(&describe_target(target).expect("describe")).expect("contract");
+    check_module_against_contract(ir, target, &contract)
+        .into_iter()
+        .map(|d| d.code)
+        .collect()
+}
+
+#[test]
+fn interpolated_view_text_needs_a_runtime_that_renders_it() {
+    let src = r#"@meta target=gui
+module(App.Core):
+  use(gui.ui) as ui
+  flow(Main):
+    start > A
+    state(A):
+      ui.text("Total: ${total}")
+      terminate
+    end
+  end
+  state():
+    total = 0
+  end
+end
+"#;
+    let ir = from_ast(parse_source(src).expect("parse"));
+    let codes = |target: &str| builtin_contract_codes(&ir, target);
+    assert_eq!(codes("gui"), vec!["C916"]);
+    assert!(!codes("web").contains(&"C916"));
+    assert!(!codes("cli").contains(&"C916"));
+}
+
+#[test]
+fn guarded_transitions_need_a_runtime_that_evaluates_them() {
+    let src = r#"@meta target=gui
+module(App.Core):
+  use(gui.ui) as ui
+  flow(Main):
+    start > A
+    state(A):
+      on done when total == 1 > B
+    end
+    state(B):
+      terminate
+    end
+  end
+  state():
+    total = 0
+  end
+end
+"#;
+    let ir = from_ast(parse_source(src).expect("parse"));
+    let codes = |target: &str| builtin_contract_codes(&ir, target);
+    assert_eq!(codes("gui"), vec!["C916"]);
+    assert!(!codes("web").contains(&"C916"));
+    assert!(!codes("cli").contains(&"C916"));
+}
+
+#[test]
+fn nested_states_need_a_runtime_that_tracks_the_hierarchy() {
+    let src = r#"@meta target=gui
+module(App.Core):
+  use(gui.ui) as ui
+  flow(Main):
+    start > Play
+    state(Play):
+      start > Running
+      state(Running):
+        on done > Paused
+      end
+      state(Paused):
+        terminate
+      end
+    end
+  end
+end
+"#;
+    let ir = from_ast(parse_source(src).expect("parse"));
+    let codes = |target: &str| builtin_contract_codes(&ir, target);
+    assert_eq!(codes("gui"), vec!["C916"]);
+    assert!(!codes("web").contains(&"C916"));
+    assert!(!codes("cli").contains(&"C916"));
+}
+
+#[test]
+fn periodic_rules_need_a_runtime_that_schedules_them() {
+    let src = r#"@meta target=web
+module(App.Core):
+  flow(Main):
+    start > A
+    state(A):
+      after(2s) > B
+    end
+    state(B):
+      terminate
+    end
+  end
+  state():
+    ticks = 0
+  end
+  rule(tick):
+    every(100ms):
+      ticks += 1
+    end
+  end
+end
+"#;
+    let ir = from_ast(parse_source(src).expect("parse"));
+    let codes = |target: &str| builtin_contract_codes(&ir, target);
+    assert_eq!(codes("web"), vec!["C916"]);
+    assert_eq!(codes("gui"), vec!["C916"]);
+    assert!(!codes("cli").contains(&"C916"));
+}
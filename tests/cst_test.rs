@@ -0,0 +1,135 @@
+use pretty_assertions::assert_eq;
+use sculpt::ast::{Item, StateStmt};
+use sculpt::cst::{NodeKind, SyntaxTree, TriviaKind};
+use sculpt::parser::parse_source;
+use std::path::{Path, PathBuf};
+
+fn ast_json(module: &sculpt::ast::Module) -> serde_json::Value {
+    serde_json::to_value(module).expect("serialize")
+}
+
+fn sculpt_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    for entry in std::fs::read_dir(dir).expect("read dir") {
+        let path = entry.expect("entry").path();
+        if path.is_dir() {
+            sculpt_files(&path, out);
+        } else if path.extension().and_then(|e| e.to_str()) == Some("sculpt") {
+            out.push(path);
+        }
+    }
+}
+
+const LEGACY: &str = "# Intro line one\r\n# Intro line two\r\n\r\nmodule(Demo):\r\n  flow(Main):\r\n    start > Title\r\n    state(Title):\r\n      # wait for the player\r\n      on key(Enter) > Exit   # go\r\n    end\r\n    state(Exit):\r\n      terminate\r\n    end\r\n  end\r\nend\r\n";
+
+#[test]
+fn examples_round_trip_byte_for_byte_and_match_parser() {
+    let mut files = Vec::new();
+    sculpt_files(Path::new("examples"), &mut files);
+    assert!(!files.is_empty());
+    for file in files {
+        let src = std::fs::read_to_string(&file).expect("read example");
+        let tree = SyntaxTree::parse_with_path(&src, &file);
+        assert_eq!(tree.to_string(), src, "{} not re-emitted", file.display());
+        let module = tree.to_ast().expect("parse example");
+        let direct = parse_source(&src).expect("parse example");
+        assert_eq!(ast_json(&module), ast_json(&direct), "{}", file.display());
+    }
+}
+
+#[test]
+fn keeps_crlf_comments_and_invalid_characters() {
+    let src = format!("{}  $ stray\n", LEGACY);
+    let tree = SyntaxTree::parse(&src);
+    assert_eq!(tree.to_string(), src);
+    assert_eq!(
+        tree.comments().collect::<Vec<_>>(),
+        vec![
+            "# Intro line one",
+            "# Intro line two",
+            "# wait for the player",
+            "# go"
+        ]
+    );
+    assert!(tree
+        .tokens()
+        .iter()
+        .flat_map(|t| &t.leading_trivia)
+        .any(|t| t.kind == TriviaKind::Skipped && t.text == "$"));
+    assert!(tree.diagnostics().iter().any(|d| d.code == "S007"));
+}
+
+#[test]
+fn nests_nodes_and_finds_leading_comments() {
+    let tree = SyntaxTree::parse(LEGACY);
+    let root = tree.root();
+    assert_eq!(root.kind, NodeKind::Module);
+    assert_eq!(
+        tree.leading_comments(root.children.first().expect("flow")),
+        Vec::<&str>::new()
+    );
+    let flow = &root.children[0];
+    assert_eq!(flow.kind, NodeKind::Flow);
+    let kinds: Vec<_> = flow.children.iter().map(|n| n.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![NodeKind::Start, NodeKind::State, NodeKind::State]
+    );
+    let on = flow
+        .descendants()
+        .into_iter()
+        .find(|n| n.kind == NodeKind::On)
+        .expect("on node");
+    assert_eq!(tree.node_text(on), "on key(Enter) > Exit");
+    assert_eq!(tree.leading_comments(on), vec!["# wait for the player"]);
+}
+
+#[test]
+fn edits_migrate_legacy_shorthand_without_losing_trivia() {
+    let mut tree = SyntaxTree::parse(LEGACY);
+    let event = tree
+        .root()
+        .descendants()
+        .into_iter()
+        .find(|n| n.kind == NodeKind::Call && tree.node_text(n) == "key(Enter)")
+        .cloned()
+        .expect("legacy event");
+    tree.replace_node(&event, "input.key(Enter)");
+    let use_at = tree.root().children[0].tokens.start;
+    tree.insert_before(use_at, "use(cli.input) as input\r\n\r\n  ");
+
+    assert_eq!(
+        tree.to_string(),
+        LEGACY.replace("on key(", "on input.key(").replace(
+            "  flow(Main)",
+            "  use(cli.input) as input\r\n\r\n  flow(Main)"
+        )
+    );
+    assert!(tree.diagnostics().is_empty());
+    let module = tree.module();
+    assert_eq!(module.uses.len(), 1);
+    let Item::Flow(flow) = &module.items[0] else {
+        panic!("expected flow");
+    };
+    let StateStmt::On { event, .. } = &flow.states[0].statements[0] else {
+        panic!("expected on");
+    };
+    assert_eq!(event.name, "input.key");
+    assert_eq!(
+        tree.comments().take(2).collect::<Vec<_>>(),
+        vec!["# Intro line one", "# Intro line two"]
+    );
+}
+
+#[test]
+fn interpolated_strings_stay_single_tokens() {
+    let src =
+        "module(M):\n  state():\n    title = \"Invoice ${id} \\${raw}\"  # note\n  end\nend\n";
+    let tree = SyntaxTree::parse(src);
+    assert_eq!(tree.to_string(), src);
+    assert!(tree.diagnostics().is_empty());
+    assert_eq!(tree.comments().collect::<Vec<_>>(), vec!["# note"]);
+    assert!(tree
+        .tokens()
+        .iter()
+        .any(|t| t.text == "\"Invoice ${id} \\${raw}\""));
+}
@@ -0,0 +1,174 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use sculpt::diagnostics::{
+    closest_name, diagnostics_to_json, diagnostics_to_sarif, Diagnostic, DiagnosticCode,
+};
+use sculpt::parser::parse_source_with_path;
+use sculpt::semantics::validate_module;
+
+/// Codes written as `"F105"` arguments or as `"C901: ..."` message prefixes.
+fn codes_in(text: &str, out: &mut BTreeSet<String>) {
+    let bytes = text.as_bytes();
+    for (idx, _) in text.match_indices('"') {
+        let rest = &bytes[idx + 1..];
+        let letters = rest.iter().take_while(|b| b.is_ascii_uppercase()).count();
+        if !(1..=2).contains(&letters) || rest.len() < letters + 4 {
+            continue;
+        }
+        let digits = &rest[letters..letters + 3];
+        let next = rest[letters + 3];
+        if digits.iter().all(u8::is_ascii_digit) && (next == b'"' || next == b':') {
+            out.insert(String::from_utf8_lossy(&rest[..letters + 3]).into_owned());
+        }
+    }
+}
+
+fn scan(dir: &Path, out: &mut BTreeSet<String>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            if !path.ends_with("diagnostics") {
+                scan(&path, out);
+            }
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            codes_in(&fs::read_to_string(&path).unwrap(), out);
+        }
+    }
+}
+
+#[test]
+fn every_emitted_code_is_registered() {
+    let mut emitted = BTreeSet::new();
+    scan(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("src"),
+        &mut emitted,
+    );
+    assert!(emitted.contains("F105") && emitted.contains("C914"));
+    let missing: Vec<_> = emitted
+        .iter()
+        .filter(|code| DiagnosticCode::lookup(code).is_none())
+        .collect();
+    assert!(missing.is_empty(), "unregistered codes: {:?}", missing);
+}
+
+#[test]
+fn registry_entries_are_unique_and_complete() {
+    let mut seen = BTreeSet::new();
+    for entry in DiagnosticCode::all() {
+        assert!(seen.insert(entry.code), "{} registered twice", entry.code);
+        assert!(!entry.summary.is_empty(), "{} has no summary", entry.code);
+        assert!(
+            !entry.explanation.trim().is_empty(),
+            "{} has no explanation",
+            entry.code
+        );
+        assert!(
+            !entry.example.trim().is_empty() && !entry.fixed.trim().is_empty(),
+            "{} needs an example and a fix",
+            entry.code
+        );
+        assert_ne!(entry.example, entry.fixed, "{}", entry.code);
+    }
+}
+
+#[test]
+fn explains_codes_case_insensitively() {
+    let entry = DiagnosticCode::lookup("f105").expect("F105 is registered");
+    assert_eq!(entry.code, "F105");
+    let text = entry.explain();
+    assert!(text.starts_with("F105 (error): Unknown transition target state\n"));
+    assert!(text.contains("\nExample:\n\n    state(Title):\n      on input.key(enter) > Gmae\n"));
+    assert!(text.contains("\nFixed:\n\n    state(Title):\n      on input.key(enter) > Game\n"));
+    assert_eq!(
+        DiagnosticCode::lookup("D002").unwrap().level_name(),
+        "warning"
+    );
+    assert!(DiagnosticCode::lookup("X999").is_none());
+}
+
+fn duplicate_state_diagnostics() -> Vec<Diagnostic> {
+    let src = r#"module(App.Core):
+  flow(Main):
+    start > A
+    state(A):
+      terminate
+    end
+    state(A):
+      terminate
+    end
+  end
+end
+"#;
+    let module = parse_source_with_path(src, Path::new("app.sculpt")).expect("parse");
+    let diagnostics = validate_module(&module);
+    assert_eq!(
+        diagnostics.iter().map(|d| d.code).collect::<Vec<_>>(),
+        vec!["F104"]
+    );
+    diagnostics
+}
+
+#[test]
+fn renders_diagnostics_as_json() {
+    let mut diagnostics = duplicate_state_diagnostics();
+    diagnostics.push(Diagnostic::warn("M711", "no span"));
+    let json = diagnostics_to_json(&diagnostics, "app.sculpt");
+    assert_eq!(json["errors"], 1);
+    assert_eq!(json["warnings"], 1);
+    let first = &json["diagnostics"][0];
+    assert_eq!(first["code"], "F104");
+    assert_eq!(first["level"], "error");
+    assert_eq!(first["message"], "Duplicate state 'A' in flow 'Main'");
+    assert_eq!(first["file"], "app.sculpt");
+    assert_eq!((&first["line"], &first["column"]), (&7.into(), &5.into()));
+    assert_eq!(
+        (&first["end_line"], &first["end_column"]),
+        (&9.into(), &8.into())
+    );
+    assert_eq!(first["labels"][0]["line"], 4);
+    assert_eq!(first["labels"][0]["message"], "first defined here");
+    assert!(first["summary"].is_string());
+    let second = &json["diagnostics"][1];
+    assert_eq!(second["level"], "warning");
+    assert_eq!(second["file"], "app.sculpt");
+    assert!(second["line"].is_null());
+}
+
+#[test]
+fn renders_diagnostics_as_sarif() {
+    let sarif = diagnostics_to_sarif(&duplicate_state_diagnostics(), "app.sculpt");
+    assert_eq!(sarif["version"], "2.1.0");
+    let run = &sarif["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "sculpt");
+    let rule = &run["tool"]["driver"]["rules"][0];
+    assert_eq!(rule["id"], "F104");
+    assert_eq!(rule["defaultConfiguration"]["level"], "error");
+    assert!(rule["help"]["text"]
+        .as_str()
+        .unwrap()
+        .starts_with("F104 (error)"));
+    let result = &run["results"][0];
+    assert_eq!(result["ruleId"], "F104");
+    assert_eq!(result["ruleIndex"], 0);
+    let location = &result["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "app.sculpt");
+    assert_eq!(location["region"]["startLine"], 7);
+    assert_eq!(
+        result["relatedLocations"][0]["message"]["text"],
+        "first defined here"
+    );
+}
+
+#[test]
+fn closest_name_prefers_unique_near_matches() {
+    assert_eq!(
+        closest_name("Plya", ["Title", "Play"]),
+        Some(("Play", true))
+    );
+    assert_eq!(closest_name("Pay", ["Play", "Pad"]), Some(("Pad", false)));
+    assert_eq!(closest_name("Pay", ["Pad", "Play"]), Some(("Pad", false)));
+    assert_eq!(closest_name("Menu", ["Title", "Play"]), None);
+    assert_eq!(closest_name("Play", ["Play"]), None);
+}
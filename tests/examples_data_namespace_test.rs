@@ -89,3 +89,31 @@ fn examples_keep_data_use_and_calls_in_sync() {
         );
     }
 }
+
+#[test]
+fn shipped_examples_pass_semantic_validation() {
+    let mut files = Vec::new();
+    collect_sculpt_files(Path::new("examples"), &mut files);
+    files.sort();
+    assert!(!files.is_empty(), "no examples found");
+    let mut failures = Vec::new();
+    for file in &files {
+        let src = fs::read_to_string(file).expect("read example");
+        let module = match sculpt::parser::parse_source(&src) {
+            Ok(module) => module,
+            Err(err) => {
+                failures.push(format!("{}: {err}", file.display()));
+                continue;
+            }
+        };
+        let diagnostics = sculpt::semantics::validate_module(&module);
+        if sculpt::semantics::has_errors(&diagnostics) {
+            failures.push(format!(
+                "{}:\n{}",
+                file.display(),
+                sculpt::semantics::format_diagnostics(&diagnostics)
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
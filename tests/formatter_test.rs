@@ -0,0 +1,493 @@
+use pretty_assertions::assert_eq;
+use sculpt::formatter::{format_module, format_source};
+use sculpt::parser::{parse_source, parse_source_with_path};
+use std::path::{Path, PathBuf};
+
+fn ast_json(src: &str) -> serde_json::Value {
+    serde_json::to_value(parse_source(src).expect("parse ok")).expect("serialize")
+}
+
+fn sculpt_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    for entry in std::fs::read_dir(dir).expect("read dir") {
+        let path = entry.expect("entry").path();
+        if path.is_dir() {
+            sculpt_files(&path, out);
+        } else if path.extension().and_then(|e| e.to_str()) == Some("sculpt") {
+            out.push(path);
+        }
+    }
+}
+
+#[test]
+fn formats_to_canonical_layout() {
+    let src = r#"# header comment
+@meta   target = cli
+@meta author="a b"
+module(Demo):
+    use(cli.ui)   as ui
+    flow(Main):
+        start>Title
+        state(Title):
+            ui.text( "Hi",color:"yellow" )   # greet
+            on key(Enter)>Exit
+
+            on key(x)::count+=1
+            on key(y):
+              count = 0
+              emit reset
+            end
+        end
+        state(Exit):
+          terminate
+        end
+    end
+    state():
+      count=0
+    end
+    rule(reset):
+      when count>=3 and (done or count<1):: emit stop
+    end
+    nd(layout, mode):
+      define theme.calm():
+        "Calm \"quiet\" palette"
+      end
+      propose ui(kind:"demo")
+      satisfy(?"keep spacing", noOverlap())
+    end
+end
+"#;
+    let expected = r#"# header comment
+@meta target=cli
+@meta author="a b"
+
+module(Demo):
+  use(cli.ui) as ui
+
+  flow(Main):
+    start > Title
+
+    state(Title):
+      ui.text("Hi", color: "yellow")  # greet
+      on key(Enter) > Exit
+
+      on key(x):: count += 1
+      on key(y):
+        count = 0
+        emit reset
+      end
+    end
+
+    state(Exit):
+      terminate
+    end
+  end
+
+  state():
+    count = 0
+  end
+
+  rule(reset):
+    when count >= 3 and (done or count < 1):
+      emit stop
+    end
+  end
+
+  nd(layout, mode):
+    define theme.calm():
+      "Calm \"quiet\" palette"
+    end
+
+    propose ui(kind: "demo")
+    satisfy(
+      ?"keep spacing",
+      noOverlap()
+    )
+  end
+end
+"#;
+    let formatted = format_source(src, Path::new("demo.sculpt")).expect("format");
+    assert_eq!(formatted, expected);
+    assert_eq!(ast_json(&formatted), ast_json(src));
+}
+
+#[test]
+fn keeps_comments_in_blocks_and_after_module() {
+    let src = r#"module(Demo):
+  state():
+    # leading
+    a = 1
+    # before end
+  end
+end
+# trailing file comment
+"#;
+    let formatted = format_source(src, Path::new("demo.sculpt")).expect("format");
+    assert_eq!(formatted, src);
+}
+
+#[test]
+fn prints_arithmetic_with_minimal_parentheses() {
+    let src = r#"module(Demo):
+  state():
+    due = (amount-paid)*rate+fee%3
+    left = a-(b-c)
+    neg = -(a+b)*-c
+  end
+  rule(r):
+    when not (due>=1 or -due<limit/2) and not done==1:
+      emit stop
+    end
+  end
+end
+"#;
+    let expected = r#"module(Demo):
+  state():
+    due = (amount - paid) * rate + fee % 3
+    left = a - (b - c)
+    neg = -(a + b) * -c
+  end
+
+  rule(r):
+    when not (due >= 1 or -due < limit / 2) and not done == 1:
+      emit stop
+    end
+  end
+end
+"#;
+    let formatted = format_source(src, Path::new("demo.sculpt")).expect("format");
+    assert_eq!(formatted, expected);
+    assert_eq!(ast_json(&formatted), ast_json(src));
+}
+
+#[test]
+fn prints_bool_list_and_record_literals() {
+    let src = r#"module(Demo):
+  state():
+    ok = true
+    cols = [ "a","b", ]
+    rows = [
+      [1, 2],
+      []
+    ]
+    cfg = {retries:3,"two words":false,end:{}}
+  end
+end
+"#;
+    let expected = r#"module(Demo):
+  state():
+    ok = true
+    cols = ["a", "b"]
+    rows = [[1, 2], []]
+    cfg = { retries: 3, "two words": false, end: {} }
+  end
+end
+"#;
+    let formatted = format_source(src, Path::new("demo.sculpt")).expect("format");
+    assert_eq!(formatted, expected);
+    assert_eq!(ast_json(&formatted), ast_json(src));
+}
+
+#[test]
+fn prints_escapes_and_interpolations() {
+    let src = r#"module(Demo):
+  state():
+    title = "Invoice ${ selectedInvoice } (${count+1})\tdue"
+    price = "Cost: \${amount} \u{e9}\r"
+  end
+end
+"#;
+    let expected = r#"module(Demo):
+  state():
+    title = "Invoice ${selectedInvoice} (${count + 1})\tdue"
+    price = "Cost: \${amount} é\r"
+  end
+end
+"#;
+    let formatted = format_source(src, Path::new("demo.sculpt")).expect("format");
+    assert_eq!(formatted, expected);
+    assert_eq!(ast_json(&formatted), ast_json(src));
+}
+
+#[test]
+fn prints_typed_declarations() {
+    let src = r#"module(Demo):
+  state():
+    speedMs:int=400
+    errorText : string
+    rows: list < list<float> > = []
+    tags: list<any>
+  end
+end
+"#;
+    let expected = r#"module(Demo):
+  state():
+    speedMs: int = 400
+    errorText: string
+    rows: list<list<float>> = []
+    tags: list
+  end
+end
+"#;
+    let formatted = format_source(src, Path::new("demo.sculpt")).expect("format");
+    assert_eq!(formatted, expected);
+    assert_eq!(ast_json(&formatted), ast_json(src));
+}
+
+#[test]
+fn prints_transition_guards() {
+    let src = r#"module(Demo):
+  flow(Main):
+    start > List
+    state(List):
+      on input.key(Enter) when selected!=null>Detail
+      on input.key(Up) when(count>3)and ready==true > List
+    end
+  end
+end
+"#;
+    let expected = r#"module(Demo):
+  flow(Main):
+    start > List
+
+    state(List):
+      on input.key(Enter) when selected != null > Detail
+      on input.key(Up) when (count > 3 and ready == true) > List
+    end
+  end
+end
+"#;
+    let formatted = format_source(src, Path::new("demo.sculpt")).expect("format");
+    assert_eq!(formatted, expected);
+    assert_eq!(ast_json(&formatted), ast_json(src));
+}
+
+#[test]
+fn prints_nested_states_and_regions() {
+    let src = r#"module(Demo):
+  flow(Game):
+    start > Play
+    state(Play):
+      on input.key(Esc) > Exit
+      region(Main):
+        start > Running
+        state(Running):
+        end
+      end
+      region(Hud):
+          start > Clock
+          state(Clock):
+            on input.tick > Clock
+          end
+      end
+    end
+    state(Exit):
+      start > Bye
+      state(Bye):
+        terminate
+      end
+    end
+  end
+end
+"#;
+    let expected = r#"module(Demo):
+  flow(Game):
+    start > Play
+
+    state(Play):
+      on input.key(Esc) > Exit
+
+      region(Main):
+        start > Running
+
+        state(Running):
+        end
+      end
+
+      region(Hud):
+        start > Clock
+
+        state(Clock):
+          on input.tick > Clock
+        end
+      end
+    end
+
+    state(Exit):
+      start > Bye
+
+      state(Bye):
+        terminate
+      end
+    end
+  end
+end
+"#;
+    let formatted = format_source(src, Path::new("demo.sculpt")).expect("format");
+    assert_eq!(formatted, expected);
+    assert_eq!(ast_json(&formatted), ast_json(src));
+}
+
+#[test]
+fn prints_flow_signatures_and_run_calls() {
+    let src = r#"module(Demo):
+  flow(Main):
+    start > Ask
+    state(Ask):
+      run Confirm("Sure?",danger:true)->ok
+      run Notify()
+      on done > Ask
+    end
+  end
+  flow(Confirm,message:string,danger:bool)->bool:
+    start > Yes
+    state(Yes):
+      return   true
+    end
+  end
+end
+"#;
+    let expected = r#"module(Demo):
+  flow(Main):
+    start > Ask
+
+    state(Ask):
+      run Confirm("Sure?", danger: true) -> ok
+      run Notify
+      on done > Ask
+    end
+  end
+
+  flow(Confirm, message: string, danger: bool) -> bool:
+    start > Yes
+
+    state(Yes):
+      return true
+    end
+  end
+end
+"#;
+    let formatted = format_source(src, Path::new("demo.sculpt")).expect("format");
+    assert_eq!(formatted, expected);
+    assert_eq!(ast_json(&formatted), ast_json(src));
+}
+
+#[test]
+fn refuses_to_drop_content_after_module_end() {
+    let src = "module(Demo):\nend\nflow(Extra):\nend\n";
+    let err = format_source(src, Path::new("demo.sculpt")).expect_err("trailing content");
+    assert!(err.to_string().contains("S010"));
+}
+
+#[test]
+fn formatting_examples_round_trips_and_is_stable() {
+    let mut files = Vec::new();
+    sculpt_files(Path::new("examples"), &mut files);
+    assert!(!files.is_empty());
+    for file in files {
+        let src = std::fs::read_to_string(&file).expect("read example");
+        let module = parse_source_with_path(&src, &file).expect("parse example");
+        let formatted = format_module(&module);
+        assert_eq!(
+            ast_json(&formatted),
+            ast_json(&src),
+            "AST changed by formatting {}",
+            file.display()
+        );
+        assert_eq!(
+            formatted,
+            src,
+            "{} is not formatted; run `sculpt fmt {}`",
+            file.display(),
+            file.display()
+        );
+    }
+}
+
+#[test]
+fn prints_durations_in_canonical_units() {
+    let src = r#"module(Demo):
+  flow(Main):
+    start > Splash
+    state(Splash):
+      fade = 1500ms
+      after( 2000ms ) > Menu
+    end
+    state(Menu):
+      rule(poll):
+        every(60s):
+          polls += 1
+        end
+      end
+    end
+  end
+end
+"#;
+    let expected = r#"module(Demo):
+  flow(Main):
+    start > Splash
+
+    state(Splash):
+      fade = 1500ms
+      after(2s) > Menu
+    end
+
+    state(Menu):
+      rule(poll):
+        every(1m):
+          polls += 1
+        end
+      end
+    end
+  end
+end
+"#;
+    let formatted = format_source(src, Path::new("demo.sculpt")).expect("format");
+    assert_eq!(formatted, expected);
+    assert_eq!(ast_json(&formatted), ast_json(src));
+}
+
+#[test]
+fn prints_doc_comments_and_attributes() {
+    let src = r#"module(Shop):
+  ## Old checkout.
+  @owner( payments )
+  @deprecated( "use Checkout2" )
+  flow(Checkout):
+    start > Pay
+    @critical
+    state(Pay):
+      ## Counts attempts.
+      @owner(ui)
+      rule(tries):
+        on submit:
+          n += 1
+        end
+      end
+    end
+  end
+end
+"#;
+    let expected = r#"module(Shop):
+  ## Old checkout.
+  @owner(payments)
+  @deprecated("use Checkout2")
+  flow(Checkout):
+    start > Pay
+
+    @critical
+    state(Pay):
+      ## Counts attempts.
+      @owner(ui)
+      rule(tries):
+        on submit:
+          n += 1
+        end
+      end
+    end
+  end
+end
+"#;
+    let formatted = format_source(src, Path::new("shop.sculpt")).expect("format");
+    assert_eq!(formatted, expected);
+    assert_eq!(ast_json(&formatted), ast_json(src));
+}
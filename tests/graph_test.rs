@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use sculpt::contracts::parse_target_contract;
+use sculpt::graph::{build_graph, EdgeKind, NodeKind, SemanticGraph};
+use sculpt::linker::link_program;
+use sculpt::parser::parse_source;
+use sculpt::targets::describe_target;
+
+const MAIN: &str = r#"@meta target=cli
+@meta requires="render.text,net.http"
+module(App.Main):
+  use(cli.ui)
+  use(cli.input) as input
+  import(Company.Shared) as Shared
+  flow(Main):
+    start > Idle
+    state(Idle):
+      ui.text("Limit ${Shared.limit}")
+      on input.key(Enter) > Busy
+    end
+    state(Busy):
+      run Shared.Confirm
+      on done > Idle
+      after(2s) > Idle
+    end
+  end
+  state():
+    count = 0
+  end
+  rule(bump):
+    on input.key(Space):
+      count += 1
+    end
+  end
+end
+"#;
+
+const SHARED: &str = r#"module(Company.Shared):
+  exports(limit, Confirm)
+  state():
+    limit = 1000
+  end
+  flow(Confirm):
+    start > Ask
+    state(Ask):
+      return
+    end
+  end
+end
+"#;
+
+fn graph() -> SemanticGraph {
+    let modules: HashMap<_, _> = [MAIN, SHARED]
+        .iter()
+        .map(|src| {
+            let module = parse_source(src).expect("parse");
+            (module.name.clone(), module)
+        })
+        .collect();
+    let (program, _) = link_program("App.Main", &modules).expect("link");
+    let contract =
+        parse_target_contract(&describe_target("cli").expect("describe")).expect("contract");
+    build_graph(&program, Some(&contract))
+}
+
+fn has_edge(graph: &SemanticGraph, from: &str, kind: EdgeKind, to: &str) -> bool {
+    graph
+        .edges
+        .iter()
+        .any(|edge| edge.from == from && edge.kind == kind && edge.to == to)
+}
+
+#[test]
+fn builds_typed_nodes_and_edges() {
+    let graph = graph();
+    let idle = "state:App.Main.Main.Idle";
+    assert_eq!(graph.node(idle).expect("state").kind, NodeKind::State);
+    assert!(graph.node(idle).expect("state").stable_id.is_some());
+
+    assert!(has_edge(
+        &graph,
+        "module:App.Main",
+        EdgeKind::Imports,
+        "module:Company.Shared"
+    ));
+    assert!(has_edge(
+        &graph,
+        "module:App.Main",
+        EdgeKind::Imports,
+        "package:cli.ui"
+    ));
+    assert!(has_edge(
+        &graph,
+        "flow:App.Main.Main",
+        EdgeKind::Contains,
+        idle
+    ));
+    assert!(has_edge(
+        &graph,
+        idle,
+        EdgeKind::Transitions,
+        "state:App.Main.Main.Busy"
+    ));
+    assert!(has_edge(&graph, idle, EdgeKind::Calls, "package:cli.ui"));
+    assert!(has_edge(
+        &graph,
+        idle,
+        EdgeKind::Reads,
+        "variable:Company.Shared.global.limit"
+    ));
+    assert!(has_edge(
+        &graph,
+        "state:App.Main.Main.Busy",
+        EdgeKind::Calls,
+        "flow:Company.Shared.Confirm"
+    ));
+    assert!(has_edge(
+        &graph,
+        "rule:App.Main.bump",
+        EdgeKind::Writes,
+        "variable:App.Main.global.count"
+    ));
+
+    let timer = graph
+        .edges
+        .iter()
+        .find(|edge| {
+            edge.kind == EdgeKind::Transitions && edge.label.as_deref() == Some("after(2s)")
+        })
+        .expect("timer transition");
+    assert_eq!(timer.from, "state:App.Main.Main.Busy");
+}
+
+#[test]
+fn contract_names_packages_and_capabilities() {
+    let graph = graph();
+    let ui = graph.node("package:cli.ui").expect("package");
+    assert_eq!(ui.attrs["package"], "builtin.cli.ui@1");
+    let provided = |capability: &str| {
+        graph
+            .node(&format!("capability:{}", capability))
+            .expect("capability")
+            .attrs["provided"]
+            .clone()
+    };
+    assert_eq!(provided("render.text"), "true");
+    assert_eq!(provided("net.http"), "false");
+    assert!(has_edge(
+        &graph,
+        "module:App.Main",
+        EdgeKind::Requires,
+        "capability:net.http"
+    ));
+}
+
+#[test]
+fn focus_keeps_direct_neighbours() {
+    let graph = graph();
+    let busy = graph.resolve("Main.Busy", "App.Main").expect("resolve");
+    let focused = graph.focus(&busy.id.clone());
+    let mut ids: Vec<&str> = focused.nodes.iter().map(|node| node.id.as_str()).collect();
+    ids.sort();
+    assert_eq!(
+        ids,
+        vec![
+            "flow:App.Main.Main",
+            "flow:Company.Shared.Confirm",
+            "state:App.Main.Main.Busy",
+            "state:App.Main.Main.Idle",
+        ]
+    );
+    assert!(focused
+        .edges
+        .iter()
+        .all(|edge| edge.from == busy.id || edge.to == busy.id));
+}
+
+#[test]
+fn renders_dot_and_mermaid() {
+    let graph = graph();
+    let focused = graph.focus("state:App.Main.Main.Idle");
+    let dot = focused.to_dot();
+    assert!(dot.starts_with("digraph sculpt {\n"));
+    assert!(dot.contains(
+        "\"state:App.Main.Main.Idle\" -> \"state:App.Main.Main.Busy\" [label=\"transitions: on input.key(Enter)\"];"
+    ));
+    let mermaid = focused.to_mermaid();
+    assert!(mermaid.starts_with("flowchart LR\n"));
+    assert!(mermaid.contains("(\"state Main.Idle\")"));
+    assert!(mermaid.contains("-->|\"calls: text\"|"));
+}
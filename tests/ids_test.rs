@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+
+use sculpt::freeze::compute_ir_hash;
+use sculpt::ir::ids::{assign_ids, IdMap};
+use sculpt::ir::{from_ast, to_pretty_json, IrModule};
+use sculpt::parser::parse_source;
+use sculpt::refactor::{rename_symbol, SymbolKind};
+
+const APP: &str = r#"module(App.Main):
+  flow(Main):
+    start > Idle
+    state(Idle):
+      ui.text("Idle: ${count}")
+      on key(Enter) > Busy
+      on key(Esc) > Idle
+    end
+    state(Busy):
+      on done(count: count) > Idle
+      after(2s) > Idle
+    end
+  end
+  state():
+    count = 0
+  end
+  rule(bump):
+    on key(Space):
+      count += 1
+    end
+  end
+  nd(layout):
+    propose grid(columns: count)
+    satisfy(readable())
+  end
+end
+"#;
+
+fn ir(src: &str) -> IrModule {
+    from_ast(parse_source(src).expect("parse"))
+}
+
+#[test]
+fn every_node_gets_a_stable_id() {
+    let ids = ir(APP).ids;
+    let keys: Vec<&str> = ids.keys().map(String::as_str).collect();
+    assert_eq!(
+        keys,
+        vec![
+            "flow:Main",
+            "global:count",
+            "nd:layout",
+            "rule:bump",
+            "state:Main.Busy",
+            "state:Main.Idle",
+            "transition:Main.Busy/after(2s)",
+            "transition:Main.Busy/on done(count: count)",
+            "transition:Main.Idle/on key(Enter)",
+            "transition:Main.Idle/on key(Esc)",
+        ]
+    );
+    assert!(ids["state:Main.Idle"].starts_with("st_"));
+
+    // IDs depend on names, not positions.
+    let moved = APP.replace("  state():\n    count = 0\n  end\n", "");
+    let moved = moved.replace(
+        "module(App.Main):\n",
+        "module(App.Main):\n  state():\n    count = 0\n  end\n",
+    );
+    assert_eq!(ir(&moved).ids, ids);
+}
+
+#[test]
+fn ids_are_part_of_ir_json_and_hash() {
+    let module = ir(APP);
+    let json = to_pretty_json(&module).expect("json");
+    assert!(json.contains(&format!("\"flow:Main\": \"{}\"", module.ids["flow:Main"])));
+
+    let mut pinned = module.clone();
+    let mut map = BTreeMap::new();
+    map.insert("flow:Main".to_string(), "fl_pinned".to_string());
+    assign_ids(&mut pinned, &map);
+    assert_eq!(pinned.ids["flow:Main"], "fl_pinned");
+    assert_eq!(pinned.ids["nd:layout"], module.ids["nd:layout"]);
+    assert_ne!(
+        compute_ir_hash(&module).expect("hash"),
+        compute_ir_hash(&pinned).expect("hash")
+    );
+}
+
+/// Renames like `sculpt rename` and returns the new source and the IDs after it.
+fn rename(
+    src: &str,
+    from: &str,
+    to: &str,
+    kind: Option<SymbolKind>,
+) -> (String, BTreeMap<String, String>) {
+    let module = parse_source(src).expect("parse");
+    let renamed = rename_symbol(&module, src, from, to, kind).expect("rename");
+    let mut map = IdMap {
+        module: module.name.clone(),
+        ids: from_ast(module).ids,
+    };
+    map.rename(&renamed.old_key, &renamed.new_key);
+    let mut after = ir(&renamed.text);
+    assign_ids(&mut after, &map.ids);
+    (renamed.text, after.ids)
+}
+
+#[test]
+fn renamed_nodes_keep_their_ids() {
+    let before = ir(APP).ids;
+
+    let (text, ids) = rename(APP, "Main.Idle", "Waiting", None);
+    assert!(text.contains("start > Waiting\n    state(Waiting):"));
+    assert!(text.contains("on key(Esc) > Waiting\n"));
+    assert!(text.contains("ui.text(\"Idle: ${count}\")"));
+    assert_eq!(ids["state:Main.Waiting"], before["state:Main.Idle"]);
+    assert_eq!(
+        ids["transition:Main.Waiting/on key(Enter)"],
+        before["transition:Main.Idle/on key(Enter)"]
+    );
+    assert!(!ids.contains_key("state:Main.Idle"));
+
+    let (_, ids) = rename(APP, "Main", "Shell", Some(SymbolKind::Flow));
+    assert_eq!(ids["flow:Shell"], before["flow:Main"]);
+    assert_eq!(ids["state:Shell.Busy"], before["state:Main.Busy"]);
+}
+
+#[test]
+fn global_rename_skips_named_arguments() {
+    let (text, ids) = rename(APP, "count", "clicks", None);
+    assert!(text.contains("ui.text(\"Idle: ${clicks}\")"));
+    assert!(text.contains("on done(count: clicks) > Idle"));
+    assert!(text.contains("    clicks = 0\n"));
+    assert!(text.contains("      clicks += 1\n"));
+    assert!(text.contains("propose grid(columns: clicks)"));
+    assert!(ids.contains_key("global:clicks"));
+}
+
+#[test]
+fn rename_rejects_conflicts_and_unknown_names() {
+    let module = parse_source(APP).expect("parse");
+    let err = rename_symbol(&module, APP, "Main.Idle", "Busy", None).expect_err("conflict");
+    assert!(err
+        .to_string()
+        .contains("A state named 'Busy' already exists"));
+    let err = rename_symbol(&module, APP, "Idle", "Other", None).expect_err("unknown");
+    assert!(err.to_string().contains("No flow, state, rule"));
+    let err = rename_symbol(&module, APP, "bump", "two words", None).expect_err("invalid");
+    assert!(err.to_string().contains("not a valid rule name"));
+}
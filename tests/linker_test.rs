@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use sculpt::ast::{Module, StateStmt};
+use sculpt::diagnostics::{DiagnosticLevel, DiagnosticsError};
+use sculpt::freeze::compute_ir_hash;
+use sculpt::linker::link_program;
+use sculpt::parser::parse_source;
+
+fn project(sources: &[&str]) -> HashMap<String, Module> {
+    sources
+        .iter()
+        .map(|src| {
+            let module = parse_source(src).expect("parse");
+            (module.name.clone(), module)
+        })
+        .collect()
+}
+
+fn link_errors(modules: &HashMap<String, Module>) -> Vec<(String, String)> {
+    let err = link_program("App.Main", modules).expect_err("must fail");
+    let failed = err
+        .downcast_ref::<DiagnosticsError>()
+        .expect("structured diagnostics");
+    failed
+        .diagnostics
+        .iter()
+        .filter(|d| d.level == DiagnosticLevel::Error)
+        .map(|d| (d.code.to_string(), d.message.clone()))
+        .collect()
+}
+
+const MAIN: &str = r#"module(App.Main):
+  import(Company.Shared) as Shared
+  flow(Main):
+    start > A
+    state(A):
+      limit = Shared.limit
+      run Shared.Confirm
+      on done > Exit
+    end
+    state(Exit):
+      terminate
+    end
+  end
+end
+"#;
+
+const SHARED: &str = r#"module(Company.Shared):
+  import(Company.Deep) as Deep
+  exports(limit, Confirm)
+  state():
+    limit = Deep.base
+  end
+  flow(Confirm):
+    start > Ask
+    state(Ask):
+      return
+    end
+  end
+end
+"#;
+
+const DEEP: &str = r#"module(Company.Deep):
+  exports(base)
+  state():
+    base = 1000
+  end
+end
+"#;
+
+#[test]
+fn links_imported_modules_into_one_program() {
+    let modules = project(&[MAIN, SHARED, DEEP]);
+    let (program, _) = link_program("App.Main", &modules).expect("link");
+
+    let linked: Vec<&str> = program.linked.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(linked, vec!["Company.Deep", "Company.Shared"]);
+    assert!(program
+        .fqns
+        .contains(&"Company.Shared.Confirm.Ask".to_string()));
+    assert!(program
+        .fqns
+        .contains(&"Company.Deep.global.base".to_string()));
+
+    // Alias-qualified references spell out the imported module's full name.
+    let statements = &program.flows[0].states[0].statements;
+    assert!(statements.iter().any(|stmt| matches!(
+        stmt,
+        StateStmt::Assign { value: sculpt::ast::Expr::Ident(name), .. }
+            if name == "Company.Shared.limit"
+    )));
+    assert!(statements.iter().any(|stmt| matches!(
+        stmt,
+        StateStmt::Run { flow, .. } if flow == "Company.Shared.Confirm"
+    )));
+    assert!(matches!(
+        &program.linked[1].global_state[0],
+        StateStmt::Assign { value: sculpt::ast::Expr::Ident(name), .. }
+            if name == "Company.Deep.base"
+    ));
+}
+
+#[test]
+fn program_hash_covers_imported_modules() {
+    let (before, _) = link_program("App.Main", &project(&[MAIN, SHARED, DEEP])).expect("link");
+    let changed = DEEP.replace("1000", "2000");
+    let (after, _) = link_program("App.Main", &project(&[MAIN, SHARED, &changed])).expect("link");
+    assert_ne!(
+        compute_ir_hash(&before).expect("hash"),
+        compute_ir_hash(&after).expect("hash")
+    );
+}
+
+#[test]
+fn reports_import_cycles() {
+    let deep = r#"module(Company.Deep):
+  import(Company.Shared) as Shared
+  state():
+    base = 1000
+  end
+end
+"#;
+    let errors = link_errors(&project(&[MAIN, SHARED, deep]));
+    assert_eq!(
+        errors,
+        vec![(
+            "U606".to_string(),
+            "Import cycle: Company.Shared -> Company.Deep -> Company.Shared".to_string()
+        )]
+    );
+}
+
+#[test]
+fn only_direct_imports_are_visible() {
+    let main = r#"module(App.Main):
+  import(Company.Shared) as Shared
+  import(Company.Shraed) as Other
+  flow(Main):
+    start > A
+    state(A):
+      a = Shared.limt
+      b = Deep.base
+      on done > Exit
+    end
+    state(Exit):
+      terminate
+    end
+  end
+end
+"#;
+    let errors = link_errors(&project(&[main, SHARED, DEEP]));
+    let codes: Vec<&str> = errors.iter().map(|(code, _)| code.as_str()).collect();
+    assert_eq!(codes, vec!["U607", "NS503", "NS504"]);
+    assert!(errors[1]
+        .1
+        .contains("module 'Company.Shared' defines no 'limt'"));
+}
+
+#[test]
+fn symbols_are_private_unless_exported() {
+    let main = r#"module(App.Main):
+  import(Company.Shared) as Shared
+  flow(Main):
+    start > A
+    state(A):
+      step = Shared.Confirm.Ask
+      run Shared.Confirm
+      on done > Exit
+    end
+    state(Exit):
+      terminate
+    end
+  end
+end
+"#;
+    let shared = r#"module(Company.Shared):
+  exports(limit, Confrim)
+  state():
+    limit = 1
+  end
+  flow(Confirm):
+    start > Ask
+    state(Ask):
+      return
+    end
+  end
+end
+"#;
+    let modules = project(&[main, shared]);
+    let err = link_program("App.Main", &modules).expect_err("must fail");
+    let failed = err
+        .downcast_ref::<DiagnosticsError>()
+        .expect("structured diagnostics");
+    let errors: Vec<_> = failed
+        .diagnostics
+        .iter()
+        .filter(|d| d.level == DiagnosticLevel::Error)
+        .collect();
+    let codes: Vec<&str> = errors.iter().map(|d| d.code).collect();
+    assert_eq!(codes, vec!["NS508", "NS507", "NS507"]);
+    assert_eq!(errors[0].suggestions[0].replacement, "Confirm");
+    assert!(errors[0].suggestions[0].safe);
+    assert!(errors[1]
+        .message
+        .contains("module 'Company.Shared' does not export 'Confirm.Ask'"));
+}
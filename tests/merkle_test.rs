@@ -0,0 +1,177 @@
+use sculpt::freeze::merkle::{compute_node_hashes, diff_node_hashes, NodeChange, NodeDiff};
+use sculpt::freeze::{create_lock, verify_lock};
+use sculpt::ir::ids::assign_ids;
+use sculpt::ir::{from_ast, IrModule};
+use sculpt::parser::parse_source;
+use sculpt::refactor::rename_symbol;
+use serde_json::json;
+
+const APP: &str = r#"module(App.Main):
+  flow(Main):
+    start > Idle
+    state(Idle):
+      ui.text("Welcome")
+      on key(Enter) > Busy
+    end
+    state(Busy):
+      ui.text("Working")
+      on done > Idle
+    end
+  end
+  state():
+    count = 0
+  end
+  rule(bump):
+    on key(Space):
+      count += 1
+    end
+  end
+end
+"#;
+
+fn ir(src: &str) -> IrModule {
+    from_ast(parse_source(src).expect("parse"))
+}
+
+fn diff(before: &str, after: &str) -> Vec<NodeDiff> {
+    let before = compute_node_hashes(&ir(before), None).expect("hash");
+    let after = compute_node_hashes(&ir(after), None).expect("hash");
+    diff_node_hashes(&before, &after)
+}
+
+fn changed(interface: bool, semantic: bool, output: bool) -> NodeChange {
+    NodeChange::Changed {
+        interface,
+        semantic,
+        output,
+    }
+}
+
+#[test]
+fn body_edits_change_only_the_semantic_hash() {
+    let before = compute_node_hashes(&ir(APP), None).expect("hash");
+    let edited = APP.replace("Welcome", "Welcome!");
+    let after = compute_node_hashes(&ir(&edited), None).expect("hash");
+    assert_ne!(before.root, after.root);
+
+    let diffs = diff_node_hashes(&before, &after);
+    let changes: Vec<(&str, &NodeChange)> = diffs
+        .iter()
+        .map(|diff| (diff.key.as_str(), &diff.change))
+        .collect();
+    // The flow's semantic hash covers its states.
+    assert_eq!(
+        changes,
+        vec![
+            ("flow:Main", &changed(false, true, false)),
+            ("state:Main.Idle", &changed(false, true, false)),
+        ]
+    );
+
+    // Moving code around changes nothing.
+    let moved = APP.replace("  state():\n    count = 0\n  end\n", "");
+    let moved = moved.replacen(
+        "  rule(bump)",
+        "  state():\n    count = 0\n  end\n  rule(bump)",
+        1,
+    );
+    assert_eq!(
+        compute_node_hashes(&ir(&moved), None).expect("hash").root,
+        before.root
+    );
+}
+
+#[test]
+fn transitions_and_triggers_are_interface() {
+    let diffs = diff(APP, &APP.replace("on done > Idle", "on done > Busy"));
+    assert_eq!(diffs[1].key, "state:Main.Busy");
+    assert_eq!(diffs[1].change, changed(true, false, false));
+
+    let diffs = diff(APP, &APP.replace("on key(Space):", "on key(Tab):"));
+    assert_eq!(diffs.len(), 1);
+    assert_eq!(diffs[0].key, "rule:bump");
+    assert_eq!(diffs[0].change, changed(true, false, false));
+
+    let diffs = diff(APP, &APP.replace("count = 0", "count = 1"));
+    assert_eq!(diffs.len(), 1);
+    assert_eq!(diffs[0].key, "module:App.Main");
+}
+
+#[test]
+fn added_removed_and_renamed_nodes() {
+    let extra = APP.replace(
+        "  end\n  state():",
+        "    state(Done):\n      terminate\n    end\n  end\n  state():",
+    );
+    let diffs = diff(APP, &extra);
+    assert!(diffs
+        .iter()
+        .any(|d| d.key == "state:Main.Done" && d.change == NodeChange::Added));
+    let diffs = diff(&extra, APP);
+    assert!(diffs
+        .iter()
+        .any(|d| d.key == "state:Main.Done" && d.change == NodeChange::Removed));
+
+    // A rename that keeps the stable ID is a change of the same node.
+    let module = parse_source(APP).expect("parse");
+    let renamed = rename_symbol(&module, APP, "Main.Busy", "Working", None).expect("rename");
+    let before = ir(APP);
+    let mut pinned = before.ids.clone();
+    let id = pinned.remove("state:Main.Busy").expect("id");
+    pinned.insert("state:Main.Working".to_string(), id);
+    let mut after = ir(&renamed.text);
+    assign_ids(&mut after, &pinned);
+    let diffs = diff_node_hashes(
+        &compute_node_hashes(&before, None).expect("hash"),
+        &compute_node_hashes(&after, None).expect("hash"),
+    );
+    let state = diffs
+        .iter()
+        .find(|d| d.key == "state:Main.Working")
+        .expect("renamed state");
+    assert_eq!(state.previous_key.as_deref(), Some("state:Main.Busy"));
+    assert_eq!(state.change, changed(true, false, false));
+    assert!(!diffs.iter().any(|d| d.change == NodeChange::Removed));
+}
+
+#[test]
+fn output_hashes_cover_generated_target_parts() {
+    let target = json!({
+        "type": "cli-ir",
+        "version": 1,
+        "views": {"Idle": [{"kind": "text", "text": "Welcome"}]},
+        "flow": {"start": "Idle", "transitions": {"Idle": {"Enter": "Busy"}}}
+    });
+    let module = ir(APP);
+    let hashes = compute_node_hashes(&module, Some(&target)).expect("hash");
+    let node = |key: &str| {
+        hashes
+            .nodes
+            .iter()
+            .find(|n| n.key == key)
+            .expect("node")
+            .clone()
+    };
+    assert!(node("state:Main.Idle").output.is_some());
+    assert!(node("state:Main.Busy").output.is_none());
+
+    let mut regenerated = target.clone();
+    regenerated["views"]["Idle"][0]["text"] = json!("Hello");
+    let after = compute_node_hashes(&module, Some(&regenerated)).expect("hash");
+    let diffs = diff_node_hashes(&hashes, &after);
+    assert_eq!(diffs[1].key, "state:Main.Idle");
+    assert_eq!(diffs[1].change, changed(false, false, true));
+}
+
+#[test]
+fn lock_mismatch_names_the_changed_nodes() {
+    let target = json!({"type": "cli-ir", "version": 1, "views": {}, "flow": {"start": "Idle"}});
+    let lock = create_lock(&ir(APP), "stub", "cli", &target, "stub").expect("lock");
+    assert!(lock.node_hashes.is_some());
+    verify_lock(&ir(APP), &lock).expect("unchanged");
+
+    let err = verify_lock(&ir(&APP.replace("Working", "Busy...")), &lock).expect_err("changed");
+    assert!(err
+        .to_string()
+        .contains("changed nodes: flow:Main (semantic), state:Main.Busy (semantic)"));
+}
@@ -1,7 +1,8 @@
-use sculpt::ast::{Item, RuleTrigger, StateStmt};
+use sculpt::ast::{BinaryOp, Expr, Item, RuleTrigger, StateStmt, StringPart, TypeName, UnaryOp};
+use sculpt::diagnostics::DiagnosticLevel;
 use sculpt::freeze::compute_ir_hash;
 use sculpt::ir::from_ast;
-use sculpt::parser::parse_source;
+use sculpt::parser::{parse_source, parse_source_recovering, SyntaxErrors};
 
 #[test]
 fn parses_minimal_module() {
@@ -132,6 +133,28 @@ end
     assert_eq!(module.imports[1].alias.as_deref(), Some("SharedUI"));
 }
 
+#[test]
+fn parses_export_lists() {
+    let src = r#"module(App):
+  exports(total, Main)
+  exports(limit)
+  state():
+    exports = 1
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse ok");
+    let names: Vec<&Vec<String>> = module.exports.iter().map(|d| &d.names).collect();
+    assert_eq!(
+        names,
+        vec![
+            &vec!["total".to_string(), "Main".to_string()],
+            &vec!["limit".to_string()]
+        ]
+    );
+    assert_eq!(module.exports[0].span.text(), "exports(total, Main)");
+}
+
 #[test]
 fn parses_module_and_nd_defines() {
     let src = r#"module(App):
@@ -262,3 +285,943 @@ end
     let module = parse_source(src).expect("parse ok");
     assert_eq!(module.name, "App");
 }
+
+#[test]
+fn records_source_spans_on_nodes() {
+    let src = "module(App):\n  flow(Main):\n    start > A\n    state(A):\n      on input.key(Enter) > A\n    end\n  end\nend\n";
+    let module = parse_source(src).expect("parse ok");
+    assert_eq!(module.span.line, 1);
+    let flow = match &module.items[0] {
+        Item::Flow(flow) => flow,
+        _ => panic!("expected flow"),
+    };
+    assert_eq!((flow.span.line, flow.span.col), (2, 3));
+    assert!(flow.span.text().starts_with("flow(Main):"));
+    assert!(flow.span.text().ends_with("end"));
+    assert_eq!(flow.start_span.as_ref().unwrap().text(), "start > A");
+    let state = &flow.states[0];
+    assert_eq!((state.span.line, state.span.col), (4, 5));
+    match &state.statements[0] {
+        StateStmt::On { event, span, .. } => {
+            assert_eq!(span.text(), "on input.key(Enter) > A");
+            assert_eq!(event.span.text(), "input.key(Enter)");
+            assert_eq!((event.span.line, event.span.col), (5, 10));
+            assert_eq!(event.args[0].span.text(), "Enter");
+        }
+        other => panic!("unexpected statement {other:?}"),
+    }
+}
+
+#[test]
+fn ir_hash_ignores_source_layout() {
+    let compact = "module(Mini):\n  state():\n    counter = 0\n  end\nend\n";
+    let spaced = "\n\nmodule(Mini):\n\n  state():\n      counter   =   0\n  end\nend\n";
+    let h1 = compute_ir_hash(&from_ast(parse_source(compact).unwrap())).unwrap();
+    let h2 = compute_ir_hash(&from_ast(parse_source(spaced).unwrap())).unwrap();
+    assert_eq!(h1, h2);
+}
+
+#[test]
+fn ir_orders_rules_by_priority() {
+    let src = r#"module(Mini):
+  @priority(2)
+  rule(last):
+    on tick:
+      x = 3
+    end
+  end
+  rule(first):
+    on tick:
+      x = 1
+    end
+  end
+  @priority(-1)
+  rule(early):
+    on tick:
+      x = 0
+    end
+  end
+  @priority(1)
+  rule(second):
+    on tick:
+      x = 2
+    end
+  end
+end
+"#;
+    let ir = from_ast(parse_source(src).unwrap());
+    let names: Vec<&str> = ir.rules.iter().map(|rule| rule.name.as_str()).collect();
+    assert_eq!(names, vec!["early", "first", "second", "last"]);
+}
+
+#[test]
+fn recovering_parse_reports_all_syntax_errors() {
+    let src = r#"@meta target=cli
+@bogus x=1
+module(Demo):
+  flow(Main):
+    start > A
+    state(A):
+      on tick >
+      on done > B
+    end
+    state(B)
+      terminate
+    end
+  end
+  rule(r1):
+    on tick:
+      emit
+    end
+  end
+  state():
+    total = 0
+  end
+end
+"#;
+    let outcome = parse_source_recovering(src);
+    let found: Vec<_> = outcome
+        .diagnostics
+        .iter()
+        .map(|d| (d.code, d.span.as_ref().map(|s| s.line)))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("S008", Some(2)),
+            ("S005", Some(7)),
+            ("S006", Some(10)),
+            ("S005", Some(16)),
+        ]
+    );
+
+    // Well-formed parts survive for semantic validation.
+    assert_eq!(outcome.module.name, "Demo");
+    let flow = outcome
+        .module
+        .items
+        .iter()
+        .find_map(|item| match item {
+            Item::Flow(flow) => Some(flow),
+            _ => None,
+        })
+        .expect("flow");
+    let states: Vec<_> = flow.states.iter().map(|s| s.name.as_deref()).collect();
+    assert_eq!(states, vec![Some("A")]);
+    assert_eq!(flow.states[0].statements.len(), 1);
+    assert!(outcome
+        .module
+        .items
+        .iter()
+        .any(|item| matches!(item, Item::GlobalState(_))));
+}
+
+#[test]
+fn recovering_parse_stops_an_unclosed_paren_at_the_line_end() {
+    let src = r#"module(Demo):
+  flow(Main):
+    start > A
+    state(A):
+      on tick > B
+    end
+    state(B:
+      terminate
+    end
+  end
+  state():
+    x = = 1
+  end
+end
+"#;
+    let outcome = parse_source_recovering(src);
+    let found: Vec<_> = outcome
+        .diagnostics
+        .iter()
+        .map(|d| (d.code, d.span.as_ref().map(|s| s.line)))
+        .collect();
+    assert_eq!(found, vec![("S006", Some(7)), ("S006", Some(12))]);
+    assert!(outcome
+        .module
+        .items
+        .iter()
+        .any(|item| matches!(item, Item::Flow(flow) if flow.name == "Main")));
+}
+
+#[test]
+fn recovering_parse_reports_missing_end_by_indentation() {
+    let src = r#"module(Demo):
+  flow(Main):
+    start > A
+    state(A):
+      on tick > B
+    state(B):
+      terminate
+    end
+  end
+end
+"#;
+    let outcome = parse_source_recovering(src);
+    assert_eq!(outcome.diagnostics.len(), 1);
+    assert_eq!(outcome.diagnostics[0].code, "S004");
+    assert_eq!(
+        outcome.diagnostics[0].message,
+        "Missing 'end' for state 'A'"
+    );
+    let Item::Flow(flow) = &outcome.module.items[0] else {
+        panic!("expected flow");
+    };
+    assert_eq!(flow.states.len(), 2);
+}
+
+#[test]
+fn parse_source_error_lists_every_syntax_error() {
+    let src = r#"module(Demo):
+  flow(Main):
+    start >
+  end
+  $
+end
+module(Other):
+end
+"#;
+    let err = parse_source(src).expect_err("syntax errors");
+    let errors = err.downcast_ref::<SyntaxErrors>().expect("syntax errors");
+    let codes: Vec<_> = errors.0.iter().map(|d| d.code).collect();
+    assert_eq!(codes, vec!["S005", "S007", "S002"]);
+    let rendered = err.to_string();
+    assert!(rendered.contains("S007: Unexpected character '$'"));
+    assert!(rendered.contains("5 |   $"));
+}
+
+#[test]
+fn content_after_module_end_is_ignored_with_a_warning() {
+    let src = "module(Demo):\nend\nflow(Extra):\nend\nmodule(Other):\nend\n";
+    let module = parse_source(src).expect("warnings only");
+    assert_eq!(module.name, "Demo");
+    assert!(module.items.is_empty());
+
+    let outcome = parse_source_recovering(src);
+    let codes: Vec<_> = outcome
+        .diagnostics
+        .iter()
+        .map(|d| (d.code, d.level))
+        .collect();
+    assert_eq!(codes, vec![("S010", DiagnosticLevel::Warning)]);
+    let outcome = parse_source_recovering("module(Demo):\nend\nmodule(Other):\nend\n");
+    assert_eq!(outcome.diagnostics[0].code, "S002");
+    assert_eq!(outcome.diagnostics[0].level, DiagnosticLevel::Warning);
+}
+
+#[test]
+fn missing_module_root_has_code() {
+    let outcome = parse_source_recovering("flow(App):\nend\n");
+    assert_eq!(outcome.diagnostics[0].code, "S001");
+}
+
+fn sexpr(expr: &Expr) -> String {
+    match expr {
+        Expr::Number(n) => n.to_string(),
+        Expr::Ident(name) => name.clone(),
+        Expr::Binary { left, op, right } => {
+            let op = match op {
+                BinaryOp::Add => "+",
+                BinaryOp::Sub => "-",
+                BinaryOp::Mul => "*",
+                BinaryOp::Div => "/",
+                BinaryOp::Mod => "%",
+                BinaryOp::Gte => ">=",
+                BinaryOp::Gt => ">",
+                BinaryOp::Lt => "<",
+                BinaryOp::Eq => "==",
+                BinaryOp::Neq => "!=",
+                BinaryOp::And => "and",
+                BinaryOp::Or => "or",
+            };
+            format!("({} {} {})", op, sexpr(left), sexpr(right))
+        }
+        Expr::Unary { op, operand } => match op {
+            UnaryOp::Neg => format!("(neg {})", sexpr(operand)),
+            UnaryOp::Not => format!("(not {})", sexpr(operand)),
+        },
+        other => format!("{:?}", other),
+    }
+}
+
+#[test]
+fn parses_arithmetic_and_unary_operators_with_precedence() {
+    let cases = [
+        ("a + b * c - d", "(- (+ a (* b c)) d)"),
+        (
+            "(amount - paid) * rate >= 10",
+            "(>= (* (- amount paid) rate) 10)",
+        ),
+        ("-x % 3 > total / 2", "(> (% (neg x) 3) (/ total 2))"),
+        (
+            "not a == b or c != 1 and not not d < -1",
+            "(or (not (== a b)) (and (!= c 1) (not (not (< d (neg 1))))))",
+        ),
+    ];
+    for (expr, expected) in cases {
+        let src = format!(
+            "module(M):\n  rule(r):\n    when {}:\n      emit done\n    end\n  end\nend\n",
+            expr
+        );
+        let module = parse_source(&src).expect("parse ok");
+        let Item::Rule(rule) = &module.items[0] else {
+            panic!("expected rule");
+        };
+        let RuleTrigger::When(when) = &rule.trigger else {
+            panic!("expected when");
+        };
+        assert_eq!(sexpr(when), expected, "{}", expr);
+    }
+}
+
+#[test]
+fn not_is_only_an_operator_before_an_operand() {
+    let src = r#"module(M):
+  state():
+    not = 1
+    flag = not
+  end
+  rule(r):
+    when not.ready == 1:
+      emit not.valid
+    end
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse ok");
+    let Item::Rule(rule) = &module.items[1] else {
+        panic!("expected rule");
+    };
+    let RuleTrigger::When(when) = &rule.trigger else {
+        panic!("expected when");
+    };
+    assert_eq!(sexpr(when), "(== not.ready 1)");
+}
+
+#[test]
+fn parses_bool_list_and_record_literals() {
+    let src = r#"module(M):
+  state():
+    valid = true
+    done = false
+    columns = ["invoice_id", "customer_id",]
+    matrix = [
+      [1, -2],
+      [],
+    ]
+    config = { retries: 3, "label text": "x", nested: { on: true } }
+    empty = {}
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse ok");
+    let Item::GlobalState(state) = &module.items[0] else {
+        panic!("expected global state");
+    };
+    let values: Vec<&Expr> = state
+        .statements
+        .iter()
+        .map(|stmt| match stmt {
+            StateStmt::Assign { value, .. } => value,
+            other => panic!("expected assignment, got {:?}", other),
+        })
+        .collect();
+    assert!(matches!(values[0], Expr::Bool(true)));
+    assert!(matches!(values[1], Expr::Bool(false)));
+    let Expr::List(columns) = values[2] else {
+        panic!("expected list");
+    };
+    assert!(
+        matches!(columns.as_slice(), [Expr::String(a), Expr::String(b)] if a == "invoice_id" && b == "customer_id")
+    );
+    let Expr::List(rows) = values[3] else {
+        panic!("expected list");
+    };
+    assert!(
+        matches!(rows.as_slice(), [Expr::List(first), Expr::List(second)] if first.len() == 2 && second.is_empty())
+    );
+    let Expr::Record(fields) = values[4] else {
+        panic!("expected record");
+    };
+    let keys: Vec<_> = fields.iter().map(|f| f.key.as_str()).collect();
+    assert_eq!(keys, vec!["retries", "label text", "nested"]);
+    assert!(matches!(&fields[2].value, Expr::Record(inner) if inner[0].key == "on"));
+    assert!(matches!(values[5], Expr::Record(fields) if fields.is_empty()));
+}
+
+#[test]
+fn unterminated_list_is_a_syntax_error() {
+    let outcome = parse_source_recovering("module(M):\n  state():\n    a = [1, 2\n  end\nend\n");
+    assert_eq!(outcome.diagnostics[0].code, "S006");
+}
+
+fn state_values(src: &str) -> Vec<Expr> {
+    let module = parse_source(src).expect("parse ok");
+    let Item::GlobalState(state) = &module.items[0] else {
+        panic!("expected global state");
+    };
+    state
+        .statements
+        .iter()
+        .map(|stmt| match stmt {
+            StateStmt::Assign { value, .. } => value.clone(),
+            other => panic!("expected assignment, got {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn decodes_string_escapes() {
+    let values = state_values(
+        r#"module(M):
+  state():
+    a = "line\n\t\"quoted\" \\ \u{1F600} \$"
+    b = "Total: \${literal}"
+  end
+end
+"#,
+    );
+    assert!(matches!(&values[0], Expr::String(s) if s == "line\n\t\"quoted\" \\ \u{1F600} $"));
+    assert!(matches!(&values[1], Expr::String(s) if s == "Total: ${literal}"));
+}
+
+#[test]
+fn parses_string_interpolation_into_parts() {
+    let values = state_values(
+        r#"module(M):
+  state():
+    title = "Invoice ${selectedInvoice} of ${count + 1}: ${ { id: 1 } }!"
+  end
+end
+"#,
+    );
+    let Expr::Interpolated(parts) = &values[0] else {
+        panic!("expected interpolated string, got {:?}", values[0]);
+    };
+    assert_eq!(parts.len(), 7);
+    assert!(matches!(&parts[0], StringPart::Text(t) if t == "Invoice "));
+    assert!(matches!(&parts[1], StringPart::Expr(Expr::Ident(id)) if id == "selectedInvoice"));
+    assert!(matches!(
+        &parts[3],
+        StringPart::Expr(Expr::Binary {
+            op: BinaryOp::Add,
+            ..
+        })
+    ));
+    assert!(matches!(&parts[5], StringPart::Expr(Expr::Record(_))));
+    assert!(matches!(&parts[6], StringPart::Text(t) if t == "!"));
+}
+
+#[test]
+fn rejects_bad_escapes_and_interpolations() {
+    for (body, code, needle) in [
+        (r#"a = "\u{ZZ}""#, "S007", "unicode escape"),
+        (
+            r#"a = "x ${name""#,
+            "S007",
+            "Unterminated string interpolation",
+        ),
+        (r#"a = "${}""#, "S006", "Empty string interpolation"),
+        (r#"a = "${a b}""#, "S006", "'}' to close the interpolation"),
+    ] {
+        let src = format!("module(M):\n  state():\n    {}\n  end\nend\n", body);
+        let outcome = parse_source_recovering(&src);
+        assert!(
+            outcome
+                .diagnostics
+                .iter()
+                .any(|d| d.code == code && d.message.contains(needle)),
+            "{}: {:?}",
+            body,
+            outcome.diagnostics
+        );
+    }
+}
+
+#[test]
+fn parses_typed_state_declarations() {
+    let module = parse_source(
+        r#"module(M):
+  state():
+    speedMs: int = 400
+    errorText: string
+    rows: list<list<float>> = []
+    tags: list
+  end
+end
+"#,
+    )
+    .expect("parse ok");
+    let Item::GlobalState(state) = &module.items[0] else {
+        panic!("expected global state");
+    };
+    let decls: Vec<_> = state
+        .statements
+        .iter()
+        .map(|stmt| match stmt {
+            StateStmt::Assign {
+                target, value, ty, ..
+            } => (target.as_str(), ty.clone(), value.clone()),
+            other => panic!("expected assignment, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(decls[0].1, Some(TypeName::Int));
+    assert!(matches!(decls[0].2, Expr::Number(n) if n == 400.0));
+    assert_eq!(decls[1].1, Some(TypeName::String));
+    assert!(matches!(decls[1].2, Expr::Null));
+    assert_eq!(
+        decls[2].1.as_ref().map(ToString::to_string).as_deref(),
+        Some("list<list<float>>")
+    );
+    assert_eq!(
+        decls[3].1.as_ref().map(ToString::to_string).as_deref(),
+        Some("list")
+    );
+}
+
+#[test]
+fn rejects_unknown_types_and_typed_add_assign() {
+    for (body, needle) in [
+        ("count: number = 1", "Unknown type 'number'"),
+        ("count: int += 1", "must use '=', not '+='"),
+    ] {
+        let src = format!("module(M):\n  state():\n    {}\n  end\nend\n", body);
+        let outcome = parse_source_recovering(&src);
+        assert!(
+            outcome
+                .diagnostics
+                .iter()
+                .any(|d| d.code == "S006" && d.message.contains(needle)),
+            "{}: {:?}",
+            body,
+            outcome.diagnostics
+        );
+    }
+}
+
+#[test]
+fn parses_guarded_transitions() {
+    let module = parse_source(
+        r#"module(M):
+  flow(Main):
+    start > List
+    state(List):
+      on input.key(Enter) when selectedInvoice != null > Detail
+      on input.key(Enter) when (count > 3) and not ready == true > Busy
+      on input.key(Esc) > List
+    end
+  end
+end
+"#,
+    )
+    .expect("parse ok");
+    let Item::Flow(flow) = &module.items[0] else {
+        panic!("expected flow");
+    };
+    let guards: Vec<_> = flow.states[0]
+        .statements
+        .iter()
+        .map(|stmt| match stmt {
+            StateStmt::On { guard, target, .. } => (guard.as_ref().map(sexpr), target.as_str()),
+            other => panic!("expected on, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(
+        guards,
+        vec![
+            (Some("(!= selectedInvoice Null)".to_string()), "Detail"),
+            (
+                Some("(and (> count 3) (not (== ready Bool(true))))".to_string()),
+                "Busy"
+            ),
+            (None, "List"),
+        ]
+    );
+}
+
+#[test]
+fn guards_need_a_transition_target() {
+    for src in [
+        "module(M):\n  flow(F):\n    start > A\n    state(A):\n      on input.key(x) when ready == 1:: emit go\n    end\n  end\nend\n",
+        "module(M):\n  flow(F):\n    start > A\n    state(A):\n      on input.key(x) when > A\n    end\n  end\nend\n",
+    ] {
+        let outcome = parse_source_recovering(src);
+        assert_eq!(outcome.diagnostics[0].code, "S006", "{:?}", outcome.diagnostics);
+    }
+}
+
+#[test]
+fn parses_nested_states_and_regions() {
+    let module = parse_source(
+        r#"module(M):
+  flow(Game):
+    start > Play
+    state(Play):
+      on input.key(Esc) > Exit
+      region(Main):
+        start > Running
+        state(Running):
+          on input.key(P) > Paused
+        end
+        state(Paused):
+          on input.key(P) > Running
+        end
+      end
+      region(Hud):
+        start > Clock
+        state(Clock):
+          on input.tick > Clock
+        end
+      end
+    end
+    state(Desk):
+      start > Inbox
+      state(Inbox):
+        on input.key(Enter) > Exit
+      end
+    end
+    state(Exit):
+      terminate
+    end
+  end
+end
+"#,
+    )
+    .expect("parse ok");
+    let Item::Flow(flow) = &module.items[0] else {
+        panic!("expected flow");
+    };
+    let play = &flow.states[0];
+    assert_eq!(play.statements.len(), 1);
+    let regions: Vec<_> = play
+        .regions
+        .iter()
+        .map(|r| (r.name.as_str(), r.start.as_deref(), r.states.len()))
+        .collect();
+    assert_eq!(
+        regions,
+        vec![("Main", Some("Running"), 2), ("Hud", Some("Clock"), 1)]
+    );
+    let desk = &flow.states[1];
+    assert_eq!(desk.start.as_deref(), Some("Inbox"));
+    assert_eq!(desk.states[0].name.as_deref(), Some("Inbox"));
+    let names: Vec<_> = flow
+        .all_states()
+        .iter()
+        .filter_map(|s| s.name.as_deref())
+        .collect();
+    assert_eq!(
+        names,
+        vec!["Play", "Running", "Paused", "Clock", "Desk", "Inbox", "Exit"]
+    );
+}
+
+#[test]
+fn regions_only_hold_start_and_states() {
+    let outcome = parse_source_recovering(
+        "module(M):\n  flow(F):\n    start > A\n    state(A):\n      region(R):\n        on input.key(x) > A\n      end\n    end\n  end\nend\n",
+    );
+    assert_eq!(
+        outcome.diagnostics[0].code, "S006",
+        "{:?}",
+        outcome.diagnostics
+    );
+    assert!(outcome.diagnostics[0]
+        .message
+        .contains("in region 'R' (expected start or state)"));
+}
+
+#[test]
+fn parses_flow_signatures_run_calls_and_returns() {
+    let module = parse_source(
+        r#"module(M):
+  flow(Main):
+    start > Ask
+    state(Ask):
+      run Confirm("Delete invoice?", danger: true) -> confirmed
+      on done > Ask
+    end
+  end
+  flow(Confirm, message: string, danger: bool) -> bool:
+    start > Prompt
+    state(Prompt):
+      on input.key(Y) > Yes
+    end
+    state(Yes):
+      return true
+    end
+  end
+  flow(Notify):
+    start > Show
+    state(Show):
+      run Helper
+      return
+    end
+  end
+end
+"#,
+    )
+    .expect("parse ok");
+    let flows: Vec<_> = module
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Flow(flow) => Some(flow),
+            _ => None,
+        })
+        .collect();
+    let StateStmt::Run {
+        flow, args, result, ..
+    } = &flows[0].states[0].statements[0]
+    else {
+        panic!("expected run");
+    };
+    assert_eq!(flow, "Confirm");
+    assert_eq!(args.len(), 2);
+    assert_eq!(args[1].name.as_deref(), Some("danger"));
+    assert_eq!(result.as_deref(), Some("confirmed"));
+
+    let confirm = flows[1];
+    let params: Vec<_> = confirm
+        .params
+        .iter()
+        .map(|p| (p.name.as_str(), p.ty.clone()))
+        .collect();
+    assert_eq!(
+        params,
+        vec![("message", TypeName::String), ("danger", TypeName::Bool)]
+    );
+    assert_eq!(confirm.returns, Some(TypeName::Bool));
+    assert!(matches!(
+        confirm.states[1].statements[0],
+        StateStmt::Return {
+            value: Some(Expr::Bool(true)),
+            ..
+        }
+    ));
+    assert!(matches!(
+        flows[2].states[0].statements[..],
+        [StateStmt::Run { .. }, StateStmt::Return { value: None, .. }]
+    ));
+}
+
+#[test]
+fn parses_duration_literals_and_timers() {
+    let module = parse_source(
+        r#"module(M):
+  flow(Main):
+    start > Splash
+    state(Splash):
+      delay = 1.5s
+      after(400ms) > Menu
+    end
+    state(Menu):
+      rule(blink):
+        every(2s):
+          visible = 1
+        end
+      end
+    end
+  end
+end
+"#,
+    )
+    .expect("parse ok");
+    let Item::Flow(flow) = &module.items[0] else {
+        panic!("expected flow");
+    };
+    let splash = &flow.states[0].statements;
+    assert!(matches!(
+        splash[0],
+        StateStmt::Assign {
+            value: Expr::Duration(1500),
+            ..
+        }
+    ));
+    let StateStmt::After {
+        delay_ms, target, ..
+    } = &splash[1]
+    else {
+        panic!("expected after");
+    };
+    assert_eq!((*delay_ms, target.as_str()), (400, "Menu"));
+    let StateStmt::Rule(rule) = &flow.states[1].statements[0] else {
+        panic!("expected rule");
+    };
+    assert!(matches!(rule.trigger, RuleTrigger::Every(2000)));
+
+    let outcome = parse_source_recovering(
+        r#"module(M):
+  flow(Main):
+    start > A
+    state(A):
+      after(5) > A
+      x = 0.5ms
+    end
+  end
+end
+"#,
+    );
+    let found: Vec<_> = outcome
+        .diagnostics
+        .iter()
+        .map(|d| (d.code, d.span.as_ref().map(|s| s.line)))
+        .collect();
+    assert_eq!(found, vec![("S006", Some(5)), ("S007", Some(6))]);
+}
+
+#[test]
+fn parses_doc_comments_and_attributes() {
+    let module = parse_source(
+        r#"module(Shop):
+  # not a doc comment
+  ## Checkout for the old provider.
+  ##
+  ## Kept for old links.
+  @owner(payments)
+  @deprecated("use Checkout2")
+  flow(Checkout):
+    start > Pay
+
+    @critical
+    state(Pay):
+      ## Counts attempts.
+      rule(tries):
+        on submit:
+          n += 1
+        end
+      end
+    end
+  end
+
+  @owner("ui")
+  nd(layout):
+    propose grid()
+    satisfy(
+      valid()
+    )
+  end
+end
+"#,
+    )
+    .expect("parse ok");
+    let Item::Flow(flow) = &module.items[0] else {
+        panic!("expected flow");
+    };
+    assert_eq!(
+        flow.annotations.doc.as_deref(),
+        Some("Checkout for the old provider.\n\nKept for old links.")
+    );
+    let names: Vec<_> = flow
+        .annotations
+        .attrs
+        .iter()
+        .map(|a| a.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["owner", "deprecated"]);
+    assert_eq!(
+        flow.annotations.attr("owner").and_then(|a| a.text_arg()),
+        Some("payments".to_string())
+    );
+    assert_eq!(
+        flow.annotations.deprecated().as_deref(),
+        Some("use Checkout2")
+    );
+    assert_eq!(flow.span.line, 6);
+
+    let pay = &flow.states[0];
+    assert!(pay.annotations.attr("critical").is_some());
+    assert!(pay.annotations.doc.is_none());
+    let StateStmt::Rule(rule) = &pay.statements[0] else {
+        panic!("expected rule");
+    };
+    assert_eq!(rule.annotations.doc.as_deref(), Some("Counts attempts."));
+    let Item::Nd(nd) = &module.items[1] else {
+        panic!("expected nd");
+    };
+    assert_eq!(
+        nd.annotations.attr("owner").and_then(|a| a.text_arg()),
+        Some("ui".to_string())
+    );
+
+    let ir = from_ast(module);
+    let json = serde_json::to_value(&ir).expect("serialize");
+    assert_eq!(
+        json["flows"][0]["annotations"]["attrs"][1]["name"],
+        "deprecated"
+    );
+
+    let outcome = parse_source_recovering(
+        r#"module(M):
+  @critical
+  state():
+    x = 1
+  end
+  @owner(a) flow(Main):
+    start > A
+    state(A):
+    end
+  end
+end
+"#,
+    );
+    let found: Vec<_> = outcome
+        .diagnostics
+        .iter()
+        .map(|d| (d.code, d.span.as_ref().map(|s| s.line)))
+        .collect();
+    assert_eq!(found, vec![("S008", Some(2)), ("S008", Some(6))]);
+}
+
+#[test]
+fn language_edition_gates_newer_constructs() {
+    let src = r#"@meta language=1.0
+module(M):
+  @critical
+  flow(Main, retries: int):
+    start > A
+    state(A):
+      on go when retries > B
+      after(2s) > B
+    end
+    state(B):
+      start > C
+      state(C):
+      end
+    end
+  end
+end
+"#;
+    let outcome = parse_source_recovering(src);
+    let found: Vec<_> = outcome
+        .diagnostics
+        .iter()
+        .map(|d| (d.code, d.span.as_ref().map(|s| s.line)))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("S009", Some(3)),
+            ("S009", Some(4)),
+            ("S009", Some(7)),
+            ("S009", Some(8)),
+            ("S009", Some(11)),
+            ("S009", Some(12)),
+        ]
+    );
+    assert!(outcome.diagnostics[0].message.contains(
+        "Item attributes require language 1.1 or newer; this file declares language=1.0"
+    ));
+    // Gated constructs still parse, so the rest of the file is validated.
+    let Item::Flow(flow) = &outcome.module.items[0] else {
+        panic!("expected flow");
+    };
+    assert_eq!(flow.states[1].states.len(), 1);
+    assert!(parse_source(src).is_err());
+
+    let pinned = parse_source(&src.replace("language=1.0", "language=1.1")).expect("parse ok");
+    assert_eq!(pinned.meta.get("language").map(String::as_str), Some("1.1"));
+}
@@ -30,3 +30,25 @@ end
     assert!(report.contains("overall_nd_score:"));
     assert!(report.contains("overall_budget_status:"));
 }
+
+#[test]
+fn report_lists_doc_comments_and_attributes() {
+    let src = r#"module(App):
+  ## Picks the screen layout.
+  @owner(design)
+  @critical
+  nd(layout):
+    propose grid()
+    satisfy(
+      noOverlap()
+    )
+  end
+end
+"#;
+
+    let module = parse_source(src).expect("parse ok");
+    let report = generate_report(&from_ast(module));
+    assert!(report.contains(
+        "Annotations\n-----------\nnd layout: @owner(design) @critical\n  Picks the screen layout.\n"
+    ));
+}
@@ -0,0 +1,363 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+fn sculpt_bin() -> &'static str {
+    env!("CARGO_BIN_EXE_sculpt")
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("time")
+        .as_nanos();
+    let dir = std::env::temp_dir().join(format!("sculpt_runtime_{name}_{stamp}"));
+    fs::create_dir_all(&dir).expect("mkdir");
+    dir
+}
+
+/// Builds `src` with the stub provider and returns the path of the generated `main.js`.
+fn build(dir: &Path, name: &str, src: &str, target: &str) -> PathBuf {
+    let input = format!("{name}.sculpt");
+    fs::write(dir.join(&input), src).expect("write script");
+    build_input(dir, &input, target);
+    dir.join("dist").join(name).join("main.js")
+}
+
+/// Builds the script or project file `input` in `dir` with the stub provider.
+fn build_input(dir: &Path, input: &str, target: &str) {
+    let out = Command::new(sculpt_bin())
+        .arg("build")
+        .arg(input)
+        .args(["--target", target, "--provider", "stub"])
+        .current_dir(dir)
+        .output()
+        .expect("run");
+    assert!(
+        out.status.success(),
+        "stdout={}\nstderr={}",
+        String::from_utf8_lossy(&out.stdout),
+        String::from_utf8_lossy(&out.stderr)
+    );
+}
+
+/// Runs `script` with node, feeding `stdin`, and returns its stdout. `None` when node is not
+/// installed, so the runtime checks are skipped there.
+fn run_node(script: &Path, stdin: &str) -> Option<String> {
+    let mut child = match Command::new("node")
+        .arg(script)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(_) => {
+            eprintln!("node not found; skipping runtime check");
+            return None;
+        }
+    };
+    child
+        .stdin
+        .take()
+        .expect("stdin")
+        .write_all(stdin.as_bytes())
+        .expect("write stdin");
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while child.try_wait().expect("wait").is_none() {
+        if Instant::now() > deadline {
+            child.kill().expect("kill");
+            panic!("node {} did not exit", script.display());
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let out = child.wait_with_output().expect("output");
+    Some(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// The source of `function name(...)` in a generated runtime.
+fn js_function(main_js: &str, name: &str) -> String {
+    let start = main_js
+        .find(&format!("function {name}("))
+        .unwrap_or_else(|| panic!("function {name}"));
+    let end = start + main_js[start..].find("\n}\n").expect("end of function") + 3;
+    main_js[start..end].to_string()
+}
+
+/// The operator helpers every generated runtime shares (`exprNumber` through `compareWhen`).
+fn expr_ops(main_js: &str) -> String {
+    let start = main_js
+        .find("function exprNumber(")
+        .expect("expression helpers");
+    let compare = js_function(main_js, "compareWhen");
+    let end = main_js.find(&compare).expect("compareWhen") + compare.len();
+    main_js[start..end].to_string()
+}
+
+const CALC: &str = r#"@meta target=cli
+module(App.Calc):
+  use(cli.ui)
+  use(cli.input) as input
+  flow(Main):
+    start > Idle
+    state(Idle):
+      ui.text("ready")
+      on input.key(Enter) > Done
+    end
+    state(Done):
+      ui.text("ratio=${ratio}")
+    end
+  end
+  state():
+    ratio = 1
+  end
+  rule(calc):
+    on input.key(Enter):
+      ratio = 7 / 0
+    end
+  end
+end
+"#;
+
+#[test]
+fn cli_and_web_runtimes_share_expression_semantics() {
+    let dir = temp_dir("expr_ops");
+    let cli = fs::read_to_string(build(&dir, "calc", CALC, "cli")).expect("cli main.js");
+    let web_src = CALC
+        .replace("target=cli", "target=web")
+        .replace("cli.", "web.");
+    let web = fs::read_to_string(build(&dir, "calcweb", &web_src, "web")).expect("web main.js");
+    let ops = expr_ops(&cli);
+    assert_eq!(ops, expr_ops(&web));
+
+    let probe = dir.join("probe.js");
+    fs::write(
+        &probe,
+        format!(
+            "{ops}\nconsole.log(JSON.stringify([binaryOp('Div', 7, 0), binaryOp('Mod', 7, 0), \
+binaryOp('Eq', null, undefined), binaryOp('Eq', 2, '2'), binaryOp('Add', 'a', 1), \
+compareWhen('neq', undefined, null)]));\n"
+        ),
+    )
+    .expect("write probe");
+    if let Some(out) = run_node(&probe, "") {
+        assert_eq!(out.trim(), r#"[0,0,1,1,"a1",false]"#);
+    }
+
+    let main = dir.join("dist").join("calc").join("main.js");
+    if let Some(out) = run_node(&main, "\r") {
+        assert!(out.contains("ratio=0"), "{out}");
+    }
+}
+
+#[test]
+fn web_views_render_computed_interpolations() {
+    let dir = temp_dir("web_template");
+    let src = r#"@meta target=web
+module(App.Count):
+  use(web.ui)
+  flow(Main):
+    start > Idle
+    state(Idle):
+      ui.text("next=${count + 1} of ${label}")
+    end
+  end
+  state():
+    count = 2
+    label = "x"
+  end
+end
+"#;
+    let main = build(&dir, "count", src, "web");
+    let main_js = fs::read_to_string(&main).expect("main.js");
+    let target: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(dir.join("dist").join("count").join("target.ir.json"))
+            .expect("target ir"),
+    )
+    .expect("json");
+    let item = &target["views"]["Idle"][0];
+    assert!(item["textTemplate"].is_array(), "{item}");
+
+    let probe = dir.join("probe.js");
+    let functions: Vec<String> = ["guardValue", "templateText", "itemText"]
+        .iter()
+        .map(|name| js_function(&main_js, name))
+        .collect();
+    fs::write(
+        &probe,
+        format!(
+            "const STATE = {{ count: 2, label: 'x' }};\n{}{}\nconsole.log(itemText({}, ''));\n",
+            expr_ops(&main_js),
+            functions.join(""),
+            item
+        ),
+    )
+    .expect("write probe");
+    if let Some(out) = run_node(&probe, "") {
+        assert_eq!(out.trim(), "next=3 of x");
+    }
+}
+
+#[test]
+fn cli_rules_append_to_string_state() {
+    let dir = temp_dir("string_add");
+    let src = r#"@meta target=cli
+module(App.Shout):
+  use(cli.ui)
+  use(cli.input) as input
+  flow(Main):
+    start > Idle
+    state(Idle):
+      ui.text("ready")
+      on input.key(Enter) > Done
+    end
+    state(Done):
+      ui.text("label=${label} count=${count}")
+    end
+  end
+  state():
+    label = "hi"
+    count = 1
+  end
+  rule(shout):
+    on input.key(Enter):
+      label += "!"
+      count += 2
+    end
+  end
+end
+"#;
+    let main = build(&dir, "shout", src, "cli");
+    if let Some(out) = run_node(&main, "\r") {
+        assert!(out.contains("label=hi! count=3"), "{out}");
+        assert!(!out.contains("NaN"), "{out}");
+    }
+}
+
+#[test]
+fn cli_state_evaluates_computed_initializers() {
+    let dir = temp_dir("computed_state");
+    let src = r#"@meta target=cli
+module(App.Start):
+  use(cli.ui)
+  use(cli.input) as input
+  flow(Main):
+    start > Idle
+    state(Idle):
+      ui.text("ready")
+      on input.key(Enter) > Done
+    end
+    state(Done):
+      ui.text("x=${x} y=${y} greeting=${greeting}")
+    end
+  end
+  state():
+    x = -5
+    y = 2 * 3
+    name = "Ada"
+    greeting = "Hi ${name}"
+  end
+  rule(bump):
+    on input.key(Enter):
+      x += 1
+    end
+  end
+end
+"#;
+    let main = build(&dir, "computed", src, "cli");
+    if let Some(out) = run_node(&main, "\r") {
+        assert!(out.contains("x=-4 y=6 greeting=Hi Ada"), "{out}");
+        assert!(!out.contains("[object Object]"), "{out}");
+    }
+}
+
+#[test]
+fn imported_module_rules_and_flows_reach_the_target_ir() {
+    let dir = temp_dir("linked_rules");
+    fs::create_dir_all(dir.join("modules")).expect("mkdir modules");
+    fs::write(
+        dir.join("modules").join("counter.sculpt"),
+        r#"module(Company.Counter):
+  use(cli.input) as input
+  exports(count)
+  flow(Tally):
+    start > Idle
+    state(Idle):
+      on input.key(Esc) when count == 1 > Idle
+    end
+  end
+  state():
+    count = 1
+    step = 2
+  end
+  rule(bump):
+    on input.key(Enter):
+      count += step
+    end
+  end
+end
+"#,
+    )
+    .expect("write counter");
+    fs::write(
+        dir.join("main.sculpt"),
+        r#"@meta target=cli
+module(App.Main):
+  import(Company.Counter) as Counter
+  use(cli.ui)
+  use(cli.input) as input
+  flow(Main):
+    start > Idle
+    state(Idle):
+      ui.text("waiting")
+      on input.key(Enter) > Done
+    end
+    state(Done):
+      ui.text("count=${Counter.count}")
+    end
+  end
+end
+"#,
+    )
+    .expect("write main");
+    fs::write(
+        dir.join("app.sculpt.json"),
+        r#"{ "name": "app", "entry": "App.Main", "modules": ["main.sculpt", "modules/counter.sculpt"] }"#,
+    )
+    .expect("write project");
+    build_input(&dir, "app.sculpt.json", "cli");
+
+    let target: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(dir.join("dist").join("app").join("target.ir.json"))
+            .expect("target ir"),
+    )
+    .expect("json");
+    let rules = target["extensions"]["runtimeRules"]
+        .as_array()
+        .expect("runtime rules");
+    let bump = rules
+        .iter()
+        .find(|rule| rule["name"] == "Company.Counter.bump")
+        .expect("imported rule is lowered");
+    assert_eq!(
+        bump["assign"][0],
+        serde_json::json!({
+          "target": "Company.Counter.count",
+          "op": "add",
+          "value": { "ident": "Company.Counter.step" }
+        })
+    );
+    assert_eq!(target["state"]["Company.Counter.count"], 1.0);
+    // The imported flow keeps its own states next to the entry's.
+    assert_eq!(target["flow"]["start"], "Idle");
+    let guard = &target["flow"]["guards"]["Company.Counter.Tally.Idle"]["key(esc)"][0];
+    assert_eq!(guard["target"], "Company.Counter.Tally.Idle");
+    assert_eq!(guard["when"]["left"], "Company.Counter.count");
+
+    let main = dir.join("dist").join("app").join("main.js");
+    if let Some(out) = run_node(&main, "\r") {
+        assert!(out.contains("count=3"), "{out}");
+    }
+}
@@ -1,5 +1,8 @@
-use sculpt::parser::parse_source;
-use sculpt::semantics::{has_errors, validate_module};
+use std::path::Path;
+
+use sculpt::diagnostics::apply_safe_suggestions;
+use sculpt::parser::{parse_source, parse_source_with_path};
+use sculpt::semantics::{format_diagnostics, has_errors, validate_module};
 
 #[test]
 fn validates_clean_program() {
@@ -93,6 +96,26 @@ end
     assert!(diagnostics.iter().any(|d| d.code == "NS504"));
 }
 
+#[test]
+fn checks_references_inside_string_interpolation() {
+    let src = r#"module(Billing.Invoice):
+  flow(Main):
+    start > Draft
+    state(Draft):
+      title = "Invoice ${External.Domain.User} for ${Billing.Invoice.global.total}"
+      terminate
+    end
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse ok");
+    let diagnostics = validate_module(&module);
+    assert!(diagnostics
+        .iter()
+        .any(|d| d.code == "NS504" && d.message.contains("External.Domain.User")));
+    assert!(diagnostics.iter().any(|d| d.code == "NS503"));
+}
+
 #[test]
 fn allows_imported_namespace_references() {
     let src = r#"module(App.Core):
@@ -393,6 +416,35 @@ end
     assert!(!diagnostics.iter().any(|d| d.code == "R204"));
 }
 
+#[test]
+fn allows_arithmetic_and_not_in_when_but_not_bare_arithmetic() {
+    let src = r#"module(App.Core):
+  state():
+    amount = 100
+    paid = 40
+    rate = 2
+  end
+
+  rule(owed):
+    when (amount - paid) * rate > 50 and not paid % 2 == 1:
+      emit done
+    end
+  end
+
+  rule(bare):
+    when amount - paid:
+      emit done
+    end
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse ok");
+    let diagnostics = validate_module(&module);
+    let r204: Vec<_> = diagnostics.iter().filter(|d| d.code == "R204").collect();
+    assert_eq!(r204.len(), 1);
+    assert!(r204[0].message.contains("'bare'"));
+}
+
 #[test]
 fn rejects_legacy_render_and_key_by_default() {
     let src = r#"module(App.Core):
@@ -452,3 +504,1044 @@ end
     assert!(diagnostics.iter().any(|d| d.code == "N320"));
     assert!(has_errors(&diagnostics));
 }
+
+#[test]
+fn diagnostics_carry_spans_and_render_excerpts() {
+    let src = r#"module(App.Core):
+  use(cli.input) as input
+  flow(Main):
+    start > Title
+    state(Title):
+      on input.key(Enter) > Missing
+    end
+    state(Title):
+    end
+  end
+end
+"#;
+    let module = parse_source_with_path(src, Path::new("app.sculpt")).expect("parse ok");
+    let diagnostics = validate_module(&module);
+
+    let f105 = diagnostics.iter().find(|d| d.code == "F105").expect("F105");
+    let span = f105.span.as_ref().expect("F105 span");
+    assert_eq!((span.line, span.col), (6, 7));
+    assert_eq!(span.text(), "on input.key(Enter) > Missing");
+
+    let f104 = diagnostics.iter().find(|d| d.code == "F104").expect("F104");
+    assert_eq!(f104.span.as_ref().map(|s| s.line), Some(8));
+    assert_eq!(f104.labels.len(), 1);
+    assert_eq!(f104.labels[0].span.line, 5);
+    assert_eq!(f104.labels[0].message, "first defined here");
+
+    let rendered = format_diagnostics(std::slice::from_ref(f105));
+    let expected = "F105: Unknown transition target 'Missing' in flow 'Main' state 'Title'
+ --> app.sculpt:6:7
+  |
+6 |       on input.key(Enter) > Missing
+  |       ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^";
+    assert_eq!(rendered, expected);
+
+    let rendered = format_diagnostics(std::slice::from_ref(f104));
+    assert!(rendered.contains(" --> app.sculpt:8:5"), "{rendered}");
+    assert!(rendered.contains(" ::: app.sculpt:5:5"), "{rendered}");
+    assert!(rendered.contains("  |     --------"), "{rendered}");
+    assert!(rendered.ends_with("first defined here"), "{rendered}");
+}
+
+fn type_codes(src: &str) -> Vec<(&'static str, String)> {
+    let module = parse_source(src).expect("parse ok");
+    validate_module(&module)
+        .into_iter()
+        .filter(|d| d.code.starts_with('T'))
+        .map(|d| (d.code, d.message))
+        .collect()
+}
+
+#[test]
+fn checks_assignments_against_declared_and_inferred_types() {
+    let src = r#"module(Game):
+  state():
+    speedMs: int = 400
+    errorText: string
+    level = 1
+    names: list<string> = ["a"]
+  end
+  rule(slowDown):
+    when level >= 2:
+      speedMs = "fast"
+      errorText = "slow"
+      level = 1.5
+      names = [1]
+    end
+  end
+  rule(reset):
+    on done:
+      level = "one"
+      speedMs = null
+    end
+  end
+end
+"#;
+    let found = type_codes(src);
+    assert_eq!(
+        found,
+        vec![
+            (
+                "T801",
+                "Cannot assign string to 'speedMs', which is declared as int".to_string()
+            ),
+            (
+                "T801",
+                "Cannot assign list<int> to 'names', which is declared as list<string>"
+                    .to_string()
+            ),
+            (
+                "T801",
+                "Cannot assign string to 'level', which holds float (add a type annotation if it may hold both)"
+                    .to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn checks_add_assign_comparisons_and_call_arguments() {
+    let src = r#"module(Game):
+  use(cli.ui) as ui
+  state():
+    score: int = 0
+    label = "Score"
+    flags: list = []
+    ratio: float
+  end
+  flow(Main):
+    start > Play
+    state(Play):
+      ui.text(label, color: 3)
+      ui.heading("Title", level: score)
+      ui.progress(label, value: "full")
+      on input.key(Enter) > Play
+    end
+  end
+  rule(tick):
+    when label > 3 and score == "3":
+      score += 1.5
+      label += score
+      flags += 1
+      ratio = score / 2
+    end
+  end
+end
+"#;
+    let codes: Vec<_> = type_codes(src).into_iter().map(|(c, _)| c).collect();
+    assert_eq!(
+        codes,
+        vec!["T804", "T804", "T803", "T803", "T802", "T802"],
+        "{:?}",
+        type_codes(src)
+    );
+    let messages: Vec<_> = type_codes(src).into_iter().map(|(_, m)| m).collect();
+    assert!(messages.contains(&"Argument 'color' of 'ui.text' expects string, got int".to_string()));
+    assert!(messages.contains(&"Cannot compare string with int using '>'".to_string()));
+    assert!(messages.contains(&"Cannot add float to 'score' (int) with '+='".to_string()));
+    assert!(messages.contains(&"'+=' needs a number or string, but 'flags' is list".to_string()));
+}
+
+#[test]
+fn reports_conflicting_declarations() {
+    let src = r#"module(Game):
+  state():
+    lives: int = 3
+  end
+  flow(Main):
+    start > Play
+    state(Play):
+      lives: string = "three"
+      terminate
+    end
+  end
+end
+"#;
+    let found = type_codes(src);
+    assert_eq!(found.len(), 1, "{:?}", found);
+    assert_eq!(found[0].0, "T805");
+    assert_eq!(found[0].1, "'lives' is declared as int and as string");
+}
+
+#[test]
+fn validates_transition_guards() {
+    let src = r#"module(Billing):
+  state():
+    selected = null
+    count = 0
+  end
+  flow(Main):
+    start > List
+    state(List):
+      on input.key(Enter) when selected != null > Detail
+      on input.key(Enter) when count >= 1 > Detail
+      on input.key(Enter) > List
+      on input.key(Tab) when count >= 1 > Detail
+      on input.key(Tab) when count >= 1 > List
+      on input.key(Esc) when count > Detail
+      on input.key(Up) when Other.Module.flag == 1 > Nowhere
+      on input.key(Down) when count == "many" > Detail
+    end
+    state(Detail):
+      terminate
+    end
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse ok");
+    let diagnostics = validate_module(&module);
+    let codes: Vec<_> = diagnostics.iter().map(|d| d.code).collect();
+    assert_eq!(
+        codes.iter().filter(|c| **c == "F106").count(),
+        1,
+        "{:?}",
+        codes
+    );
+    assert!(diagnostics
+        .iter()
+        .any(|d| d.code == "F107" && d.message.contains("Esc")));
+    assert!(codes.contains(&"F105"));
+    assert!(codes.contains(&"NS504"));
+    assert!(codes.contains(&"T803"));
+}
+
+#[test]
+fn handlers_with_different_guards_are_not_duplicates() {
+    let src = r#"module(Billing):
+  state():
+    a = 0
+    b = 0
+  end
+  flow(Main):
+    start > List
+    state(List):
+      on input.key(Enter) when a != 1 > One
+      on input.key(Enter) when b != 2 > Two
+    end
+    state(One):
+      terminate
+    end
+    state(Two):
+      terminate
+    end
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse ok");
+    let codes: Vec<_> = validate_module(&module).iter().map(|d| d.code).collect();
+    assert!(!codes.contains(&"F106"), "{:?}", codes);
+}
+
+#[test]
+fn validates_state_hierarchy() {
+    let src = r#"module(Arcade):
+  flow(Game):
+    start > Play
+    state(Play):
+      on input.key(Esc) > Exit
+      region(Main):
+        start > Running
+        state(Running):
+          on input.key(H) > Clock
+          on input.key(P) > Paused
+        end
+        state(Paused):
+          on input.key(P) > Running
+        end
+      end
+      region(Hud):
+        start > Clock
+        state(Clock):
+          on input.tick > Clock
+        end
+      end
+      region(Hud):
+        start > Missing
+      end
+    end
+    state(Menu):
+      state(Item):
+        on input.key(Esc) > Play
+      end
+    end
+    state(Exit):
+      start > Running
+      state(Running):
+        terminate
+      end
+      region(Late):
+      end
+    end
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse ok");
+    let diagnostics = validate_module(&module);
+    let codes: Vec<_> = diagnostics.iter().map(|d| d.code).collect();
+    assert!(diagnostics
+        .iter()
+        .any(|d| d.code == "F111" && d.message.contains("from 'Running' to 'Clock'")));
+    assert_eq!(
+        codes.iter().filter(|c| **c == "F111").count(),
+        1,
+        "{:?}",
+        codes
+    );
+    assert!(codes.contains(&"F112"));
+    assert!(diagnostics
+        .iter()
+        .any(|d| d.code == "F109" && d.message.contains("'Missing'")));
+    assert!(diagnostics
+        .iter()
+        .any(|d| d.code == "F108" && d.message.contains("state 'Menu'")));
+    assert!(diagnostics
+        .iter()
+        .any(|d| d.code == "F108" && d.message.contains("region 'Late'")));
+    assert!(codes.contains(&"F110"));
+    assert!(diagnostics
+        .iter()
+        .any(|d| d.code == "F104" && d.message.contains("'Running'")));
+}
+
+#[test]
+fn validates_run_arguments_and_returns() {
+    let src = r#"module(Billing):
+  state():
+    confirmed = false
+    count = 0
+  end
+  flow(Main):
+    start > Ask
+    state(Ask):
+      run Confirm("Delete?", danger: 1) -> count
+      on done > Again
+    end
+    state(Again):
+      run Confirm(verbose: true) -> confirmed
+      on done > Extra
+    end
+    state(Extra):
+      run Confirm("a", false, 3)
+      on done > Log
+    end
+    state(Log):
+      run Notify("x") -> confirmed
+      on done > Ask
+    end
+  end
+  flow(Confirm, message: string, danger: bool) -> bool:
+    start > Prompt
+    state(Prompt):
+      on input.key(Y) > Yes
+      on input.key(N) > No
+    end
+    state(Yes):
+      return true
+    end
+    state(No):
+      return
+    end
+    state(Maybe):
+      return "maybe"
+    end
+  end
+  flow(Notify, text: string):
+    start > Show
+    state(Show):
+      return text
+      ui.text(text)
+    end
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse ok");
+    let diagnostics = validate_module(&module);
+    let has = |code: &str, needle: &str| {
+        diagnostics
+            .iter()
+            .any(|d| d.code == code && d.message.contains(needle))
+    };
+    assert!(
+        has("T806", "'danger' of run Confirm expects bool, got int"),
+        "{:?}",
+        diagnostics
+    );
+    assert!(has("T801", "'count'"), "{:?}", diagnostics);
+    assert!(has("B405", "no parameter 'verbose'"));
+    assert!(has("B405", "missing argument(s) for message, danger"));
+    assert!(has("B405", "takes 2 argument(s), got 3"));
+    assert!(has("B406", "returns no value"));
+    assert!(has("B407", "needs a value"));
+    assert!(has("B407", "declares no return type"));
+    assert!(has("T807", "gives string"));
+    assert!(has("B402", "return must be the last statement"));
+}
+
+#[test]
+fn run_accepts_flows_of_imported_modules() {
+    let src = r#"module(Billing):
+  import(Shared.Dialogs) as Dialogs
+  flow(Main):
+    start > Ask
+    state(Ask):
+      run Dialogs.Confirm("Delete?") -> confirmed
+      on done > Ask
+    end
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse ok");
+    let diagnostics = validate_module(&module);
+    assert!(
+        !diagnostics.iter().any(|d| d.code == "B401"),
+        "{:?}",
+        diagnostics
+    );
+}
+
+#[test]
+fn validates_timer_transitions_and_periodic_rules() {
+    let src = r#"module(Kiosk):
+  state():
+    label: string = "idle"
+    timeoutMs: int = 30s
+  end
+  flow(Main):
+    start > Splash
+    state(Splash):
+      after(2s) > Menu
+      after(2000ms) > Idle
+      after(5s) > Missing
+      label = 500ms
+    end
+    state(Menu):
+      region(Left):
+        start > Browse
+        state(Browse):
+          after(1m) > Cart
+        end
+      end
+      region(Right):
+        start > Cart
+        state(Cart):
+        end
+      end
+    end
+    state(Idle):
+      rule(never):
+        every(0ms):
+          label = "x"
+        end
+      end
+    end
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse ok");
+    let diagnostics = validate_module(&module);
+    let has = |code: &str, needle: &str| {
+        diagnostics
+            .iter()
+            .any(|d| d.code == code && d.message.contains(needle))
+    };
+    assert!(
+        has("F106", "'after(2s)'"),
+        "{}",
+        format_diagnostics(&diagnostics)
+    );
+    assert!(has("F105", "'Missing'"));
+    assert!(has("F111", "from 'Browse' to 'Cart'"));
+    assert!(has("R206", "'never'"));
+    assert!(has("T801", "'label'"));
+    assert!(!diagnostics.iter().any(|d| d.message.contains("timeoutMs")));
+}
+
+#[test]
+fn validates_item_attributes() {
+    let src = r#"module(Shop):
+  @deprecated("use Checkout2")
+  flow(Checkout):
+    start > Pay
+    state(Pay):
+      on done > Legacy
+    end
+    @deprecated
+    state(Legacy):
+      on back > Pay
+    end
+  end
+
+  @owner(web)
+  @owner(ui)
+  @critical(1)
+  @team
+  flow(Main):
+    start > Home
+    state(Home):
+      run Checkout
+      on done > Old
+    end
+    @deprecated
+    state(Old):
+      on back > Home
+    end
+  end
+
+  @owner(a, b)
+  rule(tick):
+    on tick:
+      n += 1
+    end
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse ok");
+    let diagnostics = validate_module(&module);
+    let has = |code: &str, needle: &str| {
+        diagnostics
+            .iter()
+            .any(|d| d.code == code && d.message.contains(needle))
+    };
+    assert!(
+        has("M708", "Duplicate attribute '@owner' on flow 'Main'"),
+        "{}",
+        format_diagnostics(&diagnostics)
+    );
+    assert!(has("M708", "'@critical' on flow 'Main'"));
+    assert!(has("M708", "'@owner' on rule 'tick'"));
+    assert!(has("M707", "'@team'"));
+    assert!(has("M709", "Flow 'Checkout' is deprecated: use Checkout2"));
+    assert!(has("M709", "State 'Old' is deprecated"));
+    // Deprecated flows may keep using their own deprecated states.
+    assert!(!has("M709", "'Legacy'"));
+    assert!(diagnostics
+        .iter()
+        .filter(|d| matches!(d.code, "M707" | "M709"))
+        .all(|d| d.level == sculpt::semantics::DiagnosticLevel::Warning));
+}
+
+#[test]
+fn language_meta_decides_int_flag_severity() {
+    let body = r#"module(App.Core):
+  flow(Main):
+    start > A
+    state(A):
+      on done > A
+    end
+  end
+  state():
+    ready = 0
+  end
+  rule(check):
+    on done:
+      ready = true
+    end
+  end
+end
+"#;
+    let check = |meta: &str| {
+        let module = parse_source(&format!("{meta}{body}")).expect("parse ok");
+        validate_module(&module)
+    };
+
+    let pinned = check("@meta language=1.0\n");
+    let flags: Vec<_> = pinned.iter().filter(|d| d.code == "T801").collect();
+    assert_eq!(flags.len(), 1, "{}", format_diagnostics(&pinned));
+    assert_eq!(flags[0].level, sculpt::semantics::DiagnosticLevel::Warning);
+    assert!(!has_errors(&pinned), "{}", format_diagnostics(&pinned));
+
+    for meta in ["@meta language=1.1\n", ""] {
+        let current = check(meta);
+        assert!(
+            current
+                .iter()
+                .any(|d| d.code == "T801" && d.level == sculpt::semantics::DiagnosticLevel::Error),
+            "{meta}{}",
+            format_diagnostics(&current)
+        );
+    }
+}
+
+#[test]
+fn legacy_shorthand_is_an_error_in_every_edition() {
+    let body = r#"module(App.Core):
+  flow(Main):
+    start > A
+    state(A):
+      render text("Legacy")
+      on key(Enter) > A
+      rule(keys):
+        on key(Space):
+          n += 1
+        end
+      end
+    end
+  end
+end
+"#;
+    let check = |meta: &str| {
+        let module = parse_source(&format!("{meta}{body}")).expect("parse ok");
+        validate_module(&module)
+    };
+
+    for meta in ["@meta language=1.0\n", "@meta language=1.1\n", ""] {
+        let diagnostics = check(meta);
+        let legacy: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| matches!(d.code, "U610" | "U611"))
+            .collect();
+        assert_eq!(legacy.len(), 3, "{}", format_diagnostics(&diagnostics));
+        assert!(
+            legacy
+                .iter()
+                .all(|d| d.level == sculpt::semantics::DiagnosticLevel::Error),
+            "{meta}"
+        );
+    }
+
+    let invalid = check("@meta language=latest\n");
+    assert!(invalid
+        .iter()
+        .any(|d| d.code == "M710" && d.message.contains("'latest'")));
+    let unsupported = check("@meta language=2.0\n");
+    assert!(unsupported
+        .iter()
+        .any(|d| d.code == "M711" && d.message.contains(">=1.0 <2.0")));
+}
+
+#[test]
+fn analyzes_flow_reachability_dead_ends_and_traps() {
+    let src = r#"module(Shop):
+  use(cli.ui)
+  use(cli.input) as input
+  flow(Main):
+    start > Home
+    state(Home):
+      on input.key(s) > Split
+      on input.key(c) > Checkout
+      on input.key(h) > Help
+      on input.key(esc) > Exit
+      on ready > Orphan
+    end
+    state(Help):
+      ui.text("help")
+    end
+    state(Checkout):
+      run Pay
+      on done > Review
+    end
+    state(Review):
+      on input.key(b) > Browse
+      on loaded > Exit
+    end
+    state(Browse):
+      on input.key(n) > Compare
+    end
+    state(Compare):
+      on input.key(b) > Browse
+    end
+    state(Orphan):
+      on input.key(x) > Exit
+    end
+    state(Split):
+      region(Left):
+        start > Waiting
+        state(Waiting):
+        end
+      end
+      region(Right):
+        start > Busy
+        state(Busy):
+          after(1s) > Exit
+        end
+      end
+    end
+    state(Exit):
+      terminate
+    end
+  end
+
+  flow(Pay):
+    start > Ask
+    state(Ask):
+      on input.key(enter) > Paid
+    end
+    state(Paid):
+      return
+    end
+  end
+
+  rule(load):
+    on input.tick:
+      emit loaded
+    end
+  end
+end
+"#;
+    let diagnostics = |meta: &str| {
+        let module = parse_source(&format!("{meta}{src}")).expect("parse ok");
+        validate_module(&module)
+    };
+    let warned = diagnostics("");
+    let messages = |code: &str| {
+        warned
+            .iter()
+            .filter(|d| d.code == code)
+            .map(|d| d.message.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        messages("F113"),
+        vec!["State 'Orphan' in flow 'Main' is unreachable from start"],
+        "{}",
+        format_diagnostics(&warned)
+    );
+    // `Waiting` has no transitions of its own, but the other region leaves `Split`.
+    assert_eq!(messages("F114").len(), 1);
+    assert!(messages("F114")[0].contains("'Help'"));
+    assert_eq!(
+        messages("F115"),
+        vec!["States 'Browse', 'Compare' in flow 'Main' form a cycle with no path to terminate or return"]
+    );
+    assert!(!has_errors(&warned), "{}", format_diagnostics(&warned));
+
+    let strict = diagnostics("@meta flow_analysis=strict\n");
+    assert!(strict
+        .iter()
+        .filter(|d| d.code.starts_with("F11"))
+        .all(|d| d.level == sculpt::semantics::DiagnosticLevel::Error));
+    assert!(has_errors(&strict));
+
+    let off = diagnostics("@meta flow_analysis=off\n");
+    assert!(!off
+        .iter()
+        .any(|d| matches!(d.code, "F113" | "F114" | "F115")));
+    let invalid = diagnostics("@meta flow_analysis=loud\n");
+    assert!(invalid.iter().any(|d| d.code == "M712"));
+}
+
+#[test]
+fn analyzes_definite_assignment_of_state_variables() {
+    let src = r#"module(Batch):
+  use(cli.ui)
+  use(cli.input) as input
+  state():
+    limit = 10
+  end
+  flow(Main):
+    start > Menu
+    state(Menu):
+      valid = false
+      on input.key(l) > Load
+      on input.key(s) > Summary
+    end
+    state(Load):
+      rows = 0
+      rule(read):
+        on input.tick:
+          valid = rows < limit
+        end
+      end
+      rule(check):
+        when valid == true:
+          checked = 1
+          emit loaded
+        end
+      end
+      on loaded > Ask
+    end
+    state(Ask):
+      ui.text("Checked ${checked}")
+      run Confirm -> answer
+      on done > Summary
+    end
+    state(Summary):
+      ui.text("Rows: ${rows}")
+      on input.key(q) > Exit
+    end
+    state(Exit):
+      terminate
+    end
+  end
+
+  flow(Confirm) -> bool:
+    start > Prompt
+    state(Prompt):
+      scratch = 1
+      return true
+    end
+  end
+
+  rule(count):
+    on input.tick:
+      ticks += 1
+    end
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse ok");
+    let diagnostics = validate_module(&module);
+    let messages = |code: &str| {
+        diagnostics
+            .iter()
+            .filter(|d| d.code == code)
+            .map(|d| d.message.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        messages("D001"),
+        vec![
+            "Variable 'valid' may be read by rule 'check' in 'Main.Load' before rule 'read' assigns it",
+            "Variable 'rows' may be read before it is assigned (in 'Main.Summary')",
+        ],
+        "{}",
+        format_diagnostics(&diagnostics)
+    );
+    // `checked` reaches `Ask` through the rule that emits `loaded`.
+    assert_eq!(
+        messages("D002"),
+        vec![
+            "Variable 'answer' is assigned but never read",
+            "Variable 'scratch' is assigned but never read",
+            "Variable 'ticks' is assigned but never read",
+        ]
+    );
+    assert_eq!(
+        messages("D003"),
+        vec!["'+=' on 'ticks' rule 'count' at module level, but 'ticks' is never initialised"]
+    );
+    assert!(diagnostics
+        .iter()
+        .filter(|d| d.code.starts_with('D'))
+        .all(|d| d.level == sculpt::semantics::DiagnosticLevel::Warning));
+}
+
+#[test]
+fn detects_rule_write_conflicts_and_emit_cycles() {
+    let src = r#"module(Arcade):
+  use(cli.ui)
+  use(cli.input) as input
+  state():
+    speed = 1
+    lives = 3
+    mode = "idle"
+    level = 1
+  end
+  flow(Game):
+    start > Play
+    state(Play):
+      ui.text("${speed} ${lives} ${mode} ${level}")
+      rule(faster):
+        on input.key(f):
+          speed = 2
+        end
+      end
+      rule(slower):
+        on input.key(f):
+          speed = 0
+        end
+      end
+      rule(boost):
+        on input.key(b):
+          speed += 1
+        end
+      end
+      rule(alsoBoost):
+        on input.key(b):
+          speed += 2
+        end
+      end
+      rule(alive):
+        when lives >= 1:
+          mode = "play"
+        end
+      end
+      rule(dead):
+        when lives < 1:
+          mode = "over"
+        end
+      end
+      rule(levelUp):
+        on input.tick:
+          level = level + 1
+        end
+      end
+      @priority(1)
+      rule(levelReset):
+        when lives < 1:
+          level = 1
+        end
+      end
+      rule(ping):
+        on ping:
+          emit pong
+        end
+      end
+      rule(pong):
+        on pong:
+          emit ping
+        end
+      end
+      on input.key(q) > Exit
+    end
+    state(Exit):
+      terminate
+    end
+  end
+  @priority(2)
+  flow(Other):
+    start > Idle
+    state(Idle):
+      terminate
+    end
+  end
+  @priority("high")
+  rule(broken):
+    on input.key(x):
+      lives = 0
+    end
+  end
+end
+"#;
+    let module = parse_source(src).expect("parse ok");
+    let diagnostics = validate_module(&module);
+    let messages = |code: &str| {
+        diagnostics
+            .iter()
+            .filter(|d| d.code == code)
+            .map(|d| d.message.clone())
+            .collect::<Vec<_>>()
+    };
+    // `+=` on both sides, exclusive conditions and a higher priority are not conflicts.
+    assert_eq!(
+        messages("R210"),
+        vec!["Rules 'faster' and 'slower' can fire in the same cycle and both assign 'speed'; 'slower' runs last and wins (use @priority to choose)"],
+        "{}",
+        format_diagnostics(&diagnostics)
+    );
+    assert_eq!(
+        messages("R211"),
+        vec!["Rules 'ping', 'pong' trigger each other through emit ('ping', 'pong') and can loop forever"]
+    );
+    let cycle = diagnostics.iter().find(|d| d.code == "R211").unwrap();
+    assert_eq!(cycle.labels.len(), 2);
+    assert_eq!(
+        messages("M708"),
+        vec![
+            "Attribute '@priority' only applies to rules, not flow 'Other'",
+            "Invalid arguments for '@priority' on rule 'broken' (expected a single whole number)",
+        ]
+    );
+}
+
+#[test]
+fn suggests_closest_names_and_modern_syntax() {
+    let src = r#"module(App.Core):
+  use(cli.input)
+  define collision.stable():
+    "Collision should feel stable."
+  end
+  flow(Main):
+    start > Title
+    state(Title):
+      amount = App.Core.global.totl
+      on key(Enter) > Plya
+    end
+    state(Play):
+      run Helpr()
+      on done > Title
+    end
+  end
+  flow(Helper):
+    start > A
+    state(A):
+      return
+    end
+  end
+  state():
+    total = 0
+    amount = 0
+  end
+  nd(design):
+    propose game("breakout")
+    satisfy(?colision.stable())
+  end
+end
+"#;
+    let module = parse_source_with_path(src, Path::new("app.sculpt")).expect("parse ok");
+    let diagnostics = validate_module(&module);
+    let suggestion = |code: &str| {
+        let d = diagnostics
+            .iter()
+            .find(|d| d.code == code && !d.suggestions.is_empty())
+            .unwrap_or_else(|| panic!("{code} with suggestion"));
+        let s = &d.suggestions[0];
+        (s.span.text().to_string(), s.replacement.clone(), s.safe)
+    };
+    assert_eq!(
+        suggestion("NS503"),
+        (
+            "App.Core.global.totl".into(),
+            "App.Core.global.total".into(),
+            true
+        )
+    );
+    assert_eq!(suggestion("F105"), ("Plya".into(), "Play".into(), true));
+    assert_eq!(suggestion("B401"), ("Helpr".into(), "Helper".into(), true));
+    assert_eq!(
+        suggestion("N309"),
+        ("colision.stable".into(), "collision.stable".into(), true)
+    );
+    assert_eq!(suggestion("U610"), ("key".into(), "input.key".into(), true));
+
+    let rendered = format_diagnostics(&diagnostics);
+    assert!(
+        rendered.contains("  = help: did you mean 'Play'?"),
+        "{rendered}"
+    );
+
+    let (fixed, applied) = apply_safe_suggestions(src, "app.sculpt", &diagnostics);
+    assert_eq!(applied, 5);
+    assert!(fixed.contains("      on input.key(Enter) > Play\n"));
+    let module = parse_source(&fixed).expect("fixed source parses");
+    let remaining: Vec<_> = validate_module(&module)
+        .into_iter()
+        .filter(|d| !d.suggestions.is_empty())
+        .collect();
+    assert!(remaining.is_empty(), "{}", format_diagnostics(&remaining));
+}
+
+#[test]
+fn unsafe_suggestions_are_not_applied() {
+    let src = r#"module(App.Core):
+  flow(Main):
+    start > Title
+    state(Title):
+      on key(Enter) > Pay
+    end
+    state(Play):
+      terminate
+    end
+    state(Pad):
+      terminate
+    end
+  end
+end
+"#;
+    let module = parse_source_with_path(src, Path::new("app.sculpt")).expect("parse ok");
+    let diagnostics = validate_module(&module);
+    let unsafe_count = diagnostics
+        .iter()
+        .flat_map(|d| &d.suggestions)
+        .filter(|s| !s.safe)
+        .count();
+    // `key` needs an input import first, and 'Pay' is as close to 'Pad' as to 'Play'.
+    assert_eq!(unsafe_count, 2);
+    let (fixed, applied) = apply_safe_suggestions(src, "app.sculpt", &diagnostics);
+    assert_eq!((fixed.as_str(), applied), (src, 0));
+}
@@ -0,0 +1,24 @@
+use sculpt::versioning::{Feature, LanguageVersion, LANGUAGE_DEFAULT};
+
+#[test]
+fn parses_and_ranges_language_versions() {
+    assert_eq!(
+        LanguageVersion::parse("1.10"),
+        Some(LanguageVersion::new(1, 10))
+    );
+    for bad in ["1", "1.x", "v1.0", "1.0.2", ""] {
+        assert_eq!(LanguageVersion::parse(bad), None, "{bad}");
+    }
+    assert_eq!(
+        LanguageVersion::default_version().to_string(),
+        LANGUAGE_DEFAULT
+    );
+    assert!(LanguageVersion::new(1, 0).is_supported());
+    assert!(LanguageVersion::new(1, 99).is_supported());
+    assert!(!LanguageVersion::new(0, 9).is_supported());
+    assert!(!LanguageVersion::new(2, 0).is_supported());
+
+    assert!(!LanguageVersion::new(1, 0).has(Feature::Timers));
+    assert!(LanguageVersion::new(1, 1).has(Feature::Timers));
+    assert!(LanguageVersion::default_version().has(Feature::StrictFlagTypes));
+}